@@ -9,6 +9,7 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::hash::Hasher;
 use std::str::FromStr;
+use thiserror::Error;
 pub use witness_calculator::flat_array;
 pub use witness_calculator::WitnessCalculator;
 
@@ -20,36 +21,74 @@ pub(crate) fn fnv(inp: &str) -> (u32, u32) {
     ((h >> 32) as u32, h as u32)
 }
 
-pub fn load_input_for_witness(input_file: &str) -> HashMap<String, Vec<BigInt>> {
+/// Why the input signals of a witness can't be loaded, or don't match the circuit.
+#[derive(Error, Debug)]
+pub enum InputError {
+    #[error("unable to load {file}: {source}")]
+    Io { file: String, source: std::io::Error },
+
+    #[error("the input is not a JSON object of signals: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("input signal {signal}: {value} is not an integer")]
+    NotAnInteger { signal: String, value: String },
+
+    #[error("input signal {signal}: unsupported value {value}")]
+    Unsupported { signal: String, value: String },
+
+    #[error("the circuit has no input signal {signal}")]
+    UnknownSignal { signal: String },
+
+    #[error("input signal {signal} takes {expected} values, not {actual}")]
+    SignalSize { signal: String, expected: usize, actual: usize },
+}
+
+/// Reads the input signals of a witness from a JSON file, `{"signal": value}`, a value being an
+/// integer, a decimal string, a bool, or a nested array of them.
+pub fn load_input_for_witness(
+    input_file: &str,
+) -> Result<HashMap<String, Vec<BigInt>>, InputError> {
     let inputs_str = std::fs::read_to_string(input_file)
-        .unwrap_or_else(|_| panic!("Unable to load {input_file}"));
-    let inputs: std::collections::HashMap<String, serde_json::Value> =
-        serde_json::from_str(&inputs_str).unwrap();
+        .map_err(|source| InputError::Io { file: input_file.to_string(), source })?;
+    parse_input_for_witness(&inputs_str)
+}
+
+/// The input signals of a witness in a JSON string, see `load_input_for_witness`.
+pub fn parse_input_for_witness(
+    inputs_str: &str,
+) -> Result<HashMap<String, Vec<BigInt>>, InputError> {
+    let inputs: HashMap<String, Value> = serde_json::from_str(inputs_str)?;
 
     inputs
-        .iter()
+        .into_iter()
         .map(|(key, value)| {
-            let res = match value {
-                Value::String(inner) => {
-                    vec![BigInt::from_str(inner).unwrap()]
-                }
-                Value::Bool(inner) => {
-                    if *inner {
-                        vec![BigInt::one()]
-                    } else {
-                        vec![BigInt::zero()]
-                    }
-                }
-                Value::Number(inner) => {
-                    vec![BigInt::from_str(&inner.to_string()).unwrap()]
-                    //vec![BigInt::from(inner.as_u64().expect("not a u32"))]
-                }
-                //Value::Array(inner) => inner.iter().cloned().map(value_to_bigint).collect(),
-                Value::Array(inner) => flat_array(inner),
-                _ => panic!("{value:?}"),
-            };
-
-            (key.clone(), res)
+            let mut res = vec![];
+            flatten_input(&key, &value, &mut res)?;
+            Ok((key, res))
         })
-        .collect::<std::collections::HashMap<_, _>>()
+        .collect()
+}
+
+fn flatten_input(signal: &str, value: &Value, out: &mut Vec<BigInt>) -> Result<(), InputError> {
+    let not_an_integer =
+        || InputError::NotAnInteger { signal: signal.to_string(), value: value.to_string() };
+    match value {
+        Value::String(inner) => out.push(BigInt::from_str(inner).map_err(|_| not_an_integer())?),
+        Value::Bool(inner) => out.push(if *inner { BigInt::one() } else { BigInt::zero() }),
+        Value::Number(inner) => {
+            out.push(BigInt::from_str(&inner.to_string()).map_err(|_| not_an_integer())?)
+        }
+        Value::Array(inner) => {
+            for v in inner {
+                flatten_input(signal, v, out)?;
+            }
+        }
+        _ => {
+            return Err(InputError::Unsupported {
+                signal: signal.to_string(),
+                value: value.to_string(),
+            })
+        }
+    }
+    Ok(())
 }
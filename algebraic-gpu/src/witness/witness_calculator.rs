@@ -1,5 +1,5 @@
 // copied and modified by https://github.com/arkworks-rs/circom-compat/blob/master/src/witness/witness_calculator.rs
-use crate::witness::{circom::Wasm, fnv, memory::SafeMemory};
+use crate::witness::{circom::Wasm, fnv, memory::SafeMemory, InputError};
 use anyhow::{bail, Result};
 use ff::PrimeField;
 use num::ToPrimitive;
@@ -155,6 +155,19 @@ impl WitnessCalculator {
         // allocate the inputs
         for (name, values) in inputs.into_iter() {
             let (msb, lsb) = fnv(&name);
+            if let Some(size) = self.instance.get_input_signal_size(&mut self.store, msb, lsb)? {
+                if size < 0 {
+                    return Err(InputError::UnknownSignal { signal: name }.into());
+                }
+                if size as usize != values.len() {
+                    return Err(InputError::SignalSize {
+                        signal: name,
+                        expected: size as usize,
+                        actual: values.len(),
+                    }
+                    .into());
+                }
+            }
 
             for (i, value) in values.into_iter().enumerate() {
                 let f_arr = to_array32(&value, n32 as usize);
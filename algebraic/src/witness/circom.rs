@@ -40,6 +40,20 @@ impl Wasm {
         Ok(())
     }
 
+    /// The number of values of an input signal, negative if the circuit has no such input, None
+    /// for the wasm not exporting `getInputSignalSize`.
+    pub(crate) fn get_input_signal_size(
+        &self,
+        store: &mut Store,
+        hmsb: u32,
+        hlsb: u32,
+    ) -> Result<Option<i32>> {
+        match self.0.exports.get_function("getInputSignalSize") {
+            Ok(func) => Ok(Some(func.call(store, &[hmsb.into(), hlsb.into()])?[0].unwrap_i32())),
+            Err(_) => Ok(None),
+        }
+    }
+
     pub(crate) fn get_witness(&self, store: &mut Store, i: u32) -> Result<()> {
         let func = self.func("getWitness");
         func.call(store, &[i.into()])?;
@@ -1,6 +1,6 @@
 // copied and modified by https://github.com/arkworks-rs/circom-compat/blob/master/src/witness/witness_calculator.rs
 use crate::bellman_ce::ScalarEngine;
-use crate::witness::{circom::Wasm, fnv, memory::SafeMemory};
+use crate::witness::{circom::Wasm, fnv, memory::SafeMemory, InputError};
 use anyhow::{bail, Result};
 use num::ToPrimitive;
 use num_bigint::BigInt;
@@ -155,6 +155,19 @@ impl WitnessCalculator {
         // allocate the inputs
         for (name, values) in inputs.into_iter() {
             let (msb, lsb) = fnv(&name);
+            if let Some(size) = self.instance.get_input_signal_size(&mut self.store, msb, lsb)? {
+                if size < 0 {
+                    return Err(InputError::UnknownSignal { signal: name }.into());
+                }
+                if size as usize != values.len() {
+                    return Err(InputError::SignalSize {
+                        signal: name,
+                        expected: size as usize,
+                        actual: values.len(),
+                    }
+                    .into());
+                }
+            }
 
             for (i, value) in values.into_iter().enumerate() {
                 let f_arr = to_array32(&value, n32 as usize);
@@ -434,6 +447,26 @@ mod tests {
         });
     }
 
+    #[test]
+    fn multiplier_input_mismatch() {
+        let mut wtns =
+            WitnessCalculator::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let input = |name: &str, values: &[i64]| {
+            (name.to_string(), values.iter().map(|v| BigInt::from(*v)).collect::<Vec<_>>())
+        };
+
+        let err = wtns.calculate_witness([input("a", &[3]), input("d", &[11])], false).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<InputError>(),
+            Some(InputError::UnknownSignal { signal }) if signal == "d"
+        ));
+        let err = wtns.calculate_witness([input("a", &[3, 4])], false).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<InputError>(),
+            Some(InputError::SignalSize { expected: 1, actual: 2, .. })
+        ));
+    }
+
     // TODO: test complex samples
 
     fn run_test(case: TestCase) {
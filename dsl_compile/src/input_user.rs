@@ -85,7 +85,8 @@ impl Input {
         let output_js_path = Input::build_folder(output_path, &file_name, JS);
         let mut link_libraries: Vec<PathBuf> = vec![];
         for path in paths.into_iter() {
-            link_libraries.push(Path::new(&path).to_path_buf());
+            // `starky:<hash type>` links the templates bundled with the stark verifiers
+            link_libraries.push(stark_circuits::resolve_link_directory(&path)?);
         }
 
         let input = input.to_path_buf();
@@ -0,0 +1,19 @@
+use std::env;
+use std::path::PathBuf;
+
+/// Generates `eigen_zkvm.h` in `OUT_DIR` from the `extern "C"` items of the crate, the tests
+/// check `include/eigen_zkvm.h` against it.
+fn main() {
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .expect("Invalid cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("Unable to generate the C header")
+        .write_to_file(out_dir.join("eigen_zkvm.h"));
+}
@@ -0,0 +1,142 @@
+#ifndef EIGEN_ZKVM_H
+#define EIGEN_ZKVM_H
+
+/* Generated by cbindgen from ffi/src, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum EigenStatus {
+  EIGEN_STATUS_OK = 0,
+  /**
+   * A null pointer, a path not in UTF-8, or an input which doesn't parse.
+   */
+  EIGEN_STATUS_INVALID_ARGUMENT = 1,
+  /**
+   * The proof was checked and rejected.
+   */
+  EIGEN_STATUS_INVALID_PROOF = 2,
+  /**
+   * Setup, proving or verification failed, see `eigen_last_error`.
+   */
+  EIGEN_STATUS_FAILED = 3,
+  /**
+   * A panic was caught at the boundary.
+   */
+  EIGEN_STATUS_PANIC = 4,
+} EigenStatus;
+
+/**
+ * The setup of a PIL, its constant tree and `StarkInfo`, by `eigen_stark_setup`.
+ */
+typedef struct EigenStarkSetup EigenStarkSetup;
+
+/**
+ * Bytes allocated by the library, freed by `eigen_buffer_free`.
+ */
+typedef struct EigenBuffer {
+  uint8_t *data;
+  size_t len;
+} EigenBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The message of the last failed call on this thread, null if it succeeded. The string is
+ * owned by the library and valid until the next call on the same thread.
+ */
+const char *eigen_last_error(void);
+
+/**
+ * Frees a buffer returned by the library. Freeing an empty buffer is a no-op.
+ */
+void eigen_buffer_free(struct EigenBuffer buf);
+
+/**
+ * Computes the committed polynomials of the compressor12 PIL `pil_file` for the JSON `input`
+ * (the zkin of the proof to compress), with the verifier circuit `wasm_file` and the
+ * `exec_file` of its setup. On success `*cm_pols` is the `.cm` file, ready for
+ * `eigen_stark_prove`. The `<pil_file>.json` written by the setup is loaded, `pil_file` is
+ * compiled to it if it is missing or older.
+ */
+enum EigenStatus eigen_compressor12_exec(const uint8_t *input,
+                                         size_t input_len,
+                                         const char *wasm_file,
+                                         const char *pil_file,
+                                         const char *exec_file,
+                                         size_t num_threads,
+                                         struct EigenBuffer *cm_pols);
+
+/**
+ * Proves the circuit `circuit_file` (`.r1cs`) on the JSON `input`, the witness computed by
+ * `wasm_file`, with the proving key `pk_file`, on `curve` `BN128` or `BLS12381`. On success
+ * `*proof` and `*public_input` are the JSON `groth16_prove` writes, in hex if `to_hex`.
+ */
+enum EigenStatus eigen_groth16_prove(const char *curve,
+                                     const char *circuit_file,
+                                     const char *wasm_file,
+                                     const char *pk_file,
+                                     const uint8_t *input,
+                                     size_t input_len,
+                                     bool to_hex,
+                                     size_t num_threads,
+                                     struct EigenBuffer *proof,
+                                     struct EigenBuffer *public_input);
+
+/**
+ * Verifies the JSON `proof` of the JSON `public_input` with the JSON verifying key `vk`,
+ * `EIGEN_STATUS_OK` if it is valid and `EIGEN_STATUS_INVALID_PROOF` if it is rejected.
+ */
+enum EigenStatus eigen_groth16_verify(const char *curve,
+                                      const uint8_t *vk,
+                                      size_t vk_len,
+                                      const uint8_t *public_input,
+                                      size_t public_input_len,
+                                      const uint8_t *proof,
+                                      size_t proof_len);
+
+/**
+ * Sets up the PIL `pil` (the `.pil.json`) under `stark_struct`, both JSON, with the constant
+ * polynomials `const_pols` in the layout of a `.const` file. `num_threads` sizes the prover
+ * pool of the setup, 0 for one worker per CPU. On success `*out` owns the setup, freed by
+ * `eigen_stark_setup_free`. A setup may be shared by threads proving and verifying at once.
+ */
+enum EigenStatus eigen_stark_setup(const uint8_t *stark_struct,
+                                   size_t stark_struct_len,
+                                   const uint8_t *pil,
+                                   size_t pil_len,
+                                   const uint8_t *const_pols,
+                                   size_t const_pols_len,
+                                   size_t num_threads,
+                                   struct EigenStarkSetup **out);
+
+void eigen_stark_setup_free(struct EigenStarkSetup *setup);
+
+/**
+ * Proves the committed polynomials `cm_pols`, in the layout of a `.cm` file. On success
+ * `*zkin` is the JSON proof `stark_prove` writes to its zkin file.
+ */
+enum EigenStatus eigen_stark_prove(const struct EigenStarkSetup *setup,
+                                   const uint8_t *cm_pols,
+                                   size_t cm_pols_len,
+                                   const char *prover_addr,
+                                   struct EigenBuffer *zkin);
+
+/**
+ * Verifies the JSON proof `zkin` of `eigen_stark_prove`, `EIGEN_STATUS_OK` if it is valid and
+ * `EIGEN_STATUS_INVALID_PROOF` if it is rejected.
+ */
+enum EigenStatus eigen_stark_verify(const struct EigenStarkSetup *setup,
+                                    const uint8_t *zkin,
+                                    size_t zkin_len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* EIGEN_ZKVM_H */
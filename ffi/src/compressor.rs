@@ -0,0 +1,45 @@
+//! `eigen_compressor12_exec`, the witness of the compressor12 PIL for an aggregated proof.
+use crate::{bytes, call, check_out, prover_config, string};
+use crate::{EigenBuffer, EigenStatus};
+use recursion::compressor12_exec::exec;
+use std::ffi::c_char;
+
+/// Computes the committed polynomials of the compressor12 PIL `pil_file` for the JSON `input`
+/// (the zkin of the proof to compress), with the verifier circuit `wasm_file` and the
+/// `exec_file` of its setup. On success `*cm_pols` is the `.cm` file, ready for
+/// `eigen_stark_prove`. The `<pil_file>.json` written by the setup is loaded, `pil_file` is
+/// compiled to it if it is missing or older.
+#[no_mangle]
+pub unsafe extern "C" fn eigen_compressor12_exec(
+    input: *const u8,
+    input_len: usize,
+    wasm_file: *const c_char,
+    pil_file: *const c_char,
+    exec_file: *const c_char,
+    num_threads: usize,
+    cm_pols: *mut EigenBuffer,
+) -> EigenStatus {
+    call(|| {
+        check_out(cm_pols, "cm_pols")?;
+        let input = bytes(input, input_len, "input")?;
+        let wasm_file = string(wasm_file, "wasm_file")?;
+        let pil_file = string(pil_file, "pil_file")?;
+        let exec_file = string(exec_file, "exec_file")?;
+        let config = prover_config(num_threads)?;
+
+        let dir = tempfile::tempdir()?;
+        let input_file = dir.path().join("input.json");
+        let commit_file = dir.path().join("out.cm");
+        std::fs::write(&input_file, input)?;
+        exec(
+            &input_file.to_string_lossy(),
+            wasm_file,
+            pil_file,
+            exec_file,
+            &commit_file.to_string_lossy(),
+            &config,
+        )?;
+        *cm_pols = std::fs::read(&commit_file)?.into();
+        Ok(EigenStatus::Ok)
+    })
+}
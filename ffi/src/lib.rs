@@ -0,0 +1,164 @@
+//! C ABI of eigen-zkvm, the STARK setup/prove/verify, the groth16 prove/verify and the
+//! compressor12 exec of `eigen-zkit` for services not written in Rust.
+//!
+//! Every function returns an `EigenStatus`. On an error the message is kept per thread and read
+//! by [`eigen_last_error`]. Inputs are `(pointer, length)` byte buffers, or NUL terminated paths
+//! for the big circuit artifacts, outputs are [`EigenBuffer`]s owned by the caller and released
+//! by [`eigen_buffer_free`]. A panic never crosses the ABI, it is returned as
+//! `EIGEN_STATUS_PANIC`. `include/eigen_zkvm.h` is the header cbindgen generates, checked by the tests.
+#![allow(clippy::missing_safety_doc)]
+use anyhow::{anyhow, Result};
+use starky::prover_config::ProverConfig;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+mod compressor;
+mod snark;
+mod stark;
+
+pub use crate::compressor::*;
+pub use crate::snark::*;
+pub use crate::stark::*;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EigenStatus {
+    Ok = 0,
+    /// A null pointer, a path not in UTF-8, or an input which doesn't parse.
+    InvalidArgument = 1,
+    /// The proof was checked and rejected.
+    InvalidProof = 2,
+    /// Setup, proving or verification failed, see `eigen_last_error`.
+    Failed = 3,
+    /// A panic was caught at the boundary.
+    Panic = 4,
+}
+
+/// Bytes allocated by the library, freed by `eigen_buffer_free`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct EigenBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl From<Vec<u8>> for EigenBuffer {
+    fn from(v: Vec<u8>) -> Self {
+        let len = v.len();
+        let data = Box::into_raw(v.into_boxed_slice()) as *mut u8;
+        EigenBuffer { data, len }
+    }
+}
+
+/// The error of an argument the caller got wrong, reported as `EIGEN_STATUS_INVALID_ARGUMENT`.
+#[derive(Debug)]
+pub(crate) struct InvalidArgument(pub String);
+
+impl fmt::Display for InvalidArgument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid argument: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidArgument {}
+
+pub(crate) fn invalid(msg: impl Into<String>) -> anyhow::Error {
+    anyhow!(InvalidArgument(msg.into()))
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(msg: String) {
+    // a message can't hold a NUL
+    let msg = CString::new(msg.replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+}
+
+/// Runs `f` at the boundary, turning its error or panic into a status and the last error.
+pub(crate) fn call<F: FnOnce() -> Result<EigenStatus>>(f: F) -> EigenStatus {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(status)) => status,
+        Ok(Err(e)) => {
+            set_last_error(format!("{e:#}"));
+            match e.downcast_ref::<InvalidArgument>() {
+                Some(_) => EigenStatus::InvalidArgument,
+                None => EigenStatus::Failed,
+            }
+        }
+        Err(p) => {
+            let msg = match (p.downcast_ref::<&str>(), p.downcast_ref::<String>()) {
+                (Some(s), _) => s.to_string(),
+                (_, Some(s)) => s.clone(),
+                _ => "unknown panic".to_string(),
+            };
+            set_last_error(format!("panic: {msg}"));
+            EigenStatus::Panic
+        }
+    }
+}
+
+/// Records why a proof was rejected and returns `EIGEN_STATUS_INVALID_PROOF`.
+pub(crate) fn rejected(msg: &str) -> Result<EigenStatus> {
+    set_last_error(msg.to_string());
+    Ok(EigenStatus::InvalidProof)
+}
+
+/// The input buffer `(data, len)`, `data` may only be null if `len` is 0.
+pub(crate) unsafe fn bytes<'a>(data: *const u8, len: usize, name: &str) -> Result<&'a [u8]> {
+    if data.is_null() {
+        return match len {
+            0 => Ok(&[]),
+            _ => Err(invalid(format!("{name} is null"))),
+        };
+    }
+    Ok(std::slice::from_raw_parts(data, len))
+}
+
+pub(crate) unsafe fn string<'a>(s: *const c_char, name: &str) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(invalid(format!("{name} is null")));
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| invalid(format!("{name} is not UTF-8")))
+}
+
+pub(crate) fn json<T: serde::de::DeserializeOwned>(data: &[u8], name: &str) -> Result<T> {
+    serde_json::from_slice(data).map_err(|e| invalid(format!("{name}: {e}")))
+}
+
+pub(crate) fn check_out<T>(out: *mut T, name: &str) -> Result<()> {
+    match out.is_null() {
+        true => Err(invalid(format!("{name} is null"))),
+        false => Ok(()),
+    }
+}
+
+/// `num_threads` workers in a dedicated pool, 0 for the global pool.
+pub(crate) fn prover_config(num_threads: usize) -> Result<ProverConfig> {
+    match num_threads {
+        0 => Ok(ProverConfig::default()),
+        n => ProverConfig::with_threads(n),
+    }
+}
+
+/// The message of the last failed call on this thread, null if it succeeded. The string is
+/// owned by the library and valid until the next call on the same thread.
+#[no_mangle]
+pub extern "C" fn eigen_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| match &*e.borrow() {
+        Some(msg) => msg.as_ptr(),
+        None => std::ptr::null(),
+    })
+}
+
+/// Frees a buffer returned by the library. Freeing an empty buffer is a no-op.
+#[no_mangle]
+pub unsafe extern "C" fn eigen_buffer_free(buf: EigenBuffer) {
+    if !buf.data.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(buf.data, buf.len)));
+    }
+}
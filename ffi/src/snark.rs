@@ -0,0 +1,93 @@
+//! `eigen_groth16_prove` and `eigen_groth16_verify`. The circuit, wasm and proving key are
+//! passed by path, the input, proof, public input and verifying key as JSON buffers, staged in
+//! a temporary directory for `groth16::api`.
+use crate::{bytes, call, check_out, prover_config, rejected, string};
+use crate::{EigenBuffer, EigenStatus};
+use anyhow::Result;
+use groth16::api::{groth16_prove, groth16_verify, ProofRejected};
+use std::ffi::c_char;
+use std::path::Path;
+
+fn stage(dir: &Path, name: &str, data: &[u8]) -> Result<String> {
+    let path = dir.join(name);
+    std::fs::write(&path, data)?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Proves the circuit `circuit_file` (`.r1cs`) on the JSON `input`, the witness computed by
+/// `wasm_file`, with the proving key `pk_file`, on `curve` `BN128` or `BLS12381`. On success
+/// `*proof` and `*public_input` are the JSON `groth16_prove` writes, in hex if `to_hex`.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn eigen_groth16_prove(
+    curve: *const c_char,
+    circuit_file: *const c_char,
+    wasm_file: *const c_char,
+    pk_file: *const c_char,
+    input: *const u8,
+    input_len: usize,
+    to_hex: bool,
+    num_threads: usize,
+    proof: *mut EigenBuffer,
+    public_input: *mut EigenBuffer,
+) -> EigenStatus {
+    call(|| {
+        check_out(proof, "proof")?;
+        check_out(public_input, "public_input")?;
+        let curve = string(curve, "curve")?;
+        let circuit_file = string(circuit_file, "circuit_file")?;
+        let wasm_file = string(wasm_file, "wasm_file")?;
+        let pk_file = string(pk_file, "pk_file")?;
+        let input = bytes(input, input_len, "input")?;
+        let config = prover_config(num_threads)?;
+
+        let dir = tempfile::tempdir()?;
+        let input_file = stage(dir.path(), "input.json", input)?;
+        let proof_file = stage(dir.path(), "proof.json", &[])?;
+        let public_input_file = stage(dir.path(), "public_input.json", &[])?;
+        groth16_prove(
+            curve,
+            circuit_file,
+            wasm_file,
+            pk_file,
+            &input_file,
+            &public_input_file,
+            &proof_file,
+            to_hex,
+            &config,
+        )?;
+        *proof = std::fs::read(&proof_file)?.into();
+        *public_input = std::fs::read(&public_input_file)?.into();
+        Ok(EigenStatus::Ok)
+    })
+}
+
+/// Verifies the JSON `proof` of the JSON `public_input` with the JSON verifying key `vk`,
+/// `EIGEN_STATUS_OK` if it is valid and `EIGEN_STATUS_INVALID_PROOF` if it is rejected.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn eigen_groth16_verify(
+    curve: *const c_char,
+    vk: *const u8,
+    vk_len: usize,
+    public_input: *const u8,
+    public_input_len: usize,
+    proof: *const u8,
+    proof_len: usize,
+) -> EigenStatus {
+    call(|| {
+        let curve = string(curve, "curve")?;
+        let vk = bytes(vk, vk_len, "vk")?;
+        let public_input = bytes(public_input, public_input_len, "public_input")?;
+        let proof = bytes(proof, proof_len, "proof")?;
+
+        let dir = tempfile::tempdir()?;
+        let vk_file = stage(dir.path(), "vk.json", vk)?;
+        let public_input_file = stage(dir.path(), "public_input.json", public_input)?;
+        let proof_file = stage(dir.path(), "proof.json", proof)?;
+        match groth16_verify(curve, &vk_file, &public_input_file, &proof_file) {
+            Err(e) if e.is::<ProofRejected>() => rejected("groth16 proof rejected"),
+            r => r.map(|_| EigenStatus::Ok),
+        }
+    })
+}
@@ -0,0 +1,228 @@
+//! `eigen_stark_setup`, `eigen_stark_prove` and `eigen_stark_verify`, the steps `stark_prove`
+//! of starky runs in one go, over a setup kept between the calls.
+use crate::{bytes, call, check_out, invalid, json, prover_config, rejected, string};
+use crate::{EigenBuffer, EigenStatus};
+use anyhow::Result;
+use fields::field_gl::{Fr as FGL, MODULUS};
+use starky::merklehash::MerkleTreeGL;
+use starky::merklehash_bls12381::MerkleTreeBLS12381;
+use starky::merklehash_bn128::MerkleTreeBN128;
+use starky::polsarray::{PolKind, PolsRows};
+use starky::prover_config::ProverConfig;
+use starky::stark_gen::StarkProof;
+use starky::stark_setup::StarkSetup;
+use starky::stark_verify::stark_verify;
+use starky::traits::{MerkleTree, Transcript};
+use starky::transcript::TranscriptGL;
+use starky::transcript_bls12381::TranscriptBLS128;
+use starky::transcript_bn128::TranscriptBN128;
+use starky::types::{StarkStruct, PIL};
+use std::ffi::c_char;
+
+/// A `.const`/`.cm` file passed in memory, the row-major little-endian words of `PolsMmap`.
+struct Pols {
+    n_pols: usize,
+    n: usize,
+    words: Vec<u64>,
+}
+
+impl Pols {
+    fn new(data: &[u8], pil: &PIL, kind: PolKind, name: &str) -> Result<Self> {
+        let (n_pols, type_) = match kind {
+            PolKind::Commit => (pil.nCommitments, "cmP"),
+            PolKind::Constant => (pil.nConstants, "constP"),
+        };
+        let n = pil.references.values().find(|r| r.type_ == type_).map_or(0, |r| r.polDeg);
+        if data.len() != n_pols * n * 8 {
+            return Err(invalid(format!(
+                "{name} has {} bytes, expected {n_pols} pols x {n} rows x 8 bytes",
+                data.len()
+            )));
+        }
+        let words: Vec<u64> =
+            data.chunks_exact(8).map(|w| u64::from_le_bytes(w.try_into().unwrap())).collect();
+        if let Some(pos) = words.iter().position(|v| *v >= MODULUS.0[0]) {
+            return Err(invalid(format!(
+                "{name}: row {}, pol {}, {} is not less than the modulus",
+                pos / n_pols,
+                pos % n_pols,
+                words[pos]
+            )));
+        }
+        Ok(Pols { n_pols, n, words })
+    }
+}
+
+impl PolsRows for Pols {
+    fn n_pols(&self) -> usize {
+        self.n_pols
+    }
+
+    fn n(&self) -> usize {
+        self.n
+    }
+
+    #[inline(always)]
+    fn get_row(&self, i: usize, j: usize) -> FGL {
+        FGL::from(self.words[i * self.n_pols + j])
+    }
+}
+
+enum Setup {
+    GL(StarkSetup<MerkleTreeGL>),
+    BN128(StarkSetup<MerkleTreeBN128>),
+    BLS12381(StarkSetup<MerkleTreeBLS12381>),
+}
+
+/// The setup of a PIL, its constant tree and `StarkInfo`, by `eigen_stark_setup`.
+pub struct EigenStarkSetup {
+    pil: PIL,
+    stark_struct: StarkStruct,
+    const_pols: Pols,
+    config: ProverConfig,
+    setup: Setup,
+}
+
+fn setup(
+    stark_struct: &[u8],
+    pil: &[u8],
+    const_pols: &[u8],
+    num_threads: usize,
+) -> Result<EigenStarkSetup> {
+    let stark_struct: StarkStruct = json(stark_struct, "stark_struct")?;
+    let mut pil: PIL = json(pil, "pil")?;
+    let const_pols = Pols::new(const_pols, &pil, PolKind::Constant, "const_pols")?;
+    let config = prover_config(num_threads)?;
+    let setup = match stark_struct.verificationHashType.as_str() {
+        "GL" => Setup::GL(StarkSetup::new(&const_pols, &mut pil, &stark_struct, None, &config)?),
+        "BN128" => {
+            Setup::BN128(StarkSetup::new(&const_pols, &mut pil, &stark_struct, None, &config)?)
+        }
+        "BLS12381" => {
+            Setup::BLS12381(StarkSetup::new(&const_pols, &mut pil, &stark_struct, None, &config)?)
+        }
+        h => return Err(invalid(format!("Invalid hashtype {h}"))),
+    };
+    Ok(EigenStarkSetup { pil, stark_struct, const_pols, config, setup })
+}
+
+fn prove<M: MerkleTree + Default, T: Transcript>(
+    s: &EigenStarkSetup,
+    setup: &StarkSetup<M>,
+    cm_pols: &Pols,
+    prover_addr: &str,
+) -> Result<Vec<u8>> {
+    let proof = s.config.install(|| {
+        StarkProof::<M>::stark_gen::<T>(
+            cm_pols,
+            &s.const_pols,
+            &setup.const_tree,
+            &setup.starkinfo,
+            &setup.program,
+            &s.pil,
+            &s.stark_struct,
+            prover_addr,
+        )
+    })?;
+    Ok(serde_json::to_vec(&proof)?)
+}
+
+fn verify<M: MerkleTree + Default, T: Transcript>(
+    s: &EigenStarkSetup,
+    setup: &StarkSetup<M>,
+    zkin: &[u8],
+) -> Result<bool> {
+    let proof: StarkProof<M> = json(zkin, "zkin")?;
+    stark_verify::<M, T>(
+        &proof,
+        &setup.const_root,
+        &setup.starkinfo,
+        &s.stark_struct,
+        &setup.program,
+    )
+}
+
+/// Sets up the PIL `pil` (the `.pil.json`) under `stark_struct`, both JSON, with the constant
+/// polynomials `const_pols` in the layout of a `.const` file. `num_threads` sizes the prover
+/// pool of the setup, 0 for one worker per CPU. On success `*out` owns the setup, freed by
+/// `eigen_stark_setup_free`. A setup may be shared by threads proving and verifying at once.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn eigen_stark_setup(
+    stark_struct: *const u8,
+    stark_struct_len: usize,
+    pil: *const u8,
+    pil_len: usize,
+    const_pols: *const u8,
+    const_pols_len: usize,
+    num_threads: usize,
+    out: *mut *mut EigenStarkSetup,
+) -> EigenStatus {
+    call(|| {
+        check_out(out, "out")?;
+        let s = setup(
+            bytes(stark_struct, stark_struct_len, "stark_struct")?,
+            bytes(pil, pil_len, "pil")?,
+            bytes(const_pols, const_pols_len, "const_pols")?,
+            num_threads,
+        )?;
+        *out = Box::into_raw(Box::new(s));
+        Ok(EigenStatus::Ok)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn eigen_stark_setup_free(setup: *mut EigenStarkSetup) {
+    if !setup.is_null() {
+        drop(Box::from_raw(setup));
+    }
+}
+
+/// Proves the committed polynomials `cm_pols`, in the layout of a `.cm` file. On success
+/// `*zkin` is the JSON proof `stark_prove` writes to its zkin file.
+#[no_mangle]
+pub unsafe extern "C" fn eigen_stark_prove(
+    setup: *const EigenStarkSetup,
+    cm_pols: *const u8,
+    cm_pols_len: usize,
+    prover_addr: *const c_char,
+    zkin: *mut EigenBuffer,
+) -> EigenStatus {
+    call(|| {
+        check_out(zkin, "zkin")?;
+        let s = setup.as_ref().ok_or_else(|| invalid("setup is null"))?;
+        let cm_pols =
+            Pols::new(bytes(cm_pols, cm_pols_len, "cm_pols")?, &s.pil, PolKind::Commit, "cm_pols")?;
+        let prover_addr = string(prover_addr, "prover_addr")?;
+        let proof = match &s.setup {
+            Setup::GL(t) => prove::<_, TranscriptGL>(s, t, &cm_pols, prover_addr)?,
+            Setup::BN128(t) => prove::<_, TranscriptBN128>(s, t, &cm_pols, prover_addr)?,
+            Setup::BLS12381(t) => prove::<_, TranscriptBLS128>(s, t, &cm_pols, prover_addr)?,
+        };
+        *zkin = proof.into();
+        Ok(EigenStatus::Ok)
+    })
+}
+
+/// Verifies the JSON proof `zkin` of `eigen_stark_prove`, `EIGEN_STATUS_OK` if it is valid and
+/// `EIGEN_STATUS_INVALID_PROOF` if it is rejected.
+#[no_mangle]
+pub unsafe extern "C" fn eigen_stark_verify(
+    setup: *const EigenStarkSetup,
+    zkin: *const u8,
+    zkin_len: usize,
+) -> EigenStatus {
+    call(|| {
+        let s = setup.as_ref().ok_or_else(|| invalid("setup is null"))?;
+        let zkin = bytes(zkin, zkin_len, "zkin")?;
+        let valid = match &s.setup {
+            Setup::GL(t) => verify::<_, TranscriptGL>(s, t, zkin)?,
+            Setup::BN128(t) => verify::<_, TranscriptBN128>(s, t, zkin)?,
+            Setup::BLS12381(t) => verify::<_, TranscriptBLS128>(s, t, zkin)?,
+        };
+        match valid {
+            true => Ok(EigenStatus::Ok),
+            false => rejected("stark proof rejected"),
+        }
+    })
+}
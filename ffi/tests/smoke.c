@@ -0,0 +1,98 @@
+/*
+ * Smoke test of the C ABI: sets up, proves and verifies the plookup example of starky, then
+ * checks the errors of bad arguments and of a tampered proof.
+ *
+ *   smoke <starky/data>
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "eigen_zkvm.h"
+
+#define CHECK(cond)                                                                    \
+    do {                                                                               \
+        if (!(cond)) {                                                                 \
+            const char *err = eigen_last_error();                                      \
+            fprintf(stderr, "%s:%d: %s failed, last error: %s\n", __FILE__, __LINE__,  \
+                    #cond, err ? err : "(none)");                                      \
+            exit(1);                                                                   \
+        }                                                                              \
+    } while (0)
+
+static uint8_t *read_file(const char *dir, const char *name, size_t *len) {
+    char path[4096];
+    snprintf(path, sizeof(path), "%s/%s", dir, name);
+    FILE *f = fopen(path, "rb");
+    if (!f) {
+        fprintf(stderr, "can't open %s\n", path);
+        exit(1);
+    }
+    fseek(f, 0, SEEK_END);
+    *len = (size_t)ftell(f);
+    fseek(f, 0, SEEK_SET);
+    uint8_t *data = malloc(*len);
+    if (fread(data, 1, *len, f) != *len) {
+        fprintf(stderr, "can't read %s\n", path);
+        exit(1);
+    }
+    fclose(f);
+    return data;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 2) {
+        fprintf(stderr, "usage: %s <starky/data>\n", argv[0]);
+        return 2;
+    }
+    const char *data = argv[1];
+    size_t stark_struct_len, pil_len, const_len, cm_len;
+    uint8_t *stark_struct = read_file(data, "starkStruct.json.gl", &stark_struct_len);
+    uint8_t *pil = read_file(data, "plookup.pil.json.gl", &pil_len);
+    uint8_t *consts = read_file(data, "plookup.const.gl", &const_len);
+    uint8_t *cm = read_file(data, "plookup.cm.gl", &cm_len);
+
+    EigenStarkSetup *setup = NULL;
+
+    /* bad arguments */
+    CHECK(eigen_stark_setup(stark_struct, stark_struct_len, (const uint8_t *)"{", 1, consts,
+                            const_len, 0, &setup) == EIGEN_STATUS_INVALID_ARGUMENT);
+    CHECK(eigen_last_error() != NULL && strstr(eigen_last_error(), "pil") != NULL);
+    CHECK(eigen_stark_setup(stark_struct, stark_struct_len, pil, pil_len, consts, const_len - 8,
+                            0, &setup) == EIGEN_STATUS_INVALID_ARGUMENT);
+    CHECK(eigen_stark_setup(stark_struct, stark_struct_len, pil, pil_len, consts, const_len, 0,
+                            NULL) == EIGEN_STATUS_INVALID_ARGUMENT);
+    CHECK(eigen_stark_verify(NULL, (const uint8_t *)"{}", 2) == EIGEN_STATUS_INVALID_ARGUMENT);
+    CHECK(eigen_groth16_verify("BN254", (const uint8_t *)"{}", 2, (const uint8_t *)"[]", 2,
+                               (const uint8_t *)"{}", 2) == EIGEN_STATUS_FAILED);
+    CHECK(strstr(eigen_last_error(), "BN254") != NULL);
+
+    /* setup, prove and verify */
+    CHECK(eigen_stark_setup(stark_struct, stark_struct_len, pil, pil_len, consts, const_len, 2,
+                            &setup) == EIGEN_STATUS_OK);
+    CHECK(eigen_last_error() == NULL);
+    EigenBuffer zkin = {0};
+    CHECK(eigen_stark_prove(setup, cm, cm_len, "273030697313060285579891744179749754319274977764",
+                            &zkin) == EIGEN_STATUS_OK);
+    CHECK(eigen_stark_verify(setup, zkin.data, zkin.len) == EIGEN_STATUS_OK);
+
+    /* an evaluation changed, its last digit moved by one */
+    char *evals = strstr((char *)zkin.data, "\"evals\":[[\"");
+    CHECK(evals != NULL);
+    char *digit = evals + strlen("\"evals\":[[\"");
+    while (digit[1] != '"') {
+        digit++;
+    }
+    *digit = *digit == '0' ? '1' : *digit - 1;
+    CHECK(eigen_stark_verify(setup, zkin.data, zkin.len) == EIGEN_STATUS_INVALID_PROOF);
+    CHECK(eigen_last_error() != NULL);
+
+    eigen_buffer_free(zkin);
+    eigen_stark_setup_free(setup);
+    free(stark_struct);
+    free(pil);
+    free(consts);
+    free(cm);
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,45 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The checked-in header is the one the build generates from the crate.
+#[test]
+fn test_header_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/eigen_zkvm.h"));
+    let checked_in = include_str!("../include/eigen_zkvm.h");
+    assert!(
+        generated == checked_in,
+        "include/eigen_zkvm.h is stale, run `cbindgen --config cbindgen.toml --output \
+         include/eigen_zkvm.h` in ffi"
+    );
+}
+
+/// Builds `tests/smoke.c` against the cdylib and the checked-in header, and runs it.
+#[test]
+fn test_c_smoke() {
+    let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // target/<profile>/deps/<test> next to target/<profile>/libeigen_zkvm.{so,dylib}
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap().parent().unwrap();
+    let smoke = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("eigen_zkvm_smoke");
+
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(cc)
+        .arg(crate_dir.join("tests/smoke.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg("-L")
+        .arg(lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-leigen_zkvm")
+        .arg("-o")
+        .arg(&smoke)
+        .status()
+        .unwrap();
+    assert!(status.success(), "failed to build smoke.c");
+
+    let output = Command::new(&smoke).arg(crate_dir.join("../starky/data")).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}{}", stdout, String::from_utf8_lossy(&output.stderr));
+    assert_eq!(stdout.trim(), "ok");
+}
@@ -0,0 +1,199 @@
+//! The traits of `ff_ce` that `field_gl` implements, for the `no_std` builds where
+//! franklin-crypto is not available. They match `ff_ce` 0.12 except for the `Rand` bounds and
+//! the `std::io` encodings of `PrimeFieldRepr`.
+use alloc::string::String;
+use core::fmt;
+use core::hash;
+
+pub trait Field:
+    Sized + Eq + Copy + Clone + Send + Sync + fmt::Debug + fmt::Display + 'static + hash::Hash + Default
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn is_zero(&self) -> bool;
+    fn square(&mut self);
+    fn double(&mut self);
+    fn negate(&mut self);
+    fn add_assign(&mut self, other: &Self);
+    fn sub_assign(&mut self, other: &Self);
+    fn mul_assign(&mut self, other: &Self);
+    fn inverse(&self) -> Option<Self>;
+    fn frobenius_map(&mut self, power: usize);
+
+    /// Exponentiates by the little-endian limbs of `exp`.
+    fn pow<S: AsRef<[u64]>>(&self, exp: S) -> Self {
+        let mut res = Self::one();
+        let mut found_one = false;
+        for i in BitIterator::new(exp) {
+            if found_one {
+                res.square();
+            } else {
+                found_one = i;
+            }
+            if i {
+                res.mul_assign(self);
+            }
+        }
+        res
+    }
+}
+
+pub trait SqrtField: Field {
+    fn legendre(&self) -> LegendreSymbol;
+    fn sqrt(&self) -> Option<Self>;
+}
+
+pub trait PrimeFieldRepr:
+    Sized
+    + Copy
+    + Clone
+    + Eq
+    + Ord
+    + Send
+    + Sync
+    + Default
+    + fmt::Debug
+    + fmt::Display
+    + 'static
+    + AsRef<[u64]>
+    + AsMut<[u64]>
+    + From<u64>
+    + hash::Hash
+{
+    fn sub_noborrow(&mut self, other: &Self);
+    fn add_nocarry(&mut self, other: &Self);
+    fn num_bits(&self) -> u32;
+    fn is_zero(&self) -> bool;
+    fn is_odd(&self) -> bool;
+    fn is_even(&self) -> bool;
+    fn div2(&mut self);
+    fn shr(&mut self, amt: u32);
+    fn mul2(&mut self);
+    fn shl(&mut self, amt: u32);
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LegendreSymbol {
+    Zero = 0,
+    QuadraticResidue = 1,
+    QuadraticNonResidue = -1,
+}
+
+#[derive(Debug)]
+pub enum PrimeFieldDecodingError {
+    NotInField(String),
+}
+
+impl fmt::Display for PrimeFieldDecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PrimeFieldDecodingError::NotInField(repr) => {
+                write!(f, "not an element of the field: {repr}")
+            }
+        }
+    }
+}
+
+pub trait PrimeField: Field {
+    type Repr: PrimeFieldRepr + From<Self>;
+
+    /// Parses a decimal string without leading zeros, `None` on anything else.
+    fn from_str(s: &str) -> Option<Self> {
+        if s.is_empty() {
+            return None;
+        }
+        if s == "0" {
+            return Some(Self::zero());
+        }
+        let mut res = Self::zero();
+        let ten = Self::from_repr(Self::Repr::from(10)).unwrap();
+        let mut first_digit = true;
+        for c in s.chars() {
+            match c.to_digit(10) {
+                Some(c) => {
+                    if first_digit {
+                        if c == 0 {
+                            return None;
+                        }
+                        first_digit = false;
+                    }
+                    res.mul_assign(&ten);
+                    res.add_assign(&Self::from_repr(Self::Repr::from(u64::from(c))).unwrap());
+                }
+                None => return None,
+            }
+        }
+        Some(res)
+    }
+
+    fn from_repr(repr: Self::Repr) -> Result<Self, PrimeFieldDecodingError>;
+    fn from_raw_repr(repr: Self::Repr) -> Result<Self, PrimeFieldDecodingError>;
+    #[allow(clippy::wrong_self_convention)]
+    fn into_repr(&self) -> Self::Repr;
+    #[allow(clippy::wrong_self_convention)]
+    fn into_raw_repr(&self) -> Self::Repr;
+    fn char() -> Self::Repr;
+    const NUM_BITS: u32;
+    const CAPACITY: u32;
+    fn multiplicative_generator() -> Self;
+    const S: u32;
+    fn root_of_unity() -> Self;
+}
+
+pub trait ScalarEngine: Sized + 'static + Clone + Copy + Send + Sync + fmt::Debug {
+    type Fr: PrimeField + SqrtField;
+}
+
+/// The bits of little-endian limbs, from the most significant one.
+#[derive(Debug)]
+pub struct BitIterator<E> {
+    t: E,
+    n: usize,
+}
+
+impl<E: AsRef<[u64]>> BitIterator<E> {
+    pub fn new(t: E) -> Self {
+        let n = t.as_ref().len() * 64;
+        BitIterator { t, n }
+    }
+}
+
+impl<E: AsRef<[u64]>> Iterator for BitIterator<E> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        if self.n == 0 {
+            None
+        } else {
+            self.n -= 1;
+            let part = self.n / 64;
+            let bit = self.n - (64 * part);
+            Some(self.t.as_ref()[part] & (1 << bit) > 0)
+        }
+    }
+}
+
+/// Calculates a + b + carry, returning the sum and modifying the carry value.
+#[inline(always)]
+pub fn adc(a: u64, b: u64, carry: &mut u64) -> u64 {
+    let tmp = u128::from(a) + u128::from(b) + u128::from(*carry);
+    *carry = (tmp >> 64) as u64;
+    tmp as u64
+}
+
+/// Calculates a - b - borrow, returning the result and modifying the borrow value.
+#[inline(always)]
+pub fn sbb(a: u64, b: u64, borrow: &mut u64) -> u64 {
+    let tmp = (1u128 << 64) + u128::from(a) - u128::from(b) - u128::from(*borrow);
+    *borrow = if tmp >> 64 == 0 { 1 } else { 0 };
+    tmp as u64
+}
+
+/// Calculates a + (b * c) + carry, returning the least significant digit and setting carry to
+/// the most significant digit.
+#[inline(always)]
+pub fn mac_with_carry(a: u64, b: u64, c: u64, carry: &mut u64) -> u64 {
+    let tmp = u128::from(a) + u128::from(b) * u128::from(c) + u128::from(*carry);
+    *carry = (tmp >> 64) as u64;
+    tmp as u64
+}
@@ -1,11 +1,12 @@
 #![allow(unused_imports)]
 
 use crate::ff::*;
+use core::cmp::Ordering;
 use core::ops::{Add, Div, Mul, Neg, Sub};
 use serde::{Deserialize, Serialize};
-use std::cmp::Ordering;
 
 #[derive(Eq, Serialize, Deserialize)]
+#[repr(transparent)]
 pub struct Fr(pub FrRepr);
 
 /// This is the modulus m of the prime field
@@ -27,13 +28,14 @@ const S: u32 = 32u32;
 pub const ROOT_OF_UNITY: FrRepr = FrRepr([959634606461954525u64]);
 
 #[derive(Eq, Serialize, Deserialize)]
+#[repr(transparent)]
 pub struct FrRepr(pub [u64; 1usize]);
 
 #[automatically_derived]
 impl ::core::marker::Copy for FrRepr {}
 
 #[automatically_derived]
-impl std::clone::Clone for FrRepr {
+impl core::clone::Clone for FrRepr {
     #[inline]
     fn clone(&self) -> FrRepr {
         //let _: std::clone::AssertParamIsClone<[u64; 2usize]>;
@@ -68,8 +70,8 @@ impl ::core::default::Default for FrRepr {
     }
 }
 
-impl ::std::fmt::Debug for FrRepr {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+impl ::core::fmt::Debug for FrRepr {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         f.write_fmt(format_args!("0x"))?;
         for i in self.0.iter().rev() {
             f.write_fmt(format_args!("{0:016x}", *i))?;
@@ -78,6 +80,7 @@ impl ::std::fmt::Debug for FrRepr {
     }
 }
 
+#[cfg(feature = "std")]
 impl ::rand::Rand for FrRepr {
     #[inline(always)]
     fn rand<R: ::rand::Rng>(rng: &mut R) -> Self {
@@ -85,8 +88,8 @@ impl ::rand::Rand for FrRepr {
     }
 }
 
-impl ::std::fmt::Display for FrRepr {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+impl ::core::fmt::Display for FrRepr {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         f.write_fmt(format_args!("0x"))?;
         for i in self.0.iter().rev() {
             f.write_fmt(format_args!("{0:016x}", *i))?;
@@ -95,8 +98,8 @@ impl ::std::fmt::Display for FrRepr {
     }
 }
 
-impl std::hash::Hash for FrRepr {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+impl core::hash::Hash for FrRepr {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         for limb in self.0.iter() {
             limb.hash(state);
         }
@@ -120,7 +123,7 @@ impl AsMut<[u64]> for FrRepr {
 impl From<u64> for FrRepr {
     #[inline(always)]
     fn from(val: u64) -> FrRepr {
-        use std::default::Default;
+        use core::default::Default;
         let mut repr = Self::default();
         repr.0[0] = val;
         repr
@@ -135,13 +138,13 @@ impl Ord for FrRepr {
                 return a.cmp(b);
             }
         }
-        ::std::cmp::Ordering::Equal
+        ::core::cmp::Ordering::Equal
     }
 }
 
 impl PartialOrd for FrRepr {
     #[inline(always)]
-    fn partial_cmp(&self, other: &FrRepr) -> Option<::std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &FrRepr) -> Option<::core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
@@ -168,7 +171,7 @@ impl crate::ff::PrimeFieldRepr for FrRepr {
         while n >= 64 {
             let mut t = 0;
             for i in self.0.iter_mut().rev() {
-                ::std::mem::swap(&mut t, i);
+                ::core::mem::swap(&mut t, i);
             }
             n -= 64;
         }
@@ -214,7 +217,7 @@ impl crate::ff::PrimeFieldRepr for FrRepr {
         while n >= 64 {
             let mut t = 0;
             for i in &mut self.0 {
-                ::std::mem::swap(&mut t, i);
+                ::core::mem::swap(&mut t, i);
             }
             n -= 64;
         }
@@ -260,23 +263,23 @@ impl crate::ff::PrimeFieldRepr for FrRepr {
     }
 }
 
-impl ::std::marker::Copy for Fr {}
+impl ::core::marker::Copy for Fr {}
 
-impl ::std::clone::Clone for Fr {
+impl ::core::clone::Clone for Fr {
     fn clone(&self) -> Fr {
         *self
     }
 }
 
-impl ::std::cmp::PartialEq for Fr {
+impl ::core::cmp::PartialEq for Fr {
     fn eq(&self, other: &Fr) -> bool {
         self.0 == other.0
     }
 }
 
 //impl ::std::cmp::Eq for Fr {}
-impl ::std::fmt::Debug for Fr {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+impl ::core::fmt::Debug for Fr {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         f.write_fmt(format_args!("{0}({1:?})", "Fr", self.into_repr()))
     }
 }
@@ -284,24 +287,25 @@ impl ::std::fmt::Debug for Fr {
 /// Elements are ordered lexicographically.
 impl Ord for Fr {
     #[inline(always)]
-    fn cmp(&self, other: &Fr) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Fr) -> core::cmp::Ordering {
         self.into_repr().cmp(&other.into_repr())
     }
 }
 
 impl PartialOrd for Fr {
     #[inline(always)]
-    fn partial_cmp(&self, other: &Fr) -> Option<::std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Fr) -> Option<::core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl ::std::fmt::Display for Fr {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+impl ::core::fmt::Display for Fr {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         f.write_fmt(format_args!("{0}({1})", "Fr", self.into_repr()))
     }
 }
 
+#[cfg(feature = "std")]
 impl ::rand::Rand for Fr {
     /// Computes a uniformly random element using rejection sampling.
     fn rand<R: ::rand::Rng>(rng: &mut R) -> Self {
@@ -329,7 +333,7 @@ impl crate::ff::PrimeField for Fr {
             Ok(r)
         } else {
             Err(crate::ff::PrimeFieldDecodingError::NotInField({
-                let res = std::fmt::format(format_args!("{0}", r.0));
+                let res = alloc::format!("{0}", r.0);
                 res
             }))
         }
@@ -340,7 +344,7 @@ impl crate::ff::PrimeField for Fr {
             Ok(r)
         } else {
             Err(crate::ff::PrimeFieldDecodingError::NotInField({
-                let res = std::fmt::format(format_args!("{0}", r.0));
+                let res = alloc::format!("{0}", r.0);
                 res
             }))
         }
@@ -479,14 +483,14 @@ impl Fr {
     }
 }
 
-impl std::default::Default for Fr {
+impl core::default::Default for Fr {
     fn default() -> Self {
         Self::zero()
     }
 }
 
-impl std::hash::Hash for Fr {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+impl core::hash::Hash for Fr {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         for limb in self.0.as_ref().iter() {
             limb.hash(state);
         }
@@ -1,7 +1,12 @@
 #![cfg_attr(feature = "avx512", feature(stdarch_x86_avx512))]
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+#[cfg(feature = "std")]
 extern crate rand;
 
 pub mod arch;
+#[cfg(not(feature = "std"))]
+pub mod ff;
 pub mod field_gl;
 #[cfg(test)]
 mod field_gl_test;
@@ -9,5 +14,7 @@ pub mod packable;
 pub mod packed;
 
 pub use crate::ff::*;
+#[cfg(feature = "std")]
 pub use bellman_ce::pairing::ff;
+#[cfg(feature = "std")]
 pub use franklin_crypto::bellman as bellman_ce;
@@ -32,6 +32,7 @@ use group::WnafGroup;
 use num_traits::Zero;
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use pairing::{Engine, MultiMillerLoop};
+use prover_config::ProverConfig;
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use rand_new as rand;
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
@@ -139,7 +140,10 @@ pub fn groth16_setup_inplace(curve_type: &str, circuit_file: &str) -> Result<Set
     Ok(result)
 }
 
-#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+/// Runs the witness calculation and the witness conversion in the thread pool of `config`.
+///
+/// The thread count of `config` does not bound the proof itself: the multiexps and FFTs of the
+/// groth16 backends build their own workers, one thread per CPU.
 #[allow(clippy::too_many_arguments)]
 pub fn groth16_prove(
     curve_type: &str,
@@ -150,11 +154,38 @@ pub fn groth16_prove(
     public_input_file: &str,
     proof_file: &str,
     to_hex: bool,
+    config: &ProverConfig,
+) -> Result<()> {
+    config.install(|| {
+        groth16_prove_inner(
+            curve_type,
+            circuit_file,
+            wtns_file,
+            pk_file,
+            input_file,
+            public_input_file,
+            proof_file,
+            to_hex,
+        )
+    })
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+#[allow(clippy::too_many_arguments)]
+fn groth16_prove_inner(
+    curve_type: &str,
+    circuit_file: &str,
+    wtns_file: &str,
+    pk_file: &str,
+    input_file: &str,
+    public_input_file: &str,
+    proof_file: &str,
+    to_hex: bool,
 ) -> Result<()> {
     let mut rng = rand::thread_rng();
 
     let mut wtns = WitnessCalculator::from_file(wtns_file)?;
-    let inputs = load_input_for_witness(input_file);
+    let inputs = load_input_for_witness(input_file)?;
     let w = wtns.calculate_witness(inputs, false)?;
     match curve_type {
         "BN128" => {
@@ -216,7 +247,7 @@ pub fn groth16_prove_inplace<E: Engine + crate::json_utils::Parser>(
 ) -> Result<()> {
     let mut rng = rand::thread_rng();
     let mut wtns = WitnessCalculator::from_file(wtns_file)?;
-    let inputs = load_input_for_witness(input_file);
+    let inputs = load_input_for_witness(input_file)?;
     let w = wtns.calculate_witness(inputs, false)?;
     let circuit1 = create_circuit_add_witness(circuit, w);
     let proof = Groth16::prove(&pk, circuit1.clone(), &mut rng)?;
@@ -229,7 +260,7 @@ pub fn groth16_prove_inplace<E: Engine + crate::json_utils::Parser>(
 
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 #[allow(clippy::too_many_arguments)]
-pub fn groth16_prove(
+fn groth16_prove_inner(
     curve_type: &str,
     circuit_file: &str,
     wtns_file: &str,
@@ -242,7 +273,7 @@ pub fn groth16_prove(
     let mut rng = rand::thread_rng();
 
     let mut wtns = WitnessCalculator::from_file(wtns_file)?;
-    let inputs = load_input_for_witness(input_file);
+    let inputs = load_input_for_witness(input_file)?;
     let w = wtns.calculate_witness(inputs, false)?;
     match curve_type {
         "BLS12381" => {
@@ -287,7 +318,7 @@ pub fn groth16_prove_inplace(
 ) -> Result<()> {
     let mut rng = rand::thread_rng();
     let mut wtns = WitnessCalculator::from_file(wtns_file)?;
-    let inputs = load_input_for_witness(input_file);
+    let inputs = load_input_for_witness(input_file)?;
     let w = wtns.calculate_witness(inputs, false)?;
     let circuit1 = create_circuit_add_witness(circuit, w);
     let proof = Groth16::prove(&pk, circuit1.clone(), &mut rng)?;
@@ -298,6 +329,11 @@ pub fn groth16_prove_inplace(
     Ok(())
 }
 
+/// The error of `groth16_verify` for a proof the verifying key rejects.
+#[derive(Debug, thiserror::Error)]
+#[error("verify failed")]
+pub struct ProofRejected;
+
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 pub fn groth16_verify(
     curve_type: &str,
@@ -315,7 +351,7 @@ pub fn groth16_verify(
                 Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(&vk, &inputs, &proof);
 
             if verification_result.is_err() || !verification_result.unwrap() {
-                bail!("verify failed");
+                return Err(ProofRejected.into());
             }
         }
 
@@ -328,7 +364,7 @@ pub fn groth16_verify(
                 Groth16::<_, CircomCircuit<Bls12>>::verify_with_processed_vk(&vk, &inputs, &proof);
 
             if verification_result.is_err() || !verification_result.unwrap() {
-                bail!("verify failed");
+                return Err(ProofRejected.into());
             }
         }
 
@@ -352,7 +388,7 @@ pub fn groth16_verify_inplace<E: Engine + crate::json_utils::Parser>(
         Groth16::<_, CircomCircuit<E>>::verify_with_processed_vk(&vk, &inputs[..], &proof);
 
     if verification_result.is_err() || !verification_result.unwrap() {
-        bail!("verify failed");
+        return Err(ProofRejected.into());
     }
 
     Ok(())
@@ -374,7 +410,7 @@ pub fn groth16_verify(
                 Groth16::<_, CircomCircuit<Scalar>>::verify_with_processed_vk(&vk, &inputs, &proof);
 
             if verification_result.is_err() || !verification_result.unwrap() {
-                bail!("verify failed");
+                return Err(ProofRejected.into());
             }
         }
 
@@ -149,7 +149,7 @@ mod tests {
         //2. Prove
         let t1 = std::time::Instant::now();
         let mut wtns = WitnessCalculator::from_file(WASM_FILE)?;
-        let inputs = load_input_for_witness(INPUT_FILE);
+        let inputs = load_input_for_witness(INPUT_FILE)?;
         let w = wtns.calculate_witness(inputs, false).unwrap();
         let w = w
             .iter()
@@ -202,7 +202,7 @@ mod tests {
         let t1 = std::time::Instant::now();
         let mut rng = rand::thread_rng();
         let mut wtns = WitnessCalculator::from_file(WASM_FILE_BLS12)?;
-        let inputs = load_input_for_witness(INPUT_FILE);
+        let inputs = load_input_for_witness(INPUT_FILE)?;
         let w = wtns.calculate_witness(inputs, false).unwrap();
         let circuit1: CircomCircuit<Bls12> = create_circuit_add_witness::<Bls12>(circuit, w);
         let proof = Groth16::prove(&pk, circuit1.clone(), &mut rng)?;
@@ -297,7 +297,7 @@ mod tests {
         //2. Prove
         let t1 = std::time::Instant::now();
         let mut wtns = WitnessCalculator::from_file(WASM_FILE_BLS12)?;
-        let inputs = load_input_for_witness(INPUT_FILE);
+        let inputs = load_input_for_witness(INPUT_FILE)?;
         let w = wtns.calculate_witness(inputs, false).unwrap();
         let circuit1: CircomCircuit<Scalar> = create_circuit_add_witness(circuit, w);
         let proof = Groth16::prove(&pk, circuit1.clone(), &mut OsRng)?;
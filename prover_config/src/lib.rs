@@ -0,0 +1,132 @@
+//! Thread pool and chunking shared by the provers of the workspace, so a crate only needing
+//! them doesn't depend on `starky`.
+use anyhow::{bail, Result};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::cell::Cell;
+use std::sync::Arc;
+
+pub const MIN_OPS_PER_THREAD: usize = 1 << 12;
+pub const MAX_OPS_PER_THREAD: usize = 1 << 18;
+
+thread_local! {
+    /// (min, max) ops per thread, overridden on the workers of a `ProverConfig` pool.
+    static OPS_PER_THREAD: Cell<(usize, usize)> =
+        const { Cell::new((MIN_OPS_PER_THREAD, MAX_OPS_PER_THREAD)) };
+}
+
+/// Number of workers of the rayon pool the caller runs in, minus one.
+pub fn get_max_workers() -> usize {
+    std::cmp::max(rayon::current_num_threads(), 2) - 1
+}
+
+pub fn min_ops_per_thread() -> usize {
+    OPS_PER_THREAD.with(|c| c.get().0)
+}
+
+pub fn max_ops_per_thread() -> usize {
+    OPS_PER_THREAD.with(|c| c.get().1)
+}
+
+/// Threads and chunking used by a prover.
+///
+/// The default config runs on the global rayon pool. A config built by [`ProverConfig::new`]
+/// owns a dedicated pool, so several provers in one process don't contend for the same
+/// workers, and the chunk sizes of the provers are bounded by its
+/// `min_ops_per_thread`/`max_ops_per_thread`. Clones share the pool.
+#[derive(Clone, Debug)]
+pub struct ProverConfig {
+    pub min_ops_per_thread: usize,
+    pub max_ops_per_thread: usize,
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        ProverConfig {
+            min_ops_per_thread: MIN_OPS_PER_THREAD,
+            max_ops_per_thread: MAX_OPS_PER_THREAD,
+            pool: None,
+        }
+    }
+}
+
+impl ProverConfig {
+    /// Builds a dedicated pool of `num_threads` workers, 0 meaning one per CPU.
+    pub fn new(
+        num_threads: usize,
+        min_ops_per_thread: usize,
+        max_ops_per_thread: usize,
+    ) -> Result<Self> {
+        if min_ops_per_thread == 0 || min_ops_per_thread > max_ops_per_thread {
+            bail!(
+                "Invalid ops per thread, min: {}, max: {}",
+                min_ops_per_thread,
+                max_ops_per_thread
+            );
+        }
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|i| format!("prover-{i}"))
+            .start_handler(move |_| {
+                OPS_PER_THREAD.with(|c| c.set((min_ops_per_thread, max_ops_per_thread)))
+            })
+            .build()?;
+        Ok(ProverConfig { min_ops_per_thread, max_ops_per_thread, pool: Some(Arc::new(pool)) })
+    }
+
+    /// Dedicated pool with the default chunking.
+    pub fn with_threads(num_threads: usize) -> Result<Self> {
+        Self::new(num_threads, MIN_OPS_PER_THREAD, MAX_OPS_PER_THREAD)
+    }
+
+    pub fn num_threads(&self) -> usize {
+        match &self.pool {
+            Some(pool) => pool.current_num_threads(),
+            None => rayon::current_num_threads(),
+        }
+    }
+
+    /// Runs `op` in this config's pool. Every rayon call made by `op` stays in the pool.
+    pub fn install<OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        match &self.pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{get_max_workers, max_ops_per_thread, min_ops_per_thread, ProverConfig};
+    use rayon::prelude::*;
+
+    #[test]
+    fn test_dedicated_pools() {
+        let c1 = ProverConfig::new(2, 1 << 4, 1 << 8).unwrap();
+        let c2 = ProverConfig::new(3, 1 << 5, 1 << 9).unwrap();
+        assert_eq!(c1.num_threads(), 2);
+        assert_eq!(c2.num_threads(), 3);
+
+        let (r1, r2) = rayon::join(
+            || c1.install(|| (get_max_workers(), min_ops_per_thread(), max_ops_per_thread())),
+            || c2.install(|| (get_max_workers(), min_ops_per_thread(), max_ops_per_thread())),
+        );
+        assert_eq!(r1, (1, 1 << 4, 1 << 8));
+        assert_eq!(r2, (2, 1 << 5, 1 << 9));
+
+        // nested parallel iterators keep running on the pool's workers
+        let chunking: Vec<usize> =
+            c1.install(|| (0..64).into_par_iter().map(|_| min_ops_per_thread()).collect());
+        assert!(chunking.iter().all(|m| *m == 1 << 4));
+    }
+
+    #[test]
+    fn test_invalid_chunking() {
+        assert!(ProverConfig::new(1, 0, 1).is_err());
+        assert!(ProverConfig::new(1, 1 << 10, 1 << 4).is_err());
+    }
+}
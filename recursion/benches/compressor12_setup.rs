@@ -0,0 +1,68 @@
+#[macro_use]
+extern crate criterion;
+
+use algebraic::reader::load_r1cs;
+use criterion::Criterion;
+use fields::field_gl::GL;
+use recursion::compressor12_setup::setup;
+use recursion::r1cs2plonk::r1cs2plonk;
+use recursion::verifier_circuit::stark_verifier::{self, StarkVerifierCircuit};
+use starky::pil2circom::StarkOption;
+use starky::prover_config::ProverConfig;
+
+/// The stark of the fibonacci example whose verifier is set up, checked in with starky.
+const STARK_STRUCT: &str = "../starky/data/starkStruct.json.gl";
+const PIL_JSON: &str = "../starky/data/fib.pil.json.gl";
+const CONST_POLS: &str = "../starky/data/fib.const.gl";
+
+/// The compressor12 setup of the verifier of fib, or of the circom R1CS of `R1CS_FILE`.
+fn bench_compressor12_setup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compressor12_setup");
+    group.sample_size(10);
+
+    let dir = std::env::temp_dir().join("compressor12_setup_bench");
+    std::fs::create_dir_all(&dir).unwrap();
+    let out = |ext: &str| dir.join(format!("c12.{ext}")).to_string_lossy().into_owned();
+    let config = ProverConfig::default();
+
+    if let Ok(r1cs_file) = std::env::var("R1CS_FILE") {
+        let r1cs = load_r1cs::<GL>(&r1cs_file);
+        group.bench_function("r1cs2plonk", |b| b.iter(|| r1cs2plonk(&r1cs)));
+        group.bench_function("setup", |b| {
+            b.iter(|| {
+                setup(&r1cs_file, &out("pil"), &out("const"), &out("exec"), 0, &config).unwrap()
+            })
+        });
+        return;
+    }
+
+    let options = StarkOption {
+        enable_input: false,
+        verkey_input: false,
+        skip_main: false,
+        agg_stage: false,
+    };
+    let verifier =
+        StarkVerifierCircuit::load(STARK_STRUCT, PIL_JSON, CONST_POLS, &options, &config).unwrap();
+    group.bench_function("plonk", |b| b.iter(|| verifier.plonk().unwrap()));
+    group.bench_function("setup", |b| {
+        b.iter(|| {
+            stark_verifier::setup(
+                STARK_STRUCT,
+                PIL_JSON,
+                CONST_POLS,
+                &options,
+                &out("key.json"),
+                &out("pil"),
+                &out("const"),
+                &out("exec"),
+                0,
+                &config,
+            )
+            .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, bench_compressor12_setup);
+criterion_main!(benches);
@@ -1,16 +1,21 @@
 use crate::compressor12_pil::CompressorNameSpace::*;
 use crate::compressor12_pil::CompressorPolName::a;
-use crate::io_utils::read_vec_from_file;
-use crate::pilcom::compile_pil_from_path;
-use algebraic::witness::{load_input_for_witness, WitnessCalculator};
-use anyhow::{anyhow, Result};
+use crate::exec_file::{read_exec_file, ExecFile};
+use crate::pilcom::load_or_compile_pil;
+use crate::verifier_circuit::circom_witness::CircomWitness;
+use crate::verifier_circuit::stark_verifier::StarkVerifierCircuit;
+use algebraic::witness::{load_input_for_witness, InputError, WitnessCalculator};
+use anyhow::{anyhow, ensure, Result};
 use fields::ff::PrimeField;
-use fields::field_gl::Fr as FGL;
-use num_traits::Zero;
+use fields::field_gl::{Fr as FGL, MODULUS};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
 use starky::polsarray::{PolKind, PolsArray};
+use starky::prover_config::ProverConfig;
+use starky::types::PIL;
 use std::fs::File;
-use std::io::{BufReader, Read, Write};
-use std::path::Path;
+use std::io::BufReader;
+use thiserror::Error;
 
 // exec phase:
 // input files: .wasm, .exec,  .pil, zkin.json(input file),
@@ -21,131 +26,376 @@ pub fn exec(
     pil_file: &str,
     exec_file: &str,
     commit_file: &str,
+    config: &ProverConfig,
 ) -> Result<()> {
-    // 0. load exec_file,
-
-    let inputs_str = File::open(exec_file).map_err(|e| anyhow!("Read {}, {:?}", exec_file, e))?;
-    let reader = BufReader::new(inputs_str);
-    let (adds_len, s_map_column_len, adds, s_map) = read_exec_file(reader)?;
-
-    // 1. Compiles a .pil file to its json form , and save it.
-    // TODO: the pil_str has been compiled in plonk_setup#3
-    let pil_json = compile_pil_from_path(pil_file);
-    let mut file = File::create(Path::new(&format!("{pil_file}.json")))
-        .unwrap_or_else(|_| panic!("{pil_file:?}"));
-    let input = serde_json::to_string(&pil_json)?;
-    write!(file, "{input}")?;
-
-    // 2. construct cmPol: .pil.json -> .cm
-    let mut cm_pols = PolsArray::new(&pil_json, PolKind::Commit);
-
-    // 3. calculate witness. wasm+input->witness
-    let mut wtns = WitnessCalculator::from_file(wasm_file)?;
-    let inputs = load_input_for_witness(input_file);
-    let w = wtns.calculate_witness(inputs, false)?;
-    let mut w = w
-        .iter()
-        .map(|wi| {
-            if wi.is_zero() {
-                FGL::ZERO
-            } else {
-                assert!(wi.to_u64_digits().1.len() < 2);
-                FGL::from(wi.to_u64_digits().1[0])
-            }
-        })
-        .collect::<Vec<_>>();
+    config.install(|| {
+        let mut executor = Executor::new(wasm_file, pil_file, exec_file)?;
+        ensure!(executor.n_cols() == 12, "{pil_file} is not a compressor12 pil");
+        executor.exec_file(input_file, commit_file)
+    })
+}
 
-    for i in 0..adds_len {
-        let w2 = FGL::from_raw_repr(<FGL as PrimeField>::Repr::from(adds[i * 4 + 2]))?;
-        let w3 = FGL::from_raw_repr(<FGL as PrimeField>::Repr::from(adds[i * 4 + 3]))?;
+/// `exec` with the witness computed natively from the `.r1cs` and the `.sym` of the verifier,
+/// without its wasm.
+pub fn exec_native(
+    input_file: &str,
+    r1cs_file: &str,
+    sym_file: &str,
+    pil_file: &str,
+    exec_file: &str,
+    commit_file: &str,
+    config: &ProverConfig,
+) -> Result<()> {
+    config.install(|| {
+        let mut executor = Executor::native(r1cs_file, sym_file, pil_file, exec_file)?;
+        ensure!(executor.n_cols() == 12, "{pil_file} is not a compressor12 pil");
+        executor.exec_file(input_file, commit_file)
+    })
+}
 
-        let f_w = (w[adds[i * 4] as usize] * w2) + (w[adds[i * 4 + 1] as usize] * w3);
-        w.push(f_w);
+/// Why the committed polynomials of an input can't be computed. The exec file and the wasm
+/// are the ones of the setup, these are the errors of a bad input, or of a wasm not compiled for
+/// the Goldilocks field.
+#[derive(Error, Debug)]
+pub enum ExecError {
+    #[error(transparent)]
+    Input(#[from] InputError),
+
+    #[error("the verifier circuit fails on the input: {check}")]
+    Check { check: String },
+
+    #[error("the wasm of the verifier circuit fails on the input: {reason}")]
+    Wasm { reason: String },
+
+    #[error("the verifier code of the starkinfo can't be executed: {reason}")]
+    VerifierCode { reason: String },
+
+    #[error("the witness has {actual} signals, the r1cs of the exec file has {expected}")]
+    WitnessSize { expected: usize, actual: usize },
+
+    #[error("signal {signal} is {value}, not a Goldilocks element")]
+    NotGoldilocks { signal: usize, value: BigInt },
+
+    #[error("addition {add} reads signal {signal}, there are {n_signals}")]
+    AddSignal { add: usize, signal: u64, n_signals: usize },
+
+    #[error("addition {add} has the coefficient {coef}, not a Goldilocks element")]
+    AddCoefficient { add: usize, coef: u64 },
+
+    #[error("sMap row {row} column {col} reads signal {signal}, there are {n_signals}")]
+    SMapSignal { row: usize, col: usize, signal: u64, n_signals: usize },
+
+    #[error("signal {signal} can't be solved from the constraints, {unsolved} signals are left")]
+    Unsolved { signal: usize, unsolved: usize },
+
+    #[error("the verifier circuit fails on the input: constraint {constraint} doesn't hold")]
+    Constraint { constraint: usize },
+
+    #[error("the verifier circuit fails on the input: {template} gate {gate_use} doesn't hold")]
+    CustomGate { gate_use: usize, template: String },
+}
+
+/// How the witness of the verifier circuit is computed.
+enum Witness {
+    Wasm(WitnessCalculator),
+    Native(CircomWitness),
+    Stark(Box<StarkVerifierCircuit>),
+}
+
+/// The compiled PIL, the exec tables and the witness calculator of a compressor setup, loaded
+/// once to exec many inputs. The number of columns is the one of `Compressor.a` in the PIL, it
+/// works for compressor12 and compressor18.
+pub struct Executor {
+    pil_json: PIL,
+    exec: ExecFile,
+    wtns: Witness,
+    n_cols: usize,
+}
+
+impl Executor {
+    /// Loads `<pil_file>.json`, written by the setup, or compiles `pil_file` if it has none.
+    pub fn new(wasm_file: &str, pil_file: &str, exec_file: &str) -> Result<Self> {
+        let wtns = Witness::Wasm(WitnessCalculator::from_file(wasm_file)?);
+        Self::load(wtns, pil_file, exec_file)
     }
 
-    // 4. compress cmPol
-    let a_np_index = cm_pols.get_pol_id(&pil_json, &Compressor.to_string(), &a.to_string(), 0);
-    let N = cm_pols.array[a_np_index].len();
+    /// As `new`, the witness computed natively from the `.r1cs` and the `.sym` of the verifier
+    /// instead of its wasm.
+    pub fn native(
+        r1cs_file: &str,
+        sym_file: &str,
+        pil_file: &str,
+        exec_file: &str,
+    ) -> Result<Self> {
+        let r1cs_bytes =
+            std::fs::read(r1cs_file).map_err(|e| anyhow!("Read {}, {:?}", r1cs_file, e))?;
+        let sym =
+            std::fs::read_to_string(sym_file).map_err(|e| anyhow!("Read {}, {:?}", sym_file, e))?;
+        let wtns = CircomWitness::from_bytes(&r1cs_bytes, &sym)?;
+        Self::with_witness(wtns, &r1cs_bytes, pil_file, exec_file)
+    }
 
-    for i in 0..s_map_column_len {
-        for c in 0..12 {
-            let s = s_map[i * 12 + c] as usize;
+    /// As `native`, with the r1cs and the sym of the verifier loaded already. `r1cs` is what the
+    /// setup hashed in the exec file, the bytes of the `.r1cs`.
+    pub fn with_witness(
+        wtns: CircomWitness,
+        r1cs: &[u8],
+        pil_file: &str,
+        exec_file: &str,
+    ) -> Result<Self> {
+        let executor = Self::load(Witness::Native(wtns), pil_file, exec_file)?;
+        executor.exec.check_r1cs(r1cs)?;
+        Ok(executor)
+    }
 
-            cm_pols.set_matrix(
-                &pil_json,
-                &Compressor.to_string(),
-                &a.to_string(),
-                c,
-                i,
-                if s != 0 { w[s] } else { FGL::ZERO },
-            );
-        }
+    /// The executor of the setup of a `StarkVerifierCircuit`, which computes the witness itself.
+    pub fn stark_verifier(
+        verifier: StarkVerifierCircuit,
+        pil_file: &str,
+        exec_file: &str,
+    ) -> Result<Self> {
+        let description = verifier.description()?;
+        let executor = Self::load(Witness::Stark(Box::new(verifier)), pil_file, exec_file)?;
+        executor.exec.check_r1cs(&description)?;
+        Ok(executor)
     }
-    for i in s_map_column_len..N {
-        for c in 0..12 {
-            cm_pols.set_matrix(&pil_json, &Compressor.to_string(), &a.to_string(), c, i, FGL::ZERO);
-        }
+
+    fn load(wtns: Witness, pil_file: &str, exec_file: &str) -> Result<Self> {
+        // 1. load the pil json, compiled at setup
+        let pil_json = load_or_compile_pil(pil_file)?;
+        let a_ref = pil_json
+            .references
+            .get("Compressor.a")
+            .ok_or_else(|| anyhow!("{pil_file} has no Compressor.a"))?;
+        let n_cols = a_ref.len.unwrap_or(1);
+
+        // 2. load exec_file, checked against the pil
+        let inputs_str =
+            File::open(exec_file).map_err(|e| anyhow!("Read {}, {:?}", exec_file, e))?;
+        let reader = BufReader::new(inputs_str);
+        let exec = read_exec_file(reader, n_cols)
+            .map_err(|e| anyhow!("Read exec file {}, {}", exec_file, e))?;
+        exec.check_pil(&std::fs::read_to_string(pil_file)?)?;
+        ensure!(
+            a_ref.polDeg >= exec.s_map_column_len,
+            "{pil_file} has {} rows, the exec file {}",
+            a_ref.polDeg,
+            exec.s_map_column_len
+        );
+
+        Ok(Self { pil_json, exec, wtns, n_cols })
     }
 
-    // 5. save cmPol to file.
-    let commit_writer = std::fs::File::create(commit_file)?;
-    cm_pols.save(commit_writer)?;
+    /// The committed columns of the compressor.
+    pub fn n_cols(&self) -> usize {
+        self.n_cols
+    }
 
-    log::trace!("files Generated Correctly");
-    Result::Ok(())
-}
+    /// The compiled PIL, to prove the committed polynomials of `exec` with.
+    pub fn pil(&self) -> &PIL {
+        &self.pil_json
+    }
 
-fn read_exec_file<R: Read>(reader: R) -> Result<(usize, usize, Vec<u64>, Vec<u64>)> {
-    let mut buff = read_vec_from_file(reader)?;
+    /// Reads the input from `input_file` (the zkin.json of the proof to compress), and saves
+    /// the committed polynomials to `commit_file`. The errors of the input are `ExecError`s.
+    pub fn exec_file(&mut self, input_file: &str, commit_file: &str) -> Result<()> {
+        let inputs = load_input_for_witness(input_file).map_err(ExecError::from)?;
+        let cm_pols = self.exec(inputs)?;
 
-    let mut new_buff = buff.split_off(2);
-    let adds_len = buff[0] as usize;
-    let s_map_column_len = buff[1] as usize;
+        // save cmPol to file.
+        let commit_writer =
+            File::create(commit_file).map_err(|e| anyhow!("Create {commit_file}, {e}"))?;
+        cm_pols.save(commit_writer)?;
 
-    let size = adds_len * 4 + s_map_column_len * 12;
-    assert_eq!(new_buff.len(), size);
+        log::trace!("files Generated Correctly");
+        Ok(())
+    }
+
+    /// The committed polynomials for the verifier circuit's `inputs`.
+    pub fn exec<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        inputs: I,
+    ) -> Result<PolsArray, ExecError> {
+        // 1. calculate witness. wasm+input->witness
+        let w = match &mut self.wtns {
+            Witness::Wasm(wtns) => calculate_witness(wtns, inputs)?,
+            Witness::Native(wtns) => wtns.calculate_witness(inputs)?,
+            Witness::Stark(verifier) => verifier.witness(inputs)?,
+        };
+        self.place(w)
+    }
+
+    /// The committed polynomials of a witness of the verifier circuit, computed elsewhere.
+    pub fn place(&self, mut w: Vec<FGL>) -> Result<PolsArray, ExecError> {
+        // 2. construct cmPol: .pil.json -> .cm
+        let mut cm_pols = PolsArray::new(&self.pil_json, PolKind::Commit);
+        self.exec.check_witness(w.len())?;
+        add_signals(&mut w, &self.exec.adds)?;
+
+        // 3. compress cmPol, the rows after the sMap stay 0
+        let a_np_index =
+            cm_pols.get_pol_id(&self.pil_json, &Compressor.to_string(), &a.to_string(), 0);
+        fill_columns(
+            &mut cm_pols.array[a_np_index..a_np_index + self.n_cols],
+            &self.exec.s_map,
+            &w,
+        )?;
+
+        Ok(cm_pols)
+    }
+}
+
+/// The witness of the verifier circuit, every signal a Goldilocks element.
+fn calculate_witness<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+    wtns: &mut WitnessCalculator,
+    inputs: I,
+) -> Result<Vec<FGL>, ExecError> {
+    let w =
+        wtns.calculate_witness(inputs, false).map_err(|e| match e.downcast::<InputError>() {
+            Ok(e) => ExecError::Input(e),
+            Err(e) => ExecError::Wasm { reason: format!("{e:#}") },
+        })?;
+    w.into_iter()
+        .enumerate()
+        .map(|(signal, wi)| match wi.to_u64() {
+            Some(v) if v < MODULUS.0[0] => Ok(FGL::from(v)),
+            _ => Err(ExecError::NotGoldilocks { signal, value: wi }),
+        })
+        .collect()
+}
 
-    let s_map = new_buff.split_off(adds_len * 4);
-    let adds = new_buff;
+/// Appends the signals of the plonk additions, `[w1, w2, coef1, coef2]` each, to the witness.
+pub(crate) fn add_signals(w: &mut Vec<FGL>, adds: &[u64]) -> Result<(), ExecError> {
+    for (i, add) in adds.chunks(4).enumerate() {
+        let signal = |k: usize| {
+            w.get(add[k] as usize).copied().ok_or(ExecError::AddSignal {
+                add: i,
+                signal: add[k],
+                n_signals: w.len(),
+            })
+        };
+        let coef = |k: usize| {
+            FGL::from_raw_repr(<FGL as PrimeField>::Repr::from(add[k]))
+                .map_err(|_| ExecError::AddCoefficient { add: i, coef: add[k] })
+        };
 
-    Ok((adds_len, s_map_column_len, adds, s_map))
+        let f_w = signal(0)? * coef(2)? + signal(1)? * coef(3)?;
+        w.push(f_w);
+    }
+    Ok(())
+}
+
+/// Fills the first rows of the committed `columns` with the signals of the sMap, row-major, signal
+/// 0 being the 0 of an unused cell.
+pub(crate) fn fill_columns(
+    columns: &mut [Vec<FGL>],
+    s_map: &[u64],
+    w: &[FGL],
+) -> Result<(), ExecError> {
+    let n_cols = columns.len();
+    for (row, signals) in s_map.chunks(n_cols).enumerate() {
+        for (col, &signal) in signals.iter().enumerate() {
+            columns[col][row] = match signal {
+                0 => FGL::ZERO,
+                s => *w.get(s as usize).ok_or(ExecError::SMapSignal {
+                    row,
+                    col,
+                    signal,
+                    n_signals: w.len(),
+                })?,
+            };
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::compressor12_setup::write_exec_file;
-    use std::io::BufWriter;
+    use crate::compressor12::compressor12_setup::{write_setup, Options};
+    use crate::verifier_circuit::circuit::Circuit;
+
+    fn input(name: &str, value: BigInt) -> (String, Vec<BigInt>) {
+        (name.to_string(), vec![value])
+    }
+
+    #[test]
+    fn test_calculate_witness() {
+        // a bn128 multiplier, c <== a * b, its witness is [1, c, a, b]
+        let mut wtns =
+            WitnessCalculator::from_file("../algebraic/test-vectors/mycircuit.wasm").unwrap();
+
+        let w = calculate_witness(&mut wtns, [input("a", 3.into()), input("b", 11.into())]);
+        assert_eq!(w.unwrap(), [1u64, 33, 3, 11].map(FGL::from));
+
+        let w = calculate_witness(&mut wtns, [input("a", 3.into()), input("d", 11.into())]);
+        assert!(matches!(w, Err(ExecError::Input(InputError::UnknownSignal { .. }))));
+
+        let big = BigInt::from(MODULUS.0[0]);
+        let w = calculate_witness(&mut wtns, [input("a", big.clone()), input("b", 1.into())]);
+        assert!(matches!(w, Err(ExecError::NotGoldilocks { signal: 1, value }) if value == big));
+    }
+
+    #[test]
+    fn test_add_signals() {
+        // the exec file keeps the coefficients in their Montgomery form
+        let coef = |k: u64| u64::from(FGL::from(k));
+        let mut w = [2u64, 3].map(FGL::from).to_vec();
+        add_signals(&mut w, &[0, 1, coef(5), coef(7), 2, 0, coef(1), coef(1)]).unwrap();
+        assert_eq!(w, [2u64, 3, 31, 33].map(FGL::from));
+
+        let err = add_signals(&mut w, &[0, 4, coef(1), coef(1)]).unwrap_err();
+        assert!(matches!(err, ExecError::AddSignal { add: 0, signal: 4, n_signals: 4 }));
+        let err = add_signals(&mut w, &[0, 1, 1, 1, 0, 1, 1, MODULUS.0[0]]).unwrap_err();
+        assert!(matches!(err, ExecError::AddCoefficient { add: 1, .. }));
+    }
 
     #[test]
-    fn test_write_and_read_exec_file() {
-        let target_adds = vec![
-            // PlonkAdd()
-        ];
-
-        let target_s_map = vec![
-            vec![1, 2, 4],
-            vec![2, 3, 42],
-            vec![1, 1, 3],
-            vec![4, 5, 2],
-            vec![3, 4, 5],
-            vec![1, 2, 4],
-            vec![2, 3, 42],
-            vec![1, 1, 3],
-            vec![4, 5, 2],
-            vec![3, 4, 5],
-            vec![3, 4, 5],
-            vec![3, 4, 5],
-        ];
-
-        let out = Vec::new();
-        let mut buf = BufWriter::new(out);
-        write_exec_file(&mut buf, &target_adds, &target_s_map).unwrap();
-        let input = buf.buffer();
-
-        let reader = std::io::Cursor::new(input);
-        let (adds_len, _s_map_column_len, _adds, _s_map) = read_exec_file(reader).unwrap();
-
-        assert_eq!(adds_len, target_adds.len());
+    fn test_fill_columns() {
+        let w = [0u64, 5, 6].map(FGL::from);
+        let mut columns = vec![vec![FGL::ONE; 3]; 2];
+        fill_columns(&mut columns, &[1, 0, 2, 1], &w).unwrap();
+        assert_eq!(columns[0], [5u64, 6, 1].map(FGL::from));
+        assert_eq!(columns[1], [0u64, 5, 1].map(FGL::from));
+
+        let err = fill_columns(&mut columns, &[1, 0, 2, 3], &w).unwrap_err();
+        assert!(matches!(err, ExecError::SMapSignal { row: 1, col: 1, signal: 3, n_signals: 3 }));
+    }
+
+    #[test]
+    fn test_executor_exec_twice() {
+        // p * x, p public
+        let mut c = Circuit::new();
+        let p = c.public(FGL::ZERO);
+        let x = c.alloc(FGL::ZERO);
+        c.mul(&[(p, FGL::ONE)], &[(x, FGL::ONE)]);
+        let (r1cs, _) = c.into_r1cs();
+        let sym = format!("{p},{p},0,main.p\n{x},{x},0,main.x");
+
+        let dir = std::env::temp_dir().join("c12_exec_twice");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let (pil_file, const_file, exec_file) =
+            (file("c12.pil"), file("c12.const"), file("c12.exec"));
+        write_setup(&r1cs, b"c12", &pil_file, &const_file, &exec_file, &Options::new(0)).unwrap();
+
+        let executor = || {
+            let wtns = CircomWitness::new(r1cs.clone(), &sym).unwrap();
+            Executor::with_witness(wtns, b"c12", &pil_file, &exec_file).unwrap()
+        };
+        let inputs = |p: u64, x: u64| [input("p", p.into()), input("x", x.into())];
+        let mut resident = executor();
+        for (p, x) in [(9, 3), (4, 5), (9, 3)] {
+            let cm_pols = resident.exec(inputs(p, x)).unwrap();
+            // the same as a new executor, the public in the first cell
+            assert_eq!(cm_pols.array, executor().exec(inputs(p, x)).unwrap().array);
+            let a0 = cm_pols.get_pol_id(resident.pil(), &Compressor.to_string(), &a.to_string(), 0);
+            assert_eq!(cm_pols.array[a0][0], FGL::from(p));
+            // a failed exec leaves it as it was
+            assert!(resident.exec([input("p", p.into())]).is_err());
+        }
+        // not the r1cs of the setup
+        let wtns = CircomWitness::new(r1cs.clone(), &sym).unwrap();
+        assert!(Executor::with_witness(wtns, b"c18", &pil_file, &exec_file).is_err());
     }
 }
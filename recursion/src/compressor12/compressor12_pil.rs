@@ -1,4 +1,5 @@
 #![allow(clippy::to_string_trait_impl)]
+use super::custom_gates::GateRegistry;
 
 pub enum CompressorNameSpace {
     Global,
@@ -13,12 +14,7 @@ pub enum CompressorPolName {
     S,
     C,
     a,
-    PARTIAL,
-    POSEIDON12,
     GATE,
-    CMULADD,
-    EVPOL4,
-    FFT4,
 }
 
 // impl
@@ -29,12 +25,7 @@ impl ToString for CompressorPolName {
             Self::S => String::from("S"),
             Self::C => String::from("C"),
             Self::a => String::from("a"),
-            Self::PARTIAL => String::from("PARTIAL"),
-            Self::POSEIDON12 => String::from("POSEIDON12"),
             Self::GATE => String::from("GATE"),
-            Self::CMULADD => String::from("CMULADD"),
-            Self::EVPOL4 => String::from("EVPOL4"),
-            Self::FFT4 => String::from("FFT4"),
         }
     }
 }
@@ -47,7 +38,25 @@ impl ToString for CompressorNameSpace {
     }
 }
 
-pub fn render(n_bits: usize, n_publics: usize) -> String {
+/// The selectors pil-stark declares, in its order.
+const LEGACY_SELECTORS: [&str; 6] = ["PARTIAL", "POSEIDON12", "GATE", "CMULADD", "EVPOL4", "FFT4"];
+
+/// The constant selectors of the PIL: those of pil-stark in its order, so that the default gates
+/// give its constant columns, then the other ones of `gates` in registry order.
+fn selectors(gates: &GateRegistry) -> Vec<&str> {
+    let declared: Vec<&str> = std::iter::once("GATE")
+        .chain(gates.gates().iter().flat_map(|g| g.selectors().iter().copied()))
+        .collect();
+    LEGACY_SELECTORS
+        .into_iter()
+        .filter(|s| declared.contains(s))
+        .chain(declared.iter().copied().filter(|s| !LEGACY_SELECTORS.contains(s)))
+        .collect()
+}
+
+/// Renders the compressor12 PIL: the publics, the plonk gates, then the selectors and the
+/// constraints of each gate of `gates`.
+pub fn render(n_bits: usize, n_publics: usize, gates: &GateRegistry) -> String {
     let mut res = String::from("");
     res.push_str(&format!(
         r#"
@@ -71,12 +80,17 @@ namespace Global(N);
 namespace Compressor(N);
     pol constant S[12];
     pol constant C[12];
-    pol constant PARTIAL;
-    pol constant POSEIDON12;
-    pol constant GATE;
-    pol constant CMULADD;
-    pol constant EVPOL4;
-    pol constant FFT4;
+            "#,
+    );
+    for selector in selectors(gates) {
+        res.push_str(&format!(
+            r#"
+    pol constant {selector};
+            "#
+        ));
+    }
+    res.push_str(
+        r#"
     pol commit a[12];
             "#,
     );
@@ -122,12 +136,32 @@ namespace Compressor(N);
     pol a910 = a[9]*a[10];
     pol g91011 = C[9]*a910 + C[6]*a[9] + C[7]*a[10] + C[8]*a[11] + C[10];
     g91011*GATE = 0;
+    "#,
+    );
+
+    for gate in gates.gates() {
+        res.push_str(&gate.pil());
+    }
+
+    res.push_str(
+        r#"
+    // Connection equations
+    [a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7], a[8], a[9], a[10], a[11]] connect
+        [S[0], S[1], S[2], S[3], S[4], S[5], S[6], S[7], S[8], S[9], S[10], S[11]];
 
-    // POSEIDON12 GATE
     "#,
     );
 
+    res
+}
+
+/// The constraints of the Poseidon12 gate, a round a row.
+pub(crate) fn poseidon12_pil() -> String {
+    let mut res = String::from(
+        r#"
     // POSEIDON12 GATE
+    "#,
+    );
     for i in 0..12 {
         res.push_str(&format!(
             r#"
@@ -184,7 +218,15 @@ namespace Compressor(N);
     POSEIDON12 * (a[10]' - (41*a0_R + 16*a1_R +  2*a2_R + 28*a3_R + 13*a4_R + 13*a5_R + 39*a6_R + 18*a7_R + 34*a8_R + 20*a9_R + 17*a10_R + 15*a11_R)) = 0;
     POSEIDON12 * (a[11]' - (15*a0_R + 41*a1_R + 16*a2_R +  2*a3_R + 28*a4_R + 13*a5_R + 13*a6_R + 39*a7_R + 18*a8_R + 34*a9_R + 20*a10_R + 17*a11_R)) = 0;
 
+    "#,
+    );
+    res
+}
 
+/// The constraints of the CMulAdd gate, `a[9..12] = a[0..3] * a[3..6] + a[6..9]` in GF(p^3).
+pub(crate) fn c_mul_add_pil() -> String {
+    String::from(
+        r#"
     // CMULADD GATE
     pol ca0 = (a[0] + C[0])*C[9];
     pol ca1 = (a[1] + C[1])*C[9];
@@ -210,6 +252,14 @@ namespace Compressor(N);
     CMULADD * (ca10 - (cA + cC - 2*cE - cD) - ca7) = 0;
     CMULADD * (ca11 - (cB - cD + cE) - ca8) = 0;
 
+    "#,
+    )
+}
+
+/// The constraints of the FFT4 gate, the next row is the FFT of this one.
+pub(crate) fn fft4_pil() -> String {
+    String::from(
+        r#"
     // FFT4
 
     pol g0 = C[0]*a[0] + C[1]*a[3] + C[2]*a[6] + C[3]*a[9]  + C[6]*a[0] + C[7]*a[3];
@@ -241,8 +291,13 @@ namespace Compressor(N);
     FFT4 * (a[10]' - g10) = 0;
     FFT4 * (a[11]' - g11) = 0;
 
-       "#
-    );
+       "#,
+    )
+}
+
+/// The constraints of the EvPol4 gate, a polynomial of degree 3 in GF(p^3) evaluated by Horner.
+pub(crate) fn ev_pol4_pil() -> String {
+    let mut res = String::new();
 
     // CMulAdd
     let mut c_mul_add = |r0: &str,
@@ -355,10 +410,6 @@ namespace Compressor(N);
     EVPOL4 * (a[7]' - acc4_1 ) = 0;
     EVPOL4 * (a[8]' - acc4_2 ) = 0;
 
-    // Connection equations
-    [a[0], a[1], a[2], a[3], a[4], a[5], a[6], a[7], a[8], a[9], a[10], a[11]] connect
-        [S[0], S[1], S[2], S[3], S[4], S[5], S[6], S[7], S[8], S[9], S[10], S[11]];
-
     "#,
     );
 
@@ -374,14 +425,16 @@ macro_rules! c_mul_add {
 
 #[cfg(test)]
 mod test {
-    use crate::compressor12_pil::render;
+    use crate::compressor12::custom_gates::GateRegistry;
+    use crate::compressor12_pil::{render, selectors, LEGACY_SELECTORS};
+    use crate::pilcom::compile_pil_from_str;
     use std::fs::File;
     use std::io::Write;
     use std::path::Path;
 
     #[test]
     fn test_render() {
-        let pil_string = render(5, 5);
+        let pil_string = render(5, 5, &GateRegistry::default());
 
         let mut file = File::create(Path::new("/tmp/render_pil_rs.pil")).unwrap();
         file.write_all(pil_string.as_bytes()).unwrap();
@@ -389,8 +442,21 @@ mod test {
 
     #[test]
     fn test_render_and_compile() {
-        let pil_string = render(5, 5);
+        let pil_string = render(5, 5, &GateRegistry::default());
         let mut file = File::create(Path::new("/tmp/render_pil_rs.pil")).unwrap();
         write!(file, "{pil_string}").unwrap();
     }
+
+    #[test]
+    fn test_legacy_constant_order() {
+        // the default gates declare the constants of pil-stark, in its order
+        let gates = GateRegistry::default();
+        assert_eq!(selectors(&gates), LEGACY_SELECTORS);
+        assert_eq!(selectors(&GateRegistry::empty()), ["GATE"]);
+        let pil = compile_pil_from_str(&render(5, 5, &gates));
+        for (i, selector) in LEGACY_SELECTORS.iter().enumerate() {
+            // after Global.L1, S[12] and C[12]
+            assert_eq!(pil.references[&format!("Compressor.{selector}")].id, 25 + i);
+        }
+    }
 }
@@ -0,0 +1,440 @@
+//! The custom gates of compressor12, the circom templates placed in their own rows instead of
+//! being converted to plonk gates.
+//!
+//! A gate brings its PIL, the constant selectors enabling it, and how a use of it fills the
+//! constant columns and the sMap from the row it starts at. The setup places the uses of the gates
+//! of a [`GateRegistry`] and renders their PIL, the default registry has Poseidon12, CMulAdd, FFT4
+//! and EvPol4. A new gate is added by registering it, its PIL may use `a[12]`, `C[12]` and its own
+//! selectors, and the names of its `pol`s must not clash with those of the other gates.
+use super::compressor12_pil;
+use super::constants::CPOSEIDON;
+use algebraic::circom_circuit::{CustomGates, CustomGatesUses, R1CS};
+use anyhow::{anyhow, bail, ensure, Result};
+use fields::field_gl::Fr as FGL;
+use fields::field_gl::GL;
+use rayon::prelude::*;
+use std::sync::Arc;
+
+/// The columns of compressor12.
+const N_COLS: usize = 12;
+
+/// The names the PIL of compressor12 already declares.
+const RESERVED: [&str; 4] = ["S", "C", "GATE", "a"];
+
+/// The constant columns a use of a gate writes, `C[12]` and the selectors of the gate.
+pub struct GateConsts<'a> {
+    pub(crate) pols: &'a mut [Vec<FGL>],
+    pub(crate) c: usize,
+    pub(crate) selectors: &'a [usize],
+}
+
+impl GateConsts<'_> {
+    pub fn set_c(&mut self, k: usize, row: usize, value: FGL) {
+        self.pols[self.c + k][row] = value;
+    }
+
+    /// Sets the `i`-th selector of the gate to 1 at `row`.
+    pub fn enable(&mut self, i: usize, row: usize) {
+        self.pols[self.selectors[i]][row] = FGL::ONE;
+    }
+}
+
+pub trait CompressorGate: Send + Sync {
+    /// The name of the circom template the gate implements.
+    fn template_name(&self) -> &str;
+
+    /// The constant columns declared for the gate, the first one enables it.
+    fn selectors(&self) -> &[&str];
+
+    /// The constraints of the gate.
+    fn pil(&self) -> String;
+
+    /// The rows taken by a use of the gate.
+    fn rows(&self) -> usize;
+
+    /// Checks the parameters of a template of the gate, none by default.
+    fn check_parameters(&self, parameters: &[FGL]) -> Result<()> {
+        ensure!(parameters.is_empty(), "{} takes no parameters", self.template_name());
+        Ok(())
+    }
+
+    /// Writes the constants of a use of a template with `parameters`, from `row`.
+    fn fill_constants(&self, parameters: &[FGL], consts: &mut GateConsts, row: usize);
+
+    /// Places the signals of a use in the sMap from `row`, by default 12 a row.
+    fn place_witness(&self, signals: &[u64], s_map: &mut [Vec<u64>], row: usize) -> Result<()> {
+        ensure!(
+            signals.len() <= self.rows() * N_COLS,
+            "{} has {} signals, more than its {} rows",
+            self.template_name(),
+            signals.len(),
+            self.rows()
+        );
+        for (i, s) in signals.iter().enumerate() {
+            s_map[i % N_COLS][row + i / N_COLS] = *s;
+        }
+        Ok(())
+    }
+}
+
+/// A round a row, the last row has the output.
+pub struct Poseidon12;
+
+impl CompressorGate for Poseidon12 {
+    fn template_name(&self) -> &str {
+        "Poseidon12"
+    }
+
+    fn selectors(&self) -> &[&str] {
+        &["POSEIDON12", "PARTIAL"]
+    }
+
+    fn pil(&self) -> String {
+        compressor12_pil::poseidon12_pil()
+    }
+
+    fn rows(&self) -> usize {
+        31
+    }
+
+    fn fill_constants(&self, _parameters: &[FGL], consts: &mut GateConsts, row: usize) {
+        for j in 0..31 {
+            for k in 0..12 {
+                consts.set_c(k, row + j, FGL::from(CPOSEIDON[j * 12 + k]));
+            }
+            if j < 30 {
+                consts.enable(0, row + j);
+                if (4..26).contains(&j) {
+                    consts.enable(1, row + j);
+                }
+            }
+        }
+    }
+
+    fn place_witness(&self, signals: &[u64], s_map: &mut [Vec<u64>], row: usize) -> Result<()> {
+        ensure!(signals.len() == 31 * 12, "Poseidon12 has {} signals, not 372", signals.len());
+        for (i, s) in signals.iter().enumerate() {
+            s_map[i % 12][row + i / 12] = *s;
+        }
+        Ok(())
+    }
+}
+
+pub struct CMulAdd;
+
+impl CompressorGate for CMulAdd {
+    fn template_name(&self) -> &str {
+        "CMulAdd"
+    }
+
+    fn selectors(&self) -> &[&str] {
+        &["CMULADD"]
+    }
+
+    fn pil(&self) -> String {
+        compressor12_pil::c_mul_add_pil()
+    }
+
+    fn rows(&self) -> usize {
+        1
+    }
+
+    fn fill_constants(&self, _parameters: &[FGL], consts: &mut GateConsts, row: usize) {
+        consts.enable(0, row);
+        consts.set_c(9, row, FGL::ONE);
+        consts.set_c(10, row, FGL::ONE);
+    }
+}
+
+/// Parameterized by `[firstW, incW, scale, type]`, each set of parameters is its own template.
+pub struct FFT4;
+
+impl CompressorGate for FFT4 {
+    fn template_name(&self) -> &str {
+        "FFT4"
+    }
+
+    fn selectors(&self) -> &[&str] {
+        &["FFT4"]
+    }
+
+    fn pil(&self) -> String {
+        compressor12_pil::fft4_pil()
+    }
+
+    fn rows(&self) -> usize {
+        2
+    }
+
+    fn check_parameters(&self, parameters: &[FGL]) -> Result<()> {
+        ensure!(parameters.len() == 4, "FFT4 takes 4 parameters, not {}", parameters.len());
+        let t = parameters[3];
+        ensure!([2, 4].contains(&t.as_int()), "invalid FFT4 type: {t}");
+        Ok(())
+    }
+
+    fn fill_constants(&self, parameters: &[FGL], consts: &mut GateConsts, row: usize) {
+        consts.enable(0, row);
+
+        let (firstW, incW, scale, t) = (parameters[0], parameters[1], parameters[2], parameters[3]);
+        let firstW2 = firstW * firstW;
+        let values = if t.as_int() == 4 {
+            vec![
+                (0, scale),
+                (1, scale * firstW2),
+                (2, scale * firstW),
+                (3, scale * firstW * firstW2),
+                (4, scale * firstW * incW),
+                (5, scale * firstW * firstW2 * incW),
+            ]
+        } else {
+            vec![(6, scale), (7, scale * firstW), (8, scale * firstW * incW)]
+        };
+        for (k, value) in values {
+            consts.set_c(k, row, value);
+        }
+    }
+}
+
+/// The 4 coefficients and the point in the first row, the result in the second.
+pub struct EvPol4;
+
+impl CompressorGate for EvPol4 {
+    fn template_name(&self) -> &str {
+        "EvPol4"
+    }
+
+    fn selectors(&self) -> &[&str] {
+        &["EVPOL4"]
+    }
+
+    fn pil(&self) -> String {
+        compressor12_pil::ev_pol4_pil()
+    }
+
+    fn rows(&self) -> usize {
+        2
+    }
+
+    fn fill_constants(&self, _parameters: &[FGL], consts: &mut GateConsts, row: usize) {
+        consts.enable(0, row);
+    }
+}
+
+/// The gates the compressor12 setup places, in the order their PIL is rendered.
+#[derive(Clone)]
+pub struct GateRegistry {
+    gates: Vec<Arc<dyn CompressorGate>>,
+}
+
+impl Default for GateRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        for gate in [
+            Arc::new(Poseidon12) as Arc<dyn CompressorGate>,
+            Arc::new(CMulAdd),
+            Arc::new(FFT4),
+            Arc::new(EvPol4),
+        ] {
+            registry.register(gate).unwrap();
+        }
+        registry
+    }
+}
+
+impl GateRegistry {
+    /// No custom gates, for circuits of plonk gates only.
+    pub fn empty() -> Self {
+        Self { gates: vec![] }
+    }
+
+    /// Adds a gate, its template name and selectors must not be taken.
+    pub fn register(&mut self, gate: Arc<dyn CompressorGate>) -> Result<()> {
+        ensure!(
+            self.get(gate.template_name()).is_none(),
+            "the custom gate {} is already registered",
+            gate.template_name()
+        );
+        ensure!(!gate.selectors().is_empty(), "{} has no selector", gate.template_name());
+        for selector in gate.selectors() {
+            ensure!(
+                !RESERVED.contains(selector)
+                    && self.gates.iter().all(|g| !g.selectors().contains(selector)),
+                "the selector {selector} of {} is already declared",
+                gate.template_name()
+            );
+        }
+        self.gates.push(gate);
+        Ok(())
+    }
+
+    pub fn gates(&self) -> &[Arc<dyn CompressorGate>] {
+        &self.gates
+    }
+
+    /// The index and the gate of a template.
+    pub fn get(&self, template_name: &str) -> Option<(usize, &dyn CompressorGate)> {
+        self.gates
+            .iter()
+            .enumerate()
+            .find(|(_, g)| g.template_name() == template_name)
+            .map(|(i, g)| (i, g.as_ref()))
+    }
+}
+
+/// The gates of the custom templates of a circuit, and how many times each gate is used.
+#[derive(Debug)]
+pub(crate) struct UsedGates {
+    /// The index in the registry of the gate of each template of the circuit.
+    pub(crate) templates: Vec<usize>,
+    /// The uses of each gate of the registry.
+    pub(crate) counts: Vec<usize>,
+}
+
+impl UsedGates {
+    pub(crate) fn from_r1cs(r1cs: &R1CS<GL>, registry: &GateRegistry) -> Result<Self> {
+        Self::new(&r1cs.custom_gates, &r1cs.custom_gates_uses, registry)
+    }
+
+    pub(crate) fn new(
+        custom_gates: &[CustomGates<GL>],
+        uses: &[CustomGatesUses],
+        registry: &GateRegistry,
+    ) -> Result<Self> {
+        let templates = custom_gates
+            .iter()
+            .map(|c| {
+                let (i, gate) = registry
+                    .get(&c.template_name)
+                    .ok_or_else(|| anyhow!("Invalid custom gate {}", c.template_name))?;
+                gate.check_parameters(&c.parameters)?;
+                Ok(i)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(c) = uses.iter().find(|c| c.id as usize >= templates.len()) {
+            bail!("Custom gate not defined {}", c.id);
+        }
+        let counts = uses
+            .par_iter()
+            .fold(
+                || vec![0; registry.gates().len()],
+                |mut counts, c| {
+                    counts[templates[c.id as usize]] += 1;
+                    counts
+                },
+            )
+            .reduce(
+                || vec![0; registry.gates().len()],
+                |a, b| a.iter().zip(b).map(|(a, b)| a + b).collect(),
+            );
+
+        Ok(Self { templates, counts })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compressor12::compressor12_exec::{add_signals, fill_columns};
+    use crate::compressor12::compressor12_setup::Options;
+    use crate::compressor12::plonk_setup::PlonkSetup;
+    use crate::compressor12::setup_report::GateRows;
+    use crate::exec_file::{read_exec_file, write_exec_file, ExecHeader};
+    use crate::verifier_circuit::circuit::{Circuit, Signal};
+    use crate::verifier_circuit::stark_verifier::test::prove_and_verify;
+    use starky::polsarray::{PolKind, PolsArray};
+
+    /// The sum of the 12 cells of a row is the first cell of the next.
+    struct Sum12;
+
+    impl CompressorGate for Sum12 {
+        fn template_name(&self) -> &str {
+            "Sum12"
+        }
+
+        fn selectors(&self) -> &[&str] {
+            &["SUM12"]
+        }
+
+        fn pil(&self) -> String {
+            String::from(
+                r#"
+    SUM12 * (a[0]' - (a[0] + a[1] + a[2] + a[3] + a[4] + a[5] + a[6] + a[7] + a[8] + a[9] + a[10] + a[11])) = 0;
+    "#,
+            )
+        }
+
+        fn rows(&self) -> usize {
+            2
+        }
+
+        fn fill_constants(&self, _parameters: &[FGL], consts: &mut GateConsts, row: usize) {
+            consts.enable(0, row);
+        }
+    }
+
+    #[test]
+    fn test_register_gate() {
+        let mut registry = GateRegistry::default();
+        assert!(registry.register(Arc::new(FFT4)).is_err());
+        registry.register(Arc::new(Sum12)).unwrap();
+        assert_eq!(registry.get("Sum12").unwrap().0, 4);
+
+        let pil = compressor12_pil::render(5, 5, &registry);
+        assert!(pil.contains("pol constant SUM12;"));
+        assert!(pil.contains("SUM12 * (a[0]'"));
+        assert!(pil.contains("pol constant PARTIAL;"));
+
+        let mut s_map = vec![vec![0u64; 2]; 12];
+        Sum12.place_witness(&(1..=13).collect::<Vec<_>>(), &mut s_map, 0).unwrap();
+        assert_eq!((s_map[11][0], s_map[0][1], s_map[1][1]), (12, 13, 0));
+        assert!(Sum12.place_witness(&[1; 25], &mut s_map, 0).is_err());
+
+        let mut pols = vec![vec![FGL::ZERO; 2]; 13];
+        let mut consts = GateConsts { pols: &mut pols, c: 0, selectors: &[12] };
+        Sum12.fill_constants(&[], &mut consts, 1);
+        assert_eq!(pols[12], [FGL::ZERO, FGL::ONE]);
+    }
+
+    #[test]
+    fn test_registered_gate_setup() {
+        // a circuit using Sum12: the sum of 1..=12 is the public
+        let mut c = Circuit::new();
+        let p = c.public(FGL::from(78u64));
+        let x: Vec<Signal> = (1..=12u64).map(|v| c.alloc(FGL::from(v))).collect();
+        let sum = c.alloc(FGL::from(78u64));
+        c.gate("Sum12", &[], [x, vec![sum]].concat());
+        c.enforce_eq(p, sum, || String::from("sum"));
+        let (r1cs, mut w) = c.into_r1cs();
+
+        let mut gates = GateRegistry::default();
+        gates.register(Arc::new(Sum12)).unwrap();
+        let res = PlonkSetup::new(&r1cs, &Options { force_bits: 0, gates }).unwrap();
+        assert!(res.report.custom_gates.contains(&GateRows::new("Sum12", 1, 2)));
+
+        // the committed polynomials of the witness, as the exec places them
+        let mut exec = vec![];
+        let header = ExecHeader::new(N_COLS, b"sum12", &res.pil_str, r1cs.num_variables);
+        write_exec_file(&mut exec, &header, &res.plonk_additions, &res.s_map).unwrap();
+        let exec = read_exec_file(exec.as_slice(), N_COLS).unwrap();
+        add_signals(&mut w, &exec.adds).unwrap();
+        let pil = res.pil_json;
+        let mut cm_pols = PolsArray::new(&pil, PolKind::Commit);
+        let a = cm_pols.get_pol_id(&pil, &"Compressor".to_string(), &"a".to_string(), 0);
+        fill_columns(&mut cm_pols.array[a..a + N_COLS], &exec.s_map, &w).unwrap();
+
+        // the constraints of Sum12, of the plonk gate and the connections hold: a stark verifies
+        prove_and_verify(pil, res.const_pols, cm_pols);
+    }
+
+    #[test]
+    fn test_fft4_parameters() {
+        let p = |t: u64| [FGL::ONE, FGL::ONE, FGL::ONE, FGL::from(t)];
+        FFT4.check_parameters(&p(4)).unwrap();
+        FFT4.check_parameters(&p(2)).unwrap();
+        assert!(FFT4.check_parameters(&p(3)).is_err());
+        assert!(FFT4.check_parameters(&[]).is_err());
+        assert!(CMulAdd.check_parameters(&p(4)).is_err());
+    }
+}
@@ -3,4 +3,6 @@ pub mod compressor12_exec;
 pub(crate) mod compressor12_pil;
 pub mod compressor12_setup;
 pub(crate) mod constants;
+pub mod custom_gates;
 pub(crate) mod plonk_setup;
+pub mod setup_report;
@@ -1,34 +1,52 @@
 #![allow(non_snake_case)]
 use super::{
-    compressor12_pil, compressor12_pil::CompressorNameSpace::*,
-    compressor12_pil::CompressorPolName::*, compressor12_setup::Options, constants::CPOSEIDON,
+    compressor12_pil,
+    compressor12_pil::CompressorNameSpace::*,
+    compressor12_pil::CompressorPolName::*,
+    compressor12_setup::Options,
+    custom_gates::{GateConsts, GateRegistry, UsedGates},
+    setup_report::{GateRows, SetupReport},
 };
 use crate::pilcom::compile_pil_from_str;
-use crate::r1cs2plonk::{r1cs2plonk, PlonkAdd, PlonkGate};
-use algebraic::circom_circuit::R1CS;
-use array_tool::vec::Shift;
+use crate::r1cs2plonk::{r1cs2plonk, GateKey, PlonkAdd, PlonkGate};
+use algebraic::circom_circuit::{CustomGates, CustomGatesUses, R1CS};
+use anyhow::{ensure, Result};
 use fields::field_gl::Fr as FGL;
 use fields::field_gl::GL;
+use rayon::prelude::*;
 use starky::helper;
 use starky::polsarray::{PolKind, PolsArray};
 use starky::types::PIL;
-use std::collections::BTreeMap;
+use std::collections::{HashMap, VecDeque};
 
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct PlonkSetup {
     pub(crate) pil_str: String,
+    pub(crate) pil_json: PIL,
     pub(crate) const_pols: PolsArray,
     pub(crate) s_map: Vec<Vec<u64>>,
     pub(crate) plonk_additions: Vec<PlonkAdd>,
+    pub(crate) report: SetupReport,
 }
 
 impl PlonkSetup {
-    pub fn new(r1cs: &R1CS<GL>, opts: &Options) -> Self {
+    pub fn new(r1cs: &R1CS<GL>, opts: &Options) -> Result<Self> {
+        Self::from_plonk(PlonkCircuit::from_r1cs(r1cs), opts)
+    }
+
+    /// The setup of the gates of a plonk circuit.
+    pub fn from_plonk(plonk: PlonkCircuit, opts: &Options) -> Result<Self> {
         // 1. plonk_setup_render phase
-        let plonk_setup_info = PlonkSetupRenderInfo::plonk_setup_render(r1cs, opts);
+        let plonk_setup_info = PlonkSetupRenderInfo::plonk_setup_render(&plonk, opts)?;
+        let report = plonk_setup_info.report.clone();
+        ensure!(report.fits(), "compressor12 setup doesn't fit:\n{report}");
         // 2. render .pil file by template.
         // //      And save as a file.
-        let pil_str = compressor12_pil::render(plonk_setup_info.n_bits, plonk_setup_info.n_publics);
+        let pil_str = compressor12_pil::render(
+            plonk_setup_info.n_bits,
+            plonk_setup_info.n_publics,
+            &opts.gates,
+        );
         // let mut file = File::create(out_pil.clone()).unwrap();
         // write!(file, "{}", pil_str).unwrap();
 
@@ -36,15 +54,46 @@ impl PlonkSetup {
         let pil_json = compile_pil_from_str(&pil_str);
 
         //4. plonk_setup_fix_compressor phase
-        let (const_pols, s_map) = plonk_setup_compressor(r1cs, &pil_json, &plonk_setup_info);
+        let (const_pols, s_map) =
+            plonk_setup_compressor(&plonk, &pil_json, &plonk_setup_info, &opts.gates)?;
+
+        Ok(Self { pil_str, pil_json, const_pols, s_map, plonk_additions: plonk.adds, report })
+    }
+}
+
+/// The plonk gates and the custom gate uses compressor12 places, converted from an R1CS or built
+/// directly by a circuit.
+#[derive(Debug, Default)]
+pub struct PlonkCircuit {
+    pub n_publics: usize,
+    /// The constraints of the R1CS, or the gates of a circuit built directly.
+    pub n_constraints: usize,
+    pub gates: Vec<PlonkGate>,
+    /// The signals the conversion from an R1CS adds, computed by the exec after the witness.
+    pub adds: Vec<PlonkAdd>,
+    pub custom_gates: Vec<CustomGates<GL>>,
+    pub custom_gates_uses: Vec<CustomGatesUses>,
+}
 
-        Self { pil_str, const_pols, s_map, plonk_additions: plonk_setup_info.pa }
+impl PlonkCircuit {
+    pub fn from_r1cs(r1cs: &R1CS<GL>) -> Self {
+        let (gates, adds) = r1cs2plonk(r1cs);
+        Self {
+            n_publics: r1cs.num_inputs + r1cs.num_outputs - 1,
+            n_constraints: r1cs.constraints.len(),
+            gates,
+            adds,
+            custom_gates: r1cs.custom_gates.clone(),
+            custom_gates_uses: r1cs.custom_gates_uses.clone(),
+        }
     }
 }
 
 #[derive(Debug)]
 pub(crate) struct NormalPlonkInfo {
     pub N: usize,
+    /// The distinct coefficients of the gates.
+    pub n_kinds: usize,
     // never used fields
     // pub n_constraints: usize,
     // pub n_plonk_gates: usize,
@@ -53,24 +102,34 @@ pub(crate) struct NormalPlonkInfo {
 
 impl NormalPlonkInfo {
     pub(crate) fn new(plonk_constrains: &[PlonkGate]) -> Self {
-        let mut uses: BTreeMap<String, usize> = BTreeMap::new();
-        let plonk_constrains_len = plonk_constrains.len();
-        for (i, c) in plonk_constrains.iter().enumerate() {
-            if (i % 10000) == 0 {
-                log::trace!("Plonk info constraint processing... {i}/{plonk_constrains_len}");
-            }
-            let k = c.str_key();
-
-            uses.entry(k.clone()).and_modify(|e| *e += 1).or_insert_with(|| 1);
+        log::trace!("Plonk info constraint processing... {}", plonk_constrains.len());
+        // Placed as `plonk_setup_compressor` places them, two gates of a kind a half row: a
+        // kind opens a new row, or the second half of a row whose first half is full, and the
+        // rest of a half row it leaves open stays unused.
+        let mut open: HashMap<GateKey, usize> = HashMap::new();
+        let (mut N, mut half_rows) = (0, 0);
+        for c in plonk_constrains {
+            let n_used = open.entry(c.key()).or_default();
+            *n_used = match *n_used {
+                1 => {
+                    half_rows += 1;
+                    0
+                }
+                3 => 0,
+                _ if half_rows > 0 => {
+                    half_rows -= 1;
+                    3
+                }
+                _ => {
+                    N += 1;
+                    1
+                }
+            };
         }
-        let mut result = uses.values().collect::<Vec<_>>();
-        result.sort(); // sort by asc
-
-        let mut N = result.iter().fold(0, |acc, x| acc + (**x - 1) / 2 + 1);
-        N = (N - 1) / 2 + 1;
 
         Self {
             N,
+            n_kinds: open.len(),
             // n_constraints: r1cs.constraints.len(),
             // n_plonk_gates: plonk_constrains_len,
             // n_plonk_adds: plonk_additions.len(),
@@ -78,143 +137,89 @@ impl NormalPlonkInfo {
     }
 }
 
-#[derive(Debug)]
-pub(crate) struct CustomGateInfo {
-    pub(crate) poseidon_id: u64,
-    pub(crate) c_mul_add_id: u64,
-    pub(crate) fft_params: BTreeMap<usize, Vec<FGL>>,
-    pub(crate) ev_pol_id: u64,
-
-    pub(crate) n_poseidon: u64,
-    pub(crate) n_c_mul_add: u64,
-    pub(crate) n_fft: u64,
-    pub(crate) n_ev_pol: u64,
-}
-
-impl CustomGateInfo {
-    // equal to `typeof customGatesInfo.FFT4Parameters[cgu.id] !== "undefined"` in js
-    // Defined: properer index and has value.
-    #[inline(always)]
-    pub fn check_fft_param_defined(fft_params: &BTreeMap<usize, Vec<FGL>>, index: u64) -> bool {
-        fft_params.get(&(index as usize)).is_some()
-    }
-
-    fn from_r1cs(r1cs: &R1CS<GL>) -> Self {
-        let mut c_mul_add_id = 0;
-        let mut poseidon_id = 0;
-        let mut ev_pol_id = 0;
-        // let mut fft_params = vec![vec![]; r1cs.custom_gates.len()];
-        let mut fft_params: BTreeMap<usize, Vec<FGL>> = BTreeMap::new();
-
-        for (i, c) in r1cs.custom_gates.iter().enumerate() {
-            match c.template_name.as_str() {
-                "CMulAdd" => {
-                    c_mul_add_id = i as u64;
-                    assert!(c.parameters.is_empty());
-                }
-                "Poseidon12" => {
-                    poseidon_id = i as u64;
-                    assert!(c.parameters.is_empty());
-                }
-                "EvPol4" => {
-                    ev_pol_id = i as u64;
-                    assert!(c.parameters.is_empty());
-                }
-                "FFT4" => {
-                    fft_params.insert(i, c.parameters.clone());
-                }
-                _ => panic!("Invalid custom gate {}", c.template_name),
-            }
-        }
-
-        let mut n_c_mul_add = 0;
-        let mut n_poseidon = 0;
-        let mut n_fft = 0;
-        let mut n_ev_pol = 0;
-        for c in r1cs.custom_gates_uses.iter() {
-            if c.id == c_mul_add_id {
-                n_c_mul_add += 1;
-            } else if c.id == poseidon_id {
-                n_poseidon += 1;
-            } else if Self::check_fft_param_defined(&fft_params, c.id) {
-                n_fft += 1;
-            } else if c.id == ev_pol_id {
-                n_ev_pol += 1;
-            } else {
-                panic!("Custom gate not defined {}", c.id);
-            }
-        }
-
-        Self {
-            poseidon_id,
-            c_mul_add_id,
-            fft_params,
-            ev_pol_id,
-            n_poseidon,
-            n_c_mul_add,
-            n_fft,
-            n_ev_pol,
-        }
-    }
-}
-
 pub struct PlonkSetupRenderInfo {
     n_used: usize,
     n_bits: usize,
     n_publics: usize,
-    pub(crate) pg: Vec<PlonkGate>,
-    pub(crate) pa: Vec<PlonkAdd>,
-    custom_gates_info: CustomGateInfo,
+    used_gates: UsedGates,
+    pub(crate) report: SetupReport,
     // pub(crate) plonk_info: NormalPlonkInfo, // Never used.
 }
 
 impl PlonkSetupRenderInfo {
-    pub fn plonk_setup_render(r1cs: &R1CS<GL>, opts: &Options) -> Self {
-        // 1. r1cs to plonk
-        let (plonk_constrains, plonk_additions) = r1cs2plonk(r1cs);
-
-        // 2. get normal plonk info
-        let plonk_info = NormalPlonkInfo::new(&plonk_constrains);
-        // 3. get custom gate info
-
-        let custom_gates_info = CustomGateInfo::from_r1cs(r1cs);
-
-        // 4. calculate columns,rows,constraints info.
-        let n_publics = r1cs.num_inputs + r1cs.num_outputs - 1;
+    pub fn plonk_setup_render(plonk: &PlonkCircuit, opts: &Options) -> Result<Self> {
+        // 1. get normal plonk info
+        let plonk_info = NormalPlonkInfo::new(&plonk.gates);
+        // 2. get custom gate info
+        let used_gates =
+            UsedGates::new(&plonk.custom_gates, &plonk.custom_gates_uses, &opts.gates)?;
+
+        // 3. calculate columns,rows,constraints info.
+        let n_publics = plonk.n_publics;
         let n_public_rows = (n_publics - 1) / 12 + 1;
 
-        log::debug!("{n_publics} {n_public_rows} {} {:?}", plonk_info.N, custom_gates_info);
-        let n_used = n_public_rows
-            + plonk_info.N
-            + (custom_gates_info.n_c_mul_add
-                + custom_gates_info.n_poseidon * 31
-                + custom_gates_info.n_fft * 2
-                + custom_gates_info.n_ev_pol * 2) as usize;
-
-        let mut n_bits = helper::log2_any(n_used - 1) + 1;
-        if opts.force_bits > 0 {
-            n_bits = opts.force_bits;
-        }
+        log::debug!("{n_publics} {n_public_rows} {} {:?}", plonk_info.N, used_gates);
+        let report = SetupReport::new(
+            plonk.n_constraints,
+            plonk.gates.len(),
+            plonk.adds.len(),
+            plonk_info.n_kinds,
+            n_publics,
+            n_public_rows,
+            plonk_info.N,
+            opts.gates
+                .gates()
+                .iter()
+                .zip(used_gates.counts.iter())
+                .map(|(g, n)| GateRows::new(g.template_name(), *n, g.rows()))
+                .collect(),
+            opts.force_bits,
+        );
+        let (n_used, n_bits) = (report.n_used, report.n_bits);
+        log::info!("compressor12: {n_used} rows used, nBits {n_bits}");
+
+        Ok(Self { n_used, n_bits, n_publics, used_gates, report })
+    }
+}
 
+/// The ids of the constant polynomials of compressor12, resolved once to write the columns
+/// directly.
+struct ConstPolIds {
+    s: usize,
+    c: usize,
+    gate: usize,
+    /// The selectors of each custom gate.
+    selectors: Vec<Vec<usize>>,
+}
+
+impl ConstPolIds {
+    fn new(const_pols: &PolsArray, pil: &PIL, gates: &GateRegistry) -> Self {
+        let id =
+            |name: &str| const_pols.get_pol_id(pil, &Compressor.to_string(), &name.to_string(), 0);
         Self {
-            n_used,
-            n_bits,
-            n_publics,
-            pg: plonk_constrains,
-            pa: plonk_additions,
-            custom_gates_info,
+            s: id(&S.to_string()),
+            c: id(&C.to_string()),
+            gate: id(&GATE.to_string()),
+            selectors: gates
+                .gates()
+                .iter()
+                .map(|g| g.selectors().iter().map(|s| id(s)).collect())
+                .collect(),
         }
     }
 }
 
 pub fn plonk_setup_compressor(
-    r1cs: &R1CS<GL>,
+    plonk: &PlonkCircuit,
     pil: &PIL,
     plonk_setup_info: &PlonkSetupRenderInfo,
-) -> (PolsArray, Vec<Vec<u64>>) {
-    // 1. construct init ConstantPolsArray
+    gates: &GateRegistry,
+) -> Result<(PolsArray, Vec<Vec<u64>>)> {
+    // 1. construct init ConstantPolsArray, all 0: only the non 0 values are written below.
     log::debug!("pil: new constant");
     let mut const_pols = PolsArray::new(pil, PolKind::Constant);
+    let ids = ConstPolIds::new(&const_pols, pil, gates);
+    let pols = &mut const_pols.array;
 
     let n_used = plonk_setup_info.n_used;
     let n_publics = plonk_setup_info.n_publics;
@@ -227,35 +232,9 @@ pub fn plonk_setup_compressor(
     let mut r = 0;
 
     // Paste public inputs.
-    for i in 0..n_public_rows {
-        let index = r + i;
-        for pol_name in [EVPOL4, CMULADD, GATE, POSEIDON12, PARTIAL, FFT4] {
-            const_pols.set_matrix(
-                pil,
-                &Compressor.to_string(),
-                &pol_name.to_string(),
-                0,
-                index,
-                FGL::ZERO,
-            );
-        }
-        for k in 0..12 {
-            const_pols.set_matrix(
-                pil,
-                &Compressor.to_string(),
-                &C.to_string(),
-                k,
-                index,
-                FGL::ZERO,
-            );
-        }
-    }
     for i in 0..n_publics {
         s_map[i % 12][r + i / 12] = 1 + i as u64;
     }
-    for i in n_publics..(n_public_rows * 12) {
-        s_map[i % 12][r + i / 12] = 0;
-    }
     r += n_public_rows;
 
     // 3. Paste plonk constraints.
@@ -264,41 +243,28 @@ pub fn plonk_setup_compressor(
         row: usize,
         n_used: usize,
     }
-    // Paste plonk constraints.
-    let mut partial_rows: BTreeMap<String, ParRow> = BTreeMap::new();
-    let mut half_rows: Vec<ParRow> = vec![];
-    let plonk_constraints = &plonk_setup_info.pg;
+    let mut partial_rows: HashMap<GateKey, ParRow> = HashMap::new();
+    let mut half_rows: VecDeque<ParRow> = VecDeque::new();
+    let plonk_constraints = &plonk.gates;
     for (i, c) in plonk_constraints.iter().enumerate() {
-        if (i % 10000) == 0 {
+        if (i % 100000) == 0 {
             log::trace!("Processing constraint... {}/{}", i, plonk_constraints.len())
         }
-        let k = c.str_key();
-        let pr = partial_rows.get_mut(&k);
-        if let Some(pr) = pr {
+        let k = c.key();
+        if let Some(pr) = partial_rows.get_mut(&k) {
             s_map[pr.n_used * 3][pr.row] = c.0 as u64;
             s_map[pr.n_used * 3 + 1][pr.row] = c.1 as u64;
             s_map[pr.n_used * 3 + 2][pr.row] = c.2 as u64;
             pr.n_used += 1;
             if pr.n_used == 2 {
-                half_rows.push(*pr);
+                half_rows.push_back(*pr);
                 partial_rows.remove(&k);
             } else if pr.n_used == 4 {
                 partial_rows.remove(&k);
             }
-        } else if !half_rows.is_empty() {
-            let mut pr = half_rows.shift().unwrap();
-            let index = pr.row;
-            for (i, value) in
-                [9_usize, 6, 7, 8, 10, 11].iter().zip([c.3, c.4, c.5, c.6, c.7, FGL::ZERO].iter())
-            {
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &C.to_string(),
-                    *i,
-                    index,
-                    *value,
-                );
+        } else if let Some(mut pr) = half_rows.pop_front() {
+            for (i, value) in [9_usize, 6, 7, 8, 10, 11].iter().zip([c.3, c.4, c.5, c.6, c.7]) {
+                pols[ids.c + i][pr.row] = value;
             }
 
             s_map[pr.n_used * 3][pr.row] = c.0 as u64;
@@ -307,32 +273,10 @@ pub fn plonk_setup_compressor(
             pr.n_used += 1;
             partial_rows.insert(k, pr);
         } else {
-            let index = r;
-            for (i, value) in
-                [3_usize, 0, 1, 2, 4, 5].iter().zip([c.3, c.4, c.5, c.6, c.7, FGL::ZERO].iter())
-            {
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &C.to_string(),
-                    *i,
-                    index,
-                    *value,
-                );
-            }
-            for (pol_name, value) in [GATE, POSEIDON12, PARTIAL, CMULADD, EVPOL4, FFT4]
-                .iter()
-                .zip(vec![FGL::ONE, FGL::ZERO, FGL::ZERO, FGL::ZERO, FGL::ZERO, FGL::ZERO])
-            {
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &pol_name.to_string(),
-                    0,
-                    index,
-                    value,
-                );
+            for (i, value) in [3_usize, 0, 1, 2, 4].iter().zip([c.3, c.4, c.5, c.6, c.7]) {
+                pols[ids.c + i][r] = value;
             }
+            pols[ids.gate][r] = FGL::ONE;
 
             s_map[0][r] = c.0 as u64;
             s_map[1][r] = c.1 as u64;
@@ -342,14 +286,13 @@ pub fn plonk_setup_compressor(
         }
     }
 
-    // Terminate the empty rows (Copy the same constraint)
-    for (_, pr) in partial_rows.iter_mut() {
+    // Terminate the empty rows (Copy the same constraint), the half rows left keep their
+    // second half 0.
+    for pr in partial_rows.values() {
         if pr.n_used == 1 {
             s_map[3][pr.row] = s_map[0][pr.row];
             s_map[4][pr.row] = s_map[1][pr.row];
             s_map[5][pr.row] = s_map[2][pr.row];
-            pr.n_used += 1;
-            half_rows.push(*pr);
         } else if pr.n_used == 3 {
             s_map[9][pr.row] = s_map[6][pr.row];
             s_map[10][pr.row] = s_map[7][pr.row];
@@ -359,410 +302,146 @@ pub fn plonk_setup_compressor(
         }
     }
 
-    for hr in half_rows.iter() {
-        s_map[6][hr.row] = 0;
-        s_map[7][hr.row] = 0;
-        s_map[8][hr.row] = 0;
-        s_map[9][hr.row] = 0;
-        s_map[10][hr.row] = 0;
-        s_map[11][hr.row] = 0;
-        for i in [9, 6, 7, 8, 10, 11] {
-            const_pols.set_matrix(
-                pil,
-                &Compressor.to_string(),
-                &C.to_string(),
-                i,
-                hr.row,
-                FGL::ZERO,
-            );
-        }
-    }
-
     // 4. Generate Custom Gates
-    let custom_gates_info = &plonk_setup_info.custom_gates_info;
-    for (i, cgu) in r1cs.custom_gates_uses.iter().enumerate() {
+    let used_gates = &plonk_setup_info.used_gates;
+    for (i, cgu) in plonk.custom_gates_uses.iter().enumerate() {
         if (i % 10000) == 0 {
-            log::trace!("Processing custom gates... {}/{}", i, r1cs.custom_gates_uses.len());
-        }
-        if cgu.id == custom_gates_info.poseidon_id {
-            assert_eq!(cgu.signals.len(), 31 * 12);
-            for j in 0..31 {
-                let index = r + j;
-                for k in 0..12 {
-                    s_map[k][r + j] = cgu.signals[j * 12 + k];
-                    const_pols.set_matrix(
-                        pil,
-                        &Compressor.to_string(),
-                        &C.to_string(),
-                        k,
-                        index,
-                        FGL::from(CPOSEIDON[j * 12 + k]),
-                    );
-                }
-                for pol_name in [GATE, CMULADD, EVPOL4, FFT4] {
-                    const_pols.set_matrix(
-                        pil,
-                        &Compressor.to_string(),
-                        &pol_name.to_string(),
-                        0,
-                        index,
-                        FGL::ZERO,
-                    );
-                }
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &POSEIDON12.to_string(),
-                    0,
-                    index,
-                    if j < 30 { FGL::ONE } else { FGL::ZERO },
-                );
-                let tt = if !(4..26).contains(&j) { FGL::ZERO } else { FGL::ONE };
-                let tt = if j < 30 { tt } else { FGL::ZERO };
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &PARTIAL.to_string(),
-                    0,
-                    index,
-                    tt,
-                );
-            }
-            r += 31;
-        } else if cgu.id == custom_gates_info.c_mul_add_id {
-            if r < n_used {
-                for (j, map) in s_map.iter_mut().enumerate().take(12) {
-                    map[r] = cgu.signals[j];
-                }
-            }
-            let index = r;
-            for pol_name in [GATE, POSEIDON12, PARTIAL, EVPOL4, FFT4] {
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &pol_name.to_string(),
-                    0,
-                    index,
-                    FGL::ZERO,
-                );
-            }
-            const_pols.set_matrix(
-                pil,
-                &Compressor.to_string(),
-                &CMULADD.to_string(),
-                0,
-                index,
-                FGL::ONE,
-            );
-            for i in 0..12 {
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &C.to_string(),
-                    i,
-                    index,
-                    if i == 9 || i == 10 { FGL::ONE } else { FGL::ZERO },
-                );
-            }
-
-            r += 1;
-        } else if CustomGateInfo::check_fft_param_defined(&custom_gates_info.fft_params, cgu.id) {
-            for (j, map) in s_map.iter_mut().enumerate().take(12) {
-                map[r] = cgu.signals[j];
-                map[r + 1] = cgu.signals[12 + j];
-            }
-            let index = r;
-            for pol_name in [GATE, POSEIDON12, CMULADD, PARTIAL, EVPOL4] {
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &pol_name.to_string(),
-                    0,
-                    index,
-                    FGL::ZERO,
-                );
-            }
-            const_pols.set_matrix(
-                pil,
-                &Compressor.to_string(),
-                &FFT4.to_string(),
-                0,
-                index,
-                FGL::ONE,
-            );
-            let index = r + 1;
-            for pol_name in [GATE, POSEIDON12, CMULADD, PARTIAL, EVPOL4, FFT4] {
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &pol_name.to_string(),
-                    0,
-                    index,
-                    FGL::ZERO,
-                );
-            }
-
-            let t = custom_gates_info.fft_params[&(cgu.id as usize)][3];
-            let scale = custom_gates_info.fft_params[&(cgu.id as usize)][2];
-            let incW = custom_gates_info.fft_params[&(cgu.id as usize)][1];
-            let firstW = custom_gates_info.fft_params[&(cgu.id as usize)][0];
-            let firstW2 = firstW * firstW;
-
-            let index = r;
-            if t.as_int() == 4 {
-                for i in [6, 7, 8] {
-                    const_pols.set_matrix(
-                        pil,
-                        &Compressor.to_string(),
-                        &C.to_string(),
-                        i,
-                        index,
-                        FGL::ZERO,
-                    );
-                }
-                for (i, value) in [0, 1, 2, 3, 4, 5].iter().zip(
-                    [
-                        scale,
-                        scale * firstW2,
-                        scale * firstW,
-                        scale * firstW * firstW2,
-                        scale * firstW * incW,
-                        scale * firstW * firstW2 * incW,
-                    ]
-                    .iter(),
-                ) {
-                    const_pols.set_matrix(
-                        pil,
-                        &Compressor.to_string(),
-                        &C.to_string(),
-                        *i,
-                        index,
-                        *value,
-                    );
-                }
-            } else if t.as_int() == 2 {
-                for i in [0, 1, 2, 3, 4, 5] {
-                    const_pols.set_matrix(
-                        pil,
-                        &Compressor.to_string(),
-                        &C.to_string(),
-                        i,
-                        index,
-                        FGL::ZERO,
-                    );
-                }
-                for (i, value) in
-                    [6, 7, 8].iter().zip([scale, scale * firstW, scale * firstW * incW].iter())
-                {
-                    const_pols.set_matrix(
-                        pil,
-                        &Compressor.to_string(),
-                        &C.to_string(),
-                        *i,
-                        index,
-                        *value,
-                    );
-                }
-            } else {
-                panic!("invalid FFT4 type: {t}");
-            }
-
-            let index = r;
-            for i in [9, 10, 11] {
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &C.to_string(),
-                    i,
-                    index,
-                    FGL::ZERO,
-                );
-            }
-            for k in 0..12 {
-                let index = r + 1;
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &C.to_string(),
-                    k,
-                    index,
-                    FGL::ZERO,
-                );
-            }
-            r += 2;
-        } else if cgu.id == custom_gates_info.ev_pol_id {
-            for (j, map) in s_map.iter_mut().enumerate().take(12) {
-                map[r] = cgu.signals[j];
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &C.to_string(),
-                    j,
-                    r,
-                    FGL::ZERO,
-                );
-            }
-            for (j, map) in s_map.iter_mut().enumerate().take(9) {
-                map[r + 1] = cgu.signals[12 + j];
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &C.to_string(),
-                    j,
-                    r + 1,
-                    FGL::ZERO,
-                );
-            }
-            for (j, map) in s_map.iter_mut().enumerate().take(12).skip(9) {
-                map[r + 1] = 0;
-
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &C.to_string(),
-                    j,
-                    r + 1,
-                    FGL::ZERO,
-                );
-            }
-            let index = r;
-            for pol_name in [GATE, POSEIDON12, CMULADD, PARTIAL, FFT4] {
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &pol_name.to_string(),
-                    0,
-                    index,
-                    FGL::ZERO,
-                );
-            }
-            const_pols.set_matrix(
-                pil,
-                &Compressor.to_string(),
-                &EVPOL4.to_string(),
-                0,
-                index,
-                FGL::ONE,
-            );
-
-            let index = r + 1;
-            for pol_name in [GATE, POSEIDON12, CMULADD, PARTIAL, EVPOL4, FFT4] {
-                const_pols.set_matrix(
-                    pil,
-                    &Compressor.to_string(),
-                    &pol_name.to_string(),
-                    0,
-                    index,
-                    FGL::ZERO,
-                );
-            }
-            r += 2
-        } else {
-            panic!("Custom gate not defined: {}", cgu.id);
+            log::trace!("Processing custom gates... {}/{}", i, plonk.custom_gates_uses.len());
         }
+        let g = used_gates.templates[cgu.id as usize];
+        let gate = &gates.gates()[g];
+        gate.place_witness(&cgu.signals, &mut s_map, r)?;
+        let mut consts = GateConsts { pols: &mut pols[..], c: ids.c, selectors: &ids.selectors[g] };
+        gate.fill_constants(&plonk.custom_gates[cgu.id as usize].parameters, &mut consts, r);
+        r += gate.rows();
     }
 
-    // 5. Calculate S Polynomials
+    // 5. Calculate S Polynomials, the powers of w computed by chunks in parallel
+    const CHUNK: usize = 1 << 12;
     let N = 1 << plonk_setup_info.n_bits;
+    log::trace!("Preparing S... {}", N);
     let ks = helper::get_ks(11);
-    let mut w = FGL::ONE;
-    for i in 0..N {
-        if (i % 10000) == 0 {
-            log::trace!("Preparing S... {}/{}", i, N);
+    let g = starky::constant::MG.0[plonk_setup_info.n_bits];
+    pols[ids.s].par_chunks_mut(CHUNK).enumerate().for_each(|(k, chunk)| {
+        let mut w = g.exp((k * CHUNK) as u64);
+        for x in chunk.iter_mut() {
+            *x = w;
+            w = w * g;
         }
-        const_pols.set_matrix(pil, &Compressor.to_string(), &S.to_string(), 0, i, w);
-        for j in 1..12 {
-            const_pols.set_matrix(
-                pil,
-                &Compressor.to_string(),
-                &S.to_string(),
-                j,
-                i,
-                w * ks[j - 1],
-            );
+    });
+    let (s0, rest) = pols[ids.s..ids.s + 12].split_first_mut().unwrap();
+    rest.par_iter_mut().zip(ks.par_iter()).for_each(|(s, k)| {
+        for (x, w) in s.iter_mut().zip(s0.iter()) {
+            *x = *w * *k;
         }
-        w = w * (starky::constant::MG.0[plonk_setup_info.n_bits]);
-    }
+    });
 
-    #[derive(Debug)]
-    struct Grid {
-        row: usize,
-        col: usize,
-    }
-    let mut last_signal: BTreeMap<u64, Grid> = BTreeMap::new();
-    for i in 0..r {
-        if (i % 10000) == 0 {
+    let mut last_signal: HashMap<u64, (usize, usize)> = HashMap::new();
+    for i in 0..r.min(n_used) {
+        if (i % 100000) == 0 {
             log::trace!("Connection S... {}/{}", i, r);
         }
-        for (j, map) in s_map.iter_mut().enumerate().take(12) {
-            if i < n_used {
-                let key = map[i];
-                if key == 0 {
-                    continue;
-                }
-                let ls = last_signal.get(&key);
-                if let Some(ls) = ls {
-                    // connect and swap the value.
-                    let left = const_pols.get(
-                        pil,
-                        &Compressor.to_string(),
-                        &S.to_string(),
-                        ls.col,
-                        ls.row,
-                    );
-                    let right = const_pols.get(pil, &Compressor.to_string(), &S.to_string(), j, i);
-                    const_pols.set_matrix(pil, &Compressor.to_string(), &S.to_string(), j, i, left);
-                    const_pols.set_matrix(
-                        pil,
-                        &Compressor.to_string(),
-                        &S.to_string(),
-                        ls.col,
-                        ls.row,
-                        right,
-                    );
-                } else {
-                    last_signal.insert(key, Grid { col: j, row: i });
-                }
+        for (j, map) in s_map.iter().enumerate() {
+            let key = map[i];
+            if key == 0 {
+                continue;
+            }
+            if let Some((col, row)) = last_signal.get(&key) {
+                // connect and swap the value.
+                let left = pols[ids.s + col][*row];
+                let right = pols[ids.s + j][i];
+                pols[ids.s + j][i] = left;
+                pols[ids.s + col][*row] = right;
+            } else {
+                last_signal.insert(key, (j, i));
             }
         }
     }
 
-    // 6. Fill unused rows.
-    while r < N {
-        if (r % 100000) == 0 {
-            log::trace!("Empty gates... {}/{}", r, N);
-        }
-        let index = r;
-        for pol_name in [EVPOL4, CMULADD, GATE, POSEIDON12, PARTIAL, FFT4] {
-            const_pols.set_matrix(
-                pil,
-                &Compressor.to_string(),
-                &pol_name.to_string(),
-                0,
-                index,
-                FGL::ZERO,
-            );
-        }
-        for k in 0..12 {
-            const_pols.set_matrix(
-                pil,
-                &Compressor.to_string(),
-                &C.to_string(),
-                k,
-                index,
-                FGL::ZERO,
-            );
-        }
-        r += 1;
-    }
+    // 6. The unused rows are left 0.
     // construct Lagrange Basis Polynomial: Li(x)
     for i in 0..n_public_rows {
         let np = format!("L{}", i + 1);
-        for j in 0..N {
-            const_pols.set_matrix(pil, &Global.to_string(), &np, 0, j, FGL::ZERO);
-        }
-        const_pols.set_matrix(pil, &Global.to_string(), &np, 0, i, FGL::ONE);
+        let l = const_pols.get_pol_id(pil, &Global.to_string(), &np, 0);
+        const_pols.array[l][i] = FGL::ONE;
+    }
+
+    Ok((const_pols, s_map))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::exec_file::{hash, write_exec_file, ExecHeader};
+    use crate::verifier_circuit::stark_verifier::StarkVerifierCircuit;
+    use starky::pil2circom::StarkOption;
+    use starky::prover_config::ProverConfig;
+
+    #[test]
+    fn test_plonk_rows() {
+        let gate =
+            |k: u64| PlonkGate(1, 2, 3, FGL::from(k), FGL::ZERO, FGL::ZERO, FGL::ZERO, FGL::ONE);
+        let rows = |kinds: &[u64]| {
+            NormalPlonkInfo::new(&kinds.iter().map(|k| gate(*k)).collect::<Vec<_>>()).N
+        };
+        // a kind left with one gate keeps the second half of its row
+        assert_eq!(rows(&[1, 2]), 2);
+        assert_eq!(rows(&[1, 1, 2]), 1);
+        assert_eq!(rows(&[1, 1, 2, 2, 2]), 2);
+        assert_eq!(rows(&[1, 1, 1, 1, 2, 3]), 3);
+        assert_eq!(NormalPlonkInfo::new(&[gate(1), gate(2), gate(1)]).n_kinds, 2);
     }
 
-    (const_pols, s_map)
+    #[test]
+    fn test_setup_golden() {
+        // the setup of the verifier of fib as an R1CS is the one of the sequential conversion
+        // and placement the parallel ones replaced, and the one of its gates doesn't change
+        // silently either. The gadgets of the verifier change the hashes too.
+        let options = StarkOption {
+            enable_input: false,
+            verkey_input: false,
+            skip_main: false,
+            agg_stage: false,
+        };
+        let verifier = StarkVerifierCircuit::load(
+            "../starky/data/starkStruct.json.gl",
+            "../starky/data/fib.pil.json.gl",
+            "../starky/data/fib.const.gl",
+            &options,
+            &ProverConfig::default(),
+        )
+        .unwrap();
+        // the hashes of the constant polynomials, the sMap and the exec file
+        let hashes = |res: &PlonkSetup, n_signals: usize| {
+            let mut const_pols = vec![];
+            res.const_pols.save(&mut const_pols).unwrap();
+            let s_map: Vec<u8> = res.s_map.iter().flatten().flat_map(|s| s.to_le_bytes()).collect();
+            let mut exec = vec![];
+            let header = ExecHeader::new(12, b"fib", &res.pil_str, n_signals);
+            write_exec_file(&mut exec, &header, &res.plonk_additions, &res.s_map).unwrap();
+            [hash(&const_pols), hash(&s_map), hash(&exec)]
+        };
+
+        let (r1cs, _) = verifier.build(None).unwrap().into_r1cs();
+        let res = PlonkSetup::new(&r1cs, &Options::new(0)).unwrap();
+        assert_eq!(
+            hashes(&res, r1cs.num_variables),
+            [
+                [0x0ca029f52457527b, 0x15f3b4f4ffbc72ca, 0x6deb89ab77cd6cd0, 0x21a55923e09a9a3a],
+                [0x0295fa46002836cb, 0x3ffdd461f0ea2da2, 0xcd229f85237d6fd5, 0xfdd019d966b4b29f],
+                [0xf8187c65c8ad3667, 0x0ef2d4b51f08176a, 0x58d30a6c699ea57e, 0xa268a09979541bda],
+            ]
+        );
+
+        let (plonk, w) = verifier.build(None).unwrap().into_plonk();
+        let res = PlonkSetup::from_plonk(plonk, &Options::new(0)).unwrap();
+        assert_eq!(
+            hashes(&res, w.len()),
+            [
+                [0x2d06313f26ebffae, 0x8d47bea0c80b09ae, 0x5bc2ea31dd6a5ffa, 0xc4298f07da275ee0],
+                [0xa550627dad2c2dba, 0x59b9b3e4d60c0192, 0x1b4ae31db334d1d1, 0x740ce7e69e223c0b],
+                [0xd2609bd936cb0d85, 0x4ded453248d7d2e1, 0x03ef98da4d6b192d, 0x3dd538105981e8cf],
+            ]
+        );
+    }
 }
@@ -0,0 +1,202 @@
+//! What the rows of a compressor12 setup are spent on, and what would make it fit in a power of
+//! two less.
+use anyhow::Result;
+use serde::Serialize;
+use starky::helper::log2_any;
+use std::fmt;
+
+/// The rows taken by the uses of a custom gate.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct GateRows {
+    pub name: String,
+    pub count: usize,
+    pub rows_each: usize,
+    pub rows: usize,
+}
+
+impl GateRows {
+    pub fn new(name: &str, count: usize, rows_each: usize) -> Self {
+        Self { name: name.to_string(), count, rows_each, rows: count * rows_each }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SetupReport {
+    pub n_constraints: usize,
+    pub n_plonk_gates: usize,
+    pub n_plonk_additions: usize,
+    /// The distinct coefficients of the plonk gates, the gates sharing them share rows.
+    pub n_gate_kinds: usize,
+    pub n_publics: usize,
+    pub public_rows: usize,
+    pub plonk_rows: usize,
+    pub custom_gates: Vec<GateRows>,
+    pub n_used: usize,
+    /// The smallest nBits `n_used` fits in.
+    pub min_n_bits: usize,
+    pub n_bits: usize,
+    pub forced: bool,
+    /// The unused rows of the 2^n_bits, negative when `n_bits` is forced too small.
+    pub slack: i64,
+    pub suggestions: Vec<String>,
+}
+
+impl SetupReport {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        n_constraints: usize,
+        n_plonk_gates: usize,
+        n_plonk_additions: usize,
+        n_gate_kinds: usize,
+        n_publics: usize,
+        public_rows: usize,
+        plonk_rows: usize,
+        custom_gates: Vec<GateRows>,
+        force_bits: usize,
+    ) -> Self {
+        let n_used = public_rows + plonk_rows + custom_gates.iter().map(|g| g.rows).sum::<usize>();
+        let min_n_bits = log2_any(n_used - 1) + 1;
+        let n_bits = if force_bits > 0 { force_bits } else { min_n_bits };
+        let mut report = Self {
+            n_constraints,
+            n_plonk_gates,
+            n_plonk_additions,
+            n_gate_kinds,
+            n_publics,
+            public_rows,
+            plonk_rows,
+            custom_gates,
+            n_used,
+            min_n_bits,
+            n_bits,
+            forced: force_bits > 0,
+            slack: (1i64 << n_bits) - n_used as i64,
+            suggestions: vec![],
+        };
+        report.suggestions = report.suggest();
+        report
+    }
+
+    /// Writes the report as JSON.
+    pub fn save(&self, path: &str) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Whether the rows used fit in 2^n_bits.
+    pub fn fits(&self) -> bool {
+        self.slack >= 0
+    }
+
+    fn suggest(&self) -> Vec<String> {
+        let mut res = vec![];
+        if self.n_bits < self.min_n_bits {
+            res.push(format!(
+                "force_n_bits {} is too small, {} rows need at least {}",
+                self.n_bits, self.n_used, self.min_n_bits
+            ));
+        } else if self.n_bits > self.min_n_bits {
+            res.push(format!(
+                "force_n_bits {} is {} above the {} the rows need",
+                self.n_bits,
+                self.n_bits - self.min_n_bits,
+                self.min_n_bits
+            ));
+        }
+        if self.min_n_bits <= 1 {
+            return res;
+        }
+
+        let target = 1usize << (self.min_n_bits - 1);
+        let excess = self.n_used - target;
+        res.push(format!("{excess} rows over 2^{}, to get there:", self.min_n_bits - 1));
+        let n = res.len();
+        for g in self.custom_gates.iter().filter(|g| g.rows >= excess) {
+            res.push(format!(
+                "remove {} of the {} {} ({} rows each)",
+                excess.div_ceil(g.rows_each),
+                g.count,
+                g.name,
+                g.rows_each
+            ));
+        }
+        if self.plonk_rows >= excess {
+            res.push(format!(
+                "remove {excess} of the {} plonk rows, 4 gates a row with the same coefficients, \
+                 2 with different ones",
+                self.plonk_rows
+            ));
+        }
+        if self.public_rows > excess {
+            res.push(format!("publish {} fewer of the {} publics", excess * 12, self.n_publics));
+        }
+        if res.len() == n {
+            res.push("no kind of gate alone takes that many rows, cut several".to_string());
+        }
+        res
+    }
+}
+
+impl fmt::Display for SetupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "constraints:        {}", self.n_constraints)?;
+        writeln!(f, "plonk gates:        {}", self.n_plonk_gates)?;
+        writeln!(f, "plonk additions:    {}", self.n_plonk_additions)?;
+        writeln!(f, "gate coefficients:  {}", self.n_gate_kinds)?;
+        writeln!(f, "rows:")?;
+        writeln!(f, "  publics           {:>10}  ({} publics)", self.public_rows, self.n_publics)?;
+        writeln!(f, "  plonk             {:>10}", self.plonk_rows)?;
+        for g in self.custom_gates.iter() {
+            writeln!(f, "  {:<17} {:>10}  ({} x {})", g.name, g.rows, g.count, g.rows_each)?;
+        }
+        writeln!(f, "  total             {:>10}", self.n_used)?;
+        writeln!(
+            f,
+            "nBits:              {}{} (2^{} = {} rows, slack {})",
+            self.n_bits,
+            if self.forced { " forced" } else { "" },
+            self.n_bits,
+            1u64 << self.n_bits,
+            self.slack
+        )?;
+        for s in self.suggestions.iter() {
+            writeln!(f, "* {s}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn report(force_bits: usize) -> SetupReport {
+        let custom_gates = vec![
+            GateRows::new("CMulAdd", 100, 1),
+            GateRows::new("Poseidon12", 300, 31),
+            GateRows::new("FFT4", 40, 2),
+            GateRows::new("EvPol4", 20, 2),
+        ];
+        SetupReport::new(20000, 30000, 9000, 50, 44, 4, 7000, custom_gates, force_bits)
+    }
+
+    #[test]
+    fn test_setup_report() {
+        // 4 + 7000 + 100 + 9300 + 80 + 40 rows
+        let r = report(0);
+        assert_eq!(r.n_used, 16524);
+        assert_eq!(r.min_n_bits, 15);
+        assert_eq!((r.n_bits, r.slack), (15, 32768 - 16524));
+        assert!(r.fits());
+        assert_eq!(r.suggestions[0], "140 rows over 2^14, to get there:");
+        assert_eq!(r.suggestions[1], "remove 5 of the 300 Poseidon12 (31 rows each)");
+        assert!(r.suggestions[2].starts_with("remove 140 of the 7000 plonk rows"));
+        assert_eq!(r.suggestions.len(), 3);
+
+        let r = report(14);
+        assert!(!r.fits());
+        assert_eq!(r.slack, 16384 - 16524);
+        assert_eq!(r.suggestions[0], "force_n_bits 14 is too small, 16524 rows need at least 15");
+        assert!(r.to_string().contains("nBits:              14 forced"));
+    }
+}
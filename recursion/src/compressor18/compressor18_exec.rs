@@ -0,0 +1,23 @@
+use super::compressor18_pil::N_COLS;
+use crate::compressor12::compressor12_exec::Executor;
+use anyhow::{ensure, Result};
+use starky::prover_config::ProverConfig;
+
+// exec phase:
+// input files: .wasm, .exec,  .pil, zkin.json(input file),
+// output: .cm
+// The same as compressor12_exec, with the 18 columns of the sMap in the .exec file.
+pub fn exec(
+    input_file: &str,
+    wasm_file: &str,
+    pil_file: &str,
+    exec_file: &str,
+    commit_file: &str,
+    config: &ProverConfig,
+) -> Result<()> {
+    config.install(|| {
+        let mut executor = Executor::new(wasm_file, pil_file, exec_file)?;
+        ensure!(executor.n_cols() == N_COLS, "{pil_file} is not a compressor18 pil");
+        executor.exec_file(input_file, commit_file)
+    })
+}
@@ -0,0 +1,294 @@
+//! The PIL of compressor18, 18 committed columns.
+//!
+//! The plonk gates take 3 sets of constants a row, 6 gates. A POSEIDON12 takes 21 rows instead
+//! of 31: its 31 states are laid out one after the other, 18 values a row, so 2 rows hold 3
+//! states. An A row checks the 2 rounds from its first state, a B row the round from its
+//! second state, and the round constants follow the same layout in `C`. CMULADD, FFT4 and
+//! EVPOL4 use the first 12 columns as in compressor12.
+
+pub(crate) const N_COLS: usize = 18;
+
+const MDS: [[u64; 12]; 12] = [
+    [25, 15, 41, 16, 2, 28, 13, 13, 39, 18, 34, 20],
+    [20, 17, 15, 41, 16, 2, 28, 13, 13, 39, 18, 34],
+    [34, 20, 17, 15, 41, 16, 2, 28, 13, 13, 39, 18],
+    [18, 34, 20, 17, 15, 41, 16, 2, 28, 13, 13, 39],
+    [39, 18, 34, 20, 17, 15, 41, 16, 2, 28, 13, 13],
+    [13, 39, 18, 34, 20, 17, 15, 41, 16, 2, 28, 13],
+    [13, 13, 39, 18, 34, 20, 17, 15, 41, 16, 2, 28],
+    [28, 13, 13, 39, 18, 34, 20, 17, 15, 41, 16, 2],
+    [2, 28, 13, 13, 39, 18, 34, 20, 17, 15, 41, 16],
+    [16, 2, 28, 13, 13, 39, 18, 34, 20, 17, 15, 41],
+    [41, 16, 2, 28, 13, 13, 39, 18, 34, 20, 17, 15],
+    [15, 41, 16, 2, 28, 13, 13, 39, 18, 34, 20, 17],
+];
+
+/// A Poseidon round of the gate `sel`, from the state `inputs` plus the round constants, to
+/// `outputs`. Only the first element goes through the S-box when `partial` is 1.
+fn poseidon_round(
+    res: &mut String,
+    name: &str,
+    sel: &str,
+    partial: &str,
+    inputs: &[String],
+    outputs: &[String],
+) {
+    for (i, input) in inputs.iter().enumerate() {
+        res.push_str(&format!(
+            r#"
+    pol {name}{i}_1 = {input};
+    pol {name}{i}_2 = {name}{i}_1 * {name}{i}_1;
+    pol {name}{i}_4 = {name}{i}_2 * {name}{i}_2;
+    pol {name}{i}_6 = {name}{i}_4 * {name}{i}_2;
+    pol {name}{i}_7 = {name}{i}_6 * {name}{i}_1;"#
+        ));
+        match i {
+            0 => res.push_str(&format!("\n    pol {name}{i}_R = {name}{i}_7;")),
+            _ => res.push_str(&format!(
+                "\n    pol {name}{i}_R = {partial} * ({name}{i}_1 - {name}{i}_7) + {name}{i}_7;"
+            )),
+        }
+    }
+    res.push('\n');
+    for (i, output) in outputs.iter().enumerate() {
+        let mix =
+            MDS[i].iter().enumerate().map(|(k, m)| format!("{m}*{name}{k}_R")).collect::<Vec<_>>();
+        res.push_str(&format!("\n    {sel} * ({output} - ({})) = 0;", mix.join(" + ")));
+    }
+    res.push('\n');
+}
+
+/// `(r0, r1, r2) = a * b + c` in the cubic extension.
+fn c_mul_add(res: &mut String, r: [&str; 3], a: [&str; 3], b: [&str; 3], c: [&str; 3]) {
+    let [r0, r1, r2] = r;
+    let [a0, a1, a2] = a;
+    let [b0, b1, b2] = b;
+    let [c0, c1, c2] = c;
+    res.push_str(&format!(
+        r#"
+    pol {r0}_A = ({a0} + {a1}) * ({b0} + {b1});
+    pol {r0}_B = ({a0} + {a2}) * ({b0} + {b2});
+    pol {r0}_C = ({a1} + {a2}) * ({b1} + {b2});
+    pol {r0}_D = {a0} * {b0};
+    pol {r0}_E = {a1} * {b1};
+    pol {r0}_F = {a2} * {b2};
+    pol {r0} = {r0}_C + {r0}_D - {r0}_E - {r0}_F + {c0};
+    pol {r1} = {r0}_A + {r0}_C - 2*{r0}_E - {r0}_D + {c1};
+    pol {r2} = {r0}_B - {r0}_D + {r0}_E + {c2};
+"#
+    ));
+}
+
+pub fn render(n_bits: usize, n_publics: usize) -> String {
+    let mut res = format!(
+        r#"
+let N: int = 2**{n_bits};
+
+namespace Global(N);
+    pol constant L1;
+"#
+    );
+    for i in (N_COLS..n_publics).step_by(N_COLS) {
+        res.push_str(&format!("    pol constant L{};\n", i / N_COLS + 1));
+    }
+
+    res.push_str(&format!(
+        r#"
+namespace Compressor(N);
+    pol constant S[{N_COLS}];
+    pol constant C[{N_COLS}];
+    pol constant PARTIAL;
+    pol constant PARTIAL2;
+    pol constant POSEIDONA;
+    pol constant POSEIDONB;
+    pol constant GATE;
+    pol constant CMULADD;
+    pol constant EVPOL4;
+    pol constant FFT4;
+    pol commit a[{N_COLS}];
+"#
+    ));
+
+    for i in 0..n_publics {
+        res.push_str(&format!("\n    public pub{i} = a[{}]({});", i % N_COLS, i / N_COLS));
+    }
+    res.push('\n');
+    for i in 0..n_publics {
+        res.push_str(&format!(
+            "\n    Global.L{} * (a[{}] - :pub{i}) = 0;",
+            i / N_COLS + 1,
+            i % N_COLS
+        ));
+    }
+    res.push('\n');
+
+    // Normal plonk gates, 2 for each set of constants C[6*s..6*s+5]
+    res.push_str("\n    // Normal plonk gates");
+    for g in 0..N_COLS / 3 {
+        let (s, x) = (g / 2 * 6, g * 3);
+        res.push_str(&format!(
+            r#"
+    pol a{x}{} = a[{x}]*a[{}];
+    pol g{x} = C[{}]*a{x}{} + C[{s}]*a[{x}] + C[{}]*a[{}] + C[{}]*a[{}] + C[{}];
+    g{x}*GATE = 0;
+"#,
+            x + 1,
+            x + 1,
+            s + 3,
+            x + 1,
+            s + 1,
+            x + 1,
+            s + 2,
+            x + 2,
+            s + 4,
+        ));
+    }
+
+    // POSEIDON12, the A rows hold a state in a[0..12], the next in a[12..18] and a[0..6]',
+    // the one after in a[6..18]'. The B rows hold theirs in a[6..18] and check a[0..12]'.
+    let a = |i: usize| format!("a[{i}]");
+    let an = |i: usize| format!("a[{i}]'");
+    let c = |i: usize| format!("C[{i}]");
+    let cn = |i: usize| format!("C[{i}]'");
+    res.push_str("\n    // POSEIDON12 A rows, round 1");
+    let inputs: Vec<String> = (0..12).map(|i| format!("{} + {}", a(i), c(i))).collect();
+    let outputs: Vec<String> = (12..18).map(a).chain((0..6).map(an)).collect();
+    poseidon_round(&mut res, "pa", "POSEIDONA", "PARTIAL", &inputs, &outputs);
+
+    res.push_str("\n    // POSEIDON12 A rows, round 2");
+    let inputs: Vec<String> = (12..18)
+        .map(|i| format!("{} + {}", a(i), c(i)))
+        .chain((0..6).map(|i| format!("{} + {}", an(i), cn(i))))
+        .collect();
+    let outputs: Vec<String> = (6..18).map(an).collect();
+    poseidon_round(&mut res, "pb", "POSEIDONA", "PARTIAL2", &inputs, &outputs);
+
+    res.push_str("\n    // POSEIDON12 B rows");
+    let inputs: Vec<String> = (6..18).map(|i| format!("{} + {}", a(i), c(i))).collect();
+    let outputs: Vec<String> = (0..12).map(an).collect();
+    poseidon_round(&mut res, "pc", "POSEIDONB", "PARTIAL", &inputs, &outputs);
+
+    res.push_str(
+        r#"
+    // CMULADD GATE
+    pol ca0 = (a[0] + C[0])*C[9];
+    pol ca1 = (a[1] + C[1])*C[9];
+    pol ca2 = (a[2] + C[2])*C[9];
+    pol ca3 = a[3] + C[3];
+    pol ca4 = a[4] + C[4];
+    pol ca5 = a[5] + C[5];
+    pol ca6 = (a[6] + C[6])*C[10];
+    pol ca7 = (a[7] + C[7])*C[10];
+    pol ca8 = (a[8] + C[8])*C[10];
+    pol ca9 = a[9];
+    pol ca10 = a[10];
+    pol ca11 = a[11];
+
+    pol cA = (ca0 + ca1)  * (ca3 + ca4);
+    pol cB = (ca0 + ca2)  * (ca3 + ca5);
+    pol cC = (ca1 + ca2)  * (ca4 + ca5);
+    pol cD = ca0*ca3;
+    pol cE = ca1*ca4;
+    pol cF = ca2*ca5;
+
+    CMULADD * (ca9 - (cC + cD - cE - cF) - ca6) = 0;
+    CMULADD * (ca10 - (cA + cC - 2*cE - cD) - ca7) = 0;
+    CMULADD * (ca11 - (cB - cD + cE) - ca8) = 0;
+
+    // FFT4
+
+    pol f0 = C[0]*a[0] + C[1]*a[3] + C[2]*a[6] + C[3]*a[9]  + C[6]*a[0] + C[7]*a[3];
+    pol f1 = C[0]*a[1] + C[1]*a[4] + C[2]*a[7] + C[3]*a[10] + C[6]*a[1] + C[7]*a[4];
+    pol f2 = C[0]*a[2] + C[1]*a[5] + C[2]*a[8] + C[3]*a[11] + C[6]*a[2] + C[7]*a[5];
+
+    pol f3 = C[0]*a[0] - C[1]*a[3] + C[4]*a[6] - C[5]*a[9]  + C[6]*a[0] - C[7]*a[3];
+    pol f4 = C[0]*a[1] - C[1]*a[4] + C[4]*a[7] - C[5]*a[10] + C[6]*a[1] - C[7]*a[4];
+    pol f5 = C[0]*a[2] - C[1]*a[5] + C[4]*a[8] - C[5]*a[11] + C[6]*a[2] - C[7]*a[5];
+
+    pol f6 = C[0]*a[0] + C[1]*a[3] - C[2]*a[6] - C[3]*a[9]  + C[6]*a[6] + C[8]*a[9];
+    pol f7 = C[0]*a[1] + C[1]*a[4] - C[2]*a[7] - C[3]*a[10] + C[6]*a[7] + C[8]*a[10];
+    pol f8 = C[0]*a[2] + C[1]*a[5] - C[2]*a[8] - C[3]*a[11] + C[6]*a[8] + C[8]*a[11];
+
+    pol f9  = C[0]*a[0] - C[1]*a[3] - C[4]*a[6] + C[5]*a[9]  + C[6]*a[6] - C[8]*a[9];
+    pol f10 = C[0]*a[1] - C[1]*a[4] - C[4]*a[7] + C[5]*a[10] + C[6]*a[7] - C[8]*a[10];
+    pol f11 = C[0]*a[2] - C[1]*a[5] - C[4]*a[8] + C[5]*a[11] + C[6]*a[8] - C[8]*a[11];
+
+    FFT4 * (a[0]' - f0) = 0;
+    FFT4 * (a[1]' - f1) = 0;
+    FFT4 * (a[2]' - f2) = 0;
+    FFT4 * (a[3]' - f3) = 0;
+    FFT4 * (a[4]' - f4) = 0;
+    FFT4 * (a[5]' - f5) = 0;
+    FFT4 * (a[6]' - f6) = 0;
+    FFT4 * (a[7]' - f7) = 0;
+    FFT4 * (a[8]' - f8) = 0;
+    FFT4 * (a[9]' - f9) = 0;
+    FFT4 * (a[10]' - f10) = 0;
+    FFT4 * (a[11]' - f11) = 0;
+
+    // EVPOL4
+"#,
+    );
+
+    let x = ["a[3]'", "a[4]'", "a[5]'"];
+    c_mul_add(
+        &mut res,
+        ["acc1_0", "acc1_1", "acc1_2"],
+        ["a[0]'", "a[1]'", "a[2]'"],
+        x,
+        ["a[9]", "a[10]", "a[11]"],
+    );
+    c_mul_add(
+        &mut res,
+        ["acc2_0", "acc2_1", "acc2_2"],
+        ["acc1_0", "acc1_1", "acc1_2"],
+        x,
+        ["a[6]", "a[7]", "a[8]"],
+    );
+    c_mul_add(
+        &mut res,
+        ["acc3_0", "acc3_1", "acc3_2"],
+        ["acc2_0", "acc2_1", "acc2_2"],
+        x,
+        ["a[3]", "a[4]", "a[5]"],
+    );
+    c_mul_add(
+        &mut res,
+        ["acc4_0", "acc4_1", "acc4_2"],
+        ["acc3_0", "acc3_1", "acc3_2"],
+        x,
+        ["a[0]", "a[1]", "a[2]"],
+    );
+
+    let cols = |p: &str| (0..N_COLS).map(|i| format!("{p}[{i}]")).collect::<Vec<_>>().join(", ");
+    res.push_str(&format!(
+        r#"
+    EVPOL4 * (a[6]' - acc4_0 ) = 0;
+    EVPOL4 * (a[7]' - acc4_1 ) = 0;
+    EVPOL4 * (a[8]' - acc4_2 ) = 0;
+
+    // Connection equations
+    [{}] connect
+        [{}];
+"#,
+        cols("a"),
+        cols("S")
+    ));
+
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use crate::compressor18_pil::render;
+    use crate::pilcom::compile_pil_from_str;
+
+    #[test]
+    fn test_render_and_compile() {
+        let pil = compile_pil_from_str(&render(6, 20));
+        assert_eq!(pil.nCommitments, 18);
+        // S, C, 8 selectors and L1, L2 for the 20 publics
+        assert_eq!(pil.nConstants, 18 * 2 + 8 + 2);
+        assert_eq!(pil.publics.len(), 20);
+    }
+}
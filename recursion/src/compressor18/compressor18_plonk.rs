@@ -0,0 +1,375 @@
+//! Places the publics, the plonk gates and the custom gates of an R1CS on the rows of
+//! compressor18, then builds the constant polynomials of the rows and the sMap.
+use super::compressor18_pil::{self, N_COLS};
+use crate::compressor12::compressor12_setup::Options;
+use crate::compressor12::constants::CPOSEIDON;
+use crate::compressor12::custom_gates::{GateRegistry, UsedGates};
+use crate::pilcom::compile_pil_from_str;
+use crate::r1cs2plonk::{r1cs2plonk, GateKey, PlonkAdd, PlonkGate};
+use algebraic::circom_circuit::R1CS;
+use anyhow::{bail, ensure, Result};
+use fields::field_gl::Fr as FGL;
+use fields::field_gl::GL;
+use starky::helper;
+use starky::polsarray::{PolKind, PolsArray};
+use starky::types::PIL;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// The rows a POSEIDON12 takes, its 31 states of 12 packed 18 a row.
+pub(crate) const POSEIDON_ROWS: usize = (31 * 12 - 1) / N_COLS + 1;
+
+/// The selector set to 1 on a row.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Gate {
+    #[default]
+    None,
+    Plonk,
+    PoseidonA,
+    PoseidonB,
+    CMulAdd,
+    Fft4,
+    EvPol4,
+}
+
+const SELECTORS: [(Gate, &str); 6] = [
+    (Gate::Plonk, "GATE"),
+    (Gate::PoseidonA, "POSEIDONA"),
+    (Gate::PoseidonB, "POSEIDONB"),
+    (Gate::CMulAdd, "CMULADD"),
+    (Gate::Fft4, "FFT4"),
+    (Gate::EvPol4, "EVPOL4"),
+];
+
+/// The index in the registry of each gate compressor18 packs, None for a gate the registry
+/// doesn't have.
+struct GateIds {
+    poseidon: Option<usize>,
+    c_mul_add: Option<usize>,
+    fft4: Option<usize>,
+    ev_pol: Option<usize>,
+}
+
+impl GateIds {
+    fn new(registry: &GateRegistry) -> Self {
+        let id = |name: &str| registry.get(name).map(|(i, _)| i);
+        Self {
+            poseidon: id("Poseidon12"),
+            c_mul_add: id("CMulAdd"),
+            fft4: id("FFT4"),
+            ev_pol: id("EvPol4"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Row {
+    gate: Gate,
+    // PARTIAL and PARTIAL2
+    partial: [bool; 2],
+    signals: [u64; N_COLS],
+    c: [FGL; N_COLS],
+}
+
+#[derive(Debug)]
+pub struct PlonkSetup {
+    pub(crate) pil_str: String,
+    pub(crate) pil_json: PIL,
+    pub(crate) const_pols: PolsArray,
+    pub(crate) s_map: Vec<Vec<u64>>,
+    pub(crate) plonk_additions: Vec<PlonkAdd>,
+}
+
+impl PlonkSetup {
+    pub fn new(r1cs: &R1CS<GL>, opts: &Options) -> Result<Self> {
+        let (plonk_gates, plonk_additions) = r1cs2plonk(r1cs);
+        let used_gates = UsedGates::from_r1cs(r1cs, &opts.gates)?;
+        let n_publics = r1cs.num_inputs + r1cs.num_outputs - 1;
+
+        let mut rows = place_publics(n_publics);
+        place_plonk_gates(&mut rows, &plonk_gates);
+        place_custom_gates(&mut rows, r1cs, &used_gates, &GateIds::new(&opts.gates))?;
+
+        let n_used = rows.len();
+        let mut n_bits = helper::log2_any(n_used - 1) + 1;
+        if opts.force_bits > 0 {
+            ensure!(
+                n_used <= 1 << opts.force_bits,
+                "compressor18 uses {n_used} rows, more than 2^{}",
+                opts.force_bits
+            );
+            n_bits = opts.force_bits;
+        }
+        log::info!("compressor18: {n_used} rows used, nBits {n_bits}");
+
+        let pil_str = compressor18_pil::render(n_bits, n_publics);
+        let pil_json = compile_pil_from_str(&pil_str);
+        let const_pols = build_const_pols(&pil_json, &rows, n_bits, n_public_rows(n_publics));
+        let s_map = (0..N_COLS).map(|c| rows.iter().map(|r| r.signals[c]).collect()).collect();
+
+        Ok(Self { pil_str, pil_json, const_pols, s_map, plonk_additions })
+    }
+}
+
+fn n_public_rows(n_publics: usize) -> usize {
+    (n_publics - 1) / N_COLS + 1
+}
+
+fn place_publics(n_publics: usize) -> Vec<Row> {
+    let mut rows = vec![Row::default(); n_public_rows(n_publics)];
+    for i in 0..n_publics {
+        rows[i / N_COLS].signals[i % N_COLS] = 1 + i as u64;
+    }
+    rows
+}
+
+/// Each plonk row has 3 sets of constants, and each set 2 slots of 3 signals. The gates with
+/// the same constants share a set, as in compressor12.
+fn place_plonk_gates(rows: &mut Vec<Row>, plonk_gates: &[PlonkGate]) {
+    const SETS: usize = N_COLS / 6;
+
+    #[derive(Copy, Clone, Debug)]
+    struct Set {
+        row: usize,
+        set: usize,
+    }
+    let set_gate = |row: &mut Row, slot: usize, c: &PlonkGate| {
+        row.signals[slot * 3] = c.0 as u64;
+        row.signals[slot * 3 + 1] = c.1 as u64;
+        row.signals[slot * 3 + 2] = c.2 as u64;
+    };
+
+    let mut partial_sets: HashMap<GateKey, Set> = HashMap::new();
+    let mut free_sets: VecDeque<Set> = VecDeque::new();
+    for (i, c) in plonk_gates.iter().enumerate() {
+        if (i % 10000) == 0 {
+            log::trace!("Processing constraint... {}/{}", i, plonk_gates.len())
+        }
+        let k = c.key();
+        if let Some(ps) = partial_sets.remove(&k) {
+            set_gate(&mut rows[ps.row], ps.set * 2 + 1, c);
+            continue;
+        }
+        let ps = match free_sets.pop_front() {
+            Some(ps) => ps,
+            None => {
+                rows.push(Row { gate: Gate::Plonk, ..Default::default() });
+                let row = rows.len() - 1;
+                free_sets.extend((1..SETS).map(|set| Set { row, set }));
+                Set { row, set: 0 }
+            }
+        };
+        let row = &mut rows[ps.row];
+        for (i, value) in [3_usize, 0, 1, 2, 4, 5].iter().zip([c.3, c.4, c.5, c.6, c.7, FGL::ZERO])
+        {
+            row.c[ps.set * 6 + i] = value;
+        }
+        set_gate(row, ps.set * 2, c);
+        partial_sets.insert(k, ps);
+    }
+
+    // Terminate the half used sets (Copy the same constraint)
+    for ps in partial_sets.values() {
+        let row = &mut rows[ps.row];
+        let slot = ps.set * 6;
+        row.signals.copy_within(slot..slot + 3, slot + 3);
+    }
+}
+
+fn is_partial(round: usize) -> bool {
+    (4..26).contains(&round)
+}
+
+fn place_custom_gates(
+    rows: &mut Vec<Row>,
+    r1cs: &R1CS<GL>,
+    used_gates: &UsedGates,
+    ids: &GateIds,
+) -> Result<()> {
+    for (i, cgu) in r1cs.custom_gates_uses.iter().enumerate() {
+        if (i % 10000) == 0 {
+            log::trace!("Processing custom gates... {}/{}", i, r1cs.custom_gates_uses.len());
+        }
+        let r = rows.len();
+        let gate = Some(used_gates.templates[cgu.id as usize]);
+        if gate == ids.poseidon {
+            ensure!(cgu.signals.len() == 31 * 12, "Poseidon12 takes {} signals", 31 * 12);
+            rows.resize(r + POSEIDON_ROWS, Row::default());
+            for (p, signal) in cgu.signals.iter().enumerate() {
+                let row = &mut rows[r + p / N_COLS];
+                row.signals[p % N_COLS] = *signal;
+                row.c[p % N_COLS] = FGL::from(CPOSEIDON[p]);
+            }
+            // rows 2k and 2k+1 hold the states 3k, 3k+1 and 3k+2, the last row the output.
+            for k in 0..(POSEIDON_ROWS - 1) / 2 {
+                let a = &mut rows[r + 2 * k];
+                a.gate = Gate::PoseidonA;
+                a.partial = [is_partial(3 * k), is_partial(3 * k + 1)];
+                let b = &mut rows[r + 2 * k + 1];
+                b.gate = Gate::PoseidonB;
+                b.partial = [is_partial(3 * k + 2), false];
+            }
+        } else if gate == ids.c_mul_add {
+            let mut row = Row { gate: Gate::CMulAdd, ..Default::default() };
+            row.signals[..12].copy_from_slice(&cgu.signals[..12]);
+            row.c[9] = FGL::ONE;
+            row.c[10] = FGL::ONE;
+            rows.push(row);
+        } else if gate == ids.fft4 {
+            let params = &r1cs.custom_gates[cgu.id as usize].parameters;
+            let (firstW, incW, scale, t) = (params[0], params[1], params[2], params[3]);
+            let firstW2 = firstW * firstW;
+
+            let mut row = Row { gate: Gate::Fft4, ..Default::default() };
+            row.signals[..12].copy_from_slice(&cgu.signals[..12]);
+            if t.as_int() == 4 {
+                row.c[..6].copy_from_slice(&[
+                    scale,
+                    scale * firstW2,
+                    scale * firstW,
+                    scale * firstW * firstW2,
+                    scale * firstW * incW,
+                    scale * firstW * firstW2 * incW,
+                ]);
+            } else if t.as_int() == 2 {
+                row.c[6..9].copy_from_slice(&[scale, scale * firstW, scale * firstW * incW]);
+            } else {
+                bail!("invalid FFT4 type: {t}");
+            }
+            let mut next = Row::default();
+            next.signals[..12].copy_from_slice(&cgu.signals[12..24]);
+            rows.extend([row, next]);
+        } else if gate == ids.ev_pol {
+            let mut row = Row { gate: Gate::EvPol4, ..Default::default() };
+            row.signals[..12].copy_from_slice(&cgu.signals[..12]);
+            let mut next = Row::default();
+            next.signals[..9].copy_from_slice(&cgu.signals[12..21]);
+            rows.extend([row, next]);
+        } else {
+            let name = &r1cs.custom_gates[cgu.id as usize].template_name;
+            bail!("compressor18 has no gate for the custom template {name}");
+        }
+    }
+    Ok(())
+}
+
+fn build_const_pols(pil: &PIL, rows: &[Row], n_bits: usize, n_public_rows: usize) -> PolsArray {
+    let mut const_pols = PolsArray::new(pil, PolKind::Constant);
+    let ns = "Compressor".to_string();
+    let id = |name: &str| const_pols.get_pol_id(pil, &ns, &name.to_string(), 0);
+    let selectors = SELECTORS.map(|(gate, name)| (gate, id(name)));
+    let (c, s) = (id("C"), id("S"));
+    let partial = [id("PARTIAL"), id("PARTIAL2")];
+
+    // The unused rows are all 0, as PolsArray::new leaves them
+    for (i, row) in rows.iter().enumerate() {
+        if let Some((_, pol)) = selectors.iter().find(|(gate, _)| *gate == row.gate) {
+            const_pols.array[*pol][i] = FGL::ONE;
+        }
+        for (pol, p) in partial.iter().zip(row.partial) {
+            if p {
+                const_pols.array[*pol][i] = FGL::ONE;
+            }
+        }
+        for k in 0..N_COLS {
+            const_pols.array[c + k][i] = row.c[k];
+        }
+    }
+
+    // S Polynomials
+    let N = 1 << n_bits;
+    let ks = helper::get_ks(N_COLS - 1);
+    let mut w = FGL::ONE;
+    for i in 0..N {
+        const_pols.array[s][i] = w;
+        for j in 1..N_COLS {
+            const_pols.array[s + j][i] = w * ks[j - 1];
+        }
+        w = w * starky::constant::MG.0[n_bits];
+    }
+
+    let mut last_signal: BTreeMap<u64, (usize, usize)> = BTreeMap::new();
+    for (i, row) in rows.iter().enumerate() {
+        for (j, key) in row.signals.iter().enumerate() {
+            if *key == 0 {
+                continue;
+            }
+            if let Some((col, r)) = last_signal.get(key) {
+                // connect and swap the value.
+                let left = const_pols.array[s + col][*r];
+                const_pols.array[s + col][*r] = const_pols.array[s + j][i];
+                const_pols.array[s + j][i] = left;
+            } else {
+                last_signal.insert(*key, (j, i));
+            }
+        }
+    }
+
+    // construct Lagrange Basis Polynomial: Li(x)
+    for i in 0..n_public_rows {
+        let l = const_pols.get_pol_id(pil, &"Global".to_string(), &format!("L{}", i + 1), 0);
+        const_pols.array[l][i] = FGL::ONE;
+    }
+
+    const_pols
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compressor12::custom_gates::{CMulAdd, FFT4};
+    use crate::verifier_circuit::circuit::{Circuit, Signal};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_place_plonk_gates() {
+        let one = FGL::ONE;
+        let add = || PlonkGate(1, 2, 3, FGL::ZERO, one, one, -one, FGL::ZERO);
+        let mul = || PlonkGate(4, 5, 6, one, FGL::ZERO, FGL::ZERO, -one, FGL::ZERO);
+        let mut rows = place_publics(20);
+        assert_eq!(rows.len(), 2);
+        place_plonk_gates(&mut rows, &[add(), mul(), add(), mul(), add()]);
+
+        // 2 adds share the first set, the 2 muls the second, the last add starts the third
+        // and is copied to its second slot.
+        assert_eq!(rows.len(), 3);
+        let row = &rows[2];
+        assert_eq!(row.gate, Gate::Plonk);
+        assert_eq!(row.signals, [1, 2, 3, 1, 2, 3, 4, 5, 6, 4, 5, 6, 1, 2, 3, 1, 2, 3]);
+        assert_eq!(row.c[..6], [one, one, -one, FGL::ZERO, FGL::ZERO, FGL::ZERO]);
+        assert_eq!(row.c[9], one);
+        assert_eq!(row.c[12..18], row.c[..6]);
+    }
+
+    #[test]
+    fn test_place_custom_gates_without_poseidon() {
+        // the templates of a circuit without Poseidon12 start at CMulAdd
+        let mut c = Circuit::new();
+        let x: Vec<Signal> = (1..=12u64).map(|v| c.alloc(FGL::from(v))).collect();
+        let f3 = |i: usize| [x[i], x[i + 1], x[i + 2]];
+        c.c_mul_add(f3(0), f3(3), f3(6));
+        c.fft4([0, 3, 6, 9].map(f3), FGL::ONE, FGL::ONE, FGL::from(2u64), 4);
+        let (r1cs, _) = c.into_r1cs();
+        assert_eq!(r1cs.custom_gates[0].template_name, "CMulAdd");
+
+        let mut registry = GateRegistry::empty();
+        registry.register(Arc::new(CMulAdd)).unwrap();
+        registry.register(Arc::new(FFT4)).unwrap();
+        for registry in [GateRegistry::default(), registry] {
+            let used_gates = UsedGates::from_r1cs(&r1cs, &registry).unwrap();
+            let mut rows = vec![];
+            place_custom_gates(&mut rows, &r1cs, &used_gates, &GateIds::new(&registry)).unwrap();
+            let gates: Vec<Gate> = rows.iter().map(|r| r.gate).collect();
+            assert_eq!(gates, [Gate::CMulAdd, Gate::Fft4, Gate::None]);
+            assert_eq!(rows[1].c[0], FGL::from(2u64));
+        }
+
+        // a circuit with a template the registry doesn't have
+        let used_gates = UsedGates::from_r1cs(&r1cs, &GateRegistry::default()).unwrap();
+        let mut registry = GateRegistry::empty();
+        registry.register(Arc::new(FFT4)).unwrap();
+        let mut rows = vec![];
+        let ids = GateIds::new(&registry);
+        assert!(place_custom_gates(&mut rows, &r1cs, &used_gates, &ids).is_err());
+        assert!(UsedGates::from_r1cs(&r1cs, &registry).is_err());
+    }
+}
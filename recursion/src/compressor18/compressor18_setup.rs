@@ -0,0 +1,132 @@
+use super::compressor18_pil::N_COLS;
+use super::compressor18_plonk::PlonkSetup;
+use crate::compressor12::compressor12_setup::Options;
+use crate::exec_file::{write_exec_file, ExecHeader};
+use crate::pilcom::write_pil_json;
+use algebraic::circom_circuit::R1CS;
+use algebraic::reader::load_r1cs_from_bin;
+use anyhow::{anyhow, Result};
+use fields::field_gl::GL;
+use starky::prover_config::ProverConfig;
+use std::fs::File;
+use std::io::Write;
+
+// setup phase:
+// input: .r1cs
+// output: .pil, .pil.json, .const, .exec, laid out on 18 columns
+pub fn setup(
+    r1cs_file: &str,
+    pil_file: &str,
+    const_file: &str,
+    exec_file: &str,
+    force_n_bits: usize,
+    config: &ProverConfig,
+) -> Result<()> {
+    config.install(|| setup_inner(r1cs_file, pil_file, const_file, exec_file, force_n_bits))
+}
+
+fn setup_inner(
+    r1cs_file: &str,
+    pil_file: &str,
+    const_file: &str,
+    exec_file: &str,
+    force_n_bits: usize,
+) -> Result<()> {
+    // 0. readR1cs
+    let r1cs_bytes = std::fs::read(r1cs_file)?;
+    let (r1cs, _) = load_r1cs_from_bin::<_, GL>(std::io::Cursor::new(&r1cs_bytes));
+    let opts = Options::new(force_n_bits);
+    write_setup(&r1cs, &r1cs_bytes, pil_file, const_file, exec_file, &opts)
+}
+
+/// Writes the files of the setup of `r1cs`, `r1cs_bytes` being what the exec file hashes.
+fn write_setup(
+    r1cs: &R1CS<GL>,
+    r1cs_bytes: &[u8],
+    pil_file: &str,
+    const_file: &str,
+    exec_file: &str,
+    opts: &Options,
+) -> Result<()> {
+    // 1. plonk setup: place the gates on 18 columns, generate the pil file.
+    let res = PlonkSetup::new(r1cs, opts)?;
+
+    // 2. And write it into pil_file.
+    let mut file =
+        File::create(pil_file).map_err(|_| anyhow!("Create file error, {}", pil_file))?;
+    write!(file, "{}", res.pil_str)?;
+    // and its json next to it, for the exec
+    write_pil_json(pil_file, &res.pil_str, &res.pil_json)?;
+
+    // 3. write const pols file
+    let const_writer = File::create(const_file)?;
+    res.const_pols.save(const_writer)?;
+
+    // 4. construct and save ExecFile: header + plonk additions + sMap -> BigUint64Array
+    let mut file_writer =
+        File::create(exec_file).map_err(|e| anyhow!("Create {}, {:?}", exec_file, e))?;
+    let header = ExecHeader::new(N_COLS, r1cs_bytes, &res.pil_str, r1cs.num_variables);
+    write_exec_file(&mut file_writer, &header, &res.plonk_additions, &res.s_map)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compressor12::compressor12_exec::Executor;
+    use crate::verifier_circuit::circom_witness::CircomWitness;
+    use crate::verifier_circuit::circuit::{Circuit, Signal};
+    use crate::verifier_circuit::stark_verifier::test::prove_and_verify;
+    use fields::field_gl::Fr as FGL;
+    use num_bigint::BigInt;
+    use starky::polsarray::{PolKind, PolsArray};
+
+    fn input(name: &str, values: &[u64]) -> (String, Vec<BigInt>) {
+        (name.to_string(), values.iter().map(|v| BigInt::from(*v)).collect())
+    }
+
+    #[test]
+    fn test_setup_exec_prove() {
+        // a circuit of every gate compressor18 places: the plonk gates, Poseidon12, CMulAdd,
+        // FFT4 and EvPol4
+        let mut c = Circuit::new();
+        let p = c.public(FGL::from(9u64));
+        let a: Vec<Signal> = [3u64, 5, 7].iter().map(|v| c.alloc(FGL::from(*v))).collect();
+        let inv = c.c_inv([a[0], a[1], a[2]]);
+        let bits = c.num2bits_strict(p);
+        let state: [Signal; 12] = std::array::from_fn(|i| if i == 0 { p } else { a[i % 3] });
+        let hash = c.poseidon(state);
+        let values: Vec<[Signal; 3]> = (0..8).map(|i| [a[i % 3], inv[i % 3], hash[i]]).collect();
+        let coefs = c.ifft(&values);
+        c.eval_pol(&coefs, inv);
+        c.tree_selector(&values, &bits[..3]);
+        assert_eq!(c.failure(), None);
+        let (r1cs, _) = c.into_r1cs();
+        let sym = std::iter::once(format!("{p},{p},0,main.p[0]"))
+            .chain(a.iter().enumerate().map(|(i, s)| format!("{s},{s},0,main.a[{i}]")))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let dir = std::env::temp_dir().join("c18_setup");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let (pil_file, const_file, exec_file) =
+            (file("c18.pil"), file("c18.const"), file("c18.exec"));
+        write_setup(&r1cs, b"c18", &pil_file, &const_file, &exec_file, &Options::new(0)).unwrap();
+
+        let witness = CircomWitness::new(r1cs, &sym).unwrap();
+        let mut executor = Executor::with_witness(witness, b"c18", &pil_file, &exec_file).unwrap();
+        assert_eq!(executor.n_cols(), N_COLS);
+        let cm_pols = executor.exec([input("p", &[9]), input("a", &[3, 5, 7])]).unwrap();
+        // the public is the first cell of the first row
+        let pil = executor.pil().clone();
+        let a0 = cm_pols.get_pol_id(&pil, &"Compressor".to_string(), &"a".to_string(), 0);
+        assert_eq!(cm_pols.array[a0][0], FGL::from(9u64));
+
+        // a stark of the 18 columns verifies: the gates are placed as the PIL constrains them
+        let mut const_pols = PolsArray::new(&pil, PolKind::Constant);
+        const_pols.load(&const_file).unwrap();
+        prove_and_verify(pil, const_pols, cm_pols);
+    }
+}
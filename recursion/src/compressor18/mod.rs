@@ -0,0 +1,5 @@
+#![allow(non_snake_case)]
+pub mod compressor18_exec;
+pub(crate) mod compressor18_pil;
+pub(crate) mod compressor18_plonk;
+pub mod compressor18_setup;
@@ -0,0 +1,256 @@
+//! The `.exec` file of the compressors: the plonk additions and the sMap, saved as a JSON array
+//! of u64.
+//!
+//! From version 1 it starts with a header,
+//!
+//! `[EXEC_MAGIC, version, n_cols, r1cs_hash[4], pil_hash[4], n_wires, adds_len, s_map_column_len]`
+//!
+//! followed by the additions, 4 words each, and the sMap row by row, `n_cols` words a row. The
+//! legacy files start at `adds_len`, without anything to check them against.
+use crate::compressor12::compressor12_exec::ExecError;
+use crate::io_utils::{read_vec_from_file, write_vec_to_file};
+use crate::r1cs2plonk::PlonkAdd;
+use anyhow::{anyhow, bail, ensure, Result};
+use sha2::{Digest, Sha256};
+
+/// "EIGENEXC", far above any `adds_len` of a legacy file.
+pub const EXEC_MAGIC: u64 = u64::from_be_bytes(*b"EIGENEXC");
+pub const EXEC_VERSION: u64 = 1;
+const HEADER_LEN: usize = 14;
+
+/// The SHA-256 of `data` as 4 words, big-endian.
+pub fn hash(data: &[u8]) -> [u64; 4] {
+    let digest = Sha256::digest(data);
+    let mut res = [0u64; 4];
+    for (r, chunk) in res.iter_mut().zip(digest.chunks(8)) {
+        *r = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    res
+}
+
+pub(crate) fn to_hex(hash: &[u64; 4]) -> String {
+    hash.iter().map(|h| format!("{h:016x}")).collect()
+}
+
+/// What the exec file was set up for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecHeader {
+    pub version: u64,
+    /// The committed columns of the compressor, the width of the sMap.
+    pub n_cols: usize,
+    pub r1cs_hash: [u64; 4],
+    /// The hash of the `.pil` source written by the setup.
+    pub pil_hash: [u64; 4],
+    /// The witness size of the r1cs, that of the wasm computing it.
+    pub n_wires: usize,
+}
+
+impl ExecHeader {
+    pub fn new(n_cols: usize, r1cs: &[u8], pil: &str, n_wires: usize) -> Self {
+        Self {
+            version: EXEC_VERSION,
+            n_cols,
+            r1cs_hash: hash(r1cs),
+            pil_hash: hash(pil.as_bytes()),
+            n_wires,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ExecFile {
+    /// None for a legacy file.
+    pub header: Option<ExecHeader>,
+    pub s_map_column_len: usize,
+    /// `[w1, w2, coef1, coef2]` for each addition.
+    pub adds: Vec<u64>,
+    /// row-major, `n_cols` signals a row.
+    pub s_map: Vec<u64>,
+}
+
+impl ExecFile {
+    pub fn adds_len(&self) -> usize {
+        self.adds.len() / 4
+    }
+
+    /// Checks the `.pil` source the exec is given is the one of the setup.
+    pub fn check_pil(&self, pil: &str) -> Result<()> {
+        if let Some(header) = &self.header {
+            let pil_hash = hash(pil.as_bytes());
+            ensure!(
+                header.pil_hash == pil_hash,
+                "the pil (sha256 {}) is not the one of the exec file (sha256 {})",
+                to_hex(&pil_hash),
+                to_hex(&header.pil_hash)
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks the r1cs the exec is given is the one of the setup.
+    pub fn check_r1cs(&self, r1cs: &[u8]) -> Result<()> {
+        if let Some(header) = &self.header {
+            let r1cs_hash = hash(r1cs);
+            ensure!(
+                header.r1cs_hash == r1cs_hash,
+                "the r1cs (sha256 {}) is not the one of the exec file (sha256 {})",
+                to_hex(&r1cs_hash),
+                to_hex(&header.r1cs_hash)
+            );
+        }
+        Ok(())
+    }
+
+    /// Checks the witness computed by the wasm has the size of the r1cs of the setup.
+    pub fn check_witness(&self, witness_len: usize) -> Result<(), ExecError> {
+        match &self.header {
+            Some(header) if header.n_wires != witness_len => {
+                Err(ExecError::WitnessSize { expected: header.n_wires, actual: witness_len })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// construct and save ExecFile: header + plonk additions + sMap -> BigUint64Array, sMap has a row
+// for each committed column of the compressor
+pub fn write_exec_file<W: std::io::Write>(
+    exec_file_writer: &mut W,
+    header: &ExecHeader,
+    adds: &[PlonkAdd],
+    s_map: &[Vec<u64>],
+) -> Result<()> {
+    let adds_len = adds.len();
+    let s_map_row_len = s_map.len();
+    let s_map_column_len = s_map[0].len();
+    ensure!(s_map_row_len == header.n_cols, "s_map should have {} rows", header.n_cols);
+
+    let mut buff = Vec::with_capacity(HEADER_LEN + adds_len * 4 + s_map_row_len * s_map_column_len);
+    buff.extend([EXEC_MAGIC, header.version, header.n_cols as u64]);
+    buff.extend(header.r1cs_hash);
+    buff.extend(header.pil_hash);
+    buff.extend([header.n_wires as u64, adds_len as u64, s_map_column_len as u64]);
+
+    for add in adds {
+        buff.extend([add.0 as u64, add.1 as u64, add.2.into(), add.3.into()]);
+    }
+
+    for i in 0..s_map_column_len {
+        buff.extend(s_map.iter().map(|col| col[i]));
+    }
+
+    write_vec_to_file(exec_file_writer, &buff)
+}
+
+/// Reads an exec file of a compressor of `n_cols` columns, either version 1 or legacy.
+pub fn read_exec_file<R: std::io::Read>(reader: R, n_cols: usize) -> Result<ExecFile> {
+    let buff = read_vec_from_file(reader)?;
+
+    let (header, body) = if buff.first() == Some(&EXEC_MAGIC) {
+        ensure!(buff.len() >= HEADER_LEN, "truncated exec file header");
+        ensure!(buff[1] == EXEC_VERSION, "unsupported exec file version {}", buff[1]);
+        let header = ExecHeader {
+            version: buff[1],
+            n_cols: buff[2] as usize,
+            r1cs_hash: buff[3..7].try_into().unwrap(),
+            pil_hash: buff[7..11].try_into().unwrap(),
+            n_wires: buff[11] as usize,
+        };
+        ensure!(
+            header.n_cols == n_cols,
+            "the exec file is for {} columns, not {n_cols}",
+            header.n_cols
+        );
+        (Some(header), &buff[HEADER_LEN - 2..])
+    } else {
+        (None, &buff[..])
+    };
+
+    if body.len() < 2 {
+        bail!("truncated exec file");
+    }
+    let adds_len = body[0] as usize;
+    let s_map_column_len = body[1] as usize;
+    let size = adds_len
+        .checked_mul(4)
+        .zip(s_map_column_len.checked_mul(n_cols))
+        .and_then(|(adds_size, s_map_size)| adds_size.checked_add(s_map_size))
+        .ok_or_else(|| {
+            anyhow!(
+                "the exec file has {adds_len} additions and {s_map_column_len} rows, more than \
+                 can be addressed"
+            )
+        })?;
+    ensure!(
+        body.len() - 2 == size,
+        "the exec file has {} words, {adds_len} additions and {s_map_column_len} rows of {n_cols} \
+         take {size}",
+        body.len() - 2
+    );
+
+    let (adds, s_map) = body[2..].split_at(adds_len * 4);
+    Ok(ExecFile { header, s_map_column_len, adds: adds.to_vec(), s_map: s_map.to_vec() })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fields::field_gl::Fr as FGL;
+
+    fn s_map() -> Vec<Vec<u64>> {
+        (0..12).map(|c| vec![c, c + 1, c + 2]).collect()
+    }
+
+    #[test]
+    fn test_write_and_read_exec_file() {
+        let adds = vec![PlonkAdd(3, 4, FGL::from(5u64), FGL::from(6u64))];
+        let header = ExecHeader::new(12, b"r1cs", "pil", 7);
+
+        let mut buf = vec![];
+        write_exec_file(&mut buf, &header, &adds, &s_map()).unwrap();
+        let exec = read_exec_file(buf.as_slice(), 12).unwrap();
+
+        assert_eq!(exec.header, Some(header));
+        assert_eq!(exec.adds_len(), 1);
+        assert_eq!(exec.adds[..2], [3, 4]);
+        assert_eq!(exec.s_map_column_len, 3);
+        assert_eq!(exec.s_map[..13], [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 1]);
+        exec.check_pil("pil").unwrap();
+        exec.check_r1cs(b"r1cs").unwrap();
+        exec.check_witness(7).unwrap();
+        assert!(exec.check_pil("pil2").is_err());
+        assert!(exec.check_r1cs(b"r1cs2").is_err());
+        assert!(matches!(
+            exec.check_witness(8),
+            Err(ExecError::WitnessSize { expected: 7, actual: 8 })
+        ));
+        assert!(read_exec_file(buf.as_slice(), 18).is_err());
+    }
+
+    #[test]
+    fn test_read_legacy_exec_file() {
+        let mut legacy = vec![0, 3];
+        legacy.extend((0..3).flat_map(|i| s_map().into_iter().map(move |col| col[i])));
+        let mut buf = vec![];
+        write_vec_to_file(&mut buf, &legacy).unwrap();
+
+        let exec = read_exec_file(buf.as_slice(), 12).unwrap();
+        assert_eq!(exec.header, None);
+        assert_eq!(exec.adds_len(), 0);
+        assert_eq!(exec.s_map, legacy[2..]);
+        // nothing to check a legacy file against
+        exec.check_pil("pil").unwrap();
+        exec.check_r1cs(b"r1cs").unwrap();
+        exec.check_witness(1).unwrap();
+        assert!(read_exec_file(buf.as_slice(), 18).is_err());
+    }
+
+    #[test]
+    fn test_read_corrupt_exec_file() {
+        for header in [[u64::MAX, 0], [0, u64::MAX], [u64::MAX / 4, u64::MAX / 12]] {
+            let mut buf = vec![];
+            write_vec_to_file(&mut buf, &header).unwrap();
+            assert!(read_exec_file(buf.as_slice(), 12).is_err());
+        }
+    }
+}
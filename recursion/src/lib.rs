@@ -1,5 +1,10 @@
 pub mod compressor12;
 pub use compressor12::*;
+pub mod compressor18;
+pub use compressor18::*;
+pub mod exec_file;
 mod io_utils;
 pub mod pilcom;
 pub mod r1cs2plonk;
+pub mod verifier_circuit;
+pub mod verifier_estimate;
@@ -2,13 +2,45 @@
 use std::rc::Rc;
 mod export;
 mod expression_counter;
+mod json;
+mod source;
 
-pub use export::export;
+pub use export::{export, reference_order};
+pub use json::to_pilcom_json;
+pub use source::{flatten, FlatSource};
 
+use crate::exec_file::{hash, to_hex};
+use anyhow::Result;
 use powdr::number::GoldilocksField;
 use powdr::pil_analyzer::{analyze_file, analyze_string};
+use serde_json::{Map, Value};
 use starky::types::PIL;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How `compile_pil` looks the includes up and which namespaces it compiles, as pilcom's `-I`
+/// and `-n`.
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// Searched for an include after the directory of the including file.
+    pub include_paths: Vec<PathBuf>,
+    /// Compile only these namespaces, all if `None`.
+    pub namespaces: Option<Vec<String>>,
+}
+
+/// A compiled PIL with the declaration order of its references.
+#[derive(Debug, Clone)]
+pub struct CompiledPil {
+    pub pil: PIL,
+    pub reference_order: Vec<String>,
+}
+
+impl CompiledPil {
+    /// The pil.json as pilcom writes it.
+    pub fn to_json(&self) -> String {
+        to_pilcom_json(&self.pil, &self.reference_order)
+    }
+}
 
 pub fn compile_pil_from_str(pil_str: &str) -> PIL {
     let analyze = analyze_string::<GoldilocksField>(pil_str);
@@ -19,9 +51,71 @@ pub fn compile_pil_from_path(pil_path: &str) -> PIL {
     export(&Rc::new(analyze))
 }
 
+/// Writes `pil`, compiled from the source `pil_str`, to `<pil_file>.json`, the file
+/// `load_or_compile_pil` loads. The json keeps the `pilHash` of the source, as the exec header.
+pub fn write_pil_json(pil_file: &str, pil_str: &str, pil: &PIL) -> Result<()> {
+    let mut json = serde_json::to_value(pil)?;
+    json["pilHash"] = to_hex(&hash(pil_str.as_bytes())).into();
+    fs::write(format!("{pil_file}.json"), serde_json::to_string(&json)?)?;
+    Ok(())
+}
+
+/// Loads `<pil_file>.json` when it was written from the source in `pil_file`, otherwise compiles
+/// `pil_file` and writes it.
+pub fn load_or_compile_pil(pil_file: &str) -> Result<PIL> {
+    let pil_str = fs::read_to_string(pil_file)?;
+    let pil_hash = to_hex(&hash(pil_str.as_bytes()));
+    let json = fs::read_to_string(format!("{pil_file}.json")).ok();
+    if let Some(mut json) = json.and_then(|j| serde_json::from_str::<Map<String, Value>>(&j).ok()) {
+        if json.remove("pilHash").as_ref().and_then(Value::as_str) == Some(&pil_hash) {
+            return Ok(serde_json::from_value(Value::Object(json))?);
+        }
+    }
+    let pil = compile_pil_from_path(pil_file);
+    write_pil_json(pil_file, &pil_str, &pil)?;
+    Ok(pil)
+}
+
+/// Compiles a .pil file, with its includes, like pilcom.
+pub fn compile_pil(pil_path: &str, options: &CompileOptions) -> Result<CompiledPil> {
+    let flat = flatten(Path::new(pil_path), &options.include_paths, options.namespaces.as_deref())?;
+    let analyze = Rc::new(analyze_string::<GoldilocksField>(&flat.source));
+    let mut pil = export(&analyze);
+    // the identities point to the flattened source, map them back to their files
+    let origin = |file_name: &mut String, line: &mut usize| {
+        if let Some((f, l)) = flat.origin(*line) {
+            file_name.clone_from(f);
+            *line = *l;
+        }
+    };
+    for pi in pil.polIdentities.iter_mut() {
+        origin(&mut pi.fileName, &mut pi.line);
+    }
+    for pi in pil.plookupIdentities.iter_mut() {
+        origin(&mut pi.fileName, &mut pi.line);
+    }
+    for pi in pil.permutationIdentities.iter_mut().flatten() {
+        origin(&mut pi.fileName, &mut pi.line);
+    }
+    for ci in pil.connectionIdentities.iter_mut().flatten() {
+        origin(&mut ci.fileName, &mut ci.line);
+    }
+    Ok(CompiledPil { pil, reference_order: reference_order(&analyze) })
+}
+
+/// Compiles `pil_file` and writes it to `output` as pilcom does.
+pub fn pil_compile(pil_file: &str, output: &str, options: &CompileOptions) -> Result<()> {
+    let compiled = compile_pil(pil_file, options)?;
+    fs::write(output, compiled.to_json())?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::compressor12::compressor12_pil;
+    use crate::compressor12::custom_gates::GateRegistry;
+    use crate::compressor18::compressor18_pil;
     use starky::types::load_json;
     use std::fs;
     use std::fs::File;
@@ -45,4 +139,199 @@ mod test {
         let input = serde_json::to_string_pretty(&actual).unwrap();
         write!(file, "{input}").unwrap();
     }
+
+    /// The reference names of a pilcom pil.json, in its order.
+    fn reference_order_of(pil_json: &str) -> Vec<String> {
+        pil_json
+            .lines()
+            .skip_while(|l| *l != " \"references\": {")
+            .skip(1)
+            .take_while(|l| *l != " },")
+            .filter_map(|l| l.strip_prefix("  \"")?.strip_suffix("\": {").map(String::from))
+            .collect()
+    }
+
+    #[test]
+    fn test_pilcom_json() {
+        for pil_json in ["fib", "plookup", "pe", "connection"] {
+            let pil_json = format!("../starky/data/{pil_json}.pil.json");
+            let text = fs::read_to_string(&pil_json).unwrap();
+            let pil = load_json::<PIL>(&pil_json).unwrap();
+            assert_eq!(to_pilcom_json(&pil, &reference_order_of(&text)), text, "{pil_json}");
+        }
+    }
+
+    // The starkjs examples against the pil.json pilcom compiled them to. The identities and
+    // publics have been edited since, so only the declarations are compared.
+    #[test]
+    fn test_compile_pil_starkjs_examples() {
+        for (pil_file, pil_json, identity) in [
+            ("../starkjs/plookup/plookup_main.pil", "../starky/data/plookup.pil.json", 10),
+            ("../starkjs/permutation/permutation_main.pil", "../starky/data/pe.pil.json", 8),
+            ("../starkjs/connection/connection_main.pil", "../starky/data/connection.pil.json", 8),
+        ] {
+            let actual = compile_pil(pil_file, &CompileOptions::default()).unwrap();
+            let text = fs::read_to_string(pil_json).unwrap();
+            let target = load_json::<PIL>(pil_json).unwrap();
+            assert_eq!(actual.pil.nCommitments, target.nCommitments, "{pil_file}");
+            assert_eq!(actual.pil.nConstants, target.nConstants, "{pil_file}");
+            assert_eq!(actual.pil.references, target.references, "{pil_file}");
+            assert_eq!(actual.reference_order, reference_order_of(&text), "{pil_file}");
+            assert_eq!(actual.pil.publics[0].name, "out", "{pil_file}");
+
+            let lines: Vec<usize> = actual
+                .pil
+                .plookupIdentities
+                .iter()
+                .map(|pi| pi.line)
+                .chain(actual.pil.permutationIdentities.iter().flatten().map(|pi| pi.line))
+                .chain(actual.pil.connectionIdentities.iter().flatten().map(|ci| ci.line))
+                .collect();
+            assert_eq!(lines[0], identity, "{pil_file}");
+            let reparsed: PIL = serde_json::from_str(&actual.to_json()).unwrap();
+            assert_eq!(reparsed, actual.pil, "{pil_file}");
+        }
+    }
+
+    #[test]
+    fn test_load_or_compile_pil() {
+        let dir = std::env::temp_dir().join("pilcom_cache");
+        fs::create_dir_all(&dir).unwrap();
+        let pil_file = dir.join("fibonacci.pil");
+        fs::copy("../starkjs/fibonacci/fibonacci.pil", &pil_file).unwrap();
+        let pil_file = pil_file.to_str().unwrap();
+        let json_file = format!("{pil_file}.json");
+        let _ = fs::remove_file(&json_file);
+
+        let pil_str = fs::read_to_string(pil_file).unwrap();
+        let compiled = load_or_compile_pil(pil_file).unwrap();
+        assert_eq!(compiled, compile_pil_from_path(pil_file));
+        // loaded from the json of the source, whatever its time
+        let mut other = compiled.clone();
+        other.nQ += 1;
+        write_pil_json(pil_file, &pil_str, &other).unwrap();
+        assert_eq!(load_or_compile_pil(pil_file).unwrap(), other);
+        // a json without the hash, or of another source, is compiled again
+        fs::write(&json_file, serde_json::to_string(&other).unwrap()).unwrap();
+        assert_eq!(load_or_compile_pil(pil_file).unwrap(), compiled);
+        write_pil_json(pil_file, &format!("{pil_str}\n"), &other).unwrap();
+        assert_eq!(load_or_compile_pil(pil_file).unwrap(), compiled);
+        assert_eq!(load_or_compile_pil(pil_file).unwrap(), compiled);
+    }
+
+    #[test]
+    fn test_compile_pil_options() {
+        let dir = std::env::temp_dir().join("pilcom_options");
+        let include = dir.join("include");
+        fs::create_dir_all(&include).unwrap();
+        fs::copy("../starkjs/plookup/plookup.pil", include.join("plookup.pil")).unwrap();
+        let main = dir.join("main.pil");
+        fs::copy("../starkjs/plookup/plookup_main.pil", &main).unwrap();
+        let main = main.to_str().unwrap();
+
+        assert!(compile_pil(main, &CompileOptions::default()).is_err());
+        let options = CompileOptions { include_paths: vec![include], namespaces: None };
+        let all = compile_pil(main, &options).unwrap();
+        assert_eq!(all.pil.plookupIdentities[0].fileName, "plookup.pil");
+        assert_eq!(all.pil.plookupIdentities[0].line, 10);
+        assert_eq!(all.reference_order[0], "Global.L1");
+
+        let options = CompileOptions { namespaces: Some(vec!["Global".to_string()]), ..options };
+        let global = compile_pil(main, &options).unwrap();
+        assert!(global.pil.references.keys().all(|k| k.starts_with("Global.")));
+        assert!(global.pil.plookupIdentities.is_empty());
+
+        // a compiled namespace using a dropped one is an error, not a panic of the analyzer
+        let uses_global = dir.join("uses_global.pil");
+        fs::write(
+            &uses_global,
+            "let N: int = 2**4;\nnamespace Global(N);\n    pol constant L1;\nnamespace Main(N);\n    \
+             pol commit a;\n    Global.L1 * (a - 1) = 0;\n",
+        )
+        .unwrap();
+        let uses_global = uses_global.to_str().unwrap();
+        assert!(compile_pil(uses_global, &CompileOptions::default()).is_ok());
+        let options = CompileOptions { namespaces: Some(vec!["Main".to_string()]), ..options };
+        let err = compile_pil(uses_global, &options).unwrap_err();
+        assert!(err.to_string().starts_with("uses_global.pil:6:"), "{err}");
+    }
+
+    /// The pil.json pilcom compiles `pil_src` to, written to `dir/name`.
+    fn pilcom(dir: &Path, name: &str, pil_src: &str) -> String {
+        let pil_file = dir.join(name);
+        fs::write(&pil_file, pil_src).unwrap();
+        let json_file = dir.join(format!("{name}.json"));
+        let status = std::process::Command::new("node")
+            .arg("../starkjs/node_modules/pilcom/src/pil.js")
+            .arg(&pil_file)
+            .arg("-o")
+            .arg(&json_file)
+            .status()
+            .unwrap();
+        assert!(status.success(), "pilcom {name}");
+        fs::read_to_string(json_file).unwrap()
+    }
+
+    /// `pil_src` in the syntax of pilcom, which declares `N` as a constant.
+    fn pilcom_syntax(pil_src: &str) -> String {
+        pil_src
+            .replace("let N: int = ", "constant %N = ")
+            .replace("(N)", "(%N)")
+            .replace("(N-1)", "(%N-1)")
+    }
+
+    // The fib source pilcom compiled `starky/data/fib.pil.json` from, with the intermediate `l2c`
+    // since commented out of `starkjs/fibonacci/fibonacci.pil`.
+    const FIB_L2C: &str = "let N: int = 2**10;
+namespace Fibonacci(N);
+    pol constant L1, LLAST;
+    pol commit l1,l2;
+
+    pol l2c = l2;
+
+    public in1 = l2c(0);
+    public in2 = l1(0);
+    public out = l1(N-1);
+
+
+    (l2' - l1)*(1-LLAST) = 0;
+
+    pol next = l1*l1 + l2*l2;
+
+    (l1' - next)*(1-LLAST) = 0;
+
+    L1 * (l2 - :in1) = 0;
+    L1 * (l1 - :in2) = 0;
+    LLAST * (l1 - :out) = 0;
+";
+
+    #[test]
+    fn test_compile_pil_matches_pilcom_golden() {
+        let dir = std::env::temp_dir().join("pilcom_golden");
+        fs::create_dir_all(&dir).unwrap();
+        let pil_file = dir.join("fibonacci.pil");
+        fs::write(&pil_file, FIB_L2C).unwrap();
+        let actual = compile_pil(pil_file.to_str().unwrap(), &CompileOptions::default()).unwrap();
+        assert_eq!(actual.to_json(), fs::read_to_string("../starky/data/fib.pil.json").unwrap());
+    }
+
+    // The whole pil.json of the compressors against pilcom's: `npm i` in starkjs first.
+    #[test]
+    #[ignore]
+    fn test_compile_pil_matches_pilcom() {
+        let dir = std::env::temp_dir().join("pilcom_diff");
+        let (rust, js) = (dir.join("rust"), dir.join("js"));
+        fs::create_dir_all(&rust).unwrap();
+        fs::create_dir_all(&js).unwrap();
+        for (name, pil_src) in [
+            ("compressor12.pil", compressor12_pil::render(5, 20, &GateRegistry::default())),
+            ("compressor18.pil", compressor18_pil::render(6, 20)),
+        ] {
+            let pil_file = rust.join(name);
+            fs::write(&pil_file, &pil_src).unwrap();
+            let actual =
+                compile_pil(pil_file.to_str().unwrap(), &CompileOptions::default()).unwrap();
+            assert_eq!(actual.to_json(), pilcom(&js, name, &pilcom_syntax(&pil_src)), "{name}");
+        }
+    }
 }
@@ -78,7 +78,8 @@ pub fn export<T: FieldElement>(analyzed: &Analyzed<T>) -> PIL {
                     polId: expr.id.unwrap(),
                     idx: pub_def.index as usize,
                     id,
-                    name: name.clone(),
+                    // pilcom names the publics without their namespace
+                    name: name.rsplit(['.', ':']).next().unwrap_or(name).to_string(),
                 });
             }
             StatementIdentifier::Identity(id) => {
@@ -154,6 +155,23 @@ pub fn export<T: FieldElement>(analyzed: &Analyzed<T>) -> PIL {
     }
 }
 
+/// The names of the references in declaration order, the order pilcom lists them in.
+pub fn reference_order<T>(analyzed: &Analyzed<T>) -> Vec<String> {
+    analyzed
+        .source_order
+        .iter()
+        .filter_map(|item| match item {
+            StatementIdentifier::Definition(name) => match analyzed.definitions.get(name) {
+                Some((symbol, _)) => {
+                    matches!(symbol.kind, SymbolKind::Poly(_)).then(|| fixup_name(name))
+                }
+                None => analyzed.intermediate_columns.contains_key(name).then(|| fixup_name(name)),
+            },
+            _ => None,
+        })
+        .collect()
+}
+
 fn symbol_kind_to_json_string(k: SymbolKind) -> &'static str {
     match k {
         SymbolKind::Poly(poly_type) => polynomial_type_to_json_string(poly_type),
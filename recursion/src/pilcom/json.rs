@@ -0,0 +1,150 @@
+//! Writes a `PIL` as pilcom does, `JSON.stringify(pil, null, 1)`: the keys in pilcom's order
+//! and the references in declaration order, so the output diffs cleanly with pilcom's.
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use starky::types::{Expression, Reference, PIL};
+
+struct PilcomPil<'a> {
+    pil: &'a PIL,
+    reference_order: &'a [String],
+}
+
+struct PilcomReferences<'a>(&'a PilcomPil<'a>);
+
+struct PilcomReference<'a>(&'a Reference);
+
+/// An expression with the `deps` pilcom lists on the root ones, the intermediates they use.
+struct PilcomExpression<'a>(&'a Expression, Vec<usize>);
+
+impl<'a> PilcomExpression<'a> {
+    fn root(e: &'a Expression) -> Self {
+        let mut deps = vec![];
+        collect_deps(e, &mut deps);
+        PilcomExpression(e, deps)
+    }
+}
+
+/// The ids of the `exp` in `e`, in the order they first appear.
+fn collect_deps(e: &Expression, deps: &mut Vec<usize>) {
+    if e.op == "exp" {
+        if let Some(id) = e.id.filter(|id| !deps.contains(id)) {
+            deps.push(id);
+        }
+    }
+    for value in e.values.iter().flatten() {
+        collect_deps(value, deps);
+    }
+}
+
+impl Serialize for PilcomPil<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let pil = self.pil;
+        let expressions: Vec<_> = pil.expressions.iter().map(PilcomExpression::root).collect();
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("nCommitments", &pil.nCommitments)?;
+        map.serialize_entry("nQ", &pil.nQ)?;
+        map.serialize_entry("nIm", &pil.nIm)?;
+        map.serialize_entry("nConstants", &pil.nConstants)?;
+        map.serialize_entry("publics", &pil.publics)?;
+        map.serialize_entry("references", &PilcomReferences(self))?;
+        map.serialize_entry("expressions", &expressions)?;
+        map.serialize_entry("polIdentities", &pil.polIdentities)?;
+        map.serialize_entry("plookupIdentities", &pil.plookupIdentities)?;
+        if let Some(identities) = &pil.permutationIdentities {
+            map.serialize_entry("permutationIdentities", identities)?;
+        }
+        if let Some(identities) = &pil.connectionIdentities {
+            map.serialize_entry("connectionIdentities", identities)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for PilcomReferences<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let references = &self.0.pil.references;
+        // the declared ones first, then any other sorted
+        let mut names: Vec<&String> =
+            self.0.reference_order.iter().filter(|n| references.contains_key(*n)).collect();
+        let mut rest: Vec<&String> = references.keys().filter(|n| !names.contains(n)).collect();
+        rest.sort();
+        names.extend(rest);
+
+        let mut map = serializer.serialize_map(Some(names.len()))?;
+        for name in names {
+            map.serialize_entry(name, &PilcomReference(&references[name]))?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for PilcomReference<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let r = self.0;
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(pol_type) = &r.polType {
+            map.serialize_entry("polType", pol_type)?;
+        }
+        map.serialize_entry("type", &r.type_)?;
+        if let Some(element_type) = &r.elementType {
+            map.serialize_entry("elementType", element_type)?;
+        }
+        map.serialize_entry("id", &r.id)?;
+        map.serialize_entry("polDeg", &r.polDeg)?;
+        map.serialize_entry("isArray", &r.isArray)?;
+        if let Some(len) = &r.len {
+            map.serialize_entry("len", len)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for PilcomExpression<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let e = self.0;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("op", &e.op)?;
+        map.serialize_entry("deg", &e.deg)?;
+        if let Some(id) = &e.id {
+            map.serialize_entry("id", id)?;
+        }
+        if let Some(next) = &e.next {
+            map.serialize_entry("next", next)?;
+        }
+        if let Some(value) = &e.value {
+            map.serialize_entry("value", value)?;
+        }
+        if let Some(id_q) = &e.idQ {
+            map.serialize_entry("idQ", id_q)?;
+        }
+        if let Some(values) = &e.values {
+            let values: Vec<_> = values.iter().map(|v| PilcomExpression(v, vec![])).collect();
+            map.serialize_entry("values", &values)?;
+        }
+        if !self.1.is_empty() {
+            map.serialize_entry("deps", &self.1)?;
+        }
+        if let Some(c) = &e.const_ {
+            map.serialize_entry("const", c)?;
+        }
+        if let Some(keep) = &e.keep {
+            map.serialize_entry("keep", keep)?;
+        }
+        if let Some(keep2ns) = &e.keep2ns {
+            map.serialize_entry("keep2ns", keep2ns)?;
+        }
+        map.end()
+    }
+}
+
+/// `pil` as pilcom writes it, with the references in `reference_order`.
+pub fn to_pilcom_json(pil: &PIL, reference_order: &[String]) -> String {
+    let mut out = vec![];
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    PilcomPil { pil, reference_order }
+        .serialize(&mut serializer)
+        .expect("a PIL serializes to JSON");
+    out.push(b'\n');
+    String::from_utf8(out).expect("serde_json writes UTF-8")
+}
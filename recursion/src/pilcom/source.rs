@@ -0,0 +1,130 @@
+//! Flattens a PIL file and its includes into one source for the analyzer, the way pilcom
+//! resolves `include` and `namespaces`, and maps the lines back to their files.
+use anyhow::{anyhow, bail, Result};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A PIL source with its includes inlined.
+#[derive(Debug, Default)]
+pub struct FlatSource {
+    pub source: String,
+    /// The file name and 1 based line of each line of `source`.
+    pub lines: Vec<(String, usize)>,
+}
+
+impl FlatSource {
+    /// The file name and line of a 1 based line of `source`.
+    pub fn origin(&self, line: usize) -> Option<&(String, usize)> {
+        line.checked_sub(1).and_then(|l| self.lines.get(l))
+    }
+}
+
+struct Flattener<'a> {
+    include_paths: &'a [PathBuf],
+    namespaces: Option<&'a [String]>,
+    out: FlatSource,
+    /// The files being included, to report cycles.
+    stack: Vec<PathBuf>,
+    /// Whether the current namespace is compiled.
+    keep: bool,
+    /// The namespaces left out.
+    dropped: BTreeSet<String>,
+}
+
+/// The file of `include "file";`.
+fn include_of(line: &str) -> Option<&str> {
+    let l = line.trim().strip_prefix("include")?.trim_start();
+    let l = l.strip_prefix('"')?;
+    l.split_once('"').map(|(f, _)| f)
+}
+
+/// Whether the code of `line` names a polynomial of the namespace `ns`, as `ns.pol`.
+fn references(line: &str, ns: &str) -> bool {
+    let code = line.split("//").next().unwrap_or_default();
+    let ident = |c: char| c.is_alphanumeric() || c == '_';
+    code.match_indices(ns).any(|(i, _)| {
+        !code[..i].chars().next_back().is_some_and(ident) && code[i + ns.len()..].starts_with('.')
+    })
+}
+
+/// The name of `namespace Name(N);`.
+fn namespace_of(line: &str) -> Option<&str> {
+    let l = line.trim().strip_prefix("namespace ")?;
+    Some(l.split(['(', ';']).next().unwrap_or(l).trim())
+}
+
+impl<'a> Flattener<'a> {
+    /// Looks an include up next to the including file, then in the include paths.
+    fn resolve(&self, dir: &Path, file: &str) -> Result<PathBuf> {
+        std::iter::once(dir)
+            .chain(self.include_paths.iter().map(|p| p.as_path()))
+            .map(|d| d.join(file))
+            .find(|p| p.is_file())
+            .ok_or_else(|| {
+                anyhow!("Include {} not found in {:?} or {:?}", file, dir, self.include_paths)
+            })
+    }
+
+    fn flatten(&mut self, path: &Path) -> Result<()> {
+        let path = path.canonicalize().map_err(|e| anyhow!("Read {:?}, {:?}", path, e))?;
+        if self.stack.contains(&path) {
+            bail!("Include cycle: {:?} includes {:?}", self.stack.last(), path);
+        }
+        let source = fs::read_to_string(&path).map_err(|e| anyhow!("Read {:?}, {:?}", path, e))?;
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        let dir = path.parent().unwrap_or_else(|| Path::new(".")).to_path_buf();
+        self.stack.push(path);
+
+        for (i, line) in source.lines().enumerate() {
+            if let Some(ns) = namespace_of(line) {
+                self.keep = self.namespaces.is_none_or(|n| n.iter().any(|n| n == ns));
+                if !self.keep {
+                    self.dropped.insert(ns.to_string());
+                }
+            }
+            if !self.keep {
+                continue;
+            }
+            match include_of(line) {
+                Some(file) => {
+                    let included = self.resolve(&dir, file)?;
+                    self.flatten(&included)?;
+                }
+                None => {
+                    self.out.source.push_str(line);
+                    self.out.source.push('\n');
+                    self.out.lines.push((file_name.clone(), i + 1));
+                }
+            }
+        }
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+/// Inlines the includes of `pil_path`, looked up next to the including file and then in
+/// `include_paths`, and drops the namespaces not in `namespaces`. A polynomial of a dropped
+/// namespace used by a compiled one is an error, the analyzer would panic on it.
+pub fn flatten(
+    pil_path: &Path,
+    include_paths: &[PathBuf],
+    namespaces: Option<&[String]>,
+) -> Result<FlatSource> {
+    let mut flattener = Flattener {
+        include_paths,
+        namespaces,
+        out: FlatSource::default(),
+        stack: vec![],
+        keep: true,
+        dropped: BTreeSet::new(),
+    };
+    flattener.flatten(pil_path)?;
+    let out = flattener.out;
+    for (line, (file_name, l)) in out.source.lines().zip(&out.lines) {
+        if let Some(ns) = flattener.dropped.iter().find(|ns| references(line, ns)) {
+            bail!("{file_name}:{l}: `{}` uses namespace {ns}, which is not compiled", line.trim());
+        }
+    }
+    Ok(out)
+}
@@ -3,6 +3,7 @@ use algebraic::circom_circuit::{Constraint, R1CS};
 use array_tool::vec::Shift;
 use fields::field_gl::Fr as FGL;
 use fields::field_gl::{Fr, GL};
+use rayon::prelude::*;
 use std::collections::BTreeMap;
 use std::ops::Neg;
 
@@ -47,45 +48,57 @@ impl std::fmt::Display for PlonkAdd {
     }
 }
 
-pub fn r1cs2plonk(r1cs: &R1CS<GL>) -> (Vec<PlonkGate>, Vec<PlonkAdd>) {
-    let mut plonk_n_var = r1cs.num_variables;
-    let mut plonk_constraints: Vec<PlonkGate> = vec![];
-    let mut plonk_additions: Vec<PlonkAdd> = vec![];
-
-    let normalize = |lc: &mut BTreeMap<usize, FGL>| {
-        lc.retain(|_, v| *v != FGL::ZERO);
-    };
-
-    let join =
-        |lc1: &BTreeMap<usize, FGL>, k: &FGL, lc2: &BTreeMap<usize, FGL>| -> BTreeMap<usize, FGL> {
-            let mut res: BTreeMap<usize, FGL> = BTreeMap::new();
-            for (key, val) in lc1.iter() {
-                if !res.contains_key(key) {
-                    res.insert(*key, *k * (*val));
-                } else {
-                    let tmp = res[key];
-                    res.insert(*key, *k * (*val) + tmp);
-                }
-            }
+/// The 5 coefficients of a gate as integers, equal for the gates `str_key` is equal for.
+pub type GateKey = [u64; 5];
 
-            for (key, val) in lc2.iter() {
-                if !res.contains_key(key) {
-                    res.insert(*key, *val);
-                } else {
-                    let tmp = res[key];
-                    res.insert(*key, *val + tmp);
-                }
-            }
-            normalize(&mut res);
-            res
-        };
+impl PlonkGate {
+    pub fn key(&self) -> GateKey {
+        [self.3.as_int(), self.4.as_int(), self.5.as_int(), self.6.as_int(), self.7.as_int()]
+    }
+}
+
+/// The plonk gates and additions of a constraint. The signals it creates are numbered from the
+/// `n_var` it starts at, `n_var` is the next one when it is done.
+#[derive(Debug, Default)]
+struct Plonkified {
+    gates: Vec<PlonkGate>,
+    adds: Vec<PlonkAdd>,
+    n_var: usize,
+}
+
+fn normalize(lc: &mut BTreeMap<usize, FGL>) {
+    lc.retain(|_, v| *v != FGL::ZERO);
+}
+
+fn join(lc1: &BTreeMap<usize, FGL>, k: &FGL, lc2: &BTreeMap<usize, FGL>) -> BTreeMap<usize, FGL> {
+    let mut res: BTreeMap<usize, FGL> = BTreeMap::new();
+    for (key, val) in lc1.iter() {
+        if !res.contains_key(key) {
+            res.insert(*key, *k * (*val));
+        } else {
+            let tmp = res[key];
+            res.insert(*key, *k * (*val) + tmp);
+        }
+    }
 
-    let reduce_coefs = |lc: &BTreeMap<usize, FGL>,
-                        max_c: usize,
-                        pc: &mut Vec<PlonkGate>,
-                        pa: &mut Vec<PlonkAdd>,
-                        n_var: &mut usize|
-     -> (FGL, Vec<usize>, Vec<FGL>) {
+    for (key, val) in lc2.iter() {
+        if !res.contains_key(key) {
+            res.insert(*key, *val);
+        } else {
+            let tmp = res[key];
+            res.insert(*key, *val + tmp);
+        }
+    }
+    normalize(&mut res);
+    res
+}
+
+impl Plonkified {
+    fn reduce_coefs(
+        &mut self,
+        lc: &BTreeMap<usize, FGL>,
+        max_c: usize,
+    ) -> (FGL, Vec<usize>, Vec<FGL>) {
         // (k, s, coefs)
         let mut res: (FGL, Vec<usize>, Vec<FGL>) = (FGL::ZERO, vec![], vec![]);
         let mut cs: Vec<(usize, FGL)> = vec![];
@@ -103,8 +116,8 @@ pub fn r1cs2plonk(r1cs: &R1CS<GL>) -> (Vec<PlonkGate>, Vec<PlonkAdd>) {
 
             let sl = c1.0;
             let sr = c2.0;
-            let so = *n_var;
-            *n_var += 1;
+            let so = self.n_var;
+            self.n_var += 1;
 
             let qm = FGL::ZERO;
             let ql = c1.1.neg();
@@ -112,8 +125,8 @@ pub fn r1cs2plonk(r1cs: &R1CS<GL>) -> (Vec<PlonkGate>, Vec<PlonkAdd>) {
             let qo = FGL::ONE;
             let qc = FGL::ZERO;
 
-            pc.push(PlonkGate(sl, sr, so, qm, ql, qr, qo, qc));
-            pa.push(PlonkAdd(sl, sr, c1.1, c2.1));
+            self.gates.push(PlonkGate(sl, sr, so, qm, ql, qr, qo, qc));
+            self.adds.push(PlonkAdd(sl, sr, c1.1, c2.1));
             cs.push((so, FGL::ONE));
         }
         for c in cs.iter() {
@@ -125,17 +138,17 @@ pub fn r1cs2plonk(r1cs: &R1CS<GL>) -> (Vec<PlonkGate>, Vec<PlonkAdd>) {
             res.2.push(FGL::ZERO);
         }
         res
-    };
-
-    let add_constraint_mul = |la: &BTreeMap<usize, FGL>,
-                              lb: &BTreeMap<usize, FGL>,
-                              lc: &BTreeMap<usize, FGL>,
-                              pc: &mut Vec<PlonkGate>,
-                              pa: &mut Vec<PlonkAdd>,
-                              n_var: &mut usize| {
-        let A = reduce_coefs(la, 1, pc, pa, n_var);
-        let B = reduce_coefs(lb, 1, pc, pa, n_var);
-        let C = reduce_coefs(lc, 1, pc, pa, n_var);
+    }
+
+    fn add_constraint_mul(
+        &mut self,
+        la: &BTreeMap<usize, FGL>,
+        lb: &BTreeMap<usize, FGL>,
+        lc: &BTreeMap<usize, FGL>,
+    ) {
+        let A = self.reduce_coefs(la, 1);
+        let B = self.reduce_coefs(lb, 1);
+        let C = self.reduce_coefs(lc, 1);
 
         let sl = A.1[0];
         let sr = B.1[0];
@@ -145,14 +158,11 @@ pub fn r1cs2plonk(r1cs: &R1CS<GL>) -> (Vec<PlonkGate>, Vec<PlonkAdd>) {
         let qr = A.0 * B.2[0];
         let qo = C.2[0].neg();
         let qc = A.0 * B.0 - C.0;
-        pc.push(PlonkGate(sl, sr, so, qm, ql, qr, qo, qc));
-    };
-
-    let add_constraint_sum = |lc: &BTreeMap<usize, FGL>,
-                              pc: &mut Vec<PlonkGate>,
-                              pa: &mut Vec<PlonkAdd>,
-                              n_var: &mut usize| {
-        let C = reduce_coefs(lc, 3, pc, pa, n_var);
+        self.gates.push(PlonkGate(sl, sr, so, qm, ql, qr, qo, qc));
+    }
+
+    fn add_constraint_sum(&mut self, lc: &BTreeMap<usize, FGL>) {
+        let C = self.reduce_coefs(lc, 3);
         let sl = C.1[0];
         let sr = C.1[1];
         let so = C.1[2];
@@ -161,67 +171,88 @@ pub fn r1cs2plonk(r1cs: &R1CS<GL>) -> (Vec<PlonkGate>, Vec<PlonkAdd>) {
         let qr = C.2[1];
         let qo = C.2[2];
         let qc = C.0;
-        pc.push(PlonkGate(sl, sr, so, qm, ql, qr, qo, qc));
-    };
-
-    let to_be_map = |lc: &Vec<(usize, Fr)>| -> BTreeMap<usize, FGL> {
-        let mut res: BTreeMap<usize, FGL> = BTreeMap::new();
-        for c in lc.iter() {
-            assert!(!res.contains_key(&c.0));
-            res.insert(c.0, c.1);
+        self.gates.push(PlonkGate(sl, sr, so, qm, ql, qr, qo, qc));
+    }
+
+    fn process(c: &Constraint<GL>, n_var: usize) -> Self {
+        let mut res = Self { n_var, ..Default::default() };
+        let mut lc_a = to_be_map(&c.0);
+        let mut lc_b = to_be_map(&c.1);
+        let mut lc_c = to_be_map(&c.2);
+        let lca = get_lc_type(&mut lc_a);
+        let lcb = get_lc_type(&mut lc_b);
+        if lca.as_str() == "0" || lcb.as_str() == "0" {
+            normalize(&mut lc_c);
+            res.add_constraint_sum(&lc_c);
+        } else if lca.as_str() == "k" {
+            let lc_cc = join(&lc_b, &lc_a[&0], &lc_c);
+            res.add_constraint_sum(&lc_cc);
+        } else if lcb.as_str() == "k" {
+            let lc_cc = join(&lc_a, &lc_b[&0], &lc_c);
+            res.add_constraint_sum(&lc_cc);
+        } else {
+            res.add_constraint_mul(&lc_a, &lc_b, &lc_c);
         }
         res
-    };
-
-    let get_lc_type = |lc: &mut BTreeMap<usize, FGL>| -> String {
-        let mut k = FGL::ZERO;
-        let mut n = 0;
-        let keys: Vec<usize> = lc.keys().copied().collect();
-        for key in keys.iter() {
-            let val = lc[key];
-            if val == FGL::ZERO {
-                lc.remove(key).unwrap();
-            } else if *key == 0 {
-                k = k + val;
-            } else {
-                n += 1;
-            }
-        }
-        if n > 0 {
-            return n.to_string();
-        }
-        if k != FGL::ZERO {
-            return String::from("k");
-        }
-        String::from("0")
-    };
-
-    let process =
-        |c: &Constraint<GL>, pc: &mut Vec<PlonkGate>, pa: &mut Vec<PlonkAdd>, n_var: &mut usize| {
-            let mut lc_a = to_be_map(&c.0);
-            let mut lc_b = to_be_map(&c.1);
-            let mut lc_c = to_be_map(&c.2);
-            let lca = get_lc_type(&mut lc_a);
-            let lcb = get_lc_type(&mut lc_b);
-            if lca.as_str() == "0" || lcb.as_str() == "0" {
-                normalize(&mut lc_c);
-                add_constraint_sum(&lc_c, pc, pa, n_var);
-            } else if lca.as_str() == "k" {
-                let lc_cc = join(&lc_b, &lc_a[&0], &lc_c);
-                add_constraint_sum(&lc_cc, pc, pa, n_var);
-            } else if lcb.as_str() == "k" {
-                let lc_cc = join(&lc_a, &lc_b[&0], &lc_c);
-                add_constraint_sum(&lc_cc, pc, pa, n_var);
-            } else {
-                add_constraint_mul(&lc_a, &lc_b, &lc_c, pc, pa, n_var);
-            }
-        };
+    }
+}
 
-    for (i, c) in r1cs.constraints.iter().enumerate() {
-        if i % 100000 == 0 {
-            log::trace!("processing constraints: {}/{}", i, r1cs.constraints.len());
+fn to_be_map(lc: &[(usize, Fr)]) -> BTreeMap<usize, FGL> {
+    let mut res: BTreeMap<usize, FGL> = BTreeMap::new();
+    for c in lc.iter() {
+        assert!(!res.contains_key(&c.0));
+        res.insert(c.0, c.1);
+    }
+    res
+}
+
+fn get_lc_type(lc: &mut BTreeMap<usize, FGL>) -> String {
+    let mut k = FGL::ZERO;
+    let mut n = 0;
+    let keys: Vec<usize> = lc.keys().copied().collect();
+    for key in keys.iter() {
+        let val = lc[key];
+        if val == FGL::ZERO {
+            lc.remove(key).unwrap();
+        } else if *key == 0 {
+            k = k + val;
+        } else {
+            n += 1;
         }
-        process(c, &mut plonk_constraints, &mut plonk_additions, &mut plonk_n_var);
+    }
+    if n > 0 {
+        return n.to_string();
+    }
+    if k != FGL::ZERO {
+        return String::from("k");
+    }
+    String::from("0")
+}
+
+/// Converts the constraints in parallel, each numbering the signals it creates from
+/// `num_variables`, then shifts them after the signals of the constraints before it. The result
+/// is the one of converting them one after the other.
+pub fn r1cs2plonk(r1cs: &R1CS<GL>) -> (Vec<PlonkGate>, Vec<PlonkAdd>) {
+    let n = r1cs.num_variables;
+    log::trace!("processing constraints: {}", r1cs.constraints.len());
+    let plonkified: Vec<Plonkified> =
+        r1cs.constraints.par_iter().map(|c| Plonkified::process(c, n)).collect();
+
+    let n_gates = plonkified.iter().map(|p| p.gates.len()).sum();
+    let n_adds = plonkified.iter().map(|p| p.adds.len()).sum();
+    let mut plonk_constraints: Vec<PlonkGate> = Vec::with_capacity(n_gates);
+    let mut plonk_additions: Vec<PlonkAdd> = Vec::with_capacity(n_adds);
+    let mut offset = 0;
+    for p in plonkified {
+        let shift = |s: usize| if s >= n { s + offset } else { s };
+        plonk_constraints.extend(
+            p.gates
+                .into_iter()
+                .map(|g| PlonkGate(shift(g.0), shift(g.1), shift(g.2), g.3, g.4, g.5, g.6, g.7)),
+        );
+        plonk_additions
+            .extend(p.adds.into_iter().map(|a| PlonkAdd(shift(a.0), shift(a.1), a.2, a.3)));
+        offset += p.n_var - n;
     }
     (plonk_constraints, plonk_additions)
 }
@@ -234,6 +265,55 @@ mod test {
     use std::io::Write;
     use std::path::Path;
 
+    /// A random R1CS over `n_vars` signals, with empty, constant, linear and quadratic linear
+    /// combinations of up to 5 terms.
+    fn random_r1cs(n_constraints: usize, n_vars: usize) -> R1CS<GL> {
+        let mut seed = 0x2545f4914f6cdd1du64;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        let mut lc = || {
+            let mut signals: Vec<usize> =
+                (0..next() % 6).map(|_| (next() % n_vars as u64) as usize).collect();
+            signals.sort();
+            signals.dedup();
+            signals.into_iter().map(|s| (s, FGL::from(next() % 7))).collect::<Vec<_>>()
+        };
+        R1CS {
+            num_inputs: 1,
+            num_aux: n_vars - 1,
+            num_variables: n_vars,
+            num_outputs: 0,
+            constraints: (0..n_constraints).map(|_| (lc(), lc(), lc())).collect(),
+            custom_gates: vec![],
+            custom_gates_uses: vec![],
+        }
+    }
+
+    #[test]
+    fn test_r1cs2plonk_matches_sequential() {
+        let r1cs = random_r1cs(5000, 300);
+        let (gates, adds) = r1cs2plonk(&r1cs);
+
+        let (mut seq_gates, mut seq_adds) = (vec![], vec![]);
+        let mut n_var = r1cs.num_variables;
+        for c in r1cs.constraints.iter() {
+            let p = Plonkified::process(c, n_var);
+            n_var = p.n_var;
+            seq_gates.extend(p.gates);
+            seq_adds.extend(p.adds);
+        }
+        assert!(seq_adds.len() > 1000);
+        let to_strings = |v: &[PlonkGate]| v.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+        assert_eq!(to_strings(&gates), to_strings(&seq_gates));
+        let to_strings = |v: &[PlonkAdd]| v.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(to_strings(&adds), to_strings(&seq_adds));
+        assert!(gates.iter().all(|g| g.key().map(|k| format!("{k:x}")).join(",") == g.str_key()));
+    }
+
     /// The js dump code as below:
     /// ```js
     /// fs.writeFileSync("plonk_constrains_js.json", JSON.stringify(plonkConstraints, (key, value) =>
@@ -0,0 +1,478 @@
+//! The witness of a circom verifier computed natively, in the layout of its wasm: the inputs are
+//! placed by the `.sym` of the circuit, the other signals solved from the constraints and the
+//! custom gates of its `.r1cs`, the gates of compressor12 computed as the gadgets compute them.
+//!
+//! A constraint solves the signal it is linear in once the others are known, the bits of a
+//! `Num2Bits` are solved together from their sum, and a CMulAdd solves the `b` of `a * b + c`
+//! once its output is known, the inverse of `CInv`. The witness is checked against all the
+//! constraints and the gates before it is returned.
+use super::circuit::{Circuit, Signal};
+use super::gadgets::{f3_inv, f3_mul_add};
+use crate::compressor12::compressor12_exec::ExecError;
+use algebraic::circom_circuit::R1CS;
+use algebraic::reader::load_r1cs_from_bin;
+use algebraic::witness::InputError;
+use anyhow::{anyhow, bail, ensure, Result};
+use fields::field_gl::{Fr as FGL, GL, MODULUS};
+use fields::Field;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// A constraint or a custom gate use of the R1CS.
+#[derive(Clone, Copy)]
+enum Item {
+    Constraint(usize),
+    Gate(usize),
+}
+
+/// The R1CS and the signals of main of a circom circuit, to compute its witness without its wasm.
+pub struct CircomWitness {
+    r1cs: R1CS<GL>,
+    /// The witness indices of the signals of main by name, in the order of their flattened
+    /// values, None for a signal circom removed.
+    signals: HashMap<String, Vec<Option<usize>>>,
+    /// The items each signal is in.
+    items: Vec<Vec<Item>>,
+    /// The signals constrained to be 0 or 1.
+    bits: Vec<bool>,
+}
+
+/// The indices of a signal of main in the `.sym`, and its witness, None when optimized out.
+type SymSignal = (Vec<usize>, Option<usize>);
+
+impl CircomWitness {
+    pub fn from_files(r1cs_file: &str, sym_file: &str) -> Result<Self> {
+        let r1cs_bytes =
+            std::fs::read(r1cs_file).map_err(|e| anyhow!("Read {}, {:?}", r1cs_file, e))?;
+        let sym =
+            std::fs::read_to_string(sym_file).map_err(|e| anyhow!("Read {}, {:?}", sym_file, e))?;
+        Self::from_bytes(&r1cs_bytes, &sym)
+    }
+
+    /// As `new`, from the bytes of the `.r1cs`.
+    pub fn from_bytes(r1cs_bytes: &[u8], sym: &str) -> Result<Self> {
+        let (r1cs, _) = load_r1cs_from_bin::<_, GL>(std::io::Cursor::new(r1cs_bytes));
+        Self::new(r1cs, sym)
+    }
+
+    /// `sym` is the `.sym` of the circuit, `witness index, signal index, component, name` a line.
+    pub fn new(r1cs: R1CS<GL>, sym: &str) -> Result<Self> {
+        let n = r1cs.num_variables;
+        for gate in &r1cs.custom_gates {
+            ensure!(
+                n_inputs(&gate.template_name).is_some(),
+                "no custom gate {}",
+                gate.template_name
+            );
+        }
+
+        let mut by_name: HashMap<String, Vec<SymSignal>> = HashMap::new();
+        for (i, line) in sym.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let fields: Vec<&str> = line.trim().splitn(4, ',').collect();
+            let [_, witness, _, name] = fields[..] else {
+                bail!("sym line {}: {}", i + 1, line);
+            };
+            // the signals of main only, not those of its components
+            let Some(name) = name.strip_prefix("main.") else { continue };
+            if name.contains('.') {
+                continue;
+            }
+            let (base, indices) = name.split_at(name.find('[').unwrap_or(name.len()));
+            let indices = indices
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .split("][")
+                .filter(|s| !s.is_empty())
+                .map(str::parse)
+                .collect::<Result<Vec<usize>, _>>()
+                .map_err(|e| anyhow!("sym line {}: {}, {}", i + 1, line, e))?;
+            let witness = match witness.parse::<i64>()? {
+                -1 => None,
+                w => {
+                    ensure!((0..n as i64).contains(&w), "sym line {}: {}", i + 1, line);
+                    Some(w as usize)
+                }
+            };
+            by_name.entry(base.to_string()).or_default().push((indices, witness));
+        }
+        let signals = by_name
+            .into_iter()
+            .map(|(name, mut signals)| {
+                signals.sort();
+                (name, signals.into_iter().map(|(_, w)| w).collect())
+            })
+            .collect();
+
+        let mut items = vec![vec![]; n];
+        let mut bits = vec![false; n];
+        for (i, (a, b, c)) in r1cs.constraints.iter().enumerate() {
+            for (s, _) in a.iter().chain(b).chain(c) {
+                items[*s].push(Item::Constraint(i));
+            }
+            if let Some(bit) = boolean(a, b, c) {
+                bits[bit] = true;
+            }
+        }
+        for (i, u) in r1cs.custom_gates_uses.iter().enumerate() {
+            for s in &u.signals {
+                items[*s as usize].push(Item::Gate(i));
+            }
+        }
+        Ok(Self { r1cs, signals, items, bits })
+    }
+
+    /// The witness of the inputs of main, the values of the signals by their witness index.
+    pub fn calculate_witness<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &self,
+        inputs: I,
+    ) -> Result<Vec<FGL>, ExecError> {
+        let mut w: Vec<Option<FGL>> = vec![None; self.r1cs.num_variables];
+        w[0] = Some(FGL::ONE);
+        for (name, values) in inputs {
+            if values.is_empty() {
+                continue;
+            }
+            let Some(signals) = self.signals.get(&name) else {
+                return Err(InputError::UnknownSignal { signal: name }.into());
+            };
+            if signals.len() != values.len() {
+                let (expected, actual) = (signals.len(), values.len());
+                return Err(InputError::SignalSize { signal: name, expected, actual }.into());
+            }
+            for (s, value) in signals.iter().zip(values) {
+                let Some(s) = *s else { continue };
+                match value.to_u64() {
+                    Some(v) if v < MODULUS.0[0] => w[s] = Some(FGL::from(v)),
+                    _ => return Err(ExecError::NotGoldilocks { signal: s, value }),
+                }
+            }
+        }
+
+        let n_constraints = self.r1cs.constraints.len();
+        let mut queue: VecDeque<Item> = (0..n_constraints)
+            .map(Item::Constraint)
+            .chain((0..self.r1cs.custom_gates_uses.len()).map(Item::Gate))
+            .collect();
+        while let Some(item) = queue.pop_front() {
+            let solved = match item {
+                Item::Constraint(i) => self.solve_constraint(i, &w),
+                Item::Gate(i) => self.solve_gate(i, &w),
+            };
+            for (s, v) in solved {
+                if w[s].is_none() {
+                    w[s] = Some(v);
+                    queue.extend(&self.items[s]);
+                }
+            }
+        }
+
+        if let Some(signal) = w.iter().position(Option::is_none) {
+            let unsolved = w.iter().filter(|v| v.is_none()).count();
+            return Err(ExecError::Unsolved { signal, unsolved });
+        }
+        let w: Vec<FGL> = w.into_iter().flatten().collect();
+        let lc = |lc: &[(usize, FGL)]| lc.iter().fold(FGL::ZERO, |acc, (s, k)| acc + w[*s] * *k);
+        for (constraint, (a, b, c)) in self.r1cs.constraints.iter().enumerate() {
+            if lc(a) * lc(b) != lc(c) {
+                return Err(ExecError::Constraint { constraint });
+            }
+        }
+        for (gate_use, u) in self.r1cs.custom_gates_uses.iter().enumerate() {
+            let gate = &self.r1cs.custom_gates[u.id as usize];
+            let values: Vec<FGL> = u.signals.iter().map(|s| w[*s as usize]).collect();
+            let n_in = n_inputs(&gate.template_name).unwrap();
+            if gate_values(&gate.template_name, &gate.parameters, &values[..n_in]) != values {
+                let template = gate.template_name.clone();
+                return Err(ExecError::CustomGate { gate_use, template });
+            }
+        }
+        Ok(w)
+    }
+
+    /// The unknown signals of `a * b = c` it solves, those of a linear equation once `a` or `b`
+    /// is known.
+    fn solve_constraint(&self, i: usize, w: &[Option<FGL>]) -> Vec<(usize, FGL)> {
+        let (a, b, c) = &self.r1cs.constraints[i];
+        // the value of the known terms and the unknown terms of a linear combination
+        let split = |lc: &[(usize, FGL)]| {
+            let mut known = FGL::ZERO;
+            let mut unknown = vec![];
+            for (s, k) in lc {
+                match w[*s] {
+                    Some(v) => known = known + v * *k,
+                    None => unknown.push((*s, *k)),
+                }
+            }
+            (known, unknown)
+        };
+        let ((av, au), (bv, bu), (cv, cu)) = (split(a), split(b), split(c));
+        if !au.is_empty() && !bu.is_empty() {
+            return vec![];
+        }
+        // (av + au) * (bv + bu) = cv + cu, au or bu empty
+        let mut terms: BTreeMap<usize, FGL> = BTreeMap::new();
+        let scaled = au.iter().map(|(s, k)| (*s, *k * bv));
+        let scaled = scaled.chain(bu.iter().map(|(s, k)| (*s, *k * av)));
+        for (s, k) in scaled.chain(cu.iter().map(|(s, k)| (*s, -*k))) {
+            let t = terms.entry(s).or_insert(FGL::ZERO);
+            *t = *t + k;
+        }
+        let terms: Vec<(usize, FGL)> = terms.into_iter().filter(|(_, k)| *k != FGL::ZERO).collect();
+        let rhs = cv - av * bv;
+        match terms.as_slice() {
+            [] => vec![],
+            [(s, k)] => vec![(*s, rhs * k.inverse().unwrap())],
+            _ if terms.iter().all(|(s, _)| self.bits[*s]) => decompose(&terms, rhs),
+            _ => vec![],
+        }
+    }
+
+    /// The unknown signals of a custom gate use it solves, its outputs once its inputs are known.
+    fn solve_gate(&self, i: usize, w: &[Option<FGL>]) -> Vec<(usize, FGL)> {
+        let u = &self.r1cs.custom_gates_uses[i];
+        let gate = &self.r1cs.custom_gates[u.id as usize];
+        let values: Vec<Option<FGL>> = u.signals.iter().map(|s| w[*s as usize]).collect();
+        if values.iter().all(Option::is_some) {
+            return vec![];
+        }
+        let n_in = n_inputs(&gate.template_name).unwrap();
+        let solved = match values[..n_in].iter().copied().collect::<Option<Vec<FGL>>>() {
+            Some(inputs) => Some(gate_values(&gate.template_name, &gate.parameters, &inputs)),
+            None if gate.template_name == "CMulAdd" => c_mul_add_operand(&values),
+            None => None,
+        };
+        let Some(solved) = solved else { return vec![] };
+        u.signals
+            .iter()
+            .zip(solved)
+            .filter(|(s, _)| w[**s as usize].is_none())
+            .map(|(s, v)| (*s as usize, v))
+            .collect()
+    }
+}
+
+/// The number of inputs of a custom gate of compressor12, the signals before its outputs.
+fn n_inputs(template_name: &str) -> Option<usize> {
+    match template_name {
+        "Poseidon12" | "FFT4" => Some(12),
+        "CMulAdd" => Some(9),
+        "EvPol4" => Some(18),
+        _ => None,
+    }
+}
+
+/// The signals of a use of a custom gate of `inputs`, computed by the gadget of the gate.
+fn gate_values(template_name: &str, parameters: &[FGL], inputs: &[FGL]) -> Vec<FGL> {
+    let f3 = |i: usize| [inputs[i], inputs[i + 1], inputs[i + 2]];
+    if template_name == "EvPol4" {
+        // [coefficients(4), acc, x, out]
+        let (acc, x) = (f3(12), f3(15));
+        let out = (0..4).rev().fold(acc, |acc, i| f3_mul_add(acc, x, f3(3 * i)));
+        return [inputs, &out].concat();
+    }
+    let mut c = Circuit::new();
+    let s: Vec<Signal> = inputs.iter().map(|v| c.alloc(*v)).collect();
+    let f3s = |i: usize| [s[i], s[i + 1], s[i + 2]];
+    match template_name {
+        "Poseidon12" => {
+            c.poseidon(std::array::from_fn(|i| s[i]));
+        }
+        "CMulAdd" => {
+            c.c_mul_add(f3s(0), f3s(3), f3s(6));
+        }
+        _ => {
+            let t = parameters[3].as_int();
+            c.fft4([0, 3, 6, 9].map(f3s), parameters[0], parameters[1], parameters[2], t);
+        }
+    }
+    let (plonk, w) = c.into_plonk();
+    plonk.custom_gates_uses[0].signals.iter().map(|s| w[*s as usize]).collect()
+}
+
+/// The signals of a CMulAdd `a * b + c = out` of which `c`, `out` and `a` or `b` are known.
+fn c_mul_add_operand(values: &[Option<FGL>]) -> Option<Vec<FGL>> {
+    let f3 = |i: usize| Some([values[i]?, values[i + 1]?, values[i + 2]?]);
+    let (c, out) = (f3(6)?, f3(9)?);
+    let diff = [out[0] - c[0], out[1] - c[1], out[2] - c[2]];
+    let zero = [FGL::ZERO; 3];
+    let quotient = |d: [FGL; 3]| (d != zero).then(|| f3_mul_add(diff, f3_inv(d), zero));
+    let (a, b) = match (f3(0), f3(3)) {
+        (Some(a), None) => (a, quotient(a)?),
+        (None, Some(b)) => (quotient(b)?, b),
+        _ => return None,
+    };
+    Some([a, b, c, out].concat())
+}
+
+/// The signal `x` of `a * b = c` constraining it to be 0 or 1, `(k x + k0) * (k' x + k0') = 0`
+/// with the roots 0 and 1.
+fn boolean(a: &[(usize, FGL)], b: &[(usize, FGL)], c: &[(usize, FGL)]) -> Option<usize> {
+    // the signal and the root of `k x + k0`
+    let root = |lc: &[(usize, FGL)]| {
+        let (mut x, mut k, mut k0) = (None, FGL::ZERO, FGL::ZERO);
+        for (s, v) in lc {
+            match (*s, x) {
+                (0, _) => k0 = *v,
+                (s, None) => (x, k) = (Some(s), *v),
+                _ => return None,
+            }
+        }
+        Some((x?, -k0 * k.inverse()?))
+    };
+    let ((x, r), (y, q)) = (root(a)?, root(b)?);
+    let roots = [r, q];
+    (c.is_empty() && x == y && roots.contains(&FGL::ZERO) && roots.contains(&FGL::ONE)).then_some(x)
+}
+
+/// The bits of `sum(k_i b_i) = rhs`, the `k_i` distinct powers of 2 times one of them.
+fn decompose(terms: &[(usize, FGL)], rhs: FGL) -> Vec<(usize, FGL)> {
+    for (_, f) in terms {
+        let f_inv = f.inverse().unwrap();
+        let exps: Option<Vec<u32>> = terms
+            .iter()
+            .map(|(_, k)| {
+                let p = (*k * f_inv).as_int();
+                p.is_power_of_two().then(|| p.trailing_zeros())
+            })
+            .collect();
+        let Some(exps) = exps else { continue };
+        let mut distinct = exps.clone();
+        distinct.sort();
+        distinct.dedup();
+        if distinct.len() != exps.len() {
+            continue;
+        }
+        let v = (rhs * f_inv).as_int();
+        return terms.iter().zip(exps).map(|((s, _), e)| (*s, FGL::from((v >> e) & 1))).collect();
+    }
+    vec![]
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::verifier_circuit::stark_verifier::test::fib_stark;
+    use algebraic::witness::{parse_input_for_witness, WitnessCalculator};
+    use starky::merklehash::MerkleTreeGL;
+    use starky::pil2circom::{pil2circom, StarkOption};
+    use starky::stark_setup::StarkSetup;
+    use starky::types::{StarkStruct, PIL};
+    use std::path::PathBuf;
+
+    /// Compiles the verifier circom renders for `setup` with the templates bundled in
+    /// stark-circuits, into the `<name>.r1cs`, `<name>.sym` and `<name>_js/<name>.wasm` of the
+    /// directory it returns.
+    pub(crate) fn compile_verifier(
+        name: &str,
+        setup: &mut StarkSetup<MerkleTreeGL>,
+        stark_struct: &StarkStruct,
+        pil: &PIL,
+        options: &StarkOption,
+    ) -> PathBuf {
+        let circom = pil2circom::<FGL>(
+            pil,
+            &setup.const_root,
+            stark_struct,
+            &mut setup.starkinfo,
+            &mut setup.program,
+            options,
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().join("circom_witness");
+        std::fs::create_dir_all(&dir).unwrap();
+        let circom_file = dir.join(format!("{name}.circom"));
+        std::fs::write(&circom_file, circom).unwrap();
+        dsl_compile::circom_compiler(
+            circom_file.to_str().unwrap().to_string(),
+            "goldilocks".to_string(),
+            "full".to_string(),
+            vec!["starky:GL".to_string()],
+            dir.to_str().unwrap().to_string(),
+            false,
+            false,
+        )
+        .unwrap();
+        dir
+    }
+
+    /// The sym of the signals of main `name[i]`.
+    fn sym(inputs: &[(&str, &[Signal])]) -> String {
+        inputs
+            .iter()
+            .flat_map(|(name, signals)| {
+                signals.iter().enumerate().map(move |(i, s)| format!("{s},{s},0,main.{name}[{i}]"))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn input(name: &str, values: &[u64]) -> (String, Vec<BigInt>) {
+        (name.to_string(), values.iter().map(|v| BigInt::from(*v)).collect())
+    }
+
+    #[test]
+    fn test_circom_witness() {
+        // the gadgets of the verifier, of which the inputs only are given
+        let mut c = Circuit::new();
+        let a: Vec<Signal> = [3u64, 5, 7, 9].iter().map(|v| c.alloc(FGL::from(*v))).collect();
+        let x = [a[0], a[1], a[2]];
+        let inv = c.c_inv(x);
+        let bits = c.num2bits_strict(a[3]);
+        let state: [Signal; 12] = std::array::from_fn(|i| a[i % 4]);
+        let hash = c.poseidon(state);
+        let values: Vec<[Signal; 3]> = (0..8).map(|i| [a[i % 4], inv[i % 3], hash[i]]).collect();
+        let coefs = c.ifft(&values);
+        c.eval_pol(&coefs, inv);
+        // 9 selects the value 1
+        let selected = c.tree_selector(&values, &bits[..3]);
+        let five = c.constant(FGL::from(5u64));
+        c.enforce_eq(selected[0], five, || String::from("selected"));
+        assert_eq!(c.failure(), None);
+        let (r1cs, expected) = c.into_r1cs();
+
+        let witness = CircomWitness::new(r1cs, &sym(&[("a", &a)])).unwrap();
+        let w = witness.calculate_witness([input("a", &[3, 5, 7, 9])]).unwrap();
+        assert_eq!(w, expected);
+
+        // the errors of the inputs
+        let err = witness.calculate_witness([input("a", &[3, 5, 7])]).unwrap_err();
+        assert!(matches!(err, ExecError::Input(InputError::SignalSize { expected: 4, .. })));
+        let err = witness.calculate_witness([input("a", &[3, 5, 7, 9]), input("b", &[1])]);
+        assert!(matches!(err, Err(ExecError::Input(InputError::UnknownSignal { .. }))));
+        let err = witness.calculate_witness([input("a", &[3, 5, 7, MODULUS.0[0]])]).unwrap_err();
+        assert!(matches!(err, ExecError::NotGoldilocks { signal: 4, .. }));
+        // the selected value is not 5, an input the verifier rejects
+        let err = witness.calculate_witness([input("a", &[3, 4, 7, 9])]).unwrap_err();
+        assert!(matches!(err, ExecError::Constraint { .. }), "{err}");
+        // nothing solves the inverse of 0
+        let err = witness.calculate_witness([input("a", &[0, 0, 0, 9])]).unwrap_err();
+        assert!(matches!(err, ExecError::Unsolved { .. }), "{err}");
+    }
+
+    #[test]
+    fn test_circom_witness_matches_wasm() {
+        let (mut setup, stark_struct, pil, proof) = fib_stark(0);
+        let options = StarkOption {
+            enable_input: false,
+            verkey_input: false,
+            skip_main: false,
+            agg_stage: false,
+        };
+        let dir = compile_verifier("fib.verifier", &mut setup, &stark_struct, &pil, &options);
+        let file = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+        let zkin = parse_input_for_witness(&serde_json::to_string(&proof).unwrap()).unwrap();
+        let mut wtns =
+            WitnessCalculator::from_file(file("fib.verifier_js/fib.verifier.wasm")).unwrap();
+        let expected = wtns.calculate_witness(zkin.clone(), false).unwrap();
+        let witness =
+            CircomWitness::from_files(&file("fib.verifier.r1cs"), &file("fib.verifier.sym"))
+                .unwrap();
+        let w = witness.calculate_witness(zkin).unwrap();
+        assert_eq!(w.len(), expected.len());
+        for (i, (wi, ei)) in w.iter().zip(&expected).enumerate() {
+            assert_eq!(BigInt::from(wi.as_int()), *ei, "signal {i}");
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! A Goldilocks circuit built in Rust: the plonk gates and the custom gate uses compressor12
+//! places, and the values of its signals computed along.
+use crate::compressor12::plonk_setup::PlonkCircuit;
+use crate::r1cs2plonk::PlonkGate;
+use algebraic::circom_circuit::{CustomGates, CustomGatesUses};
+use fields::field_gl::{Fr as FGL, GL};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// A signal, its index in the witness. Signal 0 is the constant 1, the gates take it from their
+/// constant coefficient and use signal 0 for an unused wire.
+pub type Signal = usize;
+
+/// A linear combination of signals, signal 0 for the constant term.
+pub type Lc = Vec<(Signal, FGL)>;
+
+/// The signals, the plonk gates and the custom gates of a circuit. The gates depend only on the
+/// order of the calls, never on the values: building it with the values of an input gives the
+/// signals of the setup, which builds it with zeros.
+pub struct Circuit {
+    values: Vec<FGL>,
+    n_publics: usize,
+    /// None when only the witness is computed.
+    gates: Option<Vec<PlonkGate>>,
+    custom_gates: Vec<CustomGates<GL>>,
+    templates: HashMap<(String, Vec<u64>), u64>,
+    custom_gates_uses: Vec<CustomGatesUses>,
+    consts: HashMap<u64, Signal>,
+    /// The first constraint the values don't satisfy.
+    failure: Option<String>,
+    /// The constraints as enforced, before they are reduced to gates.
+    #[cfg(test)]
+    constraints: Vec<(Lc, Lc, Lc)>,
+}
+
+impl Circuit {
+    pub fn new() -> Self {
+        Self::with_gates(true)
+    }
+
+    /// A circuit keeping only the values of the signals, to compute a witness.
+    pub fn witness() -> Self {
+        Self::with_gates(false)
+    }
+
+    fn with_gates(keep: bool) -> Self {
+        Self {
+            values: vec![FGL::ONE],
+            n_publics: 0,
+            gates: keep.then(Vec::new),
+            custom_gates: vec![],
+            templates: HashMap::new(),
+            custom_gates_uses: vec![],
+            consts: HashMap::new(),
+            failure: None,
+            #[cfg(test)]
+            constraints: vec![],
+        }
+    }
+
+    /// A public input, the public inputs are the first signals after the constant 1.
+    pub fn public(&mut self, value: FGL) -> Signal {
+        assert_eq!(self.values.len(), 1 + self.n_publics, "the publics come first");
+        self.n_publics += 1;
+        self.alloc(value)
+    }
+
+    /// A signal of `value`, constrained by the caller.
+    pub fn alloc(&mut self, value: FGL) -> Signal {
+        self.values.push(value);
+        self.values.len() - 1
+    }
+
+    pub fn value(&self, s: Signal) -> FGL {
+        self.values[s]
+    }
+
+    pub fn n_signals(&self) -> usize {
+        self.values.len()
+    }
+
+    /// What the values of the signals fail, if they do.
+    pub fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+
+    /// A signal constrained to `k`, one for each constant. Custom gates can't use signal 0.
+    pub fn constant(&mut self, k: FGL) -> Signal {
+        if let Some(s) = self.consts.get(&k.as_int()) {
+            return *s;
+        }
+        let s = self.alloc(k);
+        self.plonk(PlonkGate(s, 0, 0, FGL::ZERO, FGL::ONE, FGL::ZERO, FGL::ZERO, -k));
+        self.consts.insert(k.as_int(), s);
+        s
+    }
+
+    pub fn lc_value(&self, lc: &[(Signal, FGL)]) -> FGL {
+        lc.iter().fold(FGL::ZERO, |acc, (s, k)| acc + self.values[*s] * *k)
+    }
+
+    /// The signal of a linear combination, `lc` itself if it is a signal, the signal of the
+    /// constant if it is one.
+    pub fn linear(&mut self, lc: &[(Signal, FGL)]) -> Signal {
+        let lc = normalize(lc);
+        match lc.as_slice() {
+            [] => return self.constant(FGL::ZERO),
+            [(0, k)] => return self.constant(*k),
+            [(s, k)] if *k == FGL::ONE => return *s,
+            _ => {}
+        }
+        let out = self.alloc(self.lc_value(&lc));
+        let mut c = lc;
+        c.push((out, -FGL::ONE));
+        self.enforce(&[], &[], &c, || String::from("linear combination"));
+        out
+    }
+
+    /// The signal of the product of two linear combinations.
+    pub fn mul(&mut self, a: &[(Signal, FGL)], b: &[(Signal, FGL)]) -> Signal {
+        let out = self.alloc(self.lc_value(a) * self.lc_value(b));
+        self.enforce(a, b, &[(out, FGL::ONE)], || String::from("product"));
+        out
+    }
+
+    /// Constrains `a * b = c`, `what` names the check the values fail. The linear combinations
+    /// are reduced to a signal each by addition gates, the signals they add are in the witness.
+    pub fn enforce<W: FnOnce() -> String>(
+        &mut self,
+        a: &[(Signal, FGL)],
+        b: &[(Signal, FGL)],
+        c: &[(Signal, FGL)],
+        what: W,
+    ) {
+        if self.failure.is_none() && self.lc_value(a) * self.lc_value(b) != self.lc_value(c) {
+            self.failure = Some(what());
+        }
+        let (a, b, c) = (normalize(a), normalize(b), normalize(c));
+        #[cfg(test)]
+        if self.gates.is_some() {
+            self.constraints.push((a.clone(), b.clone(), c.clone()));
+        }
+        // a constant factor makes it linear: k * b - c = 0
+        let factor = |lc: &Lc| match lc.as_slice() {
+            [] => Some(FGL::ZERO),
+            [(0, k)] => Some(*k),
+            _ => None,
+        };
+        let scaled = |lc: &Lc, k: FGL| lc.iter().map(|(s, v)| (*s, *v * k)).collect::<Lc>();
+        let linear = match (factor(&a), factor(&b)) {
+            (Some(k), _) => Some(scaled(&b, k)),
+            (_, Some(k)) => Some(scaled(&a, k)),
+            _ => None,
+        };
+        if let Some(lc) = linear {
+            let lc = normalize(&[lc, scaled(&c, -FGL::ONE)].concat());
+            let (k, terms) = self.reduce(&lc, 3);
+            let [(s0, k0), (s1, k1), (s2, k2)] = terms[..] else { unreachable!() };
+            self.plonk(PlonkGate(s0, s1, s2, FGL::ZERO, k0, k1, k2, k));
+            return;
+        }
+        let (ca, [(sa, ka)]) = self.reduce_to::<1>(&a);
+        let (cb, [(sb, kb)]) = self.reduce_to::<1>(&b);
+        let (cc, [(sc, kc)]) = self.reduce_to::<1>(&c);
+        self.plonk(PlonkGate(sa, sb, sc, ka * kb, ka * cb, ca * kb, -kc, ca * cb - cc));
+    }
+
+    /// Constrains the signals `a` and `b` to be equal.
+    pub fn enforce_eq<W: FnOnce() -> String>(&mut self, a: Signal, b: Signal, what: W) {
+        if a != b {
+            self.enforce(&[], &[], &[(a, FGL::ONE), (b, -FGL::ONE)], what);
+        }
+    }
+
+    /// The constant term and the `n` terms a normalized `lc` adds up to, the terms in excess
+    /// summed two by two in new signals, the missing ones signal 0 of coefficient 0.
+    fn reduce(&mut self, lc: &[(Signal, FGL)], n: usize) -> (FGL, Lc) {
+        let k = lc.iter().filter(|(s, _)| *s == 0).fold(FGL::ZERO, |acc, (_, v)| acc + *v);
+        let mut terms: VecDeque<(Signal, FGL)> =
+            lc.iter().filter(|(s, _)| *s != 0).copied().collect();
+        while terms.len() > n {
+            let (s1, k1) = terms.pop_front().unwrap();
+            let (s2, k2) = terms.pop_front().unwrap();
+            let sum = self.alloc(self.values[s1] * k1 + self.values[s2] * k2);
+            self.plonk(PlonkGate(s1, s2, sum, FGL::ZERO, k1, k2, -FGL::ONE, FGL::ZERO));
+            terms.push_back((sum, FGL::ONE));
+        }
+        let mut terms: Lc = terms.into();
+        terms.resize(n, (0, FGL::ZERO));
+        (k, terms)
+    }
+
+    fn reduce_to<const N: usize>(&mut self, lc: &[(Signal, FGL)]) -> (FGL, [(Signal, FGL); N]) {
+        let (k, terms) = self.reduce(lc, N);
+        (k, terms.try_into().unwrap())
+    }
+
+    fn plonk(&mut self, gate: PlonkGate) {
+        if let Some(gates) = self.gates.as_mut() {
+            gates.push(gate);
+        }
+    }
+
+    /// A use of the custom gate `template_name` with `parameters`, on `signals` placed 12 a row.
+    pub fn gate(&mut self, template_name: &str, parameters: &[FGL], signals: Vec<Signal>) {
+        assert!(!signals.contains(&0), "{template_name} uses signal 0");
+        let key = (template_name.to_string(), parameters.iter().map(|p| p.as_int()).collect());
+        let n_templates = self.custom_gates.len() as u64;
+        let id = *self.templates.entry(key).or_insert(n_templates);
+        if id == n_templates {
+            self.custom_gates.push(CustomGates {
+                template_name: template_name.to_string(),
+                parameters: parameters.to_vec(),
+            });
+        }
+        if self.gates.is_some() {
+            self.custom_gates_uses
+                .push(CustomGatesUses { id, signals: signals.iter().map(|s| *s as u64).collect() });
+        }
+    }
+
+    /// The plonk circuit of the gates, the publics being its public inputs, and the values of
+    /// its signals, its witness.
+    pub fn into_plonk(self) -> (PlonkCircuit, Vec<FGL>) {
+        let gates = self.gates.expect("the gates of a witness circuit");
+        let plonk = PlonkCircuit {
+            n_publics: self.n_publics,
+            n_constraints: gates.len(),
+            gates,
+            adds: vec![],
+            custom_gates: self.custom_gates,
+            custom_gates_uses: self.custom_gates_uses,
+        };
+        (plonk, self.values)
+    }
+
+    /// An R1CS of the constraints as enforced, as circom writes them, then of a constraint a
+    /// gate, which constrain the signals the gates add.
+    #[cfg(test)]
+    pub(crate) fn into_r1cs(mut self) -> (algebraic::circom_circuit::R1CS<GL>, Vec<FGL>) {
+        let num_inputs = 1 + self.n_publics;
+        let num_variables = self.values.len();
+        let mut constraints = std::mem::take(&mut self.constraints);
+        let (plonk, values) = self.into_plonk();
+        constraints.extend(plonk.gates.iter().map(|g| {
+            // qM * a * b = -(qL * a + qR * b + qO * c + qC)
+            let c = normalize(&[(g.0, -g.4), (g.1, -g.5), (g.2, -g.6), (0, -g.7)]);
+            match g.3 == FGL::ZERO {
+                true => (vec![], vec![], c),
+                false => (vec![(g.0, g.3)], vec![(g.1, FGL::ONE)], c),
+            }
+        }));
+        let r1cs = algebraic::circom_circuit::R1CS {
+            num_inputs,
+            num_aux: num_variables - num_inputs,
+            num_variables,
+            num_outputs: 0,
+            constraints,
+            custom_gates: plonk.custom_gates,
+            custom_gates_uses: plonk.custom_gates_uses,
+        };
+        (r1cs, values)
+    }
+
+    /// The values of the signals, the witness of the plonk circuit.
+    pub fn into_witness(self) -> Vec<FGL> {
+        self.values
+    }
+}
+
+impl Default for Circuit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Merges the terms of the same signal and drops those of coefficient 0.
+fn normalize(lc: &[(Signal, FGL)]) -> Lc {
+    let mut terms: BTreeMap<Signal, FGL> = BTreeMap::new();
+    for (s, k) in lc {
+        let t = terms.entry(*s).or_insert(FGL::ZERO);
+        *t = *t + *k;
+    }
+    terms.into_iter().filter(|(_, k)| *k != FGL::ZERO).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_circuit_plonk() {
+        let mut c = Circuit::new();
+        let p = c.public(FGL::from(3u64));
+        let a = c.alloc(FGL::from(5u64));
+        let ab = c.mul(&[(p, FGL::ONE)], &[(a, FGL::ONE), (0, FGL::ONE)]);
+        let s = c.linear(&[(ab, FGL::ONE), (a, FGL::from(2u64)), (p, FGL::ONE), (p, -FGL::ONE)]);
+        assert_eq!(c.linear(&[(s, FGL::ONE)]), s);
+        let k = c.constant(FGL::from(28u64));
+        assert_eq!(c.constant(FGL::from(28u64)), k);
+        c.enforce_eq(s, k, || String::from("s"));
+        // the sum of 4 signals is reduced to one by additions
+        let sum: Lc = [p, a, ab, s].iter().map(|s| (*s, FGL::from(2u64))).collect();
+        c.enforce(&sum, &[(0, FGL::ONE)], &[(0, FGL::from(108u64))], || String::from("sum"));
+        c.enforce(&sum, &sum, &[(0, FGL::from(108u64 * 108))], || String::from("square"));
+        assert_eq!(c.failure(), None);
+        c.gate("CMulAdd", &[], vec![p, a]);
+        c.gate("FFT4", &[FGL::ONE], vec![p]);
+        c.gate("CMulAdd", &[], vec![a]);
+
+        let (plonk, w) = c.into_plonk();
+        assert_eq!(plonk.n_publics, 1);
+        assert_eq!(w[..6], [1u64, 3, 5, 18, 28, 28].map(FGL::from));
+        assert_eq!(plonk.custom_gates.len(), 2);
+        assert_eq!(plonk.custom_gates_uses.iter().map(|u| u.id).collect::<Vec<_>>(), [0, 1, 0]);
+        // the product, the linear combination, the constant, the equality, an addition and the
+        // sum, 3 additions a side and the square
+        assert_eq!(plonk.gates.len(), 13);
+        assert!(plonk.adds.is_empty());
+
+        // the gates hold on the witness, which has the signals of the additions
+        assert_eq!(w.len(), 13);
+        for g in &plonk.gates {
+            let (a, b, c) = (w[g.0], w[g.1], w[g.2]);
+            assert_eq!(g.3 * a * b + g.4 * a + g.5 * b + g.6 * c + g.7, FGL::ZERO, "{g}");
+        }
+    }
+
+    #[test]
+    fn test_circuit_failure() {
+        let mut c = Circuit::witness();
+        let a = c.alloc(FGL::from(2u64));
+        let b = c.alloc(FGL::from(3u64));
+        c.enforce_eq(a, b, || String::from("a = b"));
+        c.enforce_eq(a, a, || String::from("a = a"));
+        c.enforce(&[(a, FGL::ONE)], &[(b, FGL::ONE)], &[], || String::from("a * b = 0"));
+        assert_eq!(c.failure(), Some("a = b"));
+        assert_eq!(c.into_witness().len(), 3);
+    }
+}
@@ -0,0 +1,441 @@
+//! The templates of the GL verifier, `circuits.gl` of pil-stark, as gadgets of a `Circuit`: the
+//! custom gates of compressor12 with their values computed natively, the Merkle proofs, the bits
+//! and the selectors.
+use super::circuit::{Circuit, Signal};
+use crate::compressor12::constants::CPOSEIDON;
+use fields::field_gl::Fr as FGL;
+use fields::Field;
+use starky::constant::MG;
+
+/// An element of the cubic extension, its 3 coefficients.
+pub type F3 = [Signal; 3];
+
+/// The first row of the MDS matrix of the Poseidon12 gate, circulant but for `M[0][0] + 8`.
+const CIRC: [u64; 12] = [17, 15, 41, 16, 2, 28, 13, 13, 39, 18, 34, 20];
+
+/// `a * b + c` in GF(p^3) = GF(p)[x] / (x^3 - x - 1), as the CMulAdd gate computes it.
+pub fn f3_mul_add(a: [FGL; 3], b: [FGL; 3], c: [FGL; 3]) -> [FGL; 3] {
+    let ca = (a[0] + a[1]) * (b[0] + b[1]);
+    let cb = (a[0] + a[2]) * (b[0] + b[2]);
+    let cc = (a[1] + a[2]) * (b[1] + b[2]);
+    let (d, e, f) = (a[0] * b[0], a[1] * b[1], a[2] * b[2]);
+    [cc + d - e - f + c[0], ca + cc - e - e - d + c[1], cb - d + e + c[2]]
+}
+
+pub(crate) fn f3_inv(a: [FGL; 3]) -> [FGL; 3] {
+    use starky::f3g::F3G;
+    use starky::traits::FieldExtension;
+    if a.iter().all(|e| *e == FGL::ZERO) {
+        return a;
+    }
+    let inv = F3G::new(a[0], a[1], a[2]).inv();
+    let e = inv.as_elements();
+    [e[0], e[1], e[2]]
+}
+
+fn inv(a: FGL) -> FGL {
+    a.inverse().unwrap_or(FGL::ZERO)
+}
+
+impl Circuit {
+    pub fn zero(&mut self) -> Signal {
+        self.constant(FGL::ZERO)
+    }
+
+    pub fn f3_value(&self, a: F3) -> [FGL; 3] {
+        a.map(|s| self.value(s))
+    }
+
+    /// `a * b + c` by a CMulAdd gate.
+    pub fn c_mul_add(&mut self, a: F3, b: F3, c: F3) -> F3 {
+        let v = f3_mul_add(self.f3_value(a), self.f3_value(b), self.f3_value(c));
+        let out = v.map(|e| self.alloc(e));
+        self.gate("CMulAdd", &[], [a, b, c, out].concat());
+        out
+    }
+
+    pub fn c_mul(&mut self, a: F3, b: F3) -> F3 {
+        let zero = self.zero();
+        self.c_mul_add(a, b, [zero; 3])
+    }
+
+    /// The inverse of `a`, constrained by `a * inv = 1`, which an `a` of 0 fails.
+    pub fn c_inv(&mut self, a: F3) -> F3 {
+        let v = f3_inv(self.f3_value(a));
+        let out = v.map(|e| self.alloc(e));
+        let one = self.c_mul(a, out);
+        for (i, e) in one.iter().enumerate() {
+            let k = if i == 0 { FGL::ONE } else { FGL::ZERO };
+            self.enforce(&[], &[], &[(*e, FGL::ONE), (0, -k)], || String::from("inverse of 0"));
+        }
+        out
+    }
+
+    /// The 12 outputs of the permutation of `[inputs(8), capacity(4)]`, a Poseidon12 gate: the
+    /// state of each round is a row of 12 signals.
+    pub fn poseidon(&mut self, state: [Signal; 12]) -> [Signal; 12] {
+        let mut signals = state.to_vec();
+        let mut row = state;
+        for j in 0..30 {
+            let r: Vec<FGL> = (0..12)
+                .map(|i| {
+                    let t = self.value(row[i]) + FGL::from(CPOSEIDON[j * 12 + i]);
+                    if i == 0 || !(4..26).contains(&j) {
+                        t.exp(7)
+                    } else {
+                        t
+                    }
+                })
+                .collect();
+            let next: [FGL; 12] = std::array::from_fn(|k| {
+                (0..12).fold(FGL::ZERO, |acc, i| {
+                    let m = CIRC[(i + 12 - k) % 12] + if k == 0 && i == 0 { 8 } else { 0 };
+                    acc + FGL::from(m) * r[i]
+                })
+            });
+            row = next.map(|v| self.alloc(v));
+            signals.extend_from_slice(&row);
+        }
+        self.gate("Poseidon12", &[], signals);
+        row
+    }
+
+    /// The first 4 outputs of Poseidon on `inputs` with a 0 capacity, as the sponge and the
+    /// Merkle levels use it.
+    fn hash4(&mut self, inputs: &[Signal], capacity: [Signal; 4]) -> [Signal; 4] {
+        let zero = self.zero();
+        let mut state = [zero; 12];
+        state[..inputs.len()].copy_from_slice(inputs);
+        state[8..].copy_from_slice(&capacity);
+        let out = self.poseidon(state);
+        [out[0], out[1], out[2], out[3]]
+    }
+
+    /// The sponge of `LinearHash::_hash`, the values themselves when there are 4 at most.
+    fn sponge(&mut self, values: &[Signal]) -> [Signal; 4] {
+        let zero = self.zero();
+        let mut st = [zero; 4];
+        if values.len() <= 4 {
+            st[..values.len()].copy_from_slice(values);
+            return st;
+        }
+        for chunk in values.chunks(8) {
+            st = self.hash4(chunk, st);
+        }
+        st
+    }
+
+    /// The leaf of `values` in a `MerkleTreeGL`, `LinearHash::hash` with the default batches.
+    pub fn linear_hash(&mut self, values: &[Signal]) -> [Signal; 4] {
+        if values.len() <= 4 {
+            return self.sponge(values);
+        }
+        let bs = std::cmp::max(8, values.len().div_ceil(4));
+        let hashes: Vec<Signal> = values.chunks(bs).flat_map(|chunk| self.sponge(chunk)).collect();
+        self.sponge(&hashes)
+    }
+
+    /// The root of the Merkle proof of `values` at the index of the bits `key`, LSB first.
+    pub fn merkle_root(
+        &mut self,
+        values: &[Signal],
+        siblings: &[[Signal; 4]],
+        key: &[Signal],
+    ) -> [Signal; 4] {
+        assert_eq!(siblings.len(), key.len());
+        let mut value = self.linear_hash(values);
+        let zero = self.zero();
+        for (sibling, bit) in siblings.iter().zip(key) {
+            let left = [0, 1, 2, 3].map(|i| self.mux(*bit, value[i], sibling[i]));
+            let right = [0, 1, 2, 3].map(|i| self.mux(*bit, sibling[i], value[i]));
+            value = self.hash4(&[left, right].concat(), [zero; 4]);
+        }
+        value
+    }
+
+    /// `a` if `bit` is 0, `b` if it is 1.
+    pub fn mux(&mut self, bit: Signal, a: Signal, b: Signal) -> Signal {
+        if a == b {
+            return a;
+        }
+        let v = self.value(a) + self.value(bit) * (self.value(b) - self.value(a));
+        let out = self.alloc(v);
+        self.enforce(
+            &[(bit, FGL::ONE)],
+            &[(b, FGL::ONE), (a, -FGL::ONE)],
+            &[(out, FGL::ONE), (a, -FGL::ONE)],
+            || String::from("mux"),
+        );
+        out
+    }
+
+    /// The element of `values` at the index of the bits `key`, LSB first.
+    pub fn tree_selector(&mut self, values: &[F3], key: &[Signal]) -> F3 {
+        assert_eq!(values.len(), 1 << key.len());
+        let mut level = values.to_vec();
+        for bit in key {
+            level = level
+                .chunks(2)
+                .map(|pair| [0, 1, 2].map(|e| self.mux(*bit, pair[0][e], pair[1][e])))
+                .collect();
+        }
+        level[0]
+    }
+
+    /// Constrains `bit` to be 0 or 1.
+    pub fn assert_bit(&mut self, bit: Signal) {
+        self.enforce(&[(bit, FGL::ONE)], &[(bit, FGL::ONE), (0, -FGL::ONE)], &[], || {
+            String::from("bit")
+        });
+    }
+
+    /// The 64 bits of `a`, LSB first, checked to be the canonical representation of `a`:
+    /// `Num2Bits_strict`.
+    pub fn num2bits_strict(&mut self, a: Signal) -> Vec<Signal> {
+        let v = self.value(a).as_int();
+        let bits: Vec<Signal> = (0..64)
+            .map(|i| {
+                let bit = self.alloc(FGL::from((v >> i) & 1));
+                self.assert_bit(bit);
+                bit
+            })
+            .collect();
+        let mut sum: Vec<(Signal, FGL)> =
+            bits.iter().enumerate().map(|(i, b)| (*b, FGL::from(1u64 << i))).collect();
+        sum.push((a, -FGL::ONE));
+        self.enforce(&[], &[], &sum, || String::from("Num2Bits"));
+
+        // p = 2^64 - 2^32 + 1: the bits alias a value >= p iff the 32 high ones are 1 and the
+        // low ones not all 0
+        let mut high = bits[32];
+        for bit in &bits[33..] {
+            high = self.mul(&[(high, FGL::ONE)], &[(*bit, FGL::ONE)]);
+        }
+        let low: Vec<(Signal, FGL)> =
+            bits[..32].iter().enumerate().map(|(i, b)| (*b, FGL::from(1u64 << i))).collect();
+        self.enforce(&[(high, FGL::ONE)], &low, &[], || String::from("Num2Bits alias"));
+        bits
+    }
+
+    /// The inverse FFT of `values`, FFT4 gates of radix 4 and a radix 2 stage first for an odd
+    /// number of bits, as `FFT::ifft`.
+    pub fn ifft(&mut self, values: &[F3]) -> Vec<F3> {
+        let n = values.len();
+        assert!(n.is_power_of_two());
+        let n_bits = n.trailing_zeros() as usize;
+        if n_bits == 0 {
+            return values.to_vec();
+        }
+        let mut buff: Vec<F3> =
+            (0..n).map(|i| values[((i as u32).reverse_bits() >> (32 - n_bits)) as usize]).collect();
+        let n_inv = inv(FGL::from(n as u64));
+
+        let mut s = 0;
+        if n_bits % 2 == 1 {
+            let scale = if n_bits == 1 { n_inv } else { FGL::ONE };
+            let zero = self.zero();
+            for i in (0..n).step_by(4) {
+                let x = [0, 1, 2, 3].map(|k| buff.get(i + k).copied().unwrap_or([zero; 3]));
+                let y = self.fft4(x, FGL::ONE, FGL::ONE, scale, 2);
+                buff[i..(i + 4).min(n)].copy_from_slice(&y[..4.min(n - i)]);
+            }
+            s = 1;
+        }
+        // stages s + 1 and s + 2 of the radix 2 FFT, roots of 1 / MG
+        let w4 = MG.1[2];
+        while s < n_bits {
+            let q = 1 << s;
+            let scale = if s + 2 == n_bits { n_inv } else { FGL::ONE };
+            let w4q = MG.1[s + 2];
+            for b in (0..n).step_by(4 * q) {
+                let mut w = FGL::ONE;
+                for j in 0..q {
+                    let pos = [0, 1, 2, 3].map(|k| b + j + k * q);
+                    let y = self.fft4(pos.map(|p| buff[p]), w, w4, scale, 4);
+                    for (p, yk) in pos.iter().zip(y) {
+                        buff[*p] = yk;
+                    }
+                    w = w * w4q;
+                }
+            }
+            s += 2;
+        }
+        buff
+    }
+
+    /// An FFT4 gate of parameters `[first_w, inc_w, scale, t]`.
+    pub(crate) fn fft4(
+        &mut self,
+        x: [F3; 4],
+        first_w: FGL,
+        inc_w: FGL,
+        scale: FGL,
+        t: u64,
+    ) -> [F3; 4] {
+        let xv = x.map(|xi| self.f3_value(xi));
+        let (w, w2) = (first_w, first_w * first_w);
+        let w3 = w2 * w;
+        let comb = |k: [FGL; 4]| -> [FGL; 3] {
+            [0, 1, 2].map(|e| scale * (0..4).fold(FGL::ZERO, |acc, i| acc + k[i] * xv[i][e]))
+        };
+        let (o, one) = (FGL::ZERO, FGL::ONE);
+        let y = if t == 4 {
+            [
+                comb([one, w2, w, w3]),
+                comb([one, -w2, w * inc_w, -w3 * inc_w]),
+                comb([one, w2, -w, -w3]),
+                comb([one, -w2, -w * inc_w, w3 * inc_w]),
+            ]
+        } else {
+            [
+                comb([one, w, o, o]),
+                comb([one, -w, o, o]),
+                comb([o, o, one, w * inc_w]),
+                comb([o, o, one, -w * inc_w]),
+            ]
+        };
+        let out = y.map(|yk| yk.map(|e| self.alloc(e)));
+        self.gate(
+            "FFT4",
+            &[first_w, inc_w, scale, FGL::from(t)],
+            [x.concat(), out.concat()].concat(),
+        );
+        out
+    }
+
+    /// `pol(x)`, EvPol4 gates from the highest coefficients, CMulAdd ones when there are not
+    /// 4k coefficients.
+    pub fn eval_pol(&mut self, pol: &[F3], x: F3) -> F3 {
+        let zero = self.zero();
+        if pol.is_empty() {
+            return [zero; 3];
+        }
+        if !pol.len().is_multiple_of(4) {
+            let mut acc = pol[pol.len() - 1];
+            for c in pol[..pol.len() - 1].iter().rev() {
+                acc = self.c_mul_add(acc, x, *c);
+            }
+            return acc;
+        }
+        let mut acc = [zero; 3];
+        for c in pol.chunks(4).rev() {
+            let xv = self.f3_value(x);
+            let v = c
+                .iter()
+                .rev()
+                .fold(self.f3_value(acc), |a, ci| f3_mul_add(a, xv, self.f3_value(*ci)));
+            let out = v.map(|e| self.alloc(e));
+            self.gate("EvPol4", &[], [c.concat(), acc.to_vec(), x.to_vec(), out.to_vec()].concat());
+            acc = out;
+        }
+        acc
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use starky::f3g::F3G;
+    use starky::fft::FFT;
+    use starky::poseidon_opt::Poseidon;
+    use starky::traits::FieldExtension;
+
+    fn inputs(c: &mut Circuit, values: &[u64]) -> Vec<Signal> {
+        values.iter().map(|v| c.alloc(FGL::from(*v))).collect()
+    }
+
+    fn f3(c: &Circuit, a: F3) -> F3G {
+        let v = c.f3_value(a);
+        F3G::new(v[0], v[1], v[2])
+    }
+
+    #[test]
+    fn test_poseidon_gadget() {
+        let mut c = Circuit::new();
+        let state: Vec<u64> = (0..12).map(|i| i * 0x1234_5678_9abc + 7).collect();
+        let s = inputs(&mut c, &state);
+        let out = c.poseidon(s.try_into().unwrap());
+        let expected = Poseidon::new()
+            .hash(
+                &state[..8].iter().map(|v| FGL::from(*v)).collect::<Vec<_>>(),
+                &state[8..].iter().map(|v| FGL::from(*v)).collect::<Vec<_>>(),
+                12,
+            )
+            .unwrap();
+        assert_eq!(out.map(|o| c.value(o)).to_vec(), expected);
+    }
+
+    #[test]
+    fn test_f3_gadgets() {
+        let mut c = Circuit::new();
+        let a: F3 = inputs(&mut c, &[3, 5, 7]).try_into().unwrap();
+        let b: F3 = inputs(&mut c, &[11, 13, 17]).try_into().unwrap();
+        let ab = c.c_mul_add(a, b, a);
+        assert_eq!(f3(&c, ab), f3(&c, a) * f3(&c, b) + f3(&c, a));
+        let a_inv = c.c_inv(a);
+        assert_eq!(f3(&c, a_inv), f3(&c, a).inv());
+        assert_eq!(c.failure(), None);
+
+        let zero = c.zero();
+        c.c_inv([zero; 3]);
+        assert_eq!(c.failure(), Some("inverse of 0"));
+    }
+
+    #[test]
+    fn test_ifft_and_eval_pol_gadgets() {
+        for n_bits in 0..6 {
+            let mut c = Circuit::new();
+            let n = 1 << n_bits;
+            let values: Vec<F3> = (0..n)
+                .map(|i| inputs(&mut c, &[i * 3 + 1, i * i, 5 * i + 2]).try_into().unwrap())
+                .collect();
+            let coefs = c.ifft(&values);
+            let expected = FFT::new().ifft(&values.iter().map(|v| f3(&c, *v)).collect::<Vec<_>>());
+            assert_eq!(coefs.iter().map(|v| f3(&c, *v)).collect::<Vec<_>>(), expected);
+
+            let x: F3 = inputs(&mut c, &[9, 8, 7]).try_into().unwrap();
+            let ev = c.eval_pol(&coefs, x);
+            let expected = starky::polutils::eval_pol(&expected, &f3(&c, x));
+            assert_eq!(f3(&c, ev), expected);
+            assert_eq!(c.failure(), None);
+        }
+    }
+
+    #[test]
+    fn test_bits_and_selectors() {
+        let mut c = Circuit::new();
+        let s = inputs(&mut c, &[0xffff_fffe_ffff_ffff, 1, 0]);
+        let bits = c.num2bits_strict(s[0]);
+        assert_eq!(bits.iter().map(|b| c.value(*b).as_int()).sum::<u64>(), 63);
+        let values: Vec<F3> =
+            (0..4).map(|i| inputs(&mut c, &[i, i + 1, i + 2]).try_into().unwrap()).collect();
+        let out = c.tree_selector(&values, &[s[1], s[2]]);
+        assert_eq!(c.f3_value(out), [1u64, 2, 3].map(FGL::from));
+        assert_eq!(c.failure(), None);
+
+        // a bit set to 2
+        let two = c.alloc(FGL::from(2u64));
+        c.assert_bit(two);
+        assert_eq!(c.failure(), Some("bit"));
+    }
+
+    #[test]
+    fn test_merkle_root_gadget() {
+        use starky::merklehash::MerkleTreeGL;
+        use starky::traits::{MTNodeType, MerkleTree};
+        // a tree of 4 leaves of 9 elements
+        let leaves: Vec<FGL> = (0..36u64).map(|i| FGL::from(i * 31 + 5)).collect();
+        let mut tree = MerkleTreeGL::new();
+        tree.merkelize(leaves.clone(), 9, 4).unwrap();
+        let idx = 2;
+        let (vals, path) = tree.get_group_proof(idx).unwrap();
+
+        let mut c = Circuit::new();
+        let v: Vec<Signal> = vals.iter().map(|e| c.alloc(*e)).collect();
+        let siblings: Vec<[Signal; 4]> =
+            path.iter().map(|p| [0, 1, 2, 3].map(|k| c.alloc(p[k]))).collect();
+        let key = inputs(&mut c, &[0, 1]);
+        let root = c.merkle_root(&v, &siblings, &key);
+        assert_eq!(root.map(|r| c.value(r)).as_slice(), tree.root().as_elements());
+    }
+}
@@ -0,0 +1,6 @@
+//! The GL stark verifier as a compressor12 circuit built in Rust, without circom, and the native
+//! witness of the verifier circom renders.
+pub mod circom_witness;
+pub mod circuit;
+pub mod gadgets;
+pub mod stark_verifier;
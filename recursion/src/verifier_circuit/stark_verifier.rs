@@ -0,0 +1,1259 @@
+//! The GL stark verifier of `pil2circom` compiled directly to a compressor12 circuit: the plonk
+//! rows and the custom gates of the circom templates, placed in Rust from the stark info, and the
+//! witness of a proof computed natively instead of by the wasm of circom.
+//!
+//! The circuit checks what the rendered `StarkVerifier` checks, in the same transcript order, of
+//! one proof or of the two proofs of the aggregation stage, but its signals are not laid out as
+//! circom lays them: the `.exec` of a setup only works with the witness of this circuit, the one
+//! of `exec` or of `proof_witness` which reads the inputs from a `StarkProof` directly.
+use super::circuit::{Circuit, Lc, Signal};
+use super::gadgets::F3;
+use crate::compressor12::compressor12_exec::{ExecError, Executor};
+use crate::compressor12::compressor12_setup::Options;
+use crate::compressor12::plonk_setup::{PlonkCircuit, PlonkSetup};
+use crate::compressor12::setup_report::SetupReport;
+use crate::exec_file::{write_exec_file, ExecHeader};
+use crate::pilcom::write_pil_json;
+use algebraic::witness::InputError;
+use anyhow::{anyhow, bail, ensure, Result};
+use fields::field_gl::{Fr as FGL, MODULUS};
+use fields::Field;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use starky::constant::{MG, SHIFT};
+use starky::f3g::F3G;
+use starky::fiat_shamir::{check_version, circuit_digest, FIAT_SHAMIR_STRONG};
+use starky::merklehash::MerkleTreeGL;
+use starky::pil2circom::StarkOption;
+use starky::polsarray::{PolKind, PolsArray};
+use starky::prover_config::ProverConfig;
+use starky::stark_gen::StarkProof;
+use starky::stark_setup::StarkSetup;
+use starky::starkinfo::{Program, StarkInfo};
+use starky::starkinfo_codegen::{Node, Section};
+use starky::traits::{FieldExtension, MTNodeType};
+use starky::types::{load_json, parse_pil_number, StarkStruct, PIL};
+use starky::verifier_key::{write_verifier_key, VerifierKey};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::Write;
+
+// setup phase:
+// input: the stark struct, .pil.json and constant polynomials of the proof to verify
+// output: the verifier key of the proof, the .pil, .pil.json, .const, .exec of the compressor12
+// of its verifier, and the report
+#[allow(clippy::too_many_arguments)]
+pub fn setup(
+    stark_struct: &str,
+    piljson: &str,
+    const_pols: &str,
+    options: &StarkOption,
+    key_file: &str,
+    pil_file: &str,
+    const_file: &str,
+    exec_file: &str,
+    force_n_bits: usize,
+    config: &ProverConfig,
+) -> Result<SetupReport> {
+    config.install(|| {
+        write_verifier_key(stark_struct, piljson, const_pols, key_file, config)?;
+        let verifier = StarkVerifierCircuit::load_key(key_file, options)?;
+        verifier.setup(pil_file, const_file, exec_file, &Options::new(force_n_bits))
+    })
+}
+
+// exec phase:
+// input: the verifier key and the files of the setup, and the zkin.json of the proof to verify
+// output: .cm
+pub fn exec(
+    key_file: &str,
+    options: &StarkOption,
+    input_file: &str,
+    pil_file: &str,
+    exec_file: &str,
+    commit_file: &str,
+    config: &ProverConfig,
+) -> Result<()> {
+    config.install(|| {
+        let verifier = StarkVerifierCircuit::load_key(key_file, options)?;
+        let mut executor = Executor::stark_verifier(verifier, pil_file, exec_file)?;
+        ensure!(executor.n_cols() == 12, "{pil_file} is not a compressor12 pil");
+        executor.exec_file(input_file, commit_file)
+    })
+}
+
+/// The verifier of the GL starks of a PIL, a stark struct and a constant root.
+pub struct StarkVerifierCircuit {
+    starkinfo: StarkInfo,
+    program: Program,
+    stark_struct: StarkStruct,
+    const_root: [FGL; 4],
+    enable_input: bool,
+    verkey_input: bool,
+    skip_main: bool,
+    agg_stage: bool,
+}
+
+impl StarkVerifierCircuit {
+    /// The verifier `pil2circom` would render with `options`.
+    pub fn new(
+        mut starkinfo: StarkInfo,
+        mut program: Program,
+        stark_struct: StarkStruct,
+        const_root: [FGL; 4],
+        options: &StarkOption,
+    ) -> Result<Self> {
+        check_version(&stark_struct)?;
+        ensure!(
+            stark_struct.verificationHashType == "GL",
+            "the verifier circuit verifies GL starks, not {}",
+            stark_struct.verificationHashType
+        );
+        ensure!(
+            stark_struct.steps.first().is_some_and(|s| s.nBits == stark_struct.nBitsExt),
+            "the first FRI step of the stark struct must have nBitsExt bits"
+        );
+        if options.agg_stage {
+            // the main of pil2circom gives the verifiers of the two proofs their constant roots,
+            // and no enable
+            ensure!(
+                options.verkey_input && !options.skip_main && !options.enable_input,
+                "the aggregation stage takes the constant roots as inputs, in a main without enable"
+            );
+            ensure!(starkinfo.n_publics >= 4, "the aggregation stage takes 4 publics at least");
+        }
+        ensure!(
+            !options.skip_main || starkinfo.n_publics > 0,
+            "the verifier of a stark without publics has no public input"
+        );
+        starkinfo.set_code_dimensions_first(&mut program.verifier_code)?;
+        starkinfo.set_code_dimensions_first(&mut program.verifier_query_code)?;
+        Ok(Self {
+            starkinfo,
+            program,
+            stark_struct,
+            const_root,
+            enable_input: options.enable_input,
+            verkey_input: options.verkey_input,
+            skip_main: options.skip_main,
+            agg_stage: options.agg_stage,
+        })
+    }
+
+    /// The verifier of the starks of `piljson` and its constant polynomials.
+    pub fn load(
+        stark_struct: &str,
+        piljson: &str,
+        const_pols: &str,
+        options: &StarkOption,
+        config: &ProverConfig,
+    ) -> Result<Self> {
+        let mut pil = load_json::<PIL>(piljson)?;
+        let stark_struct = load_json::<StarkStruct>(stark_struct)?;
+        let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
+        const_pol.load(const_pols)?;
+        let setup =
+            StarkSetup::<MerkleTreeGL>::new(&const_pol, &mut pil, &stark_struct, None, config)?;
+        let root = setup.const_root.as_elements();
+        let const_root = [root[0], root[1], root[2], root[3]];
+        Self::new(setup.starkinfo, setup.program, stark_struct, const_root, options)
+    }
+
+    /// The verifier of the starks of a verifier key, without setting its PIL up again.
+    pub fn from_key(key: VerifierKey, options: &StarkOption) -> Result<Self> {
+        let root = key.const_root.as_elements();
+        let program = Program {
+            verifier_code: key.verifier_code,
+            verifier_query_code: key.verifier_query_code,
+            ..Default::default()
+        };
+        let const_root = [root[0], root[1], root[2], root[3]];
+        Self::new(key.starkinfo, program, key.stark_struct, const_root, options)
+    }
+
+    /// `from_key` of the JSON `write_verifier_key` writes.
+    pub fn load_key(key_file: &str, options: &StarkOption) -> Result<Self> {
+        Self::from_key(load_json::<VerifierKey>(key_file)?, options)
+    }
+
+    /// Builds the circuit. Without inputs, with the gates and zeros for the inputs, for the
+    /// setup. With the inputs, only the values of the signals, which the first constraint they
+    /// fail makes an error.
+    pub fn build(&self, inputs: Option<&HashMap<String, Vec<FGL>>>) -> Result<Circuit> {
+        let mut b = Builder {
+            c: if inputs.is_some() { Circuit::witness() } else { Circuit::new() },
+            inputs,
+            read: HashSet::new(),
+            prefix: "",
+            enable: None,
+        };
+        self.verify(&mut b)?;
+        b.check_unknown(&self.ignored_inputs())?;
+        if inputs.is_some() {
+            if let Some(check) = b.c.failure() {
+                return Err(ExecError::Check { check: check.to_string() }.into());
+            }
+        }
+        Ok(b.c)
+    }
+
+    /// The plonk gates and the custom gates of the circuit.
+    pub fn plonk(&self) -> Result<PlonkCircuit> {
+        Ok(self.build(None)?.into_plonk().0)
+    }
+
+    /// The witness of the circuit for the inputs of a proof, its zkin.
+    pub fn witness<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &self,
+        inputs: I,
+    ) -> Result<Vec<FGL>, ExecError> {
+        let inputs = inputs
+            .into_iter()
+            .map(|(name, values)| {
+                let values =
+                    values.iter().map(|v| to_goldilocks(&name, v)).collect::<Result<_, _>>()?;
+                Ok((name, values))
+            })
+            .collect::<Result<HashMap<_, _>, InputError>>()?;
+        self.witness_of(&inputs)
+    }
+
+    /// The witness of the circuit for a proof, read from the proof itself, checks enabled. The
+    /// aggregation stage takes two proofs, it has none.
+    pub fn proof_witness(&self, proof: &StarkProof<MerkleTreeGL>) -> Result<Vec<FGL>, ExecError> {
+        let mut inputs = proof_inputs(proof);
+        if self.enable_input {
+            inputs.insert("enable".to_string(), vec![FGL::ONE]);
+        }
+        self.witness_of(&inputs)
+    }
+
+    fn witness_of(&self, inputs: &HashMap<String, Vec<FGL>>) -> Result<Vec<FGL>, ExecError> {
+        let c = self.build(Some(inputs)).map_err(|e| match e.downcast::<InputError>() {
+            Ok(e) => ExecError::Input(e),
+            Err(e) => e
+                .downcast::<ExecError>()
+                .unwrap_or_else(|e| ExecError::VerifierCode { reason: format!("{e:#}") }),
+        })?;
+        Ok(c.into_witness())
+    }
+
+    /// Writes the compressor12 PIL, constants and exec file of the circuit.
+    pub fn setup(
+        &self,
+        pil_file: &str,
+        const_file: &str,
+        exec_file: &str,
+        opts: &Options,
+    ) -> Result<SetupReport> {
+        let (plonk, w) = self.build(None)?.into_plonk();
+        let res = PlonkSetup::from_plonk(plonk, opts)?;
+        log::info!("compressor12 setup of the stark verifier:\n{}", res.report);
+
+        let mut file =
+            File::create(pil_file).map_err(|_| anyhow!("Create file error, {}", pil_file))?;
+        write!(file, "{}", res.pil_str)?;
+        write_pil_json(pil_file, &res.pil_str, &res.pil_json)?;
+
+        let const_writer = File::create(const_file)?;
+        res.const_pols.save(const_writer)?;
+
+        let mut file_writer =
+            File::create(exec_file).map_err(|e| anyhow!("Create {}, {:?}", exec_file, e))?;
+        let header = ExecHeader::new(12, &self.description()?, &res.pil_str, w.len());
+        write_exec_file(&mut file_writer, &header, &res.plonk_additions, &res.s_map)?;
+        Ok(res.report)
+    }
+
+    /// What the circuit depends on, hashed in the exec file in place of an r1cs.
+    pub(crate) fn description(&self) -> Result<Vec<u8>> {
+        let desc = serde_json::json!({
+            "starkStruct": self.stark_struct,
+            "constRoot": self.const_root.map(|e| e.as_int()),
+            "nPublics": self.starkinfo.n_publics,
+            "options": [self.enable_input, self.verkey_input, self.skip_main, self.agg_stage],
+        });
+        Ok(serde_json::to_vec(&desc)?)
+    }
+
+    /// The inputs of the zkin of a proof the circuit doesn't read, those of the empty trees.
+    fn ignored_inputs(&self) -> Vec<String> {
+        let mut ignored = vec![];
+        if self.starkinfo.map_sectionsN.cm2_2ns == 0 {
+            ignored.extend(["s0_vals2", "s0_siblings2"]);
+        }
+        if self.starkinfo.map_sectionsN.cm3_2ns == 0 {
+            ignored.extend(["s0_vals3", "s0_siblings3"]);
+        }
+        let prefixes: &[&str] = if self.agg_stage { &["a_", "b_"] } else { &[""] };
+        prefixes.iter().flat_map(|p| ignored.iter().map(move |i| format!("{p}{i}"))).collect()
+    }
+
+    fn verify(&self, b: &mut Builder) -> Result<()> {
+        let n_publics = self.starkinfo.n_publics;
+        if self.agg_stage {
+            // the publics of the main, as pil2circom renders it they are not bound to the proofs
+            b.publics("publics", n_publics - 4)?;
+            b.publics("rootC", 4)?;
+            for prefix in ["a_", "b_"] {
+                b.prefix = prefix;
+                let publics = b.input("publics", n_publics)?;
+                self.verify_proof(b, &publics, None)?;
+            }
+            b.prefix = "";
+            return Ok(());
+        }
+
+        // the publics come first: the publics of the stark, then rootC in a main
+        let publics = b.publics("publics", n_publics)?;
+        // the rootC of a main is public: the verifier key, or bound to the constant root
+        let root_c = match (self.skip_main, self.verkey_input) {
+            (false, true) => Some(four(&b.publics("rootC", 4)?)),
+            (false, false) => {
+                let public = b.publics("rootC", 4)?;
+                let root_c = self.const_root.map(|e| b.c.constant(e));
+                for (p, k) in public.iter().zip(root_c) {
+                    b.c.enforce_eq(*p, k, || String::from("rootC is not the constant root"));
+                }
+                Some(root_c)
+            }
+            (true, true) => None,
+            (true, false) => Some(self.const_root.map(|e| b.c.constant(e))),
+        };
+        self.verify_proof(b, &publics, root_c)
+    }
+
+    /// Verifies a proof, its constant root read after root4 when it is not given.
+    fn verify_proof(
+        &self,
+        b: &mut Builder,
+        publics: &[Signal],
+        root_c: Option<[Signal; 4]>,
+    ) -> Result<()> {
+        let ss = &self.stark_struct;
+        let si = &self.starkinfo;
+        let n_queries = ss.nQueries;
+        let steps: Vec<usize> = ss.steps.iter().map(|s| s.nBits).collect();
+        let n0 = steps[0];
+        let n_last = steps[steps.len() - 1];
+
+        let root1 = b.root("root1")?;
+        let root2 = b.root("root2")?;
+        let root3 = b.root("root3")?;
+        let root4 = b.root("root4")?;
+        let root_c = match root_c {
+            Some(root_c) => root_c,
+            None => b.root("rootC")?,
+        };
+        let evals = b.f3s("evals", si.ev_map.len())?;
+
+        // the trees of the first step, the empty ones have no input
+        let sections = [
+            si.map_sectionsN.cm1_2ns,
+            si.map_sectionsN.cm2_2ns,
+            si.map_sectionsN.cm3_2ns,
+            si.map_sectionsN.cm4_2ns,
+            si.n_constants,
+        ];
+        let names = ["1", "2", "3", "4", "C"];
+        let used: Vec<bool> =
+            sections.iter().enumerate().map(|(t, n)| *n > 0 || t == 0 || t >= 3).collect();
+        let mut s0_vals = vec![vec![]; 5];
+        for (t, name) in names.iter().enumerate() {
+            if used[t] {
+                s0_vals[t] = b.groups(&format!("s0_vals{name}"), n_queries, sections[t])?;
+            }
+        }
+        let mut s0_siblings = vec![vec![]; 5];
+        for (t, name) in names.iter().enumerate() {
+            if used[t] {
+                s0_siblings[t] = b.siblings(&format!("s0_siblings{name}"), n_queries, n0)?;
+            }
+        }
+        let mut s_roots = vec![vec![]];
+        for s in 1..steps.len() {
+            s_roots.push(b.root(&format!("s{s}_root"))?.to_vec());
+        }
+        let mut s_vals = vec![vec![]];
+        let mut s_siblings = vec![vec![]];
+        for s in 1..steps.len() {
+            let group = (1 << (steps[s - 1] - steps[s])) * 3;
+            s_vals.push(b.groups(&format!("s{s}_vals"), n_queries, group)?);
+            s_siblings.push(b.siblings(&format!("s{s}_siblings"), n_queries, steps[s])?);
+        }
+        let final_pol = b.f3s("finalPol", 1 << n_last)?;
+        if self.enable_input {
+            let enable = b.input("enable", 1)?[0];
+            b.c.assert_bit(enable);
+            b.enable = Some(enable);
+        }
+
+        // the challenges
+        let c = &mut b.c;
+        let mut transcript = Transcript::new(c);
+        if ss.fiatShamirVersion == FIAT_SHAMIR_STRONG {
+            let digest = circuit_digest(ss, si)?.map(|e| c.constant(e));
+            transcript.put(c, &digest);
+            transcript.put(c, &root_c);
+        }
+        transcript.put(c, publics);
+        let zero = c.zero();
+        let mut challenges = [[zero; 3]; 8];
+        transcript.put(c, &root1);
+        challenges[0] = transcript.get_field(c);
+        challenges[1] = transcript.get_field(c);
+        transcript.put(c, &root2);
+        challenges[2] = transcript.get_field(c);
+        challenges[3] = transcript.get_field(c);
+        transcript.put(c, &root3);
+        challenges[4] = transcript.get_field(c);
+        transcript.put(c, &root4);
+        challenges[7] = transcript.get_field(c);
+        transcript.put(c, &evals.concat());
+        challenges[5] = transcript.get_field(c);
+        challenges[6] = transcript.get_field(c);
+        let mut special_x = vec![];
+        for s in 0..steps.len() {
+            special_x.push(transcript.get_field(c));
+            if s < steps.len() - 1 {
+                transcript.put(c, &s_roots[s + 1]);
+            } else {
+                transcript.put(c, &final_pol.concat());
+            }
+        }
+        let ys = transcript.get_permutations(c, n_queries, n0);
+
+        // the evaluations
+        let mut x_n = challenges[7];
+        for _ in 0..ss.nBits {
+            x_n = b.c.c_mul(x_n, x_n);
+        }
+        let z = [b.c.linear(&[(x_n[0], FGL::ONE), (0, -FGL::ONE)]), x_n[1], x_n[2]];
+        let refs = Refs {
+            publics,
+            evals: &evals,
+            challenges: &challenges,
+            z,
+            x_div: [[zero; 3]; 2],
+            trees: [&[], &[], &[], &[]],
+            consts: &[],
+        };
+        let res = execute(&mut b.c, &self.program.verifier_code.first, &refs)?;
+        let res = ext(&mut b.c, res);
+        // q = sum(xN^i * q_i(xi))
+        let mut q: Option<F3> = None;
+        let mut x_acc = x_n;
+        for (i, qi) in si.qs.iter().take(si.q_deg).enumerate() {
+            let ev = si.ev_idx.get("cm", 0, *qi);
+            let ev = evals[*ev.ok_or_else(|| anyhow!("q{i} has no evaluation"))?];
+            q = Some(match q {
+                None => ev,
+                Some(q) => {
+                    if i > 1 {
+                        x_acc = b.c.c_mul(x_acc, x_n);
+                    }
+                    b.c.c_mul_add(x_acc, ev, q)
+                }
+            });
+        }
+        let q_z = b.c.c_mul(q.unwrap_or([zero; 3]), z);
+        b.check_f3(res, q_z, || String::from("Q(xi) * Z(xi) != C(xi)"));
+
+        // the queries of the first step
+        let w = MG.0[ss.nBits];
+        for (q, y) in ys.iter().enumerate() {
+            let shift = *SHIFT;
+            let mut x = b.c.linear(&[(y[0], shift * MG.0[n0] - shift), (0, shift)]);
+            for (i, bit) in y.iter().enumerate().skip(1) {
+                x = b.c.mul(&[(x, FGL::ONE)], &[(*bit, MG.0[n0 - i] - FGL::ONE), (0, FGL::ONE)]);
+            }
+            let xi = challenges[7];
+            let x_div = [FGL::ONE, w].map(|k| {
+                let den = [
+                    b.c.linear(&[(x, FGL::ONE), (xi[0], -k)]),
+                    b.c.linear(&[(xi[1], -k)]),
+                    b.c.linear(&[(xi[2], -k)]),
+                ];
+                let inv = b.c.c_inv(den);
+                inv.map(|e| b.c.mul(&[(x, FGL::ONE)], &[(e, FGL::ONE)]))
+            });
+            // the unused trees have no values
+            let tree = |t: usize| s0_vals[t].get(q).map_or(&[][..], Vec::as_slice);
+            let refs = Refs {
+                x_div,
+                trees: [tree(0), tree(1), tree(2), tree(3)],
+                consts: tree(4),
+                ..refs
+            };
+            let out = execute(&mut b.c, &self.program.verifier_query_code.first, &refs)?;
+            let out = ext(&mut b.c, out);
+
+            let roots = [&root1, &root2, &root3, &root4, &root_c];
+            for (t, root_t) in roots.iter().enumerate() {
+                if used[t] {
+                    let root = b.c.merkle_root(&s0_vals[t][q], &s0_siblings[t][q], y);
+                    b.check_root(root, root_t, || {
+                        format!("the Merkle proof of tree{} of query {q}", names[t])
+                    });
+                }
+            }
+            let low = if steps.len() > 1 {
+                let groups = f3s(&s_vals[1][q]);
+                b.c.tree_selector(&groups, &y[steps[1]..n0])
+            } else {
+                b.c.tree_selector(&final_pol, &y[..n0])
+            };
+            b.check_f3(low, out, || format!("the value of query {q} in the first FRI step"));
+        }
+
+        // the FRI steps
+        for s in 1..steps.len() {
+            let (prev, cur) = (steps[s - 1], steps[s]);
+            let k0 = inv(SHIFT.exp(1 << (ss.nBitsExt - prev)));
+            for (q, y) in ys.iter().enumerate() {
+                let root = b.c.merkle_root(&s_vals[s][q], &s_siblings[s][q], &y[..cur]);
+                b.check_root(root, &four(&s_roots[s]), || {
+                    format!("the Merkle proof of FRI step {s} of query {q}")
+                });
+
+                let coefs = b.c.ifft(&f3s(&s_vals[s][q]));
+                // 1 / (shift * w^y) of the domain of the previous step
+                let k = |i: usize| inv(MG.0[prev - i]) - FGL::ONE;
+                let mut sx = match cur {
+                    0 => b.c.constant(k0),
+                    _ => b.c.linear(&[(y[0], k0 * k(0)), (0, k0)]),
+                };
+                for (i, bit) in y.iter().enumerate().take(cur).skip(1) {
+                    sx = b.c.mul(&[(sx, FGL::ONE)], &[(*bit, k(i)), (0, FGL::ONE)]);
+                }
+                let x = special_x[s].map(|e| b.c.mul(&[(e, FGL::ONE)], &[(sx, FGL::ONE)]));
+                let ev = b.c.eval_pol(&coefs, x);
+                let low = if s < steps.len() - 1 {
+                    let groups = f3s(&s_vals[s + 1][q]);
+                    b.c.tree_selector(&groups, &y[steps[s + 1]..cur])
+                } else {
+                    b.c.tree_selector(&final_pol, &y[..cur])
+                };
+                b.check_f3(low, ev, || format!("the value of query {q} in FRI step {s}"));
+            }
+        }
+
+        // the degree of the last polynomial
+        let coefs = b.c.ifft(&final_pol);
+        let max_deg_bits = n_last.saturating_sub(ss.nBitsExt - ss.nBits);
+        for (k, coef) in coefs.iter().enumerate().skip(1 << max_deg_bits) {
+            for e in coef {
+                b.check(&[(*e, FGL::ONE)], || {
+                    format!("the coefficient {k} of the last polynomial")
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The inputs of the circuit read as it is built, and the enable of its checks.
+struct Builder<'a> {
+    c: Circuit,
+    /// None for the setup, the inputs are zeros.
+    inputs: Option<&'a HashMap<String, Vec<FGL>>>,
+    read: HashSet<String>,
+    /// The prefix of the inputs of the proof read, `a_` or `b_` in the aggregation stage.
+    prefix: &'static str,
+    enable: Option<Signal>,
+}
+
+impl Builder<'_> {
+    fn values(&mut self, name: &str, n: usize) -> Result<Vec<FGL>, InputError> {
+        let name = format!("{}{name}", self.prefix);
+        self.read.insert(name.clone());
+        let Some(inputs) = self.inputs else {
+            return Ok(vec![FGL::ZERO; n]);
+        };
+        let values = inputs.get(&name).map(|v| v.as_slice()).unwrap_or_default();
+        if values.len() != n {
+            return Err(InputError::SignalSize { signal: name, expected: n, actual: values.len() });
+        }
+        Ok(values.to_vec())
+    }
+
+    fn publics(&mut self, name: &str, n: usize) -> Result<Vec<Signal>, InputError> {
+        Ok(self.values(name, n)?.into_iter().map(|v| self.c.public(v)).collect())
+    }
+
+    fn input(&mut self, name: &str, n: usize) -> Result<Vec<Signal>, InputError> {
+        Ok(self.values(name, n)?.into_iter().map(|v| self.c.alloc(v)).collect())
+    }
+
+    fn root(&mut self, name: &str) -> Result<[Signal; 4], InputError> {
+        Ok(four(&self.input(name, 4)?))
+    }
+
+    fn f3s(&mut self, name: &str, n: usize) -> Result<Vec<F3>, InputError> {
+        Ok(f3s(&self.input(name, n * 3)?))
+    }
+
+    /// `n` groups of `size` values.
+    fn groups(
+        &mut self,
+        name: &str,
+        n: usize,
+        size: usize,
+    ) -> Result<Vec<Vec<Signal>>, InputError> {
+        let v = self.input(name, n * size)?;
+        Ok((0..n).map(|q| v[q * size..(q + 1) * size].to_vec()).collect())
+    }
+
+    /// The siblings of `n` Merkle proofs of `levels` levels.
+    fn siblings(
+        &mut self,
+        name: &str,
+        n: usize,
+        levels: usize,
+    ) -> Result<Vec<Vec<[Signal; 4]>>, InputError> {
+        let s = self.input(name, n * levels * 4)?;
+        Ok((0..n)
+            .map(|q| (0..levels).map(|l| four(&s[(q * levels + l) * 4..])).collect())
+            .collect())
+    }
+
+    /// An input the circuit doesn't read is an error, unless it has no values, as the
+    /// witness calculator of circom does.
+    fn check_unknown(&self, ignored: &[String]) -> Result<(), InputError> {
+        let Some(inputs) = self.inputs else {
+            return Ok(());
+        };
+        let mut names: Vec<&String> = inputs.keys().collect();
+        names.sort();
+        for name in names {
+            if !self.read.contains(name) && !ignored.contains(name) && !inputs[name].is_empty() {
+                return Err(InputError::UnknownSignal { signal: name.to_string() });
+            }
+        }
+        Ok(())
+    }
+
+    /// Constrains `lc` to 0, if enabled.
+    fn check<W: FnOnce() -> String>(&mut self, lc: &[(Signal, FGL)], what: W) {
+        match self.enable {
+            None => self.c.enforce(&[], &[], lc, what),
+            Some(enable) => self.c.enforce(&[(enable, FGL::ONE)], lc, &[], what),
+        }
+    }
+
+    fn check_f3<W: Fn() -> String>(&mut self, x: F3, y: F3, what: W) {
+        for (xe, ye) in x.iter().zip(y) {
+            if *xe != ye {
+                self.check(&[(*xe, FGL::ONE), (ye, -FGL::ONE)], &what);
+            }
+        }
+    }
+
+    fn check_root<W: Fn() -> String>(&mut self, x: [Signal; 4], y: &[Signal; 4], what: W) {
+        for (xe, ye) in x.iter().zip(y) {
+            self.check(&[(*xe, FGL::ONE), (*ye, -FGL::ONE)], &what);
+        }
+    }
+}
+
+fn four(values: &[Signal]) -> [Signal; 4] {
+    [values[0], values[1], values[2], values[3]]
+}
+
+fn f3s(values: &[Signal]) -> Vec<F3> {
+    values.chunks(3).map(|e| [e[0], e[1], e[2]]).collect()
+}
+
+fn inv(a: FGL) -> FGL {
+    a.inverse().unwrap_or(FGL::ZERO)
+}
+
+/// The zkin of a proof, the inputs of `StarkProof`'s serialization, as signal values.
+pub fn proof_inputs(proof: &StarkProof<MerkleTreeGL>) -> HashMap<String, Vec<FGL>> {
+    let ext = |v: &[F3G]| v.iter().flat_map(|e| e.as_elements()).collect::<Vec<_>>();
+    let queries = &proof.fri_proof.queries;
+    // the values and the siblings of `tree` in the queries of `step`
+    let vals = |step: usize, tree: usize| {
+        queries[step].pol_queries.iter().flat_map(|q| q[tree].0.clone()).collect::<Vec<_>>()
+    };
+    let siblings = |step: usize, tree: usize| {
+        queries[step].pol_queries.iter().flat_map(|q| q[tree].1.concat()).collect::<Vec<_>>()
+    };
+
+    let mut inputs = HashMap::new();
+    if let Some(root_c) = &proof.rootC {
+        inputs.insert("rootC".to_string(), root_c.as_elements().to_vec());
+    }
+    let roots = [&proof.root1, &proof.root2, &proof.root3, &proof.root4];
+    for (i, root) in roots.iter().enumerate() {
+        inputs.insert(format!("root{}", i + 1), root.as_elements().to_vec());
+    }
+    inputs.insert("evals".to_string(), ext(&proof.evals));
+    for (tree, name) in ["1", "2", "3", "4", "C"].iter().enumerate() {
+        inputs.insert(format!("s0_vals{name}"), vals(0, tree));
+        inputs.insert(format!("s0_siblings{name}"), siblings(0, tree));
+    }
+    for (s, query) in queries.iter().enumerate().skip(1) {
+        inputs.insert(format!("s{s}_root"), query.root.as_elements().to_vec());
+        inputs.insert(format!("s{s}_vals"), vals(s, 0));
+        inputs.insert(format!("s{s}_siblings"), siblings(s, 0));
+    }
+    inputs.insert("finalPol".to_string(), ext(&proof.fri_proof.last));
+    inputs.insert("publics".to_string(), ext(&proof.publics));
+    inputs
+}
+
+/// A value of the input `signal` of the zkin, which the circuit only takes below p.
+fn to_goldilocks(signal: &str, v: &BigInt) -> Result<FGL, InputError> {
+    match v.to_u64() {
+        Some(v) if v < MODULUS.0[0] => Ok(FGL::from(v)),
+        _ => Err(InputError::Unsupported { signal: signal.to_string(), value: v.to_string() }),
+    }
+}
+
+/// The transcript of the verifier, `TranscriptGL` on signals.
+struct Transcript {
+    state: [Signal; 4],
+    pending: Vec<Signal>,
+    out: VecDeque<Signal>,
+}
+
+impl Transcript {
+    fn new(c: &mut Circuit) -> Self {
+        Self { state: [c.zero(); 4], pending: vec![], out: VecDeque::new() }
+    }
+
+    fn put(&mut self, c: &mut Circuit, values: &[Signal]) {
+        for v in values {
+            self.out.clear();
+            self.pending.push(*v);
+            if self.pending.len() == 8 {
+                self.update_state(c);
+            }
+        }
+    }
+
+    fn update_state(&mut self, c: &mut Circuit) {
+        let mut inputs = [c.zero(); 12];
+        inputs[..self.pending.len()].copy_from_slice(&self.pending);
+        inputs[8..].copy_from_slice(&self.state);
+        let out = c.poseidon(inputs);
+        self.state = [out[0], out[1], out[2], out[3]];
+        self.out = out.into();
+        self.pending.clear();
+    }
+
+    fn get_fields1(&mut self, c: &mut Circuit) -> Signal {
+        if self.out.is_empty() {
+            self.update_state(c);
+        }
+        self.out.pop_front().unwrap()
+    }
+
+    fn get_field(&mut self, c: &mut Circuit) -> F3 {
+        [self.get_fields1(c), self.get_fields1(c), self.get_fields1(c)]
+    }
+
+    /// The bits, LSB first, of `n` indexes of `n_bits` bits, 63 bits taken from a field.
+    fn get_permutations(&mut self, c: &mut Circuit, n: usize, n_bits: usize) -> Vec<Vec<Signal>> {
+        let n_fields = (n * n_bits - 1) / 63 + 1;
+        let fields: Vec<Signal> = (0..n_fields).map(|_| self.get_fields1(c)).collect();
+        let bits: Vec<Signal> =
+            fields.into_iter().flat_map(|f| c.num2bits_strict(f).into_iter().take(63)).collect();
+        bits.chunks(n_bits).take(n).map(|b| b.to_vec()).collect()
+    }
+}
+
+/// An operand of the verifier code: a constant, an element of the base field, or of the
+/// extension.
+#[derive(Clone, Copy)]
+enum Value {
+    Num(FGL),
+    Base(Signal),
+    Ext(F3),
+}
+
+impl Value {
+    /// The linear combinations of its 3 coefficients.
+    fn lcs(self) -> [Lc; 3] {
+        match self {
+            Value::Num(k) => [vec![(0, k)], vec![], vec![]],
+            Value::Base(s) => [vec![(s, FGL::ONE)], vec![], vec![]],
+            Value::Ext(e) => e.map(|s| vec![(s, FGL::ONE)]),
+        }
+    }
+
+    fn is_ext(self) -> bool {
+        matches!(self, Value::Ext(_))
+    }
+}
+
+/// The signals of the coefficients of `v`.
+fn ext(c: &mut Circuit, v: Value) -> F3 {
+    match v {
+        Value::Ext(e) => e,
+        _ => {
+            let lcs = v.lcs();
+            [0, 1, 2].map(|i| c.linear(&lcs[i]))
+        }
+    }
+}
+
+/// `x + k * y`.
+fn add_scaled(c: &mut Circuit, x: Value, y: Value, k: FGL) -> Value {
+    if let (Value::Num(x), Value::Num(y)) = (x, y) {
+        return Value::Num(x + k * y);
+    }
+    let (lx, ly) = (x.lcs(), y.lcs());
+    let lcs: Vec<Lc> = (0..3)
+        .map(|i| lx[i].iter().copied().chain(ly[i].iter().map(|(s, ks)| (*s, *ks * k))).collect())
+        .collect();
+    if x.is_ext() || y.is_ext() {
+        Value::Ext([0, 1, 2].map(|i| c.linear(&lcs[i])))
+    } else {
+        Value::Base(c.linear(&lcs[0]))
+    }
+}
+
+fn mul(c: &mut Circuit, x: Value, y: Value) -> Value {
+    match (x, y) {
+        (Value::Num(k), Value::Num(l)) => Value::Num(k * l),
+        (Value::Num(k), v) | (v, Value::Num(k)) => add_scaled(c, Value::Num(FGL::ZERO), v, k),
+        (Value::Base(a), Value::Base(b)) => Value::Base(c.mul(&[(a, FGL::ONE)], &[(b, FGL::ONE)])),
+        (Value::Base(a), Value::Ext(e)) | (Value::Ext(e), Value::Base(a)) => {
+            Value::Ext(e.map(|s| c.mul(&[(a, FGL::ONE)], &[(s, FGL::ONE)])))
+        }
+        (Value::Ext(a), Value::Ext(b)) => Value::Ext(c.c_mul(a, b)),
+    }
+}
+
+/// `x * y + z`, a CMulAdd gate when the product is in the extension and so is `z`.
+fn mul_add(c: &mut Circuit, x: Value, y: Value, z: Value) -> Value {
+    let num = |v: Value| matches!(v, Value::Num(_));
+    if (x.is_ext() || y.is_ext()) && z.is_ext() && !num(x) && !num(y) {
+        let (x, y, z) = (ext(c, x), ext(c, y), ext(c, z));
+        return Value::Ext(c.c_mul_add(x, y, z));
+    }
+    let xy = mul(c, x, y);
+    add_scaled(c, xy, z, FGL::ONE)
+}
+
+/// What the references of the verifier code read.
+#[derive(Clone, Copy)]
+struct Refs<'a> {
+    publics: &'a [Signal],
+    evals: &'a [F3],
+    challenges: &'a [F3; 8],
+    z: F3,
+    /// `xDivXSubXi` and `xDivXSubWXi`.
+    x_div: [F3; 2],
+    trees: [&'a [Signal]; 4],
+    consts: &'a [Signal],
+}
+
+/// The value of the verifier `code`, that of the destination of its last section.
+fn execute(c: &mut Circuit, code: &[Section], refs: &Refs) -> Result<Value> {
+    let mut tmp: HashMap<usize, Value> = HashMap::new();
+    let get_ref = |r: &Node, tmp: &HashMap<usize, Value>| -> Result<Value> {
+        let at = |values: &[Signal], i: usize| {
+            values.get(i).copied().ok_or_else(|| anyhow!("{} {i} out of range", r.type_))
+        };
+        let tree = |values: &[Signal]| -> Result<Value> {
+            match r.dim {
+                1 => Ok(Value::Base(at(values, r.tree_pos)?)),
+                3 => Ok(Value::Ext([
+                    at(values, r.tree_pos)?,
+                    at(values, r.tree_pos + 1)?,
+                    at(values, r.tree_pos + 2)?,
+                ])),
+                d => bail!("Invalid dimension {d} of {}", r.type_),
+            }
+        };
+        Ok(match r.type_.as_str() {
+            "tmp" => *tmp.get(&r.id).ok_or_else(|| anyhow!("tmp {} read before set", r.id))?,
+            "tree1" => tree(refs.trees[0])?,
+            "tree2" => tree(refs.trees[1])?,
+            "tree3" => tree(refs.trees[2])?,
+            "tree4" => tree(refs.trees[3])?,
+            "const" => Value::Base(at(refs.consts, r.id)?),
+            "eval" => Value::Ext(*refs.evals.get(r.id).ok_or_else(|| anyhow!("eval {}", r.id))?),
+            "number" => Value::Num(FGL::from(parse_pil_number(r.value.as_ref().unwrap()))),
+            "public" => Value::Base(at(refs.publics, r.id)?),
+            "challenge" => Value::Ext(refs.challenges[r.id]),
+            "x" => Value::Ext(refs.challenges[7]),
+            "Z" => Value::Ext(refs.z),
+            "xDivXSubXi" => Value::Ext(refs.x_div[0]),
+            "xDivXSubWXi" => Value::Ext(refs.x_div[1]),
+            t => bail!("Invalid reference type, get: {t}"),
+        })
+    };
+
+    let last = code.last().ok_or_else(|| anyhow!("empty verifier code"))?;
+    for ci in code {
+        let src = ci.src.iter().map(|s| get_ref(s, &tmp)).collect::<Result<Vec<_>>>()?;
+        let res = match (ci.op.as_str(), src.as_slice()) {
+            ("add", [x, y]) => add_scaled(c, *x, *y, FGL::ONE),
+            ("sub", [x, y]) => add_scaled(c, *x, *y, -FGL::ONE),
+            ("mul", [x, y]) => mul(c, *x, *y),
+            ("muladd", [x, y, z]) => mul_add(c, *x, *y, *z),
+            ("copy", [x]) => *x,
+            (op, _) => bail!("Invalid op: {op} of {} operands", src.len()),
+        };
+        match ci.dest.type_.as_str() {
+            "tmp" => tmp.insert(ci.dest.id, res),
+            t => bail!("Invalid reference type set: {t}"),
+        };
+    }
+    get_ref(&last.dest, &tmp)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use algebraic::witness::parse_input_for_witness;
+    use starky::stark_verify::stark_verify;
+    use starky::transcript::TranscriptGL;
+    use starky::types::Step;
+    use starky::ElementDigest;
+
+    /// Proves the committed polynomials of a GL PIL, its FRI steps 4 bits apart, and checks the
+    /// proof verifies.
+    pub(crate) fn prove_and_verify(
+        mut pil: PIL,
+        const_pols: PolsArray,
+        cm_pols: PolsArray,
+    ) -> (StarkSetup<MerkleTreeGL>, StarkStruct, StarkProof<MerkleTreeGL>) {
+        let n_bits = cm_pols.n.trailing_zeros() as usize;
+        let stark_struct = StarkStruct {
+            nBits: n_bits,
+            nBitsExt: n_bits + 1,
+            nQueries: 8,
+            verificationHashType: "GL".to_string(),
+            steps: (2..=n_bits + 1).rev().step_by(4).map(|n| Step { nBits: n }).collect(),
+            fiatShamirVersion: 0,
+        };
+        let config = ProverConfig::default();
+        let setup =
+            StarkSetup::<MerkleTreeGL>::new(&const_pols, &mut pil, &stark_struct, None, &config)
+                .unwrap();
+        let proof = StarkProof::<MerkleTreeGL>::stark_gen::<TranscriptGL>(
+            cm_pols,
+            const_pols,
+            &setup.const_tree,
+            &setup.starkinfo,
+            &setup.program,
+            &pil,
+            &stark_struct,
+            "",
+        )
+        .unwrap();
+        assert!(stark_verify::<MerkleTreeGL, TranscriptGL>(
+            &proof,
+            &setup.const_root,
+            &setup.starkinfo,
+            &stark_struct,
+            &setup.program,
+        )
+        .unwrap());
+        (setup, stark_struct, proof)
+    }
+
+    /// The verifier of fib and a proof.
+    fn fib(
+        fiat_shamir: usize,
+        options: &StarkOption,
+    ) -> (StarkVerifierCircuit, StarkProof<MerkleTreeGL>) {
+        let (setup, stark_struct, _, proof) = fib_stark(fiat_shamir);
+        (verifier_of(setup, stark_struct, options), proof)
+    }
+
+    /// The setup of fib, its PIL and a proof.
+    pub(crate) fn fib_stark(
+        fiat_shamir: usize,
+    ) -> (StarkSetup<MerkleTreeGL>, StarkStruct, PIL, StarkProof<MerkleTreeGL>) {
+        let mut pil = load_json::<PIL>("../starky/data/fib.pil.json.gl").unwrap();
+        let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
+        const_pol.load("../starky/data/fib.const.gl").unwrap();
+        let mut cm_pol = PolsArray::new(&pil, PolKind::Commit);
+        cm_pol.load("../starky/data/fib.cm.gl").unwrap();
+        let mut stark_struct =
+            load_json::<StarkStruct>("../starky/data/starkStruct.json.gl").unwrap();
+        stark_struct.fiatShamirVersion = fiat_shamir;
+        let config = ProverConfig::default();
+        let setup =
+            StarkSetup::<MerkleTreeGL>::new(&const_pol, &mut pil, &stark_struct, None, &config)
+                .unwrap();
+        let proof = StarkProof::<MerkleTreeGL>::stark_gen::<TranscriptGL>(
+            cm_pol,
+            const_pol,
+            &setup.const_tree,
+            &setup.starkinfo,
+            &setup.program,
+            &pil,
+            &stark_struct,
+            "",
+        )
+        .unwrap();
+        (setup, stark_struct, pil, proof)
+    }
+
+    fn verifier_of(
+        setup: StarkSetup<MerkleTreeGL>,
+        stark_struct: StarkStruct,
+        options: &StarkOption,
+    ) -> StarkVerifierCircuit {
+        let root = setup.const_root.as_elements();
+        let const_root = [root[0], root[1], root[2], root[3]];
+        StarkVerifierCircuit::new(setup.starkinfo, setup.program, stark_struct, const_root, options)
+            .unwrap()
+    }
+
+    /// Every plonk gate holds on the witness.
+    fn check_gates(plonk: &PlonkCircuit, w: &[FGL]) {
+        for g in &plonk.gates {
+            let (a, b, c) = (w[g.0], w[g.1], w[g.2]);
+            assert_eq!(g.3 * a * b + g.4 * a + g.5 * b + g.6 * c + g.7, FGL::ZERO);
+        }
+    }
+
+    fn zkin(proof: &StarkProof<MerkleTreeGL>) -> HashMap<String, Vec<BigInt>> {
+        parse_input_for_witness(&serde_json::to_string(proof).unwrap()).unwrap()
+    }
+
+    fn options(enable_input: bool, verkey_input: bool, skip_main: bool) -> StarkOption {
+        StarkOption { enable_input, verkey_input, skip_main, agg_stage: false }
+    }
+
+    fn tampered(zkin: &HashMap<String, Vec<BigInt>>) -> HashMap<String, Vec<BigInt>> {
+        let mut bad = zkin.clone();
+        bad.get_mut("evals").unwrap()[0] += 1;
+        bad
+    }
+
+    #[test]
+    fn test_stark_verifier_circuit() {
+        let (verifier, proof) = fib(0, &options(false, false, false));
+        let zkin = zkin(&proof);
+        let w = verifier.witness(zkin.clone()).unwrap();
+
+        // the witness satisfies the gates of the setup
+        let plonk = verifier.plonk().unwrap();
+        assert_eq!(plonk.n_publics, verifier.starkinfo.n_publics + 4);
+        assert!(!plonk.custom_gates_uses.is_empty());
+        check_gates(&plonk, &w);
+
+        let err = verifier.witness(tampered(&zkin)).unwrap_err();
+        assert!(matches!(err, ExecError::Check { .. }), "{err}");
+        let mut missing = zkin.clone();
+        missing.remove("finalPol");
+        let err = verifier.witness(missing).unwrap_err();
+        assert!(matches!(err, ExecError::Input(InputError::SignalSize { actual: 0, .. })));
+        let mut unknown = zkin.clone();
+        unknown.insert("s9_root".to_string(), vec![BigInt::from(1)]);
+        let err = verifier.witness(unknown).unwrap_err();
+        assert!(matches!(err, ExecError::Input(InputError::UnknownSignal { .. })));
+        // the inputs are not taken modulo p
+        let mut negative = zkin.clone();
+        negative.get_mut("evals").unwrap()[0] = BigInt::from(-1);
+        let err = verifier.witness(negative).unwrap_err();
+        assert!(matches!(err, ExecError::Input(InputError::Unsupported { .. })), "{err}");
+        // the public rootC can't be another than the constant root
+        let mut forged = zkin.clone();
+        forged.get_mut("rootC").unwrap()[0] += 1;
+        let err = verifier.witness(forged).unwrap_err();
+        assert!(
+            matches!(err, ExecError::Check { check } if check == "rootC is not the constant root")
+        );
+
+        // the files of the setup and the exec, which reads the verifier key only
+        let dir = std::env::temp_dir().join("c12_stark_verifier");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        std::fs::write(file("fib.zkin.json"), serde_json::to_string(&proof).unwrap()).unwrap();
+        let config = ProverConfig::default();
+        let report = setup(
+            "../starky/data/starkStruct.json.gl",
+            "../starky/data/fib.pil.json.gl",
+            "../starky/data/fib.const.gl",
+            &options(false, false, false),
+            &file("fib.key.json"),
+            &file("fib.c12.pil"),
+            &file("fib.c12.const"),
+            &file("fib.c12.exec"),
+            0,
+            &config,
+        )
+        .unwrap();
+        assert!(report.fits());
+        exec(
+            &file("fib.key.json"),
+            &options(false, false, false),
+            &file("fib.zkin.json"),
+            &file("fib.c12.pil"),
+            &file("fib.c12.exec"),
+            &file("fib.c12.cm"),
+            &config,
+        )
+        .unwrap();
+        let key_verifier =
+            StarkVerifierCircuit::load_key(&file("fib.key.json"), &options(false, false, false))
+                .unwrap();
+        let executor =
+            Executor::stark_verifier(key_verifier, &file("fib.c12.pil"), &file("fib.c12.exec"))
+                .unwrap();
+        let mut cm_pols = PolsArray::new(executor.pil(), PolKind::Commit);
+        cm_pols.load(&file("fib.c12.cm")).unwrap();
+        assert_eq!(cm_pols.n, 1 << report.n_bits);
+        let native = executor.place(verifier.proof_witness(&proof).unwrap()).unwrap();
+        assert_eq!(native.array, cm_pols.array);
+        // the exec file is the one of the verifier of the setup
+        let other = fib(0, &options(false, true, false)).0;
+        let err = Executor::stark_verifier(other, &file("fib.c12.pil"), &file("fib.c12.exec"));
+        assert!(err.is_err());
+
+        // a stark of the compressor12 of the committed polynomials verifies: the custom gates,
+        // the plonk additions and the columns are placed as the constraints of the PIL expect
+        let c12_pil = load_json::<PIL>(&file("fib.c12.pil.json")).unwrap();
+        let mut c12_const = PolsArray::new(&c12_pil, PolKind::Constant);
+        c12_const.load(&file("fib.c12.const")).unwrap();
+        let (c12_setup, c12_struct, c12_proof) = prove_and_verify(c12_pil, c12_const, cm_pols);
+
+        // the aggregation stage verifies two proofs of the compressor12, the publics of its main
+        // are left free, as pil2circom renders them
+        let agg_options = StarkOption {
+            enable_input: false,
+            verkey_input: true,
+            skip_main: false,
+            agg_stage: true,
+        };
+        let agg = verifier_of(c12_setup, c12_struct, &agg_options);
+        let n_publics = agg.starkinfo.n_publics;
+        let mut inputs = HashMap::new();
+        for prefix in ["a_", "b_"] {
+            for (name, values) in proof_inputs(&c12_proof) {
+                inputs.insert(format!("{prefix}{name}"), values);
+            }
+        }
+        inputs.insert("publics".to_string(), vec![FGL::ZERO; n_publics - 4]);
+        inputs.insert("rootC".to_string(), vec![FGL::ZERO; 4]);
+        let plonk = agg.plonk().unwrap();
+        assert_eq!(plonk.n_publics, n_publics);
+        check_gates(&plonk, &agg.witness_of(&inputs).unwrap());
+        let mut bad = inputs.clone();
+        bad.get_mut("b_evals").unwrap()[0].add_assign(&FGL::ONE);
+        let err = agg.witness_of(&bad).unwrap_err();
+        assert!(matches!(err, ExecError::Check { .. }), "{err}");
+        let mut bad = inputs;
+        bad.get_mut("a_rootC").unwrap()[0].add_assign(&FGL::ONE);
+        assert!(agg.witness_of(&bad).is_err());
+        // fib has a public only, and the aggregation stage takes the roots as inputs
+        assert!(StarkVerifierCircuit::load_key(&file("fib.key.json"), &agg_options).is_err());
+        let no_verkey = StarkOption { verkey_input: false, ..agg_options };
+        assert!(StarkVerifierCircuit::load_key(&file("fib.key.json"), &no_verkey).is_err());
+    }
+
+    #[test]
+    fn test_stark_verifier_circuit_agrees() {
+        // the verifier circom renders and this circuit check what stark_verify checks: they
+        // accept and reject the same proofs
+        let (setup, stark_struct, _, proof) = fib_stark(0);
+        let node = |n: &ElementDigest<4, FGL>| {
+            let mut e = n.as_elements().to_vec();
+            e[1].add_assign(&FGL::ONE);
+            ElementDigest::<4, FGL>::new(&e)
+        };
+        let copy = |p: &StarkProof<MerkleTreeGL>| -> StarkProof<MerkleTreeGL> {
+            serde_json::from_str(&serde_json::to_string(p).unwrap()).unwrap()
+        };
+        let mut proofs = vec![("none", copy(&proof))];
+        let mut tamper = |what: &'static str, f: &dyn Fn(&mut StarkProof<MerkleTreeGL>)| {
+            let mut bad = copy(&proof);
+            f(&mut bad);
+            proofs.push((what, bad));
+        };
+        tamper("publics", &|p| p.publics[0] += F3G::ONE);
+        tamper("root1", &|p| p.root1 = node(&p.root1));
+        tamper("root3", &|p| p.root3 = node(&p.root3));
+        tamper("evals", &|p| p.evals[1] += F3G::ONE);
+        tamper("s0_vals1", &|p| {
+            p.fri_proof.queries[0].pol_queries[0][0].0[0].add_assign(&FGL::ONE)
+        });
+        tamper("s0_valsC", &|p| {
+            p.fri_proof.queries[0].pol_queries[1][4].0[0].add_assign(&FGL::ONE)
+        });
+        tamper("s0_siblings4", &|p| {
+            p.fri_proof.queries[0].pol_queries[2][3].1[0][0].add_assign(&FGL::ONE)
+        });
+        tamper("s1_root", &|p| p.fri_proof.queries[1].root = node(&p.fri_proof.queries[1].root));
+        tamper("s1_vals", &|p| p.fri_proof.queries[1].pol_queries[3][0].0[1].add_assign(&FGL::ONE));
+        tamper("s2_siblings", &|p| {
+            p.fri_proof.queries[2].pol_queries[4][0].1[1][2].add_assign(&FGL::ONE)
+        });
+        tamper("finalPol", &|p| p.fri_proof.last[0] += F3G::ONE);
+        let accepted: Vec<bool> = proofs
+            .iter()
+            .map(|(_, p)| {
+                let ok = stark_verify::<MerkleTreeGL, TranscriptGL>(
+                    p,
+                    &setup.const_root,
+                    &setup.starkinfo,
+                    &stark_struct,
+                    &setup.program,
+                );
+                matches!(ok, Ok(true))
+            })
+            .collect();
+        assert!(accepted[0] && accepted[1..].iter().all(|ok| !ok));
+
+        let verifier = verifier_of(setup, stark_struct, &options(false, false, false));
+        for ((what, p), ok) in proofs.iter().zip(accepted) {
+            assert_eq!(verifier.proof_witness(p).is_ok(), ok, "{what}");
+        }
+    }
+
+    #[test]
+    fn test_stark_verifier_proof_witness() {
+        let (verifier, proof) = fib(0, &options(true, false, false));
+        // the inputs read from the proof are those of its zkin
+        let zkin = zkin(&proof);
+        let inputs = proof_inputs(&proof);
+        assert_eq!(inputs.len(), zkin.len());
+        for (name, values) in &zkin {
+            let values: Vec<FGL> = values.iter().map(|v| to_goldilocks(name, v).unwrap()).collect();
+            assert_eq!(inputs[name], values, "{name}");
+        }
+
+        let mut zkin = zkin;
+        zkin.insert("enable".to_string(), vec![BigInt::from(1)]);
+        assert_eq!(verifier.proof_witness(&proof).unwrap(), verifier.witness(zkin).unwrap());
+
+        let mut bad = proof;
+        bad.fri_proof.last[0] += F3G::ONE;
+        let err = verifier.proof_witness(&bad).unwrap_err();
+        assert!(matches!(err, ExecError::Check { .. }), "{err}");
+    }
+
+    #[test]
+    fn test_stark_verifier_circuit_enable() {
+        // the constant root an input, the publics those of the stark only
+        let (verifier, proof) = fib(FIAT_SHAMIR_STRONG, &options(true, true, true));
+        let zkin = zkin(&proof);
+        assert_eq!(verifier.plonk().unwrap().n_publics, verifier.starkinfo.n_publics);
+
+        let with_enable = |zkin: &HashMap<String, Vec<BigInt>>, enable: u64| {
+            let mut inputs = zkin.clone();
+            inputs.insert("enable".to_string(), vec![BigInt::from(enable)]);
+            inputs
+        };
+        assert!(verifier.witness(with_enable(&zkin, 1)).is_ok());
+        assert!(verifier.witness(with_enable(&tampered(&zkin), 1)).is_err());
+        // the checks are off
+        assert!(verifier.witness(with_enable(&tampered(&zkin), 0)).is_ok());
+        let err = verifier.witness(with_enable(&zkin, 2)).unwrap_err();
+        assert!(matches!(err, ExecError::Check { check } if check == "bit"));
+
+        let mut bad_root = with_enable(&zkin, 1);
+        bad_root.get_mut("rootC").unwrap()[0] += 1;
+        assert!(verifier.witness(bad_root).is_err());
+    }
+}
@@ -0,0 +1,541 @@
+//! What the GL recursive verifier `pil2circom` would render for a PIL costs, without rendering
+//! nor compiling it: the uses of the custom gates, the R1CS constraints left, and the rows of the
+//! compressor12 PIL of the R1CS.
+//!
+//! The custom gates are counted as the renderer emits them. The constraints of the `circuits.gl`
+//! templates, the plonk gates per constraint and per row are approximations, checked within 5% on
+//! the verifier of fib compiled by circom: the estimate is meant to compare stark structs, the
+//! setup of the compiled circuit has the exact count.
+use crate::compressor12::custom_gates::GateRegistry;
+use crate::compressor12::setup_report::GateRows;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use starky::fiat_shamir::FIAT_SHAMIR_STRONG;
+use starky::helper::log2_any;
+use starky::pil2circom::StarkOption;
+use starky::starkinfo::{ImPolsSelection, Program, StarkInfo};
+use starky::starkinfo_codegen::Section;
+use starky::types::{load_json, StarkStruct, PIL};
+use std::fmt;
+use std::io::Write;
+use std::ops::{Add, AddAssign, Mul};
+
+/// Constraints of a `Num2Bits_strict`: the 64 bits, their sum, the 31 products of the high bits
+/// and the alias check.
+const NUM2BITS_STRICT: usize = 97;
+/// Constraints of a level of a `Merkle`, the 8 inputs of the hash selected by the key bit.
+const MERKLE_LEVEL: usize = 8;
+/// The plonk gates of 10 constraints, a product and the additions of its linear combinations.
+/// The verifier circuit of fib has 12109 gates for 4643 constraints.
+const PLONK_GATES_PER_10_CONSTRAINTS: usize = 26;
+/// compressor12 packs 4 gates a row with the same coefficients, 2 with different ones. The
+/// verifier of fib packs its 12109 gates in 3030 rows.
+const PLONK_GATES_PER_ROW: usize = 4;
+
+/// The custom gates and the R1CS constraints of a part of the verifier.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct Tally {
+    pub constraints: usize,
+    pub poseidon12: usize,
+    pub c_mul_add: usize,
+    pub fft4: usize,
+    pub ev_pol4: usize,
+}
+
+impl Add for Tally {
+    type Output = Tally;
+
+    fn add(self, o: Tally) -> Tally {
+        Tally {
+            constraints: self.constraints + o.constraints,
+            poseidon12: self.poseidon12 + o.poseidon12,
+            c_mul_add: self.c_mul_add + o.c_mul_add,
+            fft4: self.fft4 + o.fft4,
+            ev_pol4: self.ev_pol4 + o.ev_pol4,
+        }
+    }
+}
+
+impl AddAssign for Tally {
+    fn add_assign(&mut self, o: Tally) {
+        *self = *self + o;
+    }
+}
+
+impl Mul<usize> for Tally {
+    type Output = Tally;
+
+    fn mul(self, n: usize) -> Tally {
+        Tally {
+            constraints: self.constraints * n,
+            poseidon12: self.poseidon12 * n,
+            c_mul_add: self.c_mul_add * n,
+            fft4: self.fft4 * n,
+            ev_pol4: self.ev_pol4 * n,
+        }
+    }
+}
+
+fn constraints(n: usize) -> Tally {
+    Tally { constraints: n, ..Default::default() }
+}
+
+fn poseidon(n: usize) -> Tally {
+    Tally { poseidon12: n, ..Default::default() }
+}
+
+fn c_mul(n: usize) -> Tally {
+    Tally { c_mul_add: n, ..Default::default() }
+}
+
+fn c_inv() -> Tally {
+    // the inverse is checked by a CMul
+    c_mul(1) + constraints(3)
+}
+
+/// The sponge of `LinearHash`, 8 elements a hash.
+fn sponge(n_elements: usize) -> Tally {
+    if n_elements <= 4 {
+        Tally::default()
+    } else {
+        poseidon(n_elements.div_ceil(8))
+    }
+}
+
+/// `LinearHash` of a leaf of a `MerkleTreeGL`: the sponge of each batch, then of their digests.
+fn linear_hash(n_elements: usize) -> Tally {
+    if n_elements <= 4 {
+        return Tally::default();
+    }
+    let batch = std::cmp::max(8, n_elements.div_ceil(4));
+    let n_batches = n_elements.div_ceil(batch);
+    let last = n_elements - (n_batches - 1) * batch;
+    sponge(batch) * (n_batches - 1) + sponge(last) + sponge(4 * n_batches)
+}
+
+fn merkle_hash(e_size: usize, elements_in_linear: usize, n_bits: usize) -> Tally {
+    linear_hash(e_size * elements_in_linear) + (poseidon(1) + constraints(MERKLE_LEVEL)) * n_bits
+}
+
+fn tree_selector(n_levels: usize, e_size: usize) -> Tally {
+    constraints(((1 << n_levels) - 1) * e_size)
+}
+
+/// `FFT(n_bits, 3, _)`, radix 4 layers and a radix 2 one for an odd `n_bits`, each FFT4 takes 4
+/// elements of a layer.
+fn fft(n_bits: usize) -> Tally {
+    let uses = ((1usize << n_bits) / 4).max(1) * n_bits.div_ceil(2);
+    Tally { fft4: uses, ..Default::default() }
+}
+
+/// `EvalPol(n)`, EvPol4 for 4k coefficients, CMulAdd otherwise.
+fn eval_pol(n: usize) -> Tally {
+    match n {
+        0 | 1 => Tally::default(),
+        _ if n.is_multiple_of(4) => Tally { ev_pol4: n / 4, ..Default::default() },
+        _ => c_mul(n - 1),
+    }
+}
+
+/// The code unrolled by `unrollCode`, the products by a number are linear.
+fn unrolled_code(code: &[Section]) -> Tally {
+    let mut res = Tally::default();
+    for inst in code {
+        let is_number = |i: usize| inst.src[i].type_ == "number";
+        let dims = |i: usize| inst.src[i].dim;
+        match inst.op.as_str() {
+            "mul" if is_number(0) || is_number(1) => {}
+            "mul" => match (dims(0), dims(1)) {
+                (1, 1) => res += constraints(1),
+                (3, 3) => res += c_mul(1),
+                _ => res += constraints(3),
+            },
+            "muladd" if is_number(0) || is_number(1) => {}
+            "muladd" => match (dims(0), dims(1), dims(2)) {
+                (1, 1, _) => res += constraints(1),
+                (3, 3, _) | (_, _, 3) => res += c_mul(1),
+                _ => res += constraints(3),
+            },
+            _ => {}
+        }
+    }
+    res
+}
+
+/// Counts the hashes of the transcript of the verifier as `Transcript` of the renderer does.
+#[derive(Default)]
+struct TranscriptCount {
+    pending: usize,
+    out: usize,
+    hashes: usize,
+}
+
+impl TranscriptCount {
+    fn put(&mut self, n: usize) {
+        for _ in 0..n {
+            self.out = 0;
+            self.pending += 1;
+            if self.pending == 8 {
+                self.hash();
+            }
+        }
+    }
+
+    fn get_fields(&mut self, n: usize) {
+        for _ in 0..n {
+            if self.out == 0 {
+                self.hash();
+            }
+            self.out -= 1;
+        }
+    }
+
+    fn hash(&mut self) {
+        self.hashes += 1;
+        self.pending = 0;
+        self.out = 12;
+    }
+}
+
+/// The verifier of a proof, by part.
+fn stark_verifier(
+    starkinfo: &StarkInfo,
+    program: &Program,
+    stark_struct: &StarkStruct,
+    n_publics: usize,
+    options: &StarkOption,
+) -> Vec<(String, Tally)> {
+    let steps = &stark_struct.steps;
+    let n_queries = stark_struct.nQueries;
+    let last = steps.len() - 1;
+    let sections = &starkinfo.map_sectionsN;
+    let n_evals = starkinfo.ev_map.len();
+    let mut parts = vec![];
+
+    // the challenges, then the bits of the queries
+    let mut t = TranscriptCount::default();
+    if stark_struct.fiatShamirVersion == FIAT_SHAMIR_STRONG {
+        t.put(4 + 4);
+    }
+    t.put(n_publics + 4);
+    t.get_fields(6);
+    t.put(4);
+    t.get_fields(6);
+    t.put(4);
+    t.get_fields(3);
+    t.put(4);
+    t.get_fields(3);
+    t.put(n_evals * 3);
+    t.get_fields(6);
+    for si in 0..steps.len() {
+        t.get_fields(3);
+        if si < last {
+            t.put(4);
+        } else {
+            t.put((1 << steps[last].nBits) * 3);
+        }
+    }
+    let n_fields = (n_queries * steps[0].nBits - 1) / 63 + 1;
+    t.get_fields(n_fields);
+    let enable = if options.enable_input { 1 } else { 0 };
+    parts.push((
+        "transcript".to_string(),
+        poseidon(t.hashes) + constraints(n_fields * NUM2BITS_STRICT + enable),
+    ));
+
+    let q_deg = starkinfo.q_deg;
+    let evaluations = c_mul(stark_struct.nBits + 2 * (q_deg - 1) + 1)
+        + constraints(3)
+        + unrolled_code(&program.verifier_code.first);
+    parts.push(("evaluations".to_string(), evaluations));
+
+    // step 0: the trees of the committed and constant polynomials, and the query of the
+    // constraint polynomial
+    let nb0 = steps[0].nBits;
+    let widths = [
+        sections.cm1_2ns,
+        sections.cm2_2ns,
+        sections.cm3_2ns,
+        sections.cm4_2ns,
+        starkinfo.n_constants,
+    ];
+    let trees = widths.iter().enumerate().filter(|(i, w)| **w > 0 || [0, 3, 4].contains(i));
+    let n_trees = trees.clone().count();
+    let mut query = Tally::default();
+    for (_, width) in trees {
+        query += merkle_hash(1, *width, nb0);
+    }
+    query += tree_selector(nb0 - if last > 0 { steps[1].nBits } else { 0 }, 3);
+    query += c_inv() * 2 + constraints(nb0 - 1 + 6);
+    query += unrolled_code(&program.verifier_query_code.first);
+    // the checks of the roots and the values are rendered in the loop of each step
+    query += constraints((4 * n_trees + 3) * last);
+    parts.push(("step 0 queries".to_string(), query * n_queries));
+
+    for s in 1..steps.len() {
+        let fold_bits = steps[s - 1].nBits - steps[s].nBits;
+        let next_bits = if s < last { steps[s + 1].nBits } else { 0 };
+        let query = merkle_hash(3, 1 << fold_bits, steps[s].nBits)
+            + fft(fold_bits)
+            + eval_pol(1 << fold_bits)
+            + tree_selector(steps[s].nBits - next_bits, 3)
+            + constraints(steps[s].nBits - 1 + 3 + 3 + 4);
+        parts.push((format!("step {s} queries"), query * n_queries));
+    }
+
+    let last_bits = steps[last].nBits;
+    let max_deg_bits = last_bits - (stark_struct.nBitsExt - stark_struct.nBits);
+    parts.push((
+        "last FFT".to_string(),
+        fft(last_bits) + constraints(3 * ((1 << last_bits) - (1 << max_deg_bits))),
+    ));
+    parts
+}
+
+/// The estimated cost of the recursive verifier of a PIL and a stark struct.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct VerifierEstimate {
+    /// 2 for the aggregation of 2 proofs.
+    pub n_verifiers: usize,
+    /// The parts of a verifier.
+    pub parts: Vec<(String, Tally)>,
+    pub total: Tally,
+    pub n_plonk_gates: usize,
+    /// The publics of the circuit, those of the PIL and the constant root with a main.
+    pub n_publics: usize,
+    pub public_rows: usize,
+    pub plonk_rows: usize,
+    pub custom_gates: Vec<GateRows>,
+    /// The compressor12 rows.
+    pub n_used: usize,
+    pub n_bits: usize,
+}
+
+impl VerifierEstimate {
+    /// `program` has the dimensions of its verifier code set, as `pil2circom` does.
+    pub fn new(
+        starkinfo: &StarkInfo,
+        program: &Program,
+        stark_struct: &StarkStruct,
+        n_publics: usize,
+        options: &StarkOption,
+    ) -> Result<Self> {
+        if stark_struct.verificationHashType != "GL" {
+            bail!(
+                "the {} verifier is not compressed by compressor12, only GL is estimated",
+                stark_struct.verificationHashType
+            );
+        }
+        let parts = stark_verifier(starkinfo, program, stark_struct, n_publics, options);
+        let n_verifiers = if options.agg_stage { 2 } else { 1 };
+        let total = parts.iter().fold(Tally::default(), |acc, (_, t)| acc + *t) * n_verifiers;
+
+        let n_plonk_gates = total.constraints * PLONK_GATES_PER_10_CONSTRAINTS / 10;
+        let plonk_rows = n_plonk_gates.div_ceil(PLONK_GATES_PER_ROW);
+        let circuit_publics = if options.skip_main { n_publics } else { n_publics + 4 };
+        let public_rows = circuit_publics.div_ceil(12);
+
+        let gates = GateRegistry::default();
+        let custom_gates = [
+            ("Poseidon12", total.poseidon12),
+            ("CMulAdd", total.c_mul_add),
+            ("FFT4", total.fft4),
+            ("EvPol4", total.ev_pol4),
+        ]
+        .iter()
+        .map(|(name, count)| GateRows::new(name, *count, gates.get(name).unwrap().1.rows()))
+        .collect::<Vec<_>>();
+
+        let n_used = public_rows + plonk_rows + custom_gates.iter().map(|g| g.rows).sum::<usize>();
+        Ok(Self {
+            n_verifiers,
+            parts,
+            total,
+            n_plonk_gates,
+            n_publics: circuit_publics,
+            public_rows,
+            plonk_rows,
+            custom_gates,
+            n_used,
+            n_bits: log2_any(n_used - 1) + 1,
+        })
+    }
+}
+
+impl fmt::Display for VerifierEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<18}{:>12}{:>12}{:>10}{:>10}{:>10}",
+            "part", "constraints", "Poseidon12", "CMulAdd", "FFT4", "EvPol4"
+        )?;
+        let row = |f: &mut fmt::Formatter<'_>, name: &str, t: &Tally| {
+            writeln!(
+                f,
+                "{:<18}{:>12}{:>12}{:>10}{:>10}{:>10}",
+                name, t.constraints, t.poseidon12, t.c_mul_add, t.fft4, t.ev_pol4
+            )
+        };
+        for (name, t) in self.parts.iter() {
+            row(f, name, t)?;
+        }
+        if self.n_verifiers > 1 {
+            writeln!(f, "x {} verifiers", self.n_verifiers)?;
+        }
+        row(f, "total", &self.total)?;
+        writeln!(f)?;
+        writeln!(f, "compressor12 rows, estimated:")?;
+        writeln!(f, "  publics           {:>10}  ({} publics)", self.public_rows, self.n_publics)?;
+        writeln!(f, "  plonk             {:>10}  ({} gates)", self.plonk_rows, self.n_plonk_gates)?;
+        for g in self.custom_gates.iter() {
+            writeln!(f, "  {:<17} {:>10}  ({} x {})", g.name, g.rows, g.count, g.rows_each)?;
+        }
+        writeln!(f, "  total             {:>10}", self.n_used)?;
+        writeln!(f, "nBits:              {}", self.n_bits)
+    }
+}
+
+/// Estimates the recursive verifier of the PIL `pil_file` proven with the stark struct
+/// `stark_struct`, as `pil2circom` renders it with `options`.
+pub fn estimate(
+    pil_file: &str,
+    stark_struct: &str,
+    options: &StarkOption,
+    im_pols: ImPolsSelection,
+) -> Result<VerifierEstimate> {
+    let mut pil = load_json::<PIL>(pil_file)?;
+    let stark_struct = load_json::<StarkStruct>(stark_struct)?;
+    let n_publics = pil.publics.len();
+    let (mut starkinfo, mut program) = StarkInfo::new(&mut pil, &stark_struct, None, im_pols)?;
+    starkinfo.set_code_dimensions_first(&mut program.verifier_code)?;
+    starkinfo.set_code_dimensions_first(&mut program.verifier_query_code)?;
+    VerifierEstimate::new(&starkinfo, &program, &stark_struct, n_publics, options)
+}
+
+/// Prints the `VerifierEstimate` of a PIL as a table, or as JSON.
+pub fn verifier_estimate<W: Write>(
+    pil_file: &str,
+    stark_struct: &str,
+    options: &StarkOption,
+    im_pols: ImPolsSelection,
+    json: bool,
+    mut writer: W,
+) -> Result<()> {
+    let estimate = estimate(pil_file, stark_struct, options, im_pols)?;
+    if json {
+        serde_json::to_writer_pretty(&mut writer, &estimate)?;
+        writeln!(writer)?;
+    } else {
+        write!(writer, "{estimate}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compressor12::compressor12_setup::Options;
+    use crate::compressor12::plonk_setup::{PlonkCircuit, PlonkSetupRenderInfo};
+    use crate::verifier_circuit::circom_witness::test::compile_verifier;
+    use algebraic::reader::load_r1cs_from_bin;
+    use fields::field_gl::GL;
+    use starky::merklehash::MerkleTreeGL;
+    use starky::polsarray::{PolKind, PolsArray};
+    use starky::prover_config::ProverConfig;
+    use starky::stark_setup::StarkSetup;
+    use starky::types::Step;
+
+    fn options(agg_stage: bool) -> StarkOption {
+        StarkOption { enable_input: false, verkey_input: false, agg_stage, skip_main: false }
+    }
+
+    #[test]
+    fn test_templates() {
+        assert_eq!(linear_hash(4), Tally::default());
+        assert_eq!(linear_hash(17).poseidon12, 4);
+        assert_eq!(linear_hash(48).poseidon12, 10);
+        assert_eq!(merkle_hash(1, 4, 11).poseidon12, 11);
+        assert_eq!(tree_selector(4, 3).constraints, 45);
+        assert_eq!(fft(4).fft4, 8);
+        assert_eq!(fft(3).fft4, 4);
+        assert_eq!(fft(1).fft4, 1);
+        assert_eq!(eval_pol(16).ev_pol4, 4);
+        assert_eq!(eval_pol(4).ev_pol4, 1);
+        assert_eq!(eval_pol(2).c_mul_add, 1);
+
+        let mut t = TranscriptCount::default();
+        t.put(9);
+        t.get_fields(13);
+        assert_eq!(t.hashes, 3);
+    }
+
+    #[test]
+    fn test_estimate() {
+        let pil = "../starky/data/fib.pil.json.gl";
+        let stark_struct = "../starky/data/starkStruct.json.gl";
+        let single = estimate(pil, stark_struct, &options(false), ImPolsSelection::Greedy).unwrap();
+        let agg = estimate(pil, stark_struct, &options(true), ImPolsSelection::Greedy).unwrap();
+
+        assert_eq!(single.parts.len(), 6);
+        // 8 queries of 11 levels in each of the cm1, cm4 and constant trees of fib
+        assert!(single.parts[2].1.poseidon12 >= 8 * 3 * 11);
+        assert_eq!(agg.total, single.total * 2);
+        assert!(agg.n_used > single.n_used);
+        assert_eq!(
+            single.n_used,
+            single.custom_gates.iter().map(|g| g.rows).sum::<usize>()
+                + single.plonk_rows
+                + single.public_rows
+        );
+        assert!(single.to_string().contains("nBits:"));
+
+        let bn128 = "../starky/data/starkStruct.json";
+        assert!(estimate(pil, bn128, &options(false), ImPolsSelection::Greedy).is_err());
+    }
+
+    #[test]
+    fn test_estimate_calibration() {
+        // the verifier of fib compiled by circom, for two stark structs
+        let pil_file = "../starky/data/fib.pil.json.gl";
+        let mut stark_structs =
+            vec![load_json::<StarkStruct>("../starky/data/starkStruct.json.gl").unwrap()];
+        stark_structs.push(StarkStruct {
+            nQueries: 12,
+            steps: [11, 8, 5, 2].map(|nBits| Step { nBits }).to_vec(),
+            ..stark_structs[0].clone()
+        });
+        for (i, stark_struct) in stark_structs.iter().enumerate() {
+            let mut pil = load_json::<PIL>(pil_file).unwrap();
+            let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
+            const_pol.load("../starky/data/fib.const.gl").unwrap();
+            let config = ProverConfig::default();
+            let mut setup =
+                StarkSetup::<MerkleTreeGL>::new(&const_pol, &mut pil, stark_struct, None, &config)
+                    .unwrap();
+            let name = format!("fib.estimate{i}");
+            let dir = compile_verifier(&name, &mut setup, stark_struct, &pil, &options(false));
+            let r1cs_bytes = std::fs::read(dir.join(format!("{name}.r1cs"))).unwrap();
+            let (r1cs, _) = load_r1cs_from_bin::<_, GL>(std::io::Cursor::new(r1cs_bytes));
+            let plonk = PlonkCircuit::from_r1cs(&r1cs);
+            let report =
+                PlonkSetupRenderInfo::plonk_setup_render(&plonk, &Options::new(0)).unwrap().report;
+            let est = VerifierEstimate::new(
+                &setup.starkinfo,
+                &setup.program,
+                stark_struct,
+                pil.publics.len(),
+                &options(false),
+            )
+            .unwrap();
+
+            // the custom gates are exact, the constraints, the gates and the rows within 5%
+            assert_eq!(est.custom_gates, report.custom_gates);
+            assert_eq!(est.public_rows, report.public_rows);
+            let within = |est: usize, real: usize| est.abs_diff(real) * 20 <= real;
+            assert!(within(est.total.constraints, report.n_constraints), "{est}\n{report}");
+            assert!(within(est.n_plonk_gates, report.n_plonk_gates), "{est}\n{report}");
+            assert!(within(est.plonk_rows, report.plonk_rows), "{est}\n{report}");
+            assert!(within(est.n_used, report.n_used), "{est}\n{report}");
+        }
+    }
+}
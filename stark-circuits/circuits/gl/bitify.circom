@@ -0,0 +1,54 @@
+pragma circom 2.1.0;
+
+template Num2Bits(n) {
+    signal input in;
+    signal output out[n];
+    var lc1=0;
+
+    var e2=1;
+    for (var i = 0; i<n; i++) {
+        out[i] <-- (in >> i) & 1;
+        out[i] * (out[i] -1 ) === 0;
+        lc1 += out[i] * e2;
+        e2 = e2+e2;
+    }
+
+    lc1 === in;
+}
+
+template Bits2Num(n) {
+    signal input in[n];
+    signal output out;
+    var lc1=0;
+
+    var e2 = 1;
+    for (var i = 0; i<n; i++) {
+        lc1 += in[i] * e2;
+        e2 = e2 + e2;
+    }
+
+    lc1 ==> out;
+}
+
+// The 64 bits of in, which alias a value >= p = 2^64 - 2^32 + 1 iff the 32 high ones are 1
+// and the low ones not all 0
+template Num2Bits_strict() {
+    signal input in;
+    signal output out[64];
+
+    component n2b = Num2Bits(64);
+    n2b.in <== in;
+
+    signal high[32];
+    high[0] <== n2b.out[32];
+    for (var i=1; i<32; i++) {
+        high[i] <== high[i-1] * n2b.out[32+i];
+    }
+    var low = 0;
+    for (var i=0; i<32; i++) {
+        low += n2b.out[i] * 2**i;
+    }
+    high[31] * low === 0;
+
+    out <== n2b.out;
+}
@@ -0,0 +1,49 @@
+pragma circom 2.1.0;
+
+include "cmul.circom";
+
+// The inverse of a 0 is 0, which the check of CInv rejects
+function _CInv(a) {
+    var aa = a[0] * a[0];
+    var ac = a[0] * a[2];
+    var ba = a[1] * a[0];
+    var bb = a[1] * a[1];
+    var bc = a[1] * a[2];
+    var cc = a[2] * a[2];
+
+    var aaa = aa * a[0];
+    var aac = aa * a[2];
+    var abc = ba * a[2];
+    var abb = ba * a[1];
+    var acc = ac * a[2];
+    var bbb = bb * a[1];
+    var bcc = bc * a[2];
+    var ccc = cc * a[2];
+
+    var t = -aaa - aac - aac + abc + abc + abc + abb - acc - bbb + bcc - ccc;
+    var tinv = 0;
+    if (t != 0) {
+        tinv = 1 / t;
+    }
+
+    var out[3];
+    out[0] = (-aa - ac - ac + bc + bb - cc) * tinv;
+    out[1] = (ba - cc) * tinv;
+    out[2] = (-bb + ac + cc) * tinv;
+    return out;
+}
+
+template CInv() {
+    signal input in[3];
+    signal output out[3];
+
+    var r[3] = _CInv(in);
+    out[0] <-- r[0];
+    out[1] <-- r[1];
+    out[2] <-- r[2];
+
+    signal check[3] <== CMul()(in, out);
+    check[0] === 1;
+    check[1] === 0;
+    check[2] === 0;
+}
@@ -0,0 +1,11 @@
+pragma circom 2.1.0;
+
+include "cmuladd.circom";
+
+template CMul() {
+    signal input ina[3];
+    signal input inb[3];
+    signal output out[3];
+
+    out <== CMulAdd()(ina, inb, [0, 0, 0]);
+}
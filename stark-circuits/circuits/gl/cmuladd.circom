@@ -0,0 +1,31 @@
+pragma circom 2.1.0;
+pragma custom_templates;
+
+// ina * inb + inc in GF(p^3) = GF(p)[x] / (x^3 - x - 1)
+function _CMulAdd(ina, inb, inc) {
+    var A = (ina[0] + ina[1]) * (inb[0] + inb[1]);
+    var B = (ina[0] + ina[2]) * (inb[0] + inb[2]);
+    var C = (ina[1] + ina[2]) * (inb[1] + inb[2]);
+    var D = ina[0] * inb[0];
+    var E = ina[1] * inb[1];
+    var F = ina[2] * inb[2];
+    var G = D - E;
+
+    var out[3];
+    out[0] = C + G - F + inc[0];
+    out[1] = A + C - E - E - D + inc[1];
+    out[2] = B - G + inc[2];
+    return out;
+}
+
+template custom CMulAdd() {
+    signal input ina[3];
+    signal input inb[3];
+    signal input inc[3];
+    signal output out[3];
+
+    var r[3] = _CMulAdd(ina, inb, inc);
+    out[0] <-- r[0];
+    out[1] <-- r[1];
+    out[2] <-- r[2];
+}
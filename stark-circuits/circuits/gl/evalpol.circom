@@ -0,0 +1,67 @@
+pragma circom 2.1.0;
+pragma custom_templates;
+
+include "cmuladd.circom";
+
+// init x^4 + coefs[3] x^3 + coefs[2] x^2 + coefs[1] x + coefs[0]
+template custom EvPol4() {
+    signal input coefs[4][3];
+    signal input init[3];
+    signal input x[3];
+    signal output out[3];
+
+    var acc[3] = [init[0], init[1], init[2]];
+    for (var i=0; i<4; i++) {
+        acc = _CMulAdd(acc, x, coefs[3-i]);
+    }
+    out[0] <-- acc[0];
+    out[1] <-- acc[1];
+    out[2] <-- acc[2];
+}
+
+// pol(x) from the highest coefficients, by EvPol4 for a multiple of 4 coefficients, by CMulAdd
+// otherwise
+template EvalPol(n) {
+    signal input pol[n][3];
+    signal input x[3];
+    signal output out[3];
+
+    var nEvPol4 = n % 4 == 0 ? n \ 4 : 0;
+    var nCMulAdd = n % 4 == 0 ? 0 : n - 1;
+    component evPol4[nEvPol4];
+    component cmuladd[nCMulAdd];
+
+    for (var i=0; i<nEvPol4; i++) {
+        evPol4[i] = EvPol4();
+        for (var j=0; j<4; j++) {
+            evPol4[i].coefs[j] <== pol[n-4*(i+1)+j];
+        }
+        if (i == 0) {
+            for (var e=0; e<3; e++) {
+                evPol4[i].init[e] <== 0;
+            }
+        } else {
+            evPol4[i].init <== evPol4[i-1].out;
+        }
+        evPol4[i].x <== x;
+    }
+
+    for (var i=0; i<nCMulAdd; i++) {
+        cmuladd[i] = CMulAdd();
+        if (i == 0) {
+            cmuladd[i].ina <== pol[n-1];
+        } else {
+            cmuladd[i].ina <== cmuladd[i-1].out;
+        }
+        cmuladd[i].inb <== x;
+        cmuladd[i].inc <== pol[n-2-i];
+    }
+
+    if (nEvPol4 > 0) {
+        out <== evPol4[nEvPol4-1].out;
+    } else if (nCMulAdd > 0) {
+        out <== cmuladd[nCMulAdd-1].out;
+    } else {
+        out <== pol[0];
+    }
+}
@@ -0,0 +1,178 @@
+pragma circom 2.1.0;
+pragma custom_templates;
+
+function roots(i) {
+    var roots[33] = [
+        1,
+        18446744069414584320,
+        281474976710656,
+        18446744069397807105,
+        17293822564807737345,
+        70368744161280,
+        549755813888,
+        17870292113338400769,
+        13797081185216407910,
+        1803076106186727246,
+        11353340290879379826,
+        455906449640507599,
+        17492915097719143606,
+        1532612707718625687,
+        16207902636198568418,
+        17776499369601055404,
+        6115771955107415310,
+        12380578893860276750,
+        9306717745644682924,
+        18146160046829613826,
+        3511170319078647661,
+        17654865857378133588,
+        5416168637041100469,
+        16905767614792059275,
+        9713644485405565297,
+        5456943929260765144,
+        17096174751763063430,
+        1213594585890690845,
+        6414415596519834757,
+        16116352524544190054,
+        9123114210336311365,
+        4614640910117430873,
+        1753635133440165772
+    ];
+    return roots[i];
+}
+
+function _fft4Coefs(w, incW, type) {
+    var w2 = w * w;
+    var w3 = w2 * w;
+    if (type == 4) {
+        return [
+            [1, w2, w, w3],
+            [1, -w2, w*incW, -w3*incW],
+            [1, w2, -w, -w3],
+            [1, -w2, -w*incW, w3*incW]
+        ];
+    }
+    return [
+        [1, w, 0, 0],
+        [1, -w, 0, 0],
+        [0, 0, 1, w*incW],
+        [0, 0, 1, -w*incW]
+    ];
+}
+
+// Stages s+1 and s+2 of a radix 2 FFT on 4 elements at the roots w, w * incW, ... for type 4,
+// two butterflies of stage 1 for type 2, scaled by scale
+template custom FFT4(firstW, incW, scale, type) {
+    signal input in[4][3];
+    signal output out[4][3];
+
+    var k[4][4] = _fft4Coefs(firstW, incW, type);
+    var acc;
+    for (var i=0; i<4; i++) {
+        for (var e=0; e<3; e++) {
+            acc = 0;
+            for (var j=0; j<4; j++) {
+                acc = acc + k[i][j] * in[j][e];
+            }
+            out[i][e] <-- scale * acc;
+        }
+    }
+}
+
+function reverseBits(i, nBits) {
+    var r = 0;
+    for (var k=0; k<nBits; k++) {
+        r = r*2 + ((i >> k) & 1);
+    }
+    return r;
+}
+
+// The FFT, the inverse one if inv, of the elements of GF(p^3) in: a radix 2 stage first for an
+// odd nBits, then radix 4 ones
+template FFT(nBits, eSize, inv) {
+    assert(eSize == 3);
+    var n = 1 << nBits;
+    signal input in[n][3];
+    signal output out[n][3];
+
+    var nStages = (nBits + 1) \ 2;
+    var nGroups = n > 4 ? n \ 4 : 1;
+    component fft4[nStages][nGroups];
+
+    var buff[n][3];
+    for (var i=0; i<n; i++) {
+        for (var e=0; e<3; e++) {
+            buff[i][e] = in[reverseBits(i, nBits)][e];
+        }
+    }
+
+    var nInv = 1;
+    if (inv) {
+        nInv = 1 / n;
+    }
+
+    var st = 0;
+    var s = 0;
+    var scale;
+    var q;
+    var w;
+    var g;
+    if (nBits % 2 == 1) {
+        scale = nBits == 1 ? nInv : 1;
+        for (g=0; g<nGroups; g++) {
+            fft4[st][g] = FFT4(1, 1, scale, 2);
+            for (var k=0; k<4; k++) {
+                for (var e=0; e<3; e++) {
+                    if (4*g + k < n) {
+                        fft4[st][g].in[k][e] <== buff[4*g + k][e];
+                    } else {
+                        fft4[st][g].in[k][e] <== 0;
+                    }
+                }
+            }
+            for (var k=0; k<4; k++) {
+                if (4*g + k < n) {
+                    for (var e=0; e<3; e++) {
+                        buff[4*g + k][e] = fft4[st][g].out[k][e];
+                    }
+                }
+            }
+        }
+        st = 1;
+        s = 1;
+    }
+
+    var w4 = inv ? 1 / roots(2) : roots(2);
+    var w4q;
+    while (s < nBits) {
+        q = 1 << s;
+        scale = s + 2 == nBits ? nInv : 1;
+        w4q = inv ? 1 / roots(s + 2) : roots(s + 2);
+        g = 0;
+        for (var b=0; b<n; b += 4*q) {
+            w = 1;
+            for (var j=0; j<q; j++) {
+                fft4[st][g] = FFT4(w, w4, scale, 4);
+                for (var k=0; k<4; k++) {
+                    for (var e=0; e<3; e++) {
+                        fft4[st][g].in[k][e] <== buff[b + j + k*q][e];
+                    }
+                }
+                for (var k=0; k<4; k++) {
+                    for (var e=0; e<3; e++) {
+                        buff[b + j + k*q][e] = fft4[st][g].out[k][e];
+                    }
+                }
+                w = w * w4q;
+                g++;
+            }
+        }
+        st++;
+        s += 2;
+    }
+
+    for (var i=0; i<n; i++) {
+        for (var e=0; e<3; e++) {
+            out[i][e] <== buff[i][e];
+        }
+    }
+}
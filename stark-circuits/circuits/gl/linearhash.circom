@@ -0,0 +1,102 @@
+pragma circom 2.1.0;
+
+include "poseidon.circom";
+
+// The first 4 outputs of Poseidon on the chunks of 8 of in, each hash the capacity of the next,
+// in itself padded with 0 when it has 4 elements at most
+template Sponge(n) {
+    signal input in[n];
+    signal output out[4];
+
+    var nHashes = n <= 4 ? 0 : (n - 1) \ 8 + 1;
+    component hash[nHashes];
+
+    for (var i=0; i<nHashes; i++) {
+        hash[i] = Poseidon(4);
+        for (var j=0; j<8; j++) {
+            if (i*8 + j < n) {
+                hash[i].in[j] <== in[i*8 + j];
+            } else {
+                hash[i].in[j] <== 0;
+            }
+        }
+        for (var j=0; j<4; j++) {
+            if (i == 0) {
+                hash[i].capacity[j] <== 0;
+            } else {
+                hash[i].capacity[j] <== hash[i-1].out[j];
+            }
+        }
+    }
+
+    for (var j=0; j<4; j++) {
+        if (nHashes > 0) {
+            out[j] <== hash[nHashes-1].out[j];
+        } else if (j < n) {
+            out[j] <== in[j];
+        } else {
+            out[j] <== 0;
+        }
+    }
+}
+
+// The leaf of a Merkle tree of Poseidon: the sponge of the sponges of the batches of the
+// nInputs * eSize elements, max(8, n / 4) a batch
+template LinearHash(nInputs, eSize) {
+    signal input in[nInputs][eSize];
+    signal output out[4];
+
+    var n = nInputs * eSize;
+    var bs = (n + 3) \ 4;
+    if (bs < 8) {
+        bs = 8;
+    }
+    var nBatches = n <= 4 ? 0 : (n - 1) \ bs + 1;
+    var nLast = n - (nBatches - 1) * bs;
+
+    component batch[nBatches > 0 ? nBatches - 1 : 0];
+    component lastBatch;
+    component hashes;
+
+    var f;
+    for (var b=0; b<nBatches; b++) {
+        if (b == nBatches - 1) {
+            lastBatch = Sponge(nLast);
+        } else {
+            batch[b] = Sponge(bs);
+        }
+        for (var i=0; i<bs && b*bs + i < n; i++) {
+            f = b*bs + i;
+            if (b == nBatches - 1) {
+                lastBatch.in[i] <== in[f \ eSize][f % eSize];
+            } else {
+                batch[b].in[i] <== in[f \ eSize][f % eSize];
+            }
+        }
+    }
+
+    if (nBatches > 1) {
+        hashes = Sponge(4*nBatches);
+        for (var b=0; b<nBatches; b++) {
+            for (var j=0; j<4; j++) {
+                if (b == nBatches - 1) {
+                    hashes.in[4*b + j] <== lastBatch.out[j];
+                } else {
+                    hashes.in[4*b + j] <== batch[b].out[j];
+                }
+            }
+        }
+    }
+
+    for (var j=0; j<4; j++) {
+        if (nBatches > 1) {
+            out[j] <== hashes.out[j];
+        } else if (nBatches == 1) {
+            out[j] <== lastBatch.out[j];
+        } else if (j < n) {
+            out[j] <== in[j \ eSize][j % eSize];
+        } else {
+            out[j] <== 0;
+        }
+    }
+}
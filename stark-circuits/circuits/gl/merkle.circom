@@ -0,0 +1,39 @@
+pragma circom 2.1.0;
+
+include "poseidon.circom";
+
+// The root of the Merkle proof of value at the index of the bits key, LSB first
+template Merkle(keyBits) {
+    signal input value[4];
+    signal input siblings[keyBits][4];
+    signal input key[keyBits];
+    signal output root[4];
+
+    signal left[keyBits][4];
+    signal right[keyBits][4];
+    component hash[keyBits];
+
+    for (var i=0; i<keyBits; i++) {
+        hash[i] = Poseidon(4);
+        for (var j=0; j<4; j++) {
+            if (i == 0) {
+                left[i][j] <== key[i]*(siblings[i][j] - value[j]) + value[j];
+                right[i][j] <== key[i]*(value[j] - siblings[i][j]) + siblings[i][j];
+            } else {
+                left[i][j] <== key[i]*(siblings[i][j] - hash[i-1].out[j]) + hash[i-1].out[j];
+                right[i][j] <== key[i]*(hash[i-1].out[j] - siblings[i][j]) + siblings[i][j];
+            }
+            hash[i].in[j] <== left[i][j];
+            hash[i].in[4+j] <== right[i][j];
+            hash[i].capacity[j] <== 0;
+        }
+    }
+
+    for (var j=0; j<4; j++) {
+        if (keyBits == 0) {
+            root[j] <== value[j];
+        } else {
+            root[j] <== hash[keyBits-1].out[j];
+        }
+    }
+}
@@ -0,0 +1,24 @@
+pragma circom 2.1.0;
+
+include "linearhash.circom";
+include "merkle.circom";
+include "utils.circom";
+
+template parallel MerkleHash(eSize, elementsInLinear, nLinears) {
+    var nBits = log2(nLinears);
+    assert(1 << nBits == nLinears);
+    signal input values[elementsInLinear][eSize];
+    signal input siblings[nBits][4];
+    signal input key[nBits];
+    signal output root[4];
+
+    component linearHash = LinearHash(elementsInLinear, eSize);
+    linearHash.in <== values;
+
+    component merkle = Merkle(nBits);
+    merkle.value <== linearHash.out;
+    merkle.siblings <== siblings;
+    merkle.key <== key;
+
+    root <== merkle.root;
+}
@@ -0,0 +1,69 @@
+pragma circom 2.1.0;
+pragma custom_templates;
+
+include "poseidon_constants.circom";
+
+// The MDS matrix, circulant but for M[0][0] + 8
+function MDS(k, i) {
+    var circ[12] = [17, 15, 41, 16, 2, 28, 13, 13, 39, 18, 34, 20];
+    var d = 0;
+    if (k == 0 && i == 0) {
+        d = 8;
+    }
+    return circ[(i + 12 - k) % 12] + d;
+}
+
+// The permutation of [in, capacity], the state after each of the 30 rounds in im and out
+template custom Poseidon12() {
+    signal input in[8];
+    signal input capacity[4];
+    signal output im[29][12];
+    signal output out[12];
+
+    var C[360] = POSEIDON_C();
+
+    var st[12];
+    var next[12];
+    for (var i=0; i<8; i++) {
+        st[i] = in[i];
+    }
+    for (var i=0; i<4; i++) {
+        st[8+i] = capacity[i];
+    }
+
+    for (var r=0; r<30; r++) {
+        for (var i=0; i<12; i++) {
+            st[i] = st[i] + C[r*12+i];
+            if (i == 0 || r < 4 || r >= 26) {
+                st[i] = st[i]**7;
+            }
+        }
+        for (var k=0; k<12; k++) {
+            next[k] = 0;
+            for (var i=0; i<12; i++) {
+                next[k] = next[k] + MDS(k, i) * st[i];
+            }
+        }
+        for (var k=0; k<12; k++) {
+            st[k] = next[k];
+            if (r < 29) {
+                im[r][k] <-- st[k];
+            } else {
+                out[k] <-- st[k];
+            }
+        }
+    }
+}
+
+template Poseidon(nOuts) {
+    signal input in[8];
+    signal input capacity[4];
+    signal output out[nOuts];
+
+    component p = Poseidon12();
+    p.in <== in;
+    p.capacity <== capacity;
+    for (var i=0; i<nOuts; i++) {
+        out[i] <== p.out[i];
+    }
+}
@@ -0,0 +1,158 @@
+//! The circom templates included by the verifiers of starky's `pil2circom`: those of pil-stark's
+//! `circuits.gl` and `circuits.bn128`, and their BLS12-381 counterparts. The big field verifiers
+//! also include circomlib, which still has to be linked with `-l`.
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A link directory of this form, e.g. `starky:BLS12381`, resolves to the bundled templates.
+pub const LIB_PREFIX: &str = "starky:";
+
+macro_rules! template {
+    ($dir:literal, $name:literal) => {
+        ($name, include_str!(concat!("../circuits/", $dir, "/", $name)))
+    };
+}
+
+const GL: &[(&str, &str)] = &[
+    template!("gl", "bitify.circom"),
+    template!("gl", "cinv.circom"),
+    template!("gl", "cmul.circom"),
+    template!("gl", "cmuladd.circom"),
+    template!("gl", "evalpol.circom"),
+    template!("gl", "fft.circom"),
+    template!("gl", "iszero.circom"),
+    template!("gl", "linearhash.circom"),
+    template!("gl", "merkle.circom"),
+    template!("gl", "merklehash.circom"),
+    template!("gl", "mux1.circom"),
+    template!("gl", "poseidon.circom"),
+    template!("gl", "poseidon_constants.circom"),
+    template!("gl", "treeselector.circom"),
+    template!("gl", "utils.circom"),
+];
+
+const BN128: &[(&str, &str)] = &[
+    template!("bn128", "bn1togl3.circom"),
+    template!("bn128", "compconstant64.circom"),
+    template!("bn128", "evalpol.circom"),
+    template!("bn128", "fft.circom"),
+    template!("bn128", "gl.circom"),
+    template!("bn128", "linearhash.circom"),
+    template!("bn128", "merkle.circom"),
+    template!("bn128", "merklehash.circom"),
+    template!("bn128", "poseidon.circom"),
+    template!("bn128", "poseidon_constants.circom"),
+    template!("bn128", "treeselector.circom"),
+    template!("bn128", "utils.circom"),
+];
+
+const BLS12381: &[(&str, &str)] = &[
+    template!("bls12381", "bn1togl3.circom"),
+    template!("bls12381", "compconstant64.circom"),
+    template!("bls12381", "evalpol.circom"),
+    template!("bls12381", "fft.circom"),
+    template!("bls12381", "gl.circom"),
+    template!("bls12381", "linearhash.circom"),
+    template!("bls12381", "merkle.circom"),
+    template!("bls12381", "merklehash.circom"),
+    template!("bls12381", "poseidon.circom"),
+    template!("bls12381", "poseidon_constants.circom"),
+    template!("bls12381", "treeselector.circom"),
+    template!("bls12381", "utils.circom"),
+];
+
+/// The bundled templates of the verifier over `hash_type`, as (file name, source).
+pub fn templates(hash_type: &str) -> Result<&'static [(&'static str, &'static str)]> {
+    match hash_type {
+        "GL" => Ok(GL),
+        "BN128" => Ok(BN128),
+        "BLS12381" => Ok(BLS12381),
+        _ => bail!("Invalid hash type {}", hash_type),
+    }
+}
+
+/// The files included by a circom source.
+pub fn includes(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .filter_map(|l| {
+            let l = l.trim().strip_prefix("include ")?.trim();
+            let l = l.strip_suffix(';').unwrap_or(l).trim();
+            Some(l.trim_matches('"').trim_start_matches("./").to_string())
+        })
+        .collect()
+}
+
+/// The includes of `source` which are not bundled for `hash_type`, to be linked with `-l`.
+pub fn unbundled_includes(hash_type: &str, source: &str) -> Result<Vec<String>> {
+    let templates = templates(hash_type)?;
+    Ok(includes(source).into_iter().filter(|i| !templates.iter().any(|(n, _)| n == i)).collect())
+}
+
+/// Writes the bundled templates of `hash_type` into `dir`.
+pub fn write_library<P: AsRef<Path>>(hash_type: &str, dir: P) -> Result<()> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    for (name, source) in templates(hash_type)? {
+        fs::write(dir.join(name), source)?;
+    }
+    Ok(())
+}
+
+/// Writes the bundled templates of `hash_type` next to the verifier `circom_file`, and returns
+/// the includes of the verifier which are left to `-l`.
+pub fn write_verifier_library(hash_type: &str, circom_file: &str) -> Result<Vec<String>> {
+    let path = Path::new(circom_file);
+    write_library(hash_type, path.parent().unwrap_or_else(|| Path::new(".")))?;
+    unbundled_includes(hash_type, &fs::read_to_string(path)?)
+}
+
+/// Writes the bundled templates of `hash_type` under the temporary directory, in a directory
+/// per version of this crate, and returns it.
+pub fn library_dir(hash_type: &str) -> Result<PathBuf> {
+    let dir = std::env::temp_dir()
+        .join(format!("stark-circuits-{}", env!("CARGO_PKG_VERSION")))
+        .join(hash_type);
+    write_library(hash_type, &dir)?;
+    Ok(dir)
+}
+
+/// Resolves `starky:<hash type>` to the bundled templates, any other link directory as is.
+pub fn resolve_link_directory(path: &str) -> Result<PathBuf> {
+    match path.strip_prefix(LIB_PREFIX) {
+        Some(hash_type) => library_dir(hash_type),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_templates() {
+        // the templates only depend on each other and circomlib's bitify
+        for (hash_type, circomlib) in [("GL", &[][..]), ("BN128", &["bitify.circom"][..])] {
+            for (_, source) in templates(hash_type).unwrap() {
+                for i in unbundled_includes(hash_type, source).unwrap() {
+                    assert!(circomlib.contains(&i.as_str()), "{hash_type}: {i}");
+                }
+            }
+        }
+        for (_, source) in templates("BLS12381").unwrap() {
+            for i in unbundled_includes("BLS12381", source).unwrap() {
+                assert_eq!(i, "bitify.circom");
+            }
+        }
+        assert!(templates("BN254").is_err());
+        assert_eq!(
+            includes("include \"cmul.circom\";\ninclude \"./poseidon_constants.circom\";"),
+            vec!["cmul.circom", "poseidon_constants.circom"]
+        );
+
+        let dir = resolve_link_directory("starky:GL").unwrap();
+        assert_eq!(fs::read_to_string(dir.join("fft.circom")).unwrap(), GL[5].1);
+        assert_eq!(resolve_link_directory("circuits").unwrap(), PathBuf::from("circuits"));
+    }
+}
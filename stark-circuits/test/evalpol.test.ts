@@ -8,7 +8,7 @@ describe("EvalPol Circuit Test", function () {
     this.timeout(1000000);
 
     before( async () => {
-        circuit = await test.genMain("circuits/evalpol.circom","EvalPol", "", [32], {"include": "node_modules/circomlib/circuits", "prime": "bls12381"});
+        circuit = await test.genMain("circuits/bls12381/evalpol.circom","EvalPol", "", [32], {"include": "node_modules/circomlib/circuits", "prime": "bls12381"});
     });
 
     it("Should calculate polynomial evaluation selector", async () => {
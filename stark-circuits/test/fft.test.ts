@@ -8,8 +8,8 @@ describe("FFT Circuit Test", function () {
     this.timeout(1000000);
 
     before( async () => {
-        circuitFFT = await test.genMain("circuits/fft.circom","FFT", "", [3,0], {"include": "node_modules/circomlib/circuits", "prime": "bls12381"});
-        circuitIFFT = await test.genMain("circuits/fft.circom","FFT", "", [3,1], {"include": "node_modules/circomlib/circuits", "prime": "bls12381"});
+        circuitFFT = await test.genMain("circuits/bls12381/fft.circom","FFT", "", [3,0], {"include": "node_modules/circomlib/circuits", "prime": "bls12381"});
+        circuitIFFT = await test.genMain("circuits/bls12381/fft.circom","FFT", "", [3,1], {"include": "node_modules/circomlib/circuits", "prime": "bls12381"});
     });
 
     it("Should calculate shifted fft and shifted ifft size 8", async () => {
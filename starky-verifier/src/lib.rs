@@ -0,0 +1,10 @@
+//! The wasm-bindgen wrapper of starky's GL verifier, `starky::verifier_key::verify` built
+//! without starky's `std` feature, by `wasm-pack build --target web starky-verifier`.
+use wasm_bindgen::prelude::*;
+
+/// Verifies the zkin JSON of a proof with the JSON of its verifier key, throws on malformed
+/// inputs.
+#[wasm_bindgen]
+pub fn verify(key: &str, proof: &str) -> Result<bool, JsError> {
+    starky::verifier_key::verify(key, proof).map_err(|e| JsError::new(&e.to_string()))
+}
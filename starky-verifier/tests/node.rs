@@ -0,0 +1,32 @@
+//! Runs under node with `wasm-pack test --node starky-verifier`.
+#![cfg(target_arch = "wasm32")]
+use serde_json::Value;
+use starky_verifier::verify;
+use wasm_bindgen_test::*;
+
+// the fib proof of `starky/data/fib.pil.json.gl` and the key of its setup, with
+// `fiatShamirVersion` 1, so the circuit digest is recomputed from the key in wasm
+const KEY: &str = include_str!("data/fib.verifier_key.json");
+const ZKIN: &str = include_str!("data/fib.zkin.json");
+
+#[wasm_bindgen_test]
+fn test_verify_fib_wasm() {
+    assert!(verify(KEY, ZKIN).unwrap());
+
+    // an evaluation and the last FRI polynomial changed
+    for key in ["evals", "finalPol"] {
+        let mut bad: Value = serde_json::from_str(ZKIN).unwrap();
+        let e = &mut bad[key][0][0];
+        let v: u64 = e.as_str().unwrap().parse().unwrap();
+        *e = Value::String(((v + 1) % 0xffffffff00000001).to_string());
+        assert!(!verify(KEY, &bad.to_string()).unwrap_or(false));
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_verify_malformed_wasm() {
+    assert!(verify("{}", "{}").is_err());
+    assert!(verify("not json", "{}").is_err());
+    assert!(verify(KEY, "{}").is_err());
+    assert!(verify(KEY, &ZKIN.replace("\"s0_vals1\":[[", "\"s0_vals1\":[")).is_err());
+}
@@ -2,8 +2,10 @@
 extern crate criterion;
 
 use criterion::Criterion;
+use fields::field_gl::Fr as FGL;
 use starky::dev::gen_rand_goldfields;
-use starky::fft_p::{fft, ifft};
+use starky::fft_p::{fft, ifft, interpolate};
+use starky::ntt_gl::{coset_lde, ntt};
 use starky::traits::FieldExtension;
 use starky::{f3g::F3G, fft::FFT};
 
@@ -37,16 +39,46 @@ fn bench_p_fft<F: FieldExtension>(c: &mut Criterion) {
     for k in MIN_K..=MAX_K {
         // prepare data.
         let a: Vec<F> = gen_rand_goldfields(k);
+        let mut out = vec![F::ZERO; a.len()];
         // bench fft
         group.bench_function(format!("p_fft/k/{k}"), |b| {
             b.iter(|| {
-                fft(&a, 1, k, &mut vec![]);
+                fft(&a, 1, k, &mut out);
             });
         });
         // bench ifft
         group.bench_function(format!("p_ifft/k/{k}"), |b| {
             b.iter(|| {
-                ifft(&a, 1, k, &mut vec![]);
+                ifft(&a, 1, k, &mut out);
+            });
+        });
+    }
+}
+
+fn bench_gl_ntt(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gl_ntt");
+    let n_pols = 8;
+    for k in MIN_K..=MAX_K - 3 {
+        // base field columns, as committed by the prover.
+        let a: Vec<F3G> =
+            gen_rand_goldfields::<F3G>(k + 3).iter().map(|e| F3G::from(e.cube[0])).collect();
+        let a_be: Vec<FGL> = a.iter().map(|e| e.to_be()).collect();
+        let mut out = vec![FGL::ZERO; a_be.len()];
+        group.bench_function(format!("ntt/k/{k}"), |b| {
+            b.iter(|| {
+                ntt(&a_be, n_pols, k, &mut out);
+            });
+        });
+        let mut out_ext = vec![FGL::ZERO; a_be.len() * 2];
+        group.bench_function(format!("coset_lde/k/{k}"), |b| {
+            b.iter(|| {
+                coset_lde(&a_be, n_pols, k, &mut out_ext, k + 1);
+            });
+        });
+        let mut out_ext = vec![F3G::ZERO; a.len() * 2];
+        group.bench_function(format!("interpolate/k/{k}"), |b| {
+            b.iter(|| {
+                interpolate(&a, n_pols, k, &mut out_ext, k + 1);
             });
         });
     }
@@ -55,6 +87,7 @@ fn bench_p_fft<F: FieldExtension>(c: &mut Criterion) {
 fn criterion_benchmark(c: &mut Criterion) {
     bench_standard_fft::<F3G>(c);
     bench_p_fft::<F3G>(c);
+    bench_gl_ntt(c);
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,66 @@
+#![allow(non_snake_case)]
+//! Shared by the examples ported from `starkjs`: each builds its constant and committed
+//! polynomials with `TraceBuilder`, then runs the same flow as `eigen-zkit stark_prove`.
+use anyhow::Result;
+use starky::polsarray::PolsArray;
+use starky::prove::stark_prove;
+use starky::prover_config::ProverConfig;
+use starky::starkinfo::ImPolsSelection;
+use starky::types::{StarkStruct, Step};
+use std::fs::File;
+
+/// 0x2FD31EB1BB3f0Ac8C4feBaF1114F42431c1F29E4
+const PROVER_ADDR: &str = "273030697313060285579891744179749754319274977764";
+
+/// `[pil.json] [workspace]`, the PIL compiled by pilcom, and the output directory.
+pub fn args(default_pil: &str) -> (String, String) {
+    let mut args = std::env::args().skip(1);
+    let pil_file = args.next().unwrap_or(default_pil.to_string());
+    let workspace =
+        args.next().unwrap_or(std::env::temp_dir().to_str().unwrap_or("/tmp").to_string());
+    (pil_file, workspace)
+}
+
+pub fn stark_struct(verificationHashType: &str, steps: &[usize]) -> StarkStruct {
+    StarkStruct {
+        nBits: 10,
+        nBitsExt: 11,
+        nQueries: 8,
+        verificationHashType: verificationHashType.to_string(),
+        steps: steps.iter().map(|nBits| Step { nBits: *nBits }).collect(),
+        fiatShamirVersion: 0,
+    }
+}
+
+/// Saves `$workspace/$name.{const,cm,starkStruct.json}`, then proves and verifies them, and
+/// generates `$name.verifier.circom` and `$name.zkin.json`.
+pub fn prove(
+    name: &str,
+    pil_file: &str,
+    workspace: &str,
+    const_pols: &PolsArray,
+    cm_pols: &PolsArray,
+    stark_struct: &StarkStruct,
+) -> Result<()> {
+    let path = |ext: &str| format!("{workspace}/{name}.{ext}");
+    const_pols.save(File::create(path("const"))?)?;
+    cm_pols.save(File::create(path("cm"))?)?;
+    serde_json::to_writer_pretty(File::create(path("starkStruct.json"))?, stark_struct)?;
+
+    stark_prove(
+        &path("starkStruct.json"),
+        pil_file,
+        false,
+        false,
+        false,
+        &path("const"),
+        &path("cm"),
+        &path("verifier.circom"),
+        &path("zkin.json"),
+        PROVER_ADDR,
+        ImPolsSelection::default(),
+        &ProverConfig::default(),
+    )?;
+    println!("{} proved, outputs in {}", name, workspace);
+    Ok(())
+}
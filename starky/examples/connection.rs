@@ -0,0 +1,84 @@
+//! Port of `starkjs/connection`.
+//!
+//! cargo run --release --example connection -- data/connection.pil.json /tmp
+mod common;
+
+use anyhow::Result;
+use fields::field_gl::Fr as FGL;
+use starky::constant::MG;
+use starky::helper::{get_ks, log2_any};
+use starky::polsarray::{PolKind, PolsArray};
+use starky::trace_builder::TraceBuilder;
+use starky::types::{load_json, PIL};
+
+fn build_constants(pil: &PIL) -> Result<PolsArray> {
+    let mut pols = TraceBuilder::new(pil, PolKind::Constant);
+    let n = pols.n();
+    pols.fill("Global.L1", 0, |i| FGL::from((i == 0) as u64))?;
+
+    let ks = get_ks(2);
+    let wn = MG.0[log2_any(n)];
+    let xs: Vec<FGL> = (0..n)
+        .scan(FGL::ONE, |w, _| {
+            let x = *w;
+            *w = *w * wn;
+            Some(x)
+        })
+        .collect();
+    let mut s1 = xs.clone();
+    let mut s2: Vec<FGL> = xs.iter().map(|x| *x * ks[0]).collect();
+    let mut s3: Vec<FGL> = xs.iter().map(|x| *x * ks[1]).collect();
+
+    // b is a permutation of a, and c of b, by the even rows then the odd rows
+    for i in 0..n {
+        let j = if i % 2 == 0 { i / 2 } else { n / 2 + (i - 1) / 2 };
+        std::mem::swap(&mut s1[i], &mut s2[j]);
+        std::mem::swap(&mut s2[i], &mut s3[j]);
+    }
+    pols.column_mut("Connection.S1", 0)?.copy_from_slice(&s1);
+    pols.column_mut("Connection.S2", 0)?.copy_from_slice(&s2);
+    pols.column_mut("Connection.S3", 0)?.copy_from_slice(&s3);
+    Ok(pols.build())
+}
+
+fn execute(pil: &PIL) -> Result<PolsArray> {
+    let mut pols = TraceBuilder::new(pil, PolKind::Commit);
+    let n = pols.n();
+    let permute = |src: &[FGL]| -> Vec<FGL> {
+        (0..n).map(|i| if i < n / 2 { src[i * 2] } else { src[(i - n / 2) * 2 + 1] }).collect()
+    };
+    let a: Vec<FGL> = (0..n).map(|i| FGL::from(i as u64)).collect();
+    let b = permute(&a);
+    let c = permute(&b);
+    pols.column_mut("Connection.a", 0)?.copy_from_slice(&a);
+    pols.column_mut("Connection.b", 0)?.copy_from_slice(&b);
+    pols.column_mut("Connection.c", 0)?.copy_from_slice(&c);
+    Ok(pols.build())
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let (pil_file, workspace) = common::args("data/connection.pil.json");
+    let pil = load_json::<PIL>(&pil_file)?;
+    let const_pols = build_constants(&pil)?;
+    let cm_pols = execute(&pil)?;
+    let stark_struct = common::stark_struct("GL", &[11, 3]);
+    common::prove("connection", &pil_file, &workspace, &const_pols, &cm_pols, &stark_struct)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_trace() {
+        let pil = load_json::<PIL>("data/connection.pil.json").unwrap();
+        let mut const_pols = PolsArray::new(&pil, PolKind::Constant);
+        const_pols.load("data/connection.const").unwrap();
+        let mut cm_pols = PolsArray::new(&pil, PolKind::Commit);
+        cm_pols.load("data/connection.cm").unwrap();
+
+        assert_eq!(build_constants(&pil).unwrap().array, const_pols.array);
+        assert_eq!(execute(&pil).unwrap().array, cm_pols.array);
+    }
+}
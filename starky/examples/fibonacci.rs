@@ -0,0 +1,58 @@
+//! Port of `starkjs/fibonacci`.
+//!
+//! cargo run --release --example fibonacci -- data/fib.pil.json /tmp
+mod common;
+
+use anyhow::Result;
+use fields::field_gl::Fr as FGL;
+use starky::polsarray::{PolKind, PolsArray};
+use starky::trace_builder::TraceBuilder;
+use starky::types::{load_json, PIL};
+
+fn build_constants(pil: &PIL) -> Result<PolsArray> {
+    let mut pols = TraceBuilder::new(pil, PolKind::Constant);
+    let n = pols.n();
+    pols.fill("Fibonacci.L1", 0, |i| FGL::from((i == 0) as u64))?;
+    pols.fill("Fibonacci.LLAST", 0, |i| FGL::from((i == n - 1) as u64))?;
+    Ok(pols.build())
+}
+
+fn execute(pil: &PIL, input: [u64; 2]) -> Result<PolsArray> {
+    let mut pols = TraceBuilder::new(pil, PolKind::Commit);
+    pols.set("Fibonacci.l2", 0, 0, FGL::from(input[0]))?;
+    pols.set("Fibonacci.l1", 0, 0, FGL::from(input[1]))?;
+    for i in 1..pols.n() {
+        let l1 = pols.get("Fibonacci.l1", 0, i - 1)?;
+        let l2 = pols.get("Fibonacci.l2", 0, i - 1)?;
+        pols.set("Fibonacci.l2", 0, i, l1)?;
+        pols.set("Fibonacci.l1", 0, i, l1 * l1 + l2 * l2)?;
+    }
+    Ok(pols.build())
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let (pil_file, workspace) = common::args("data/fib.pil.json");
+    let pil = load_json::<PIL>(&pil_file)?;
+    let const_pols = build_constants(&pil)?;
+    let cm_pols = execute(&pil, [1, 2])?;
+    let stark_struct = common::stark_struct("BN128", &[11, 7, 3]);
+    common::prove("fib", &pil_file, &workspace, &const_pols, &cm_pols, &stark_struct)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fibonacci_trace() {
+        let pil = load_json::<PIL>("data/fib.pil.json").unwrap();
+        let mut const_pols = PolsArray::new(&pil, PolKind::Constant);
+        const_pols.load("data/fib.const").unwrap();
+        let mut cm_pols = PolsArray::new(&pil, PolKind::Commit);
+        cm_pols.load("data/fib.cm").unwrap();
+
+        assert_eq!(build_constants(&pil).unwrap().array, const_pols.array);
+        assert_eq!(execute(&pil, [1, 2]).unwrap().array, cm_pols.array);
+    }
+}
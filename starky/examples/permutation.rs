@@ -0,0 +1,65 @@
+//! Port of `starkjs/permutation`.
+//!
+//! cargo run --release --example permutation -- data/pe.pil.json /tmp
+mod common;
+
+use anyhow::Result;
+use fields::field_gl::Fr as FGL;
+use starky::polsarray::{PolKind, PolsArray};
+use starky::trace_builder::TraceBuilder;
+use starky::types::{load_json, PIL};
+
+fn build_constants(pil: &PIL) -> Result<PolsArray> {
+    let mut pols = TraceBuilder::new(pil, PolKind::Constant);
+    pols.fill("Global.L1", 0, |i| FGL::from((i == 0) as u64))?;
+    Ok(pols.build())
+}
+
+fn execute(pil: &PIL) -> Result<PolsArray> {
+    let mut pols = TraceBuilder::new(pil, PolKind::Commit);
+    let n = pols.n();
+    for i in 0..n {
+        let a = FGL::from((i * i + i + 1) as u64);
+        pols.set("Permutation.a", 0, i, a)?;
+        pols.set("Permutation.b", 0, n - i - 1, a)?;
+        if i % 2 == 0 {
+            pols.set("Permutation.selC", 0, i, FGL::ONE)?;
+            pols.set("Permutation.c", 0, i, a)?;
+            pols.set("Permutation.selD", 0, i / 2, FGL::ONE)?;
+            pols.set("Permutation.d", 0, i / 2, a)?;
+        } else {
+            pols.set("Permutation.selC", 0, i, FGL::ZERO)?;
+            pols.set("Permutation.c", 0, i, FGL::from(44))?;
+            pols.set("Permutation.selD", 0, n / 2 + (i - 1) / 2, FGL::ZERO)?;
+            pols.set("Permutation.d", 0, n / 2 + (i - 1) / 2, FGL::from(55))?;
+        }
+    }
+    Ok(pols.build())
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let (pil_file, workspace) = common::args("data/pe.pil.json");
+    let pil = load_json::<PIL>(&pil_file)?;
+    let const_pols = build_constants(&pil)?;
+    let cm_pols = execute(&pil)?;
+    let stark_struct = common::stark_struct("BN128", &[11, 3]);
+    common::prove("pe", &pil_file, &workspace, &const_pols, &cm_pols, &stark_struct)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permutation_trace() {
+        let pil = load_json::<PIL>("data/pe.pil.json").unwrap();
+        let mut const_pols = PolsArray::new(&pil, PolKind::Constant);
+        const_pols.load("data/pe.const").unwrap();
+        let mut cm_pols = PolsArray::new(&pil, PolKind::Commit);
+        cm_pols.load("data/pe.cm").unwrap();
+
+        assert_eq!(build_constants(&pil).unwrap().array, const_pols.array);
+        assert_eq!(execute(&pil).unwrap().array, cm_pols.array);
+    }
+}
@@ -0,0 +1,70 @@
+//! Port of `starkjs/plookup`.
+//!
+//! cargo run --release --example plookup -- data/plookup.pil.json /tmp
+mod common;
+
+use anyhow::Result;
+use fields::field_gl::Fr as FGL;
+use starky::polsarray::{PolKind, PolsArray};
+use starky::trace_builder::TraceBuilder;
+use starky::types::{load_json, PIL};
+
+fn build_constants(pil: &PIL) -> Result<PolsArray> {
+    let mut pols = TraceBuilder::new(pil, PolKind::Constant);
+    pols.fill("Global.L1", 0, |i| FGL::from((i == 0) as u64))?;
+    // the 16x16 multiplication table, then zeros
+    pols.fill("Plookup.A", 0, |p| FGL::from(if p < 256 { p as u64 / 16 } else { 0 }))?;
+    pols.fill("Plookup.B", 0, |p| FGL::from(if p < 256 { p as u64 % 16 } else { 0 }))?;
+    pols.fill("Plookup.SEL", 0, |p| FGL::from((p < 256) as u64))?;
+    Ok(pols.build())
+}
+
+fn execute(pil: &PIL) -> Result<PolsArray> {
+    let mut pols = TraceBuilder::new(pil, PolKind::Commit);
+    let n = pols.n();
+    pols.fill("Plookup.cc", 0, |p| {
+        FGL::from(if p < 256 { (p as u64 / 16) * (p as u64 % 16) } else { p as u64 })
+    })?;
+
+    for p in 0..10 {
+        pols.set("Plookup.sel", 0, p, FGL::ONE)?;
+        pols.set("Plookup.a", 0, p, FGL::from(p as u64))?;
+        pols.set("Plookup.b", 0, p, FGL::from(if p == 0 { 55 } else { p as u64 + 3 }))?;
+    }
+    pols.set("Plookup.sel", 0, 10, FGL::ZERO)?;
+    pols.set("Plookup.a", 0, 10, FGL::from(55))?;
+    pols.set("Plookup.b", 0, 10, FGL::from(10))?;
+    for p in 11..n {
+        pols.set("Plookup.sel", 0, p, FGL::ZERO)?;
+        pols.set("Plookup.a", 0, p, FGL::from(55))?;
+        pols.set("Plookup.b", 0, p, FGL::from(55))?;
+    }
+    Ok(pols.build())
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let (pil_file, workspace) = common::args("data/plookup.pil.json");
+    let pil = load_json::<PIL>(&pil_file)?;
+    let const_pols = build_constants(&pil)?;
+    let cm_pols = execute(&pil)?;
+    let stark_struct = common::stark_struct("GL", &[11, 7, 3]);
+    common::prove("plookup", &pil_file, &workspace, &const_pols, &cm_pols, &stark_struct)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plookup_trace() {
+        let pil = load_json::<PIL>("data/plookup.pil.json").unwrap();
+        let mut const_pols = PolsArray::new(&pil, PolKind::Constant);
+        const_pols.load("data/plookup.const").unwrap();
+        let mut cm_pols = PolsArray::new(&pil, PolKind::Commit);
+        cm_pols.load("data/plookup.cm").unwrap();
+
+        assert_eq!(build_constants(&pil).unwrap().array, const_pols.array);
+        assert_eq!(execute(&pil).unwrap().array, cm_pols.array);
+    }
+}
@@ -0,0 +1,260 @@
+//! Port of `starkjs/poseidon`, the Goldilocks Poseidon state machine of
+//! https://github.com/0xPolygonHermez/zkevm-proverjs/blob/v0.6.0.0/src/sm/sm_poseidong.js
+//!
+//! `data/poseidong.pil.json` is `starkjs/poseidon/poseidong.pil` compiled by `pil_compile`:
+//! cargo run --release --example poseidon -- data/poseidong.pil.json /tmp
+#![allow(clippy::needless_range_loop)]
+mod common;
+
+use anyhow::{bail, Result};
+use fields::field_gl::Fr as FGL;
+use starky::polsarray::{PolKind, PolsArray};
+use starky::poseidon_constants::CPOSEIDON;
+use starky::trace_builder::TraceBuilder;
+use starky::types::{load_json, PIL};
+use std::ops::Range;
+
+const T: usize = 12;
+const N_ROUNDS_F: usize = 8;
+const N_ROUNDS_P: usize = 22;
+/// The rounds of the partial S-box, only on the first element.
+const PARTIAL_ROUNDS: Range<usize> = N_ROUNDS_F / 2..N_ROUNDS_F / 2 + N_ROUNDS_P;
+/// Rows per hash, the input and a row per round.
+const N_ROWS: usize = N_ROUNDS_F + N_ROUNDS_P + 1;
+
+const MCIRC: [u64; T] = [17, 15, 41, 16, 2, 28, 13, 13, 39, 18, 34, 20];
+const MDIAG: [u64; T] = [8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+const STATE: [&str; T] = [
+    "PoseidonG.in0",
+    "PoseidonG.in1",
+    "PoseidonG.in2",
+    "PoseidonG.in3",
+    "PoseidonG.in4",
+    "PoseidonG.in5",
+    "PoseidonG.in6",
+    "PoseidonG.in7",
+    "PoseidonG.hashType",
+    "PoseidonG.cap1",
+    "PoseidonG.cap2",
+    "PoseidonG.cap3",
+];
+const HASH: [&str; 4] =
+    ["PoseidonG.hash0", "PoseidonG.hash1", "PoseidonG.hash2", "PoseidonG.hash3"];
+const RESULT: [&str; 3] = ["PoseidonG.result1", "PoseidonG.result2", "PoseidonG.result3"];
+
+fn pow7(a: FGL) -> FGL {
+    let a2 = a * a;
+    let a4 = a2 * a2;
+    a * a2 * a4
+}
+
+/// Applies round `r` to `state`.
+fn round(state: &[FGL; T], r: usize) -> [FGL; T] {
+    let mut s = [FGL::ZERO; T];
+    for i in 0..T {
+        s[i] = state[i] + FGL::from(CPOSEIDON[r * T + i]);
+    }
+    if !PARTIAL_ROUNDS.contains(&r) {
+        s.iter_mut().for_each(|a| *a = pow7(*a));
+    } else {
+        s[0] = pow7(s[0]);
+    }
+
+    let mut out = [FGL::ZERO; T];
+    for i in 0..T {
+        for j in 0..T {
+            let mut m = FGL::from(MCIRC[(T + j - i) % T]);
+            if i == j {
+                m = m + FGL::from(MDIAG[i]);
+            }
+            out[i] = out[i] + m * s[j];
+        }
+    }
+    out
+}
+
+fn build_constants(pil: &PIL) -> Result<PolsArray> {
+    let mut pols = TraceBuilder::new(pil, PolKind::Constant);
+    let n = pols.n();
+    let max_hashes = n / N_ROWS;
+    let flag = |b: bool| FGL::from(b as u64);
+
+    pols.fill("PoseidonG.LINPUT", 0, |i| flag(i == 0))?;
+    pols.fill("PoseidonG.LOUTPUT", 0, |i| flag(i == n - 1))?;
+    pols.fill("PoseidonG.LAST", 0, |i| flag(i == n - 1 || i % N_ROWS == N_ROWS - 1))?;
+    pols.fill("PoseidonG.LATCH", 0, |i| flag(i / N_ROWS < max_hashes && i % N_ROWS == 0))?;
+    pols.fill("PoseidonG.LASTBLOCK", 0, |i| flag(i % N_ROWS == N_ROWS - 1))?;
+    pols.fill("PoseidonG.PARTIAL", 0, |i| flag(PARTIAL_ROUNDS.contains(&(i % N_ROWS))))?;
+    for j in 0..T {
+        pols.fill("PoseidonG.C", j, |i| FGL::from(CPOSEIDON[T * (i % N_ROWS) + j]))?;
+    }
+    Ok(pols.build())
+}
+
+/// Each input is the 12 elements of the state, the expected 4 elements of the hash, and
+/// the result selector, 0 for none.
+fn execute(pil: &PIL, input: &[[u64; 17]]) -> Result<PolsArray> {
+    let mut pols = TraceBuilder::new(pil, PolKind::Commit);
+    let n = pols.n();
+    if input.len() > n / N_ROWS {
+        bail!("Not enough Poseidon slots");
+    }
+
+    let mut set_row = |p: usize, state: &[FGL; T], hash: &[FGL], result: u64| -> Result<()> {
+        for (name, v) in STATE.iter().zip(state) {
+            pols.set(name, 0, p, *v)?;
+        }
+        for (name, v) in HASH.iter().zip(hash) {
+            pols.set(name, 0, p, *v)?;
+        }
+        for (k, name) in RESULT.iter().enumerate() {
+            pols.set(name, 0, p, FGL::from((result == k as u64 + 1) as u64))?;
+        }
+        Ok(())
+    };
+
+    let mut p = 0;
+    for inp in input {
+        let mut state = [FGL::ZERO; T];
+        for i in 0..T {
+            state[i] = FGL::from(inp[i]);
+        }
+        let hash: Vec<FGL> = inp[T..T + 4].iter().map(|v| FGL::from(*v)).collect();
+        set_row(p, &state, &hash, inp[16])?;
+        p += 1;
+        for r in 0..N_ROUNDS_F + N_ROUNDS_P {
+            state = round(&state, r);
+            set_row(p, &state, &hash, 0)?;
+            p += 1;
+        }
+    }
+
+    // the unused slots hash the zero state
+    let mut st0 = vec![[FGL::ZERO; T]];
+    for r in 0..N_ROUNDS_F + N_ROUNDS_P {
+        st0.push(round(&st0[r], r));
+    }
+    let hash0 = st0[N_ROWS - 1][..4].to_vec();
+    while p < n {
+        set_row(p, &st0[p % N_ROWS], &hash0, 0)?;
+        p += 1;
+    }
+    Ok(pols.build())
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+    let (pil_file, workspace) = common::args("data/poseidong.pil.json");
+    let pil = load_json::<PIL>(&pil_file)?;
+    let const_pols = build_constants(&pil)?;
+    let input = [[
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0x3c18a9786cb0b359,
+        0xc4055e3364a246c3,
+        0x7953db0ab48808f4,
+        0xc71603f33a1144ca,
+        0,
+    ]];
+    let cm_pols = execute(&pil, &input)?;
+    let stark_struct = common::stark_struct("GL", &[11, 7, 3]);
+    common::prove("poseidon", &pil_file, &workspace, &const_pols, &cm_pols, &stark_struct)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: [[u64; 17]; 2] = [
+        [
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0x3c18a9786cb0b359,
+            0xc4055e3364a246c3,
+            0x7953db0ab48808f4,
+            0xc71603f33a1144ca,
+            0,
+        ],
+        [
+            0,
+            1,
+            2,
+            3,
+            4,
+            5,
+            6,
+            7,
+            8,
+            9,
+            10,
+            11,
+            0xd64e1e3efc5b8e9e,
+            0x53666633020aaa47,
+            0xd40285597c6a8825,
+            0x613a4f81e81231d2,
+            1,
+        ],
+    ];
+
+    #[test]
+    fn test_poseidon_trace() {
+        let pil = load_json::<PIL>("data/poseidong.pil.json").unwrap();
+        let const_pols = build_constants(&pil).unwrap();
+        assert_eq!(const_pols.array[4 + 3][N_ROWS + 2], FGL::from(CPOSEIDON[2 * T + 3]));
+
+        let cm_pols = execute(&pil, &INPUT).unwrap();
+        // the state after the last round is the expected hash
+        for (h, inp) in INPUT.iter().enumerate() {
+            let last = h * N_ROWS + N_ROWS - 1;
+            for k in 0..4 {
+                assert_eq!(cm_pols.array[k][last], FGL::from(inp[T + k]));
+                assert_eq!(cm_pols.array[T + k][last], FGL::from(inp[T + k]));
+            }
+        }
+        assert_eq!(cm_pols.array[T + 4][N_ROWS], FGL::ONE);
+        // the padding hashes the zero state
+        assert_eq!(cm_pols.array[T][1023], FGL::from(INPUT[0][T]));
+    }
+
+    #[test]
+    fn test_poseidon_prove() {
+        let pil_file = "data/poseidong.pil.json";
+        let pil = load_json::<PIL>(pil_file).unwrap();
+        let const_pols = build_constants(&pil).unwrap();
+        let cm_pols = execute(&pil, &INPUT).unwrap();
+
+        let workspace = std::env::temp_dir().join("poseidon_example");
+        std::fs::create_dir_all(&workspace).unwrap();
+        let stark_struct = common::stark_struct("GL", &[11, 7, 3]);
+        common::prove(
+            "poseidon",
+            pil_file,
+            workspace.to_str().unwrap(),
+            &const_pols,
+            &cm_pols,
+            &stark_struct,
+        )
+        .unwrap();
+    }
+}
@@ -18,17 +18,28 @@ use crate::arch::x86_64::avx2_poseidon_gl::{load_constants_avx2, ConstantsAvx2};
     target_feature = "avx512vl"
 ))]
 use crate::arch::x86_64::avx512_poseidon_gl::{load_constants_avx512, ConstantsAvx512};
+#[cfg(feature = "std")]
 use crate::field_bls12381::Fr as Fr_bls12381;
+#[cfg(feature = "std")]
 use crate::field_bn128::Fr as Fr_bn128;
+#[cfg(feature = "std")]
 use crate::poseidon_bls12381::load_constants as load_constants_bls12381;
+#[cfg(feature = "std")]
 use crate::poseidon_bls12381::Constants as ConstantsBls12381;
+#[cfg(feature = "std")]
 use crate::poseidon_bls12381_opt::load_constants as load_constants_bls12381_opt;
+#[cfg(feature = "std")]
 use crate::poseidon_bn128::{load_constants, Constants};
+#[cfg(feature = "std")]
 use crate::poseidon_bn128_opt::load_constants as load_constants_opt;
-use ff::*;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use fields::ff::*;
 use fields::field_gl::Fr;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
 
+#[cfg(feature = "std")]
 lazy_static::lazy_static! {
     pub static ref OFFSET_2_64: Fr_bn128 = Fr_bn128::from_str("18446744073709551616").unwrap();
     pub static ref OFFSET_2_128: Fr_bn128 = Fr_bn128::from_str("340282366920938463463374607431768211456").unwrap();
@@ -49,6 +60,21 @@ lazy_static::lazy_static! {
         m
     };
 
+    pub static ref POSEIDON_BN128_CONSTANTS_OPT: Constants = {
+        load_constants_opt()
+    };
+    pub static ref POSEIDON_BN128_CONSTANTS: Constants = {
+        load_constants()
+    };
+    pub static ref POSEIDON_BLS12381_CONSTANTS_OPT: ConstantsBls12381 = {
+        load_constants_bls12381_opt()
+    };
+    pub static ref POSEIDON_BLS12381_CONSTANTS: ConstantsBls12381 = {
+        load_constants_bls12381()
+    };
+}
+
+lazy_static::lazy_static! {
     pub static ref SHIFT: Fr = Fr::from(49u64);
     pub static ref SHIFT_INV: Fr = SHIFT.inverse().unwrap();
     pub static ref MG: (Vec<Fr>, Vec<Fr>) = {
@@ -67,18 +93,6 @@ lazy_static::lazy_static! {
         (w, wi)
     };
 
-    pub static ref POSEIDON_BN128_CONSTANTS_OPT: Constants = {
-        load_constants_opt()
-    };
-    pub static ref POSEIDON_BN128_CONSTANTS: Constants = {
-        load_constants()
-    };
-    pub static ref POSEIDON_BLS12381_CONSTANTS_OPT: ConstantsBls12381 = {
-        load_constants_bls12381_opt()
-    };
-    pub static ref POSEIDON_BLS12381_CONSTANTS: ConstantsBls12381 = {
-        load_constants_bls12381()
-    };
     pub static ref POSEIDON_CONSTANTS_OPT: crate::poseidon_opt::Constants = {
         crate::poseidon_opt::load_constants()
     };
@@ -113,10 +127,9 @@ lazy_static::lazy_static! {
     };
 }
 
-pub const MIN_OPS_PER_THREAD: usize = 1 << 12;
-pub const MAX_OPS_PER_THREAD: usize = 1 << 18;
 pub const GLOBAL_L1: &str = "Global.L1";
 
-pub fn get_max_workers() -> usize {
-    num_cpus::get() - 1
-}
+#[cfg(feature = "std")]
+pub use prover_config::{
+    get_max_workers, max_ops_per_thread, min_ops_per_thread, MAX_OPS_PER_THREAD, MIN_OPS_PER_THREAD,
+};
@@ -1,18 +1,24 @@
 #![allow(non_snake_case)]
+#[cfg(feature = "std")]
 use crate::field_bls12381::Fr as Fr_bls12381;
+#[cfg(feature = "std")]
 use crate::field_bls12381::FrRepr as FrRepr_bls12381;
+#[cfg(feature = "std")]
 use crate::field_bn128::{Fr, FrRepr};
+#[cfg(feature = "std")]
 use crate::helper;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::traits::MTNodeType;
-use ff::*;
+use core::any::TypeId;
+use core::fmt;
+use core::fmt::Display;
+use core::marker::PhantomData;
+use fields::ff::*;
 use fields::field_gl::Fr as FGL;
 use serde::de::{SeqAccess, Visitor};
 use serde::ser::SerializeSeq;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
-use std::any::TypeId;
-use std::fmt;
-use std::fmt::Display;
-use std::marker::PhantomData;
 
 /// the trait F is used to keep track of source data type, so we can implement its deserializer
 #[repr(C)]
@@ -66,7 +72,7 @@ impl<const N: usize, F: PrimeField + Default> MTNodeType for ElementDigest<N, F>
 }
 
 impl<const N: usize, F: PrimeField + Default> Display for ElementDigest<N, F> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for i in 0..N {
             writeln!(f, "{}", self.0[i].as_int())?;
         }
@@ -82,15 +88,17 @@ impl<const N: usize, F: PrimeField + Default> Default for ElementDigest<N, F> {
 }
 
 impl<const N: usize, F: PrimeField + Default> Serialize for ElementDigest<N, F> {
-    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
         let source = TypeId::of::<F>();
+        #[cfg(feature = "std")]
         if source == TypeId::of::<Fr>() {
             let r: Fr = Fr(self.as_scalar::<Fr>());
             return serializer.serialize_str(&helper::fr_to_biguint(&r).to_string());
         }
+        #[cfg(feature = "std")]
         if source == TypeId::of::<Fr_bls12381>() {
             let r: Fr_bls12381 = Fr_bls12381(self.as_scalar::<Fr_bls12381>());
             return serializer.serialize_str(&helper::fr_to_biguint(&r).to_string());
@@ -112,7 +120,7 @@ impl<const N: usize, F: PrimeField + Default> Serialize for ElementDigest<N, F>
 }
 
 impl<'de, const N: usize, F: PrimeField + Default> Deserialize<'de> for ElementDigest<N, F> {
-    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
@@ -125,31 +133,36 @@ impl<'de, const N: usize, F: PrimeField + Default> Deserialize<'de> for ElementD
                 formatter.write_str("struct ElementDigest")
             }
 
-            fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+            fn visit_seq<A>(self, mut seq: A) -> core::result::Result<Self::Value, A::Error>
             where
                 A: SeqAccess<'de>,
             {
                 let mut entries = Vec::new();
                 while let Some(entry) = seq.next_element::<String>()? {
-                    let entry: u64 = entry.parse().unwrap();
+                    let entry: u64 = entry.parse().map_err(de::Error::custom)?;
                     entries.push(FGL::from(entry));
                 }
+                if entries.len() < N {
+                    return Err(de::Error::invalid_length(entries.len(), &self));
+                }
                 Ok(ElementDigest::<N, F>::new(&entries))
             }
 
             // it could be one-dim GL, BN128, or BLS12381
-            fn visit_str<E>(self, s: &str) -> std::result::Result<Self::Value, E>
+            fn visit_str<E>(self, s: &str) -> core::result::Result<Self::Value, E>
             where
                 E: de::Error,
             {
                 let source = TypeId::of::<F>();
                 if source == TypeId::of::<FGL>() {
                     // one-dim GL elements
-                    let value = FGL::from_str(s).unwrap();
+                    let value = FGL::from_str(s)
+                        .ok_or_else(|| E::invalid_value(de::Unexpected::Str(s), &self))?;
                     Ok(ElementDigest::<N, F>::new(&[value, FGL::ZERO, FGL::ZERO, FGL::ZERO]))
                 } else {
                     // BN128 or BLS12381
-                    let t = F::from_str(s).unwrap();
+                    let t = F::from_str(s)
+                        .ok_or_else(|| E::invalid_value(de::Unexpected::Str(s), &self))?;
                     Ok(ElementDigest::<N, F>::from_scalar(&t))
                 }
             }
@@ -158,6 +171,7 @@ impl<'de, const N: usize, F: PrimeField + Default> Deserialize<'de> for ElementD
     }
 }
 
+#[cfg(feature = "std")]
 #[inline(always)]
 pub fn to_bn128(e: &[FGL; 4]) -> Fr {
     let mut buf: Vec<u8> = vec![0u8; 32];
@@ -173,6 +187,7 @@ pub fn to_bn128(e: &[FGL; 4]) -> Fr {
     Fr::from_repr(repr).unwrap()
 }
 
+#[cfg(feature = "std")]
 #[inline(always)]
 pub fn to_bls12381(e: &[FGL; 4]) -> Fr_bls12381 {
     let mut buf: Vec<u8> = vec![0u8; 32];
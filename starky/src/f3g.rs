@@ -1,10 +1,12 @@
 #![allow(dead_code)]
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::traits::FieldExtension;
+use core::hash::{Hash, Hasher};
 use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use core::slice;
 use fields::field_gl::Fr;
 use fields::Field;
-use std::hash::{Hash, Hasher};
-use std::slice;
 
 use core::fmt::{Display, Formatter};
 /// GF(2^3) implementation
@@ -68,7 +70,7 @@ impl FieldExtension for F3G {
     #[inline(always)]
     fn to_be(&self) -> Fr {
         assert_eq!(self.dim, 1);
-        self.as_elements()[0]
+        self.cube[0]
     }
 
     #[inline(always)]
@@ -234,6 +236,7 @@ impl F3G {
     }
 }
 
+#[cfg(feature = "std")]
 impl ::rand::Rand for F3G {
     fn rand<R: rand::Rng>(rng: &mut R) -> Self {
         Self::from(Fr::rand(rng))
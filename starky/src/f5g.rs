@@ -55,7 +55,7 @@ impl FieldExtension for F5G {
     #[inline(always)]
     fn to_be(&self) -> Fr {
         assert_eq!(self.dim, 1);
-        self.as_elements()[0]
+        self.cube[0]
     }
 
     #[inline(always)]
@@ -2,8 +2,9 @@
 
 use crate::constant::MG;
 use crate::helper::log2_any;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::traits::FieldExtension;
-use rayon::prelude::*;
 
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Default)]
@@ -78,7 +79,7 @@ impl<F: FieldExtension> FFT<F> {
         let mut res = vec![F::ZERO; q.len()];
 
         res[0] = q[0] * n2inv;
-        res[1..].par_iter_mut().enumerate().for_each(|(i, out)| *out = q[n - i - 1] * n2inv);
+        res[1..].iter_mut().enumerate().for_each(|(i, out)| *out = q[n - i - 1] * n2inv);
         res
     }
 }
@@ -1,68 +1,26 @@
 #![allow(dead_code, non_snake_case, clippy::ptr_arg)]
-use crate::constant::{get_max_workers, MAX_OPS_PER_THREAD, MIN_OPS_PER_THREAD, SHIFT};
+use crate::constant::{get_max_workers, max_ops_per_thread, min_ops_per_thread, SHIFT};
 use crate::fft_worker::{fft_block, interpolate_prepare_block};
 use crate::helper::log2_any;
+use crate::ntt_gl::get_tables;
 use crate::traits::FieldExtension;
 use core::cmp::min;
-use lazy_static::lazy_static;
 use rayon::prelude::*;
-use std::collections::HashMap;
-use std::sync::Mutex;
 
-lazy_static! {
-    static ref BR_CACHE: Mutex<HashMap<usize, Vec<usize>>> = Mutex::new(HashMap::new());
-}
 pub fn BR(x: usize, domain_pow: usize) -> usize {
     assert!(domain_pow <= 32);
-    let cal = |x: usize, domain_pow: usize| -> usize {
-        let mut x = x;
-        x = (x >> 16) | (x << 16);
-        x = ((x & 0xFF00FF00) >> 8) | ((x & 0x00FF00FF) << 8);
-        x = ((x & 0xF0F0F0F0) >> 4) | ((x & 0x0F0F0F0F) << 4);
-        x = ((x & 0xCCCCCCCC) >> 2) | ((x & 0x33333333) << 2);
-        (((x & 0xAAAAAAAA) >> 1) | ((x & 0x55555555) << 1)) >> (32 - domain_pow)
-    };
-
-    // get cache by domain_pow
-    let mut map = BR_CACHE.lock().unwrap();
-    let mut cache = if map.contains_key(&domain_pow) {
-        map.remove(&domain_pow).unwrap() // get and remove the old values.
-    } else {
-        vec![]
-    };
-    // check if need append more to cache
-    let cache_len = cache.len();
-    let n = 1 << domain_pow;
-    if cache_len <= n || cache_len < x {
-        let end = if n >= x { n } else { x };
-        // todo parallel
-        for i in cache_len..=end {
-            let a = cal(i, domain_pow);
-            cache.push(a);
-        }
-    }
-    let res = cache[x];
-    // update map with cache
-    map.insert(domain_pow, cache);
-    res
+    let mut x = x;
+    x = (x >> 16) | (x << 16);
+    x = ((x & 0xFF00FF00) >> 8) | ((x & 0x00FF00FF) << 8);
+    x = ((x & 0xF0F0F0F0) >> 4) | ((x & 0x0F0F0F0F) << 4);
+    x = ((x & 0xCCCCCCCC) >> 2) | ((x & 0x33333333) << 2);
+    (((x & 0xAAAAAAAA) >> 1) | ((x & 0x55555555) << 1)) >> (32 - domain_pow)
 }
+
 fn BRs(start: usize, end: usize, domain_pow: usize) -> Vec<usize> {
     assert!(end > start);
-    // 1. obtain a useless one to precompute the cache.
-    //      to make sure the cache existed and its len >= end.
-    BR(end, domain_pow);
-
-    // 2. get cache by domain_pow
-    let map = BR_CACHE.lock().unwrap();
-    let cache = if map.contains_key(&domain_pow) {
-        map.get(&domain_pow).unwrap()
-    } else {
-        // double check
-        BR(end, domain_pow);
-        map.get(&domain_pow).unwrap()
-    };
-
-    (start..end).map(|i| cache[i]).collect()
+    let tables = get_tables(domain_pow);
+    tables.bit_rev[start..end].iter().map(|r| *r as usize).collect()
 }
 
 pub fn transpose<F: FieldExtension>(
@@ -145,8 +103,8 @@ pub fn interpolate_prepare<F: FieldExtension>(buff: &mut Vec<F>, n_pols: usize,
     let n = 1 << nbits;
     let inv_n = F::inv(&F::from(n));
     let mut n_per_thread_f = (n - 1) / get_max_workers() + 1;
-    let max_corrected = MAX_OPS_PER_THREAD / n_pols;
-    let min_corrected = MIN_OPS_PER_THREAD / n_pols;
+    let max_corrected = max_ops_per_thread() / n_pols;
+    let min_corrected = min_ops_per_thread() / n_pols;
 
     if n_per_thread_f > max_corrected {
         n_per_thread_f = max_corrected
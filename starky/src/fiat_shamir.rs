@@ -0,0 +1,221 @@
+//! Transcript initialization, selected by `StarkStruct::fiatShamirVersion`.
+//!
+//! Version 0 is pil-stark's: the transcript starts from the publics. Version 1 first absorbs
+//! the digest of the circuit, i.e. the `StarkStruct`, the PIL and the `StarkInfo`, then the
+//! root of the constant polynomials, so the challenges are bound to the circuit being proven.
+//! The digest is of an explicit encoding, which adding a field to the structs doesn't change.
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::starkinfo::StarkInfo;
+use crate::traits::{MTNodeType, Transcript};
+use crate::transcript::TranscriptGL;
+use crate::types::{Expression, StarkStruct, PIL};
+use anyhow::{bail, Result};
+use fields::field_gl::Fr as FGL;
+
+pub const FIAT_SHAMIR_WEAK: usize = 0;
+pub const FIAT_SHAMIR_STRONG: usize = 1;
+
+pub fn check_version(stark_struct: &StarkStruct) -> Result<()> {
+    match stark_struct.fiatShamirVersion {
+        FIAT_SHAMIR_WEAK | FIAT_SHAMIR_STRONG => Ok(()),
+        v => bail!("Unsupported Fiat-Shamir version {}", v),
+    }
+}
+
+/// The canonical encoding of a circuit, a Goldilocks element a number. It changes with what is
+/// encoded only, not with the fields or the names of the structs holding it.
+#[derive(Default)]
+struct Encoder(Vec<FGL>);
+
+impl Encoder {
+    fn u64(&mut self, v: u64) {
+        self.0.push(FGL::from(v));
+    }
+
+    fn usize(&mut self, v: usize) {
+        self.u64(v as u64);
+    }
+
+    /// The length, then the bytes packed 7 per element.
+    fn str(&mut self, s: &str) {
+        self.usize(s.len());
+        for chunk in s.as_bytes().chunks(7) {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.u64(u64::from_le_bytes(word));
+        }
+    }
+
+    fn list<T>(&mut self, items: &[T], f: impl Fn(&mut Self, &T)) {
+        self.usize(items.len());
+        for item in items {
+            f(self, item);
+        }
+    }
+
+    /// 0 for None, 1 then the value otherwise.
+    fn opt<T>(&mut self, v: Option<&T>, f: impl FnOnce(&mut Self, &T)) {
+        self.usize(v.is_some() as usize);
+        if let Some(v) = v {
+            f(self, v);
+        }
+    }
+
+    fn ids(&mut self, ids: Option<&Vec<usize>>) {
+        self.opt(ids, |e, ids| e.list(ids, |e, id| e.usize(*id)));
+    }
+
+    /// The tree of an expression, without the degree and the annotations of pil_info.
+    fn expression(&mut self, exp: &Expression) {
+        self.str(&exp.op);
+        self.opt(exp.id.as_ref(), |e, id| e.usize(*id));
+        self.usize(exp.next() as usize);
+        self.opt(exp.value.as_ref(), |e, v| e.str(v));
+        self.opt(exp.values.as_ref(), |e, values| e.list(values, Self::expression));
+    }
+
+    fn lookup(
+        &mut self,
+        f: Option<&Vec<usize>>,
+        t: Option<&Vec<usize>>,
+        sel_f: Option<usize>,
+        sel_t: Option<usize>,
+    ) {
+        self.ids(f);
+        self.ids(t);
+        self.opt(sel_f.as_ref(), |e, s| e.usize(*s));
+        self.opt(sel_t.as_ref(), |e, s| e.usize(*s));
+    }
+
+    /// Poseidon digest of the elements, after their number.
+    fn digest(self) -> Result<[FGL; 4]> {
+        let mut elements = vec![FGL::from(self.0.len() as u64)];
+        elements.extend(self.0);
+        let mut sponge = TranscriptGL::new();
+        sponge.put(&[elements])?;
+        Ok([
+            sponge.get_fields1()?,
+            sponge.get_fields1()?,
+            sponge.get_fields1()?,
+            sponge.get_fields1()?,
+        ])
+    }
+}
+
+/// Poseidon digest of the constraints of a PIL: the publics, the expressions and the
+/// identities. The names, the files and lines, and what pil_info adds are left out.
+pub fn pil_hash(pil: &PIL) -> Result<[FGL; 4]> {
+    let mut e = Encoder::default();
+    e.usize(pil.nCommitments);
+    e.usize(pil.nConstants);
+    e.list(&pil.publics, |e, p| {
+        e.str(&p.polType);
+        e.usize(p.polId);
+        e.usize(p.idx);
+    });
+    e.list(&pil.expressions, Encoder::expression);
+    e.list(&pil.polIdentities, |e, pi| e.usize(pi.e));
+    e.list(&pil.plookupIdentities, |e, pi| {
+        e.lookup(pi.f.as_ref(), pi.t.as_ref(), pi.selF, pi.selT)
+    });
+    e.opt(pil.permutationIdentities.as_ref(), |e, pis| {
+        e.list(pis, |e, pi| e.lookup(pi.f.as_ref(), pi.t.as_ref(), pi.selF, pi.selT))
+    });
+    e.opt(pil.connectionIdentities.as_ref(), |e, cis| {
+        e.list(cis, |e, ci| {
+            e.ids(ci.pols.as_ref());
+            e.ids(ci.connections.as_ref());
+        })
+    });
+    e.digest()
+}
+
+/// Poseidon digest of the circuit: the `StarkStruct`, the hash of the PIL and the polynomials
+/// the `StarkInfo` commits, which the rest of it is computed from.
+pub fn circuit_digest(stark_struct: &StarkStruct, starkinfo: &StarkInfo) -> Result<[FGL; 4]> {
+    let mut e = Encoder::default();
+    e.usize(stark_struct.nBits);
+    e.usize(stark_struct.nBitsExt);
+    e.usize(stark_struct.nQueries);
+    e.str(&stark_struct.verificationHashType);
+    e.list(&stark_struct.steps, |e, step| e.usize(step.nBits));
+    e.usize(stark_struct.fiatShamirVersion);
+
+    for h in starkinfo.pil_hash {
+        e.u64(h);
+    }
+    for n in [
+        starkinfo.n_cm1,
+        starkinfo.n_cm2,
+        starkinfo.n_cm3,
+        starkinfo.n_cm4,
+        starkinfo.n_q,
+        starkinfo.n_constants,
+        starkinfo.n_publics,
+        starkinfo.q_deg,
+        starkinfo.q_dim,
+    ] {
+        e.usize(n);
+    }
+    e.list(&starkinfo.im_exps_list, |e, id| e.usize(*id));
+    e.digest()
+}
+
+/// Absorbs what the version binds before the publics, nothing for version 0.
+pub fn init_transcript<T: Transcript, N: MTNodeType>(
+    transcript: &mut T,
+    stark_struct: &StarkStruct,
+    starkinfo: &StarkInfo,
+    const_root: &N,
+) -> Result<()> {
+    check_version(stark_struct)?;
+    if stark_struct.fiatShamirVersion == FIAT_SHAMIR_STRONG {
+        let digest = circuit_digest(stark_struct, starkinfo)?;
+        let b = digest.iter().map(|e| vec![*e]).collect::<Vec<Vec<FGL>>>();
+        transcript.put(&b[..])?;
+        transcript.put(&[const_root.as_elements().to_vec()])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fiat_shamir::*;
+    use crate::starkinfo::{ImPolsSelection, StarkInfo};
+    use crate::types::{load_json, PIL};
+
+    #[test]
+    fn test_circuit_digest() {
+        let mut pil = load_json::<PIL>("data/fib.pil.json").unwrap();
+        let mut stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
+        let (starkinfo, _) =
+            StarkInfo::new(&mut pil, &stark_struct, None, ImPolsSelection::Greedy).unwrap();
+
+        let mut pil2 = load_json::<PIL>("data/fib.pil.json").unwrap();
+        let (starkinfo2, _) =
+            StarkInfo::new(&mut pil2, &stark_struct, None, ImPolsSelection::Greedy).unwrap();
+        let d = circuit_digest(&stark_struct, &starkinfo).unwrap();
+        assert_eq!(d, circuit_digest(&stark_struct, &starkinfo2).unwrap());
+
+        stark_struct.nQueries += 1;
+        assert_ne!(d, circuit_digest(&stark_struct, &starkinfo).unwrap());
+        stark_struct.nQueries -= 1;
+
+        // the PIL is bound by its constraints, not by its names or lines
+        let mut pil = load_json::<PIL>("data/fib.pil.json").unwrap();
+        let h = pil_hash(&pil).unwrap();
+        assert_eq!(starkinfo.pil_hash, h.map(|e| e.as_int()));
+        pil.polIdentities[0].line += 1;
+        pil.publics[0].name = String::from("renamed");
+        assert_eq!(pil_hash(&pil).unwrap(), h);
+        pil.polIdentities[0].e = pil.polIdentities[1].e;
+        assert_ne!(pil_hash(&pil).unwrap(), h);
+        let mut starkinfo = starkinfo2;
+        starkinfo.pil_hash[0] += 1;
+        assert_ne!(d, circuit_digest(&stark_struct, &starkinfo).unwrap());
+
+        stark_struct.fiatShamirVersion = 2;
+        assert!(check_version(&stark_struct).is_err());
+    }
+}
@@ -1,13 +1,21 @@
 #![allow(clippy::map_all_any_identity)]
 
-use crate::constant::{MG, SHIFT, SHIFT_INV};
+#[cfg(feature = "std")]
+use crate::constant::SHIFT_INV;
+use crate::constant::{MG, SHIFT};
 use crate::fft::FFT;
+#[cfg(feature = "std")]
 use crate::helper::log2_any;
-use crate::polutils::{eval_pol, pol_mul_axi};
+use crate::polutils::eval_pol;
+#[cfg(feature = "std")]
+use crate::polutils::pol_mul_axi;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::traits::{FieldExtension, MTNodeType, MerkleTree, Transcript};
 use crate::types::{StarkStruct, Step};
 use anyhow::{bail, Result};
 use fields::field_gl::Fr as FGL;
+#[cfg(feature = "std")]
 use profiler_macro::time_profiler;
 
 #[allow(clippy::upper_case_acronyms)]
@@ -80,6 +88,7 @@ impl FRI {
         }
     }
 
+    #[cfg(feature = "std")]
     #[time_profiler("fri_prove")]
     pub fn prove<F: FieldExtension, M: MerkleTree<ExtendField = F>, T: Transcript>(
         &mut self,
@@ -183,7 +192,7 @@ impl FRI {
         Ok(proof)
     }
 
-    #[time_profiler("fri_verify")]
+    #[cfg_attr(feature = "std", time_profiler("fri_verify"))]
     pub fn verify<F: FieldExtension, M: MerkleTree<ExtendField = F>, T: Transcript>(
         &self,
         transcript: &mut T,
@@ -296,6 +305,7 @@ impl FRI {
     }
 }
 
+#[cfg(feature = "std")]
 fn get_transposed_buffer<F: FieldExtension>(pol: &[F], transpose_bits: usize) -> Vec<FGL> {
     let n = pol.len();
     let w = 1 << transpose_bits;
@@ -1,10 +1,12 @@
 #![allow(dead_code)]
-use ff::*;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use core::fmt::Write;
+use core::ops::Mul;
+use fields::ff::*;
 use fields::field_gl::Fr as FGL;
 use num_bigint::BigUint;
 use num_traits::ToPrimitive;
-use std::fmt::Write;
-use std::ops::Mul;
 
 ///exports.getKs = function getKs(Fr, n) {
 ///    const ks = [Fr.k];
@@ -48,6 +50,7 @@ pub fn log2_any(val: usize) -> usize {
     }) | (if (val & 0xAAAAAAAA) != 0 { 1 } else { 0 })
 }
 
+#[cfg(feature = "std")]
 #[inline(always)]
 pub fn fr_to_biguint<F: PrimeField>(f: &F) -> BigUint {
     let repr = f.into_repr();
@@ -69,7 +72,7 @@ pub fn biguint_to_fr<F: PrimeField>(f: &BigUint) -> F {
     F::from_str(&f.to_string()).unwrap()
 }
 
-use std::fmt::{Debug, Display};
+use core::fmt::{Debug, Display};
 pub fn pretty_print_array<T: Debug + Display>(cols: &[T]) -> String {
     let mut msg = String::new();
     writeln!(&mut msg, "array size: {}", cols.len()).unwrap();
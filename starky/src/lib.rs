@@ -3,79 +3,150 @@
 #![allow(clippy::type_complexity)]
 #![allow(clippy::too_many_arguments)]
 #![cfg_attr(feature = "avx512", feature(stdarch_x86_avx512))]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+// Without `std`, only the GL verifier is built: `stark_verify` with `MerkleTreeGL` and
+// `TranscriptGL`, the `StarkProof` of the serializer and the `VerifierKey`.
+
+#[cfg(feature = "std")]
+pub mod pil_info;
+#[cfg(feature = "std")]
+pub mod pil_print;
+#[cfg(feature = "std")]
+pub mod pil_validate;
+#[cfg(feature = "std")]
+pub mod pols_format;
+#[cfg(feature = "std")]
 pub mod polsarray;
 pub mod polutils;
+#[cfg(feature = "std")]
 pub mod stark_verifier_circom;
+#[cfg(feature = "std")]
 pub mod stark_verifier_circom_onchain;
+#[cfg(feature = "std")]
+pub mod stark_verifier_rust;
+#[cfg(feature = "std")]
+pub mod trace_builder;
 pub mod traits;
 pub mod types;
+pub mod verifier_key;
 
 pub mod arch;
 
 pub mod linearhash;
+#[cfg(feature = "std")]
 pub mod linearhash_bls12381;
+#[cfg(feature = "std")]
 pub mod linearhash_bn128;
 
+#[cfg(feature = "std")]
 pub mod field_bn128;
+#[cfg(feature = "std")]
 mod poseidon_bn128;
+#[cfg(feature = "std")]
 mod poseidon_bn128_constants;
+#[cfg(feature = "std")]
 mod poseidon_bn128_constants_opt;
+#[cfg(feature = "std")]
 pub mod poseidon_bn128_opt;
+pub mod poseidon_constants;
 mod poseidon_constants_avx2;
 mod poseidon_constants_avx512;
 mod poseidon_constants_opt;
 pub mod poseidon_opt;
 
+#[cfg(feature = "std")]
 mod field_bls12381;
+#[cfg(feature = "std")]
 mod poseidon_bls12381;
+#[cfg(feature = "std")]
 mod poseidon_bls12381_constants;
+#[cfg(feature = "std")]
 mod poseidon_bls12381_constants_opt;
+#[cfg(feature = "std")]
 pub mod poseidon_bls12381_opt;
 
 pub mod merklehash;
+#[cfg(feature = "std")]
 pub mod merklehash_bls12381;
 
+#[cfg(feature = "std")]
 pub mod merklehash_bn128;
 
 mod digest;
 pub use digest::ElementDigest;
 
 pub mod constant;
+#[cfg(feature = "std")]
 mod expressionops;
 pub mod f3g;
+#[cfg(feature = "std")]
 pub mod f5g;
 pub mod fft;
+#[cfg(feature = "std")]
 pub mod fft_p;
+#[cfg(feature = "std")]
 mod fft_worker;
+pub mod fiat_shamir;
 mod fri;
 pub mod helper;
+#[cfg(feature = "std")]
 mod interpreter;
+#[cfg(feature = "std")]
+pub mod ntt_gl;
 pub mod stark_gen;
+#[cfg(feature = "std")]
 pub mod stark_setup;
 pub mod stark_verify;
 pub mod starkinfo;
+#[cfg(feature = "std")]
 mod starkinfo_Z;
-mod starkinfo_codegen;
+pub mod starkinfo_codegen;
+#[cfg(feature = "std")]
 mod starkinfo_cp_prover;
+#[cfg(feature = "std")]
 mod starkinfo_cp_ver;
+#[cfg(feature = "std")]
 mod starkinfo_fri_prover;
+#[cfg(feature = "std")]
 mod starkinfo_fri_ver;
+#[cfg(feature = "std")]
 mod starkinfo_map;
 pub mod transcript;
+#[cfg(feature = "std")]
 pub mod transcript_bls12381;
+#[cfg(feature = "std")]
 pub mod transcript_bn128;
 
+#[cfg(feature = "std")]
 pub mod pil2circom;
+#[cfg(feature = "std")]
 pub mod prove;
+#[cfg(feature = "std")]
+pub mod prover_config;
 pub mod serializer;
+#[cfg(feature = "std")]
 pub mod zkin_join;
 
+#[cfg(feature = "std")]
 pub mod dev;
 
+#[cfg(not(feature = "std"))]
+mod prelude {
+    pub use alloc::borrow::ToOwned;
+    pub use alloc::boxed::Box;
+    pub use alloc::format;
+    pub use alloc::string::{String, ToString};
+    pub use alloc::vec;
+    pub use alloc::vec::Vec;
+    pub use hashbrown::HashMap;
+}
+
 #[macro_use]
 extern crate serde_json;
 
+extern crate alloc;
+#[cfg(feature = "std")]
 extern crate ff;
 extern crate lazy_static;
 extern crate log;
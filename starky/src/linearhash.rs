@@ -27,11 +27,15 @@ use crate::arch::x86_64::avx512_poseidon_gl::Poseidon;
     target_feature = "avx512vl"
 )))]
 use crate::poseidon_opt::Poseidon;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::traits::MTNodeType;
 use crate::ElementDigest;
 use anyhow::Result;
 use fields::field_gl::Fr as FGL;
+#[cfg(feature = "std")]
 use profiler_macro::time_profiler;
+#[cfg(feature = "std")]
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -45,6 +49,7 @@ impl LinearHash {
         LinearHash { h: Poseidon::new() }
     }
 
+    #[cfg(feature = "std")]
     #[time_profiler()]
     #[cfg(not(any(
         target_feature = "avx512bw",
@@ -144,6 +149,7 @@ impl LinearHash {
         Ok(ElementDigest::<4, FGL>::new(&st))
     }
 
+    #[cfg(feature = "std")]
     #[cfg(all(
         target_feature = "avx512bw",
         target_feature = "avx512cd",
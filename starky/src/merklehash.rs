@@ -11,7 +11,8 @@
     ))
 ))]
 use crate::arch::x86_64::avx2_poseidon_gl::Poseidon;
-use crate::constant::{get_max_workers, MAX_OPS_PER_THREAD, MIN_OPS_PER_THREAD};
+#[cfg(feature = "std")]
+use crate::constant::{get_max_workers, max_ops_per_thread, min_ops_per_thread};
 use crate::digest::ElementDigest;
 use crate::f3g::F3G;
 use crate::linearhash::LinearHash;
@@ -26,12 +27,16 @@ use crate::linearhash::LinearHash;
     )
 ))]
 use crate::poseidon_opt::Poseidon;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::traits::MTNodeType;
 use crate::traits::MerkleTree;
 use anyhow::{bail, Result};
 use fields::field_gl::Fr as FGL;
+#[cfg(feature = "std")]
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use std::time::Instant;
 
 #[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
@@ -75,11 +80,12 @@ impl MerkleTreeGL {
         result
     }
 
+    #[cfg(feature = "std")]
     #[inline]
     fn merklize_level(&mut self, p_in: usize, n_ops: usize, p_out: usize) -> Result<()> {
         let mut n_ops_per_thread = (n_ops - 1) / (get_max_workers() * 2) + 1;
-        if n_ops_per_thread < MIN_OPS_PER_THREAD {
-            n_ops_per_thread = MIN_OPS_PER_THREAD;
+        if n_ops_per_thread < min_ops_per_thread() {
+            n_ops_per_thread = min_ops_per_thread();
         }
 
         let buff = &self.nodes[p_in..(p_in + n_ops * 2)];
@@ -100,6 +106,7 @@ impl MerkleTreeGL {
         Ok(())
     }
 
+    #[cfg(feature = "std")]
     #[cfg(not(any(
         target_feature = "avx512bw",
         target_feature = "avx512cd",
@@ -133,6 +140,7 @@ impl MerkleTreeGL {
         Ok(buff_out64)
     }
 
+    #[cfg(feature = "std")]
     #[cfg(all(
         target_feature = "avx512bw",
         target_feature = "avx512cd",
@@ -191,6 +199,9 @@ impl MerkleTreeGL {
         if mp.len() == offset {
             return Ok(*value);
         }
+        if mp[offset].len() < 4 {
+            bail!("MerkleTreeError: sibling {} has {} elements", offset, mp[offset].len());
+        }
         let cur_idx = idx & 1;
         let next_idx = idx / 2;
         let init = [FGL::ZERO; 4];
@@ -267,6 +278,7 @@ impl MerkleTree for MerkleTreeGL {
         self.elements.len()
     }
 
+    #[cfg(feature = "std")]
     fn to_extend(&self, p_be: &mut Vec<F3G>) {
         assert_eq!(p_be.len(), self.elements.len());
         p_be.par_iter_mut().zip(&self.elements).for_each(|(be_out, f3g_in)| {
@@ -283,6 +295,7 @@ impl MerkleTree for MerkleTreeGL {
         Self::MTNode::new(&[*node, FGL::ZERO, FGL::ZERO, FGL::ZERO])
     }
 
+    #[cfg(feature = "std")]
     #[cfg(not(any(
         target_feature = "avx512bw",
         target_feature = "avx512cd",
@@ -296,8 +309,8 @@ impl MerkleTree for MerkleTreeGL {
         let mut n_per_thread_f = (height - 1) / max_workers + 1;
 
         let div = core::cmp::max(width / 8, 1);
-        let max_corrected = MAX_OPS_PER_THREAD / div;
-        let min_corrected = MIN_OPS_PER_THREAD / div;
+        let max_corrected = max_ops_per_thread() / div;
+        let min_corrected = min_ops_per_thread() / div;
 
         if n_per_thread_f > max_corrected {
             n_per_thread_f = max_corrected;
@@ -345,6 +358,7 @@ impl MerkleTree for MerkleTreeGL {
         Ok(())
     }
 
+    #[cfg(feature = "std")]
     #[cfg(all(
         target_feature = "avx512bw",
         target_feature = "avx512cd",
@@ -358,8 +372,8 @@ impl MerkleTree for MerkleTreeGL {
         let mut n_per_thread_f = (height - 1) / max_workers + 1;
 
         let div = core::cmp::max(width / 8, 1);
-        let max_corrected = MAX_OPS_PER_THREAD / div;
-        let min_corrected = MIN_OPS_PER_THREAD / div;
+        let max_corrected = max_ops_per_thread() / div;
+        let min_corrected = min_ops_per_thread() / div;
 
         if n_per_thread_f > max_corrected {
             n_per_thread_f = max_corrected;
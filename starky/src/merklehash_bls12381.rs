@@ -1,5 +1,5 @@
 #![allow(dead_code)]
-use crate::constant::{get_max_workers, MAX_OPS_PER_THREAD, MIN_OPS_PER_THREAD};
+use crate::constant::{get_max_workers, max_ops_per_thread, min_ops_per_thread};
 use crate::digest::ElementDigest;
 use crate::f3g::F3G;
 use crate::field_bls12381::Fr;
@@ -44,8 +44,8 @@ impl MerkleTreeBLS12381 {
     #[inline]
     pub fn merklize_level(&mut self, p_in: usize, n_ops: usize, p_out: usize) -> Result<()> {
         let mut n_ops_per_thread = (n_ops - 1) / (get_max_workers() * 16) + 1;
-        if n_ops_per_thread < MIN_OPS_PER_THREAD {
-            n_ops_per_thread = MIN_OPS_PER_THREAD;
+        if n_ops_per_thread < min_ops_per_thread() {
+            n_ops_per_thread = min_ops_per_thread();
         }
 
         let buff = &self.nodes[p_in..(p_in + n_ops * 16)];
@@ -179,13 +179,13 @@ impl MerkleTree for MerkleTreeBLS12381 {
         let mut n_per_thread_f = (height - 1) / max_workers + 1;
         let mut min_pt = 0;
         if width > 1 {
-            min_pt = MIN_OPS_PER_THREAD / ((width - 1) / (3 * 16) + 1);
+            min_pt = min_ops_per_thread() / ((width - 1) / (3 * 16) + 1);
         }
         if n_per_thread_f < min_pt {
             n_per_thread_f = min_pt;
         }
-        if n_per_thread_f > MAX_OPS_PER_THREAD {
-            n_per_thread_f = MAX_OPS_PER_THREAD;
+        if n_per_thread_f > max_ops_per_thread() {
+            n_per_thread_f = max_ops_per_thread();
         }
         let mut nodes = vec![ElementDigest::<4, Fr>::default(); get_n_nodes(height)];
         let now = Instant::now();
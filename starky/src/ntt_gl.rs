@@ -0,0 +1,485 @@
+//! Goldilocks NTT engine.
+//!
+//! Buffers are row-major base field matrices, `buff[row * n_pols + col]`, the same layout as
+//! `fft_p`. Twiddles and the bit-reverse permutation are computed once per domain size and
+//! cached. Butterflies are applied to whole rows, so on AVX2/AVX512 builds the columns of a row
+//! are processed with the packed Goldilocks types from `fields::arch`.
+//!
+//! The packed kernels multiply the raw (Montgomery) representation of the data by the canonical
+//! representation of the twiddle, which yields the Montgomery representation of the product.
+//! The packed add/sub/mul keep their results canonical, so the output can be read back as `FGL`.
+use crate::constant::{MG, SHIFT};
+use fields::field_gl::{Fr as FGL, FrRepr};
+#[cfg(target_feature = "avx2")]
+use fields::packed::PackedField;
+use fields::{Field, PrimeField};
+use lazy_static::lazy_static;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+#[cfg(all(
+    target_feature = "avx2",
+    not(all(
+        target_feature = "avx512bw",
+        target_feature = "avx512cd",
+        target_feature = "avx512dq",
+        target_feature = "avx512f",
+        target_feature = "avx512vl"
+    ))
+))]
+type Packed = fields::arch::x86_64::avx2_field_gl::Avx2GoldilocksField;
+
+#[cfg(all(
+    target_feature = "avx512bw",
+    target_feature = "avx512cd",
+    target_feature = "avx512dq",
+    target_feature = "avx512f",
+    target_feature = "avx512vl"
+))]
+type Packed = fields::arch::x86_64::avx512_field_gl::Avx512GoldilocksField;
+
+/// Number of rows handled by one task when computing powers.
+const POWERS_CHUNK: usize = 1 << 12;
+
+lazy_static! {
+    static ref NTT_TABLES: RwLock<HashMap<usize, Arc<NttTables>>> = RwLock::new(HashMap::new());
+}
+
+/// A twiddle factor, kept in Montgomery form for the scalar kernel and in canonical form for
+/// the packed kernel.
+#[derive(Clone, Copy, Debug)]
+pub struct Twiddle {
+    pub mont: FGL,
+    pub canon: FrRepr,
+}
+
+impl From<FGL> for Twiddle {
+    fn from(w: FGL) -> Self {
+        Twiddle { mont: w, canon: w.into_repr() }
+    }
+}
+
+/// Precomputed tables for the domain of size `2^nbits`.
+pub struct NttTables {
+    pub nbits: usize,
+    /// `w^i` for `i < 2^(nbits-1)`, where `w = MG.0[nbits]`.
+    pub twiddles: Vec<Twiddle>,
+    /// `i` with its `nbits` low bits reversed.
+    pub bit_rev: Vec<u32>,
+}
+
+impl NttTables {
+    pub fn new(nbits: usize) -> Self {
+        assert!(nbits <= 32);
+        let n = 1usize << nbits;
+        let w = MG.0[nbits];
+        let mut twiddles = vec![Twiddle::from(FGL::ONE); n >> 1];
+        twiddles.par_chunks_mut(POWERS_CHUNK).enumerate().for_each(|(c, out)| {
+            let mut acc = w.exp((c * POWERS_CHUNK) as u64);
+            for t in out.iter_mut() {
+                *t = Twiddle::from(acc);
+                acc = acc * w;
+            }
+        });
+        let bit_rev = (0..n).into_par_iter().map(|i| reverse_bits(i, nbits) as u32).collect();
+        NttTables { nbits, twiddles, bit_rev }
+    }
+}
+
+#[inline(always)]
+fn reverse_bits(i: usize, nbits: usize) -> usize {
+    if nbits == 0 {
+        return 0;
+    }
+    ((i as u32).reverse_bits() >> (32 - nbits)) as usize
+}
+
+/// Returns the cached tables for `2^nbits`, computing them on first use.
+pub fn get_tables(nbits: usize) -> Arc<NttTables> {
+    if let Some(tables) = NTT_TABLES.read().unwrap().get(&nbits) {
+        return tables.clone();
+    }
+    let tables = Arc::new(NttTables::new(nbits));
+    NTT_TABLES.write().unwrap().entry(nbits).or_insert(tables).clone()
+}
+
+/// Forward NTT of `n_pols` columns of size `2^nbits`.
+pub fn ntt(buffsrc: &[FGL], n_pols: usize, nbits: usize, buffdst: &mut [FGL]) {
+    let n = 1 << nbits;
+    assert_eq!(buffsrc.len(), n * n_pols);
+    assert_eq!(buffdst.len(), n * n_pols);
+    if n_pols == 0 {
+        return;
+    }
+    let tables = get_tables(nbits);
+    bit_reverse_rows(buffdst, slice_rows(buffsrc, n_pols), n_pols, &tables, false);
+    dit_layers(buffdst, n_pols, &tables, 0);
+}
+
+/// Inverse NTT of `n_pols` columns of size `2^nbits`.
+pub fn intt(buffsrc: &[FGL], n_pols: usize, nbits: usize, buffdst: &mut [FGL]) {
+    let n = 1 << nbits;
+    assert_eq!(buffsrc.len(), n * n_pols);
+    assert_eq!(buffdst.len(), n * n_pols);
+    if n_pols == 0 {
+        return;
+    }
+    let tables = get_tables(nbits);
+    bit_reverse_rows(buffdst, slice_rows(buffsrc, n_pols), n_pols, &tables, true);
+    dit_layers(buffdst, n_pols, &tables, 0);
+}
+
+/// Low degree extension: evaluates the polynomials interpolating the `2^nbits` rows of
+/// `buffsrc` on the coset `SHIFT * <w>` of size `2^nbits_ext`.
+///
+/// The coefficients are scaled by the coset powers in place and scattered, zero padded and
+/// bit-reversed, straight into `buffdst`. The first `nbits_ext - nbits` layers of the forward
+/// transform only see zeros in their odd inputs, so they are replaced by a copy.
+pub fn coset_lde(
+    buffsrc: &[FGL],
+    n_pols: usize,
+    nbits: usize,
+    buffdst: &mut [FGL],
+    nbits_ext: usize,
+) {
+    assert_eq!(buffsrc.len(), (1 << nbits) * n_pols);
+    coset_lde_rows(slice_rows(buffsrc, n_pols), n_pols, nbits, buffdst, nbits_ext);
+}
+
+/// `coset_lde` of a source read row by row, `read_row(r, out)` writes the `n_pols` columns of
+/// row `r` to `out`, so the trace is never copied into a buffer of its own.
+pub fn coset_lde_rows(
+    read_row: impl Fn(usize, &mut [FGL]) + Sync,
+    n_pols: usize,
+    nbits: usize,
+    buffdst: &mut [FGL],
+    nbits_ext: usize,
+) {
+    assert!(nbits_ext >= nbits);
+    let n = 1 << nbits;
+    let n_ext = 1 << nbits_ext;
+    assert_eq!(buffdst.len(), n_ext * n_pols);
+    if n_pols == 0 {
+        return;
+    }
+
+    let tables = get_tables(nbits);
+    let mut coefs = vec![FGL::ZERO; n * n_pols];
+    bit_reverse_rows(&mut coefs, read_row, n_pols, &tables, true);
+    dit_layers(&mut coefs, n_pols, &tables, 0);
+    scale_by_powers(&mut coefs, n_pols, *SHIFT);
+
+    let ext_bits = nbits_ext - nbits;
+    buffdst.par_chunks_mut(n_pols << ext_bits).enumerate().for_each(|(b, blk)| {
+        let r = tables.bit_rev[b] as usize;
+        let row = &coefs[r * n_pols..(r + 1) * n_pols];
+        for out in blk.chunks_mut(n_pols) {
+            out.copy_from_slice(row);
+        }
+    });
+
+    let tables_ext = get_tables(nbits_ext);
+    dit_layers(buffdst, n_pols, &tables_ext, ext_bits);
+}
+
+/// Reads row `bit_rev[i]` of the source into row `i` of `dst`. The inverse variant reads row
+/// `(n - bit_rev[i]) % n` and scales by `1/n`.
+fn bit_reverse_rows(
+    dst: &mut [FGL],
+    read_row: impl Fn(usize, &mut [FGL]) + Sync,
+    n_pols: usize,
+    tables: &NttTables,
+    inverse: bool,
+) {
+    let n = 1 << tables.nbits;
+    if inverse {
+        let n_inv = Twiddle::from(FGL::from(n as u64).inverse().unwrap());
+        dst.par_chunks_mut(n_pols).enumerate().for_each(|(i, out)| {
+            read_row((n - tables.bit_rev[i] as usize) % n, out);
+            scale_row(out, &n_inv);
+        });
+    } else {
+        dst.par_chunks_mut(n_pols).enumerate().for_each(|(i, out)| {
+            read_row(tables.bit_rev[i] as usize, out);
+        });
+    }
+}
+
+/// Reads row `r` of a row-major buffer.
+fn slice_rows(src: &[FGL], n_pols: usize) -> impl Fn(usize, &mut [FGL]) + Sync + '_ {
+    move |r, out| out.copy_from_slice(&src[r * n_pols..(r + 1) * n_pols])
+}
+
+/// Multiplies row `i` by `base^i`.
+fn scale_by_powers(buff: &mut [FGL], n_pols: usize, base: FGL) {
+    buff.par_chunks_mut(POWERS_CHUNK * n_pols).enumerate().for_each(|(c, blk)| {
+        let mut acc = base.exp((c * POWERS_CHUNK) as u64);
+        for row in blk.chunks_mut(n_pols) {
+            scale_row(row, &Twiddle::from(acc));
+            acc = acc * base;
+        }
+    });
+}
+
+/// Runs the decimation-in-time layers of a transform over a bit-reversed buffer, starting at the
+/// layer whose butterflies span `2^start_layer` rows.
+fn dit_layers(buff: &mut [FGL], n_pols: usize, tables: &NttTables, start_layer: usize) {
+    let nbits = tables.nbits;
+    let mut log_h = start_layer;
+    while log_h < nbits {
+        if nbits - log_h >= 2 {
+            radix4_layer(buff, n_pols, log_h, tables);
+            log_h += 2;
+        } else {
+            radix2_layer(buff, n_pols, log_h, tables);
+            log_h += 1;
+        }
+    }
+}
+
+/// Two radix-2 layers, with half sizes `h` and `2h`, fused so that each group of four rows is
+/// loaded once.
+fn radix4_layer(buff: &mut [FGL], n_pols: usize, log_h: usize, tables: &NttTables) {
+    let h = 1 << log_h;
+    // w_{2h}^j = w_n^{j * n / 2h}, w_{4h}^j = w_n^{j * n / 4h}
+    let s1 = tables.nbits - log_h - 1;
+    let s2 = tables.nbits - log_h - 2;
+    let tw = &tables.twiddles;
+    let block = 4 * h * n_pols;
+
+    let run_block = |blk: &mut [FGL], parallel: bool| {
+        let (q0, rest) = blk.split_at_mut(h * n_pols);
+        let (q1, rest) = rest.split_at_mut(h * n_pols);
+        let (q2, q3) = rest.split_at_mut(h * n_pols);
+        let kernel = |j: usize, r0: &mut [FGL], r1: &mut [FGL], r2: &mut [FGL], r3: &mut [FGL]| {
+            radix4_rows(r0, r1, r2, r3, &tw[j << s1], &tw[j << s2], &tw[(j + h) << s2]);
+        };
+        if parallel {
+            q0.par_chunks_mut(n_pols)
+                .zip(q1.par_chunks_mut(n_pols))
+                .zip(q2.par_chunks_mut(n_pols))
+                .zip(q3.par_chunks_mut(n_pols))
+                .enumerate()
+                .for_each(|(j, (((r0, r1), r2), r3))| kernel(j, r0, r1, r2, r3));
+        } else {
+            q0.chunks_mut(n_pols)
+                .zip(q1.chunks_mut(n_pols))
+                .zip(q2.chunks_mut(n_pols))
+                .zip(q3.chunks_mut(n_pols))
+                .enumerate()
+                .for_each(|(j, (((r0, r1), r2), r3))| kernel(j, r0, r1, r2, r3));
+        }
+    };
+
+    if buff.len() / block >= rayon::current_num_threads() {
+        buff.par_chunks_mut(block).for_each(|blk| run_block(blk, false));
+    } else {
+        buff.chunks_mut(block).for_each(|blk| run_block(blk, true));
+    }
+}
+
+/// A single radix-2 layer with half size `h`, used for the last layer of odd sized domains.
+fn radix2_layer(buff: &mut [FGL], n_pols: usize, log_h: usize, tables: &NttTables) {
+    let h = 1 << log_h;
+    let s1 = tables.nbits - log_h - 1;
+    let tw = &tables.twiddles;
+    let block = 2 * h * n_pols;
+
+    let run_block = |blk: &mut [FGL], parallel: bool| {
+        let (q0, q1) = blk.split_at_mut(h * n_pols);
+        if parallel {
+            q0.par_chunks_mut(n_pols)
+                .zip(q1.par_chunks_mut(n_pols))
+                .enumerate()
+                .for_each(|(j, (r0, r1))| butterfly_rows(r0, r1, &tw[j << s1]));
+        } else {
+            q0.chunks_mut(n_pols)
+                .zip(q1.chunks_mut(n_pols))
+                .enumerate()
+                .for_each(|(j, (r0, r1))| butterfly_rows(r0, r1, &tw[j << s1]));
+        }
+    };
+
+    if buff.len() / block >= rayon::current_num_threads() {
+        buff.par_chunks_mut(block).for_each(|blk| run_block(blk, false));
+    } else {
+        buff.chunks_mut(block).for_each(|blk| run_block(blk, true));
+    }
+}
+
+#[inline(always)]
+fn butterfly(x: &mut FGL, y: &mut FGL, w: &FGL) {
+    let t = *y * *w;
+    *y = *x - t;
+    *x = *x + t;
+}
+
+#[cfg(target_feature = "avx2")]
+#[inline(always)]
+fn butterfly_packed(x: &mut Packed, y: &mut Packed, w: FrRepr) {
+    let t = *y * w;
+    *y = *x - t;
+    *x += t;
+}
+
+/// Views a row as packed vectors.
+#[cfg(target_feature = "avx2")]
+#[inline(always)]
+fn pack_row(row: &mut [FGL]) -> &mut [Packed] {
+    // Safety: `FGL` is `repr(transparent)` over `FrRepr`.
+    let raw = unsafe { std::slice::from_raw_parts_mut(row.as_mut_ptr() as *mut FrRepr, row.len()) };
+    Packed::pack_slice_mut(raw)
+}
+
+/// `(r0, r1) <- (r0 + w * r1, r0 - w * r1)`
+#[inline(always)]
+fn butterfly_rows(r0: &mut [FGL], r1: &mut [FGL], w: &Twiddle) {
+    #[cfg(target_feature = "avx2")]
+    let (r0, r1) = {
+        let split = r0.len() - r0.len() % Packed::WIDTH;
+        let (p0, t0) = r0.split_at_mut(split);
+        let (p1, t1) = r1.split_at_mut(split);
+        for (x, y) in pack_row(p0).iter_mut().zip(pack_row(p1).iter_mut()) {
+            butterfly_packed(x, y, w.canon);
+        }
+        (t0, t1)
+    };
+    for (x, y) in r0.iter_mut().zip(r1.iter_mut()) {
+        butterfly(x, y, &w.mont);
+    }
+}
+
+/// Radix-2 layer `h` on the pairs `(r0, r1)` and `(r2, r3)` with `w1 = w_{2h}^j`, followed by
+/// layer `2h` on `(r0, r2)` with `w2 = w_{4h}^j` and on `(r1, r3)` with `w3 = w_{4h}^{j+h}`.
+#[inline(always)]
+fn radix4_rows(
+    r0: &mut [FGL],
+    r1: &mut [FGL],
+    r2: &mut [FGL],
+    r3: &mut [FGL],
+    w1: &Twiddle,
+    w2: &Twiddle,
+    w3: &Twiddle,
+) {
+    #[cfg(target_feature = "avx2")]
+    let (r0, r1, r2, r3) = {
+        let split = r0.len() - r0.len() % Packed::WIDTH;
+        let (p0, t0) = r0.split_at_mut(split);
+        let (p1, t1) = r1.split_at_mut(split);
+        let (p2, t2) = r2.split_at_mut(split);
+        let (p3, t3) = r3.split_at_mut(split);
+        for (((x0, x1), x2), x3) in pack_row(p0)
+            .iter_mut()
+            .zip(pack_row(p1).iter_mut())
+            .zip(pack_row(p2).iter_mut())
+            .zip(pack_row(p3).iter_mut())
+        {
+            butterfly_packed(x0, x1, w1.canon);
+            butterfly_packed(x2, x3, w1.canon);
+            butterfly_packed(x0, x2, w2.canon);
+            butterfly_packed(x1, x3, w3.canon);
+        }
+        (t0, t1, t2, t3)
+    };
+    for (((x0, x1), x2), x3) in
+        r0.iter_mut().zip(r1.iter_mut()).zip(r2.iter_mut()).zip(r3.iter_mut())
+    {
+        butterfly(x0, x1, &w1.mont);
+        butterfly(x2, x3, &w1.mont);
+        butterfly(x0, x2, &w2.mont);
+        butterfly(x1, x3, &w3.mont);
+    }
+}
+
+#[inline(always)]
+fn scale_row(row: &mut [FGL], w: &Twiddle) {
+    #[cfg(target_feature = "avx2")]
+    let row = {
+        let split = row.len() - row.len() % Packed::WIDTH;
+        let (p, t) = row.split_at_mut(split);
+        for x in pack_row(p).iter_mut() {
+            *x *= w.canon;
+        }
+        t
+    };
+    for x in row.iter_mut() {
+        *x = *x * w.mont;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::f3g::F3G;
+    use crate::fft::FFT;
+    use crate::ntt_gl::{coset_lde, intt, ntt, reverse_bits};
+    use crate::polutils::extend_pol;
+    use fields::field_gl::Fr as FGL;
+    use rand::Rand;
+
+    fn rand_columns(n: usize, n_pols: usize) -> Vec<FGL> {
+        let mut rng = ::rand::thread_rng();
+        (0..n * n_pols).map(|_| FGL::rand(&mut rng)).collect()
+    }
+
+    fn column(buff: &[FGL], n_pols: usize, k: usize) -> Vec<F3G> {
+        buff.iter().skip(k).step_by(n_pols).map(|e| F3G::from(*e)).collect()
+    }
+
+    #[test]
+    fn test_reverse_bits() {
+        assert_eq!(reverse_bits(10, 2), 1);
+        assert_eq!(reverse_bits(10, 11), 640);
+        assert_eq!(reverse_bits(0, 0), 0);
+    }
+
+    #[test]
+    fn test_ntt_matches_fft() {
+        let mut sfft = FFT::new();
+        for nbits in 0..9 {
+            for n_pols in [1, 3, 4, 9] {
+                let n = 1 << nbits;
+                let buff = rand_columns(n, n_pols);
+                let mut out = vec![FGL::ZERO; n * n_pols];
+                ntt(&buff, n_pols, nbits, &mut out);
+                let mut inv = vec![FGL::ZERO; n * n_pols];
+                intt(&buff, n_pols, nbits, &mut inv);
+                for k in 0..n_pols {
+                    let col = column(&buff, n_pols, k);
+                    assert_eq!(column(&out, n_pols, k), sfft.fft(&col));
+                    if n > 1 {
+                        assert_eq!(column(&inv, n_pols, k), sfft.ifft(&col));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_intt_roundtrip() {
+        let nbits = 13;
+        let n_pols = 7;
+        let n = 1 << nbits;
+        let buff = rand_columns(n, n_pols);
+        let mut evals = vec![FGL::ZERO; n * n_pols];
+        let mut coefs = vec![FGL::ZERO; n * n_pols];
+        ntt(&buff, n_pols, nbits, &mut evals);
+        intt(&evals, n_pols, nbits, &mut coefs);
+        assert_eq!(buff, coefs);
+    }
+
+    #[test]
+    fn test_coset_lde() {
+        for (nbits, extbits) in [(1, 1), (4, 1), (5, 2), (8, 3), (10, 0)] {
+            let n_pols = 5;
+            let n = 1 << nbits;
+            let buff = rand_columns(n, n_pols);
+            let mut out = vec![FGL::ZERO; (n << extbits) * n_pols];
+            coset_lde(&buff, n_pols, nbits, &mut out, nbits + extbits);
+            for k in 0..n_pols {
+                let expected = extend_pol(&column(&buff, n_pols, k), extbits);
+                assert_eq!(column(&out, n_pols, k), expected);
+            }
+        }
+    }
+}
@@ -26,6 +26,7 @@ pub fn pil2circom<F: ff::PrimeField + Default>(
     program: &mut Program,
     options: &StarkOption,
 ) -> Result<String> {
+    crate::fiat_shamir::check_version(stark_struct)?;
     starkinfo.set_code_dimensions_first(&mut program.verifier_code)?;
     starkinfo.set_code_dimensions_first(&mut program.verifier_query_code)?;
     let res = match stark_struct.verificationHashType.as_str() {
@@ -36,7 +37,7 @@ pub fn pil2circom<F: ff::PrimeField + Default>(
             stark_struct,
             const_root,
             options,
-        ),
+        )?,
         "BN128" | "BLS12381" => crate::stark_verifier_circom_onchain::render(
             starkinfo,
             program,
@@ -44,7 +45,7 @@ pub fn pil2circom<F: ff::PrimeField + Default>(
             stark_struct,
             const_root,
             options,
-        ),
+        )?,
         _ => panic!("Invalid hash type: {}", stark_struct.verificationHashType),
     };
     Ok(res)
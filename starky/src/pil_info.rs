@@ -0,0 +1,306 @@
+#![allow(non_snake_case)]
+use crate::f3g::F3G;
+use crate::pil_validate::{exp_degree, validate};
+use crate::starkinfo::{ImPolsSelection, StarkInfo};
+use crate::types::{load_json, StarkStruct, PIL};
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Bytes of a Merkle node, 4 Goldilocks elements or one BN128/BLS12-381 scalar.
+const NODE_SIZE: usize = 32;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct IdentityDegree {
+    /// pol, plookup, permutation or connection
+    pub kind: String,
+    pub fileName: String,
+    pub line: usize,
+    /// Degree of the polynomial identity, or the max degree of the expressions of the others.
+    pub degree: i32,
+}
+
+/// What a proof of a PIL costs, computed from the `StarkInfo` before proving.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PilInfo {
+    pub nBits: usize,
+    pub nBitsExt: usize,
+    pub nQueries: usize,
+    pub verificationHashType: String,
+    pub n_constants: usize,
+    pub n_publics: usize,
+    pub n_cm1: usize,
+    pub n_cm2: usize,
+    pub n_cm3: usize,
+    pub n_cm4: usize,
+    pub q_deg: usize,
+    pub q_dim: usize,
+    pub im_exps: Vec<usize>,
+    pub n_evals: usize,
+    /// Bytes of the roots, evaluations, queries and last FRI polynomial of a proof.
+    pub proof_size: usize,
+    /// Bytes of the main buffers and Merkle trees of `stark_gen`, a lower bound of its peak.
+    pub peak_memory: usize,
+    pub identities: Vec<IdentityDegree>,
+}
+
+impl PilInfo {
+    pub fn new(pil: &PIL, stark_struct: &StarkStruct, im_pols: ImPolsSelection) -> Result<Self> {
+        let (starkinfo, _) = StarkInfo::new(&mut pil.clone(), stark_struct, None, im_pols)?;
+        // after StarkInfo::new checked the expressions have no cycle
+        let identities = identity_degrees(pil);
+
+        Ok(PilInfo {
+            nBits: stark_struct.nBits,
+            nBitsExt: stark_struct.nBitsExt,
+            nQueries: stark_struct.nQueries,
+            verificationHashType: stark_struct.verificationHashType.clone(),
+            n_constants: starkinfo.n_constants,
+            n_publics: starkinfo.n_publics,
+            n_cm1: starkinfo.n_cm1,
+            n_cm2: starkinfo.n_cm2,
+            n_cm3: starkinfo.n_cm3,
+            n_cm4: starkinfo.n_cm4,
+            q_deg: starkinfo.q_deg,
+            q_dim: starkinfo.q_dim,
+            im_exps: starkinfo.im_exps_list.clone(),
+            n_evals: starkinfo.ev_map.len(),
+            proof_size: proof_size(&starkinfo, stark_struct)?,
+            peak_memory: peak_memory(&starkinfo, stark_struct),
+            identities,
+        })
+    }
+
+    pub fn write_table<W: Write>(&self, w: &mut W) -> Result<()> {
+        let rows = [
+            ("nBits", self.nBits.to_string()),
+            ("nBitsExt", self.nBitsExt.to_string()),
+            ("nQueries", self.nQueries.to_string()),
+            ("verificationHashType", self.verificationHashType.clone()),
+            ("n_constants", self.n_constants.to_string()),
+            ("n_publics", self.n_publics.to_string()),
+            ("n_cm1", self.n_cm1.to_string()),
+            ("n_cm2", self.n_cm2.to_string()),
+            ("n_cm3", self.n_cm3.to_string()),
+            ("n_cm4", self.n_cm4.to_string()),
+            ("q_deg", self.q_deg.to_string()),
+            ("q_dim", self.q_dim.to_string()),
+            ("im_exps", format!("{:?}", self.im_exps)),
+            ("n_evals", self.n_evals.to_string()),
+            ("proof_size", format!("{} bytes", self.proof_size)),
+            ("peak_memory", format!("{} bytes", self.peak_memory)),
+        ];
+        for (k, v) in rows.iter() {
+            writeln!(w, "{:<22}{}", k, v)?;
+        }
+        writeln!(w)?;
+        writeln!(w, "{:<14}{:<40}degree", "identity", "file:line")?;
+        for id in self.identities.iter() {
+            writeln!(
+                w,
+                "{:<14}{:<40}{}",
+                id.kind,
+                format!("{}:{}", id.fileName, id.line),
+                id.degree
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn identity_degrees(pil: &PIL) -> Vec<IdentityDegree> {
+    let mut degrees = HashMap::new();
+    let mut max_degree = |ids: &[Option<&Vec<usize>>], sels: &[Option<usize>]| {
+        ids.iter()
+            .flat_map(|v| v.cloned().unwrap_or_default())
+            .chain(sels.iter().flatten().copied())
+            .map(|e| exp_degree(pil, &pil.expressions[e], &mut degrees))
+            .max()
+            .unwrap_or(0)
+    };
+    let identity = |kind: &str, fileName: &String, line: usize, degree: i32| IdentityDegree {
+        kind: kind.to_string(),
+        fileName: fileName.clone(),
+        line,
+        degree,
+    };
+
+    let mut identities = vec![];
+    for pi in pil.polIdentities.iter() {
+        let degree = max_degree(&[], &[Some(pi.e)]);
+        identities.push(identity("pol", &pi.fileName, pi.line, degree));
+    }
+    for pi in pil.plookupIdentities.iter() {
+        let degree = max_degree(&[pi.f.as_ref(), pi.t.as_ref()], &[pi.selF, pi.selT]);
+        identities.push(identity("plookup", &pi.fileName, pi.line, degree));
+    }
+    for pi in pil.permutationIdentities.iter().flatten() {
+        let degree = max_degree(&[pi.f.as_ref(), pi.t.as_ref()], &[pi.selF, pi.selT]);
+        identities.push(identity("permutation", &pi.fileName, pi.line, degree));
+    }
+    for ci in pil.connectionIdentities.iter().flatten() {
+        let degree = max_degree(&[ci.pols.as_ref(), ci.connections.as_ref()], &[]);
+        identities.push(identity("connection", &ci.fileName, ci.line, degree));
+    }
+    identities
+}
+
+/// Bytes of a Merkle proof of a leaf of a tree of `2^n_bits` leaves.
+fn merkle_path_size(hash_type: &str, n_bits: usize) -> Result<usize> {
+    match hash_type {
+        // a sibling per level
+        "GL" => Ok(n_bits * NODE_SIZE),
+        // the 16 nodes of a level
+        "BN128" | "BLS12381" => Ok(n_bits.div_ceil(4) * 16 * NODE_SIZE),
+        _ => bail!("Invalid hash type {}", hash_type),
+    }
+}
+
+fn proof_size(starkinfo: &StarkInfo, stark_struct: &StarkStruct) -> Result<usize> {
+    let hash_type = stark_struct.verificationHashType.as_str();
+    let steps = &stark_struct.steps;
+    let ext = 3 * 8;
+
+    // root1-4, rootC and the roots of the FRI steps
+    let mut size = (5 + steps.len() - 1) * NODE_SIZE;
+    size += starkinfo.ev_map.len() * ext + starkinfo.n_publics * 8;
+    size += (1 << steps[steps.len() - 1].nBits) * ext;
+
+    let s = &starkinfo.map_sectionsN;
+    let widths = [s.cm1_n, s.cm2_n, s.cm3_n, s.cm4_n, starkinfo.n_constants];
+    let path = merkle_path_size(hash_type, stark_struct.nBitsExt)?;
+    let mut query = widths.iter().map(|w| w * 8 + path).sum::<usize>();
+    for si in 1..steps.len() {
+        let group_size = 3 << (steps[si - 1].nBits - steps[si].nBits);
+        query += group_size * 8 + merkle_path_size(hash_type, steps[si].nBits)?;
+    }
+    Ok(size + stark_struct.nQueries * query)
+}
+
+fn peak_memory(starkinfo: &StarkInfo, stark_struct: &StarkStruct) -> usize {
+    let n = 1 << stark_struct.nBits;
+    let next = 1 << stark_struct.nBitsExt;
+    let ext = std::mem::size_of::<F3G>();
+    let s = &starkinfo.map_sectionsN;
+
+    let buffers_n = (s.cm1_n + s.cm2_n + s.cm3_n + s.tmpexp_n + 1) * n;
+    // cm1-4, const, q, f, x and xDivXSubXi/xDivXSubWXi over the extended domain
+    let width_2ns = s.cm1_n + s.cm2_n + s.cm3_n + s.cm4_n + starkinfo.n_constants;
+    let buffers_2ns = (width_2ns + starkinfo.q_dim + 3 + 1 + 2) * next;
+    // the leaves of the 5 trees, and about 2 nodes per row of the extended domain in each
+    let trees = width_2ns * next * 8 + 5 * 2 * next * NODE_SIZE;
+    (buffers_n + buffers_2ns) * ext + trees
+}
+
+/// Prints the `PilInfo` of a PIL as a table, or as JSON.
+pub fn pil_info<W: Write>(
+    pil_file: &str,
+    stark_struct: &str,
+    im_pols: ImPolsSelection,
+    json: bool,
+    mut writer: W,
+) -> Result<()> {
+    let pil = load_json::<PIL>(pil_file)?;
+    let stark_struct = load_json::<StarkStruct>(stark_struct)?;
+    for w in validate(&pil).into_result()? {
+        log::warn!("{}", w);
+    }
+    let info = PilInfo::new(&pil, &stark_struct, im_pols)?;
+    if json {
+        serde_json::to_writer_pretty(&mut writer, &info)?;
+        writeln!(writer)?;
+    } else {
+        info.write_table(&mut writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merklehash::MerkleTreeGL;
+    use crate::pil_info::*;
+    use crate::polsarray::{PolKind, PolsArray};
+    use crate::prover_config::ProverConfig;
+    use crate::stark_gen::StarkProof;
+    use crate::stark_setup::StarkSetup;
+    use crate::transcript::TranscriptGL;
+    use serde_json::Value;
+
+    /// The Goldilocks elements of a serialized GL proof, roots and siblings 4 each.
+    fn elements(v: &Value) -> usize {
+        match v {
+            Value::Array(a) => a.iter().map(elements).sum(),
+            Value::Object(m) => m.values().map(elements).sum(),
+            _ => 1,
+        }
+    }
+
+    #[test]
+    fn test_proof_size() {
+        let pil = load_json::<PIL>("data/fib.pil.json.gl").unwrap();
+        let stark_struct = load_json::<StarkStruct>("data/starkStruct.json.gl").unwrap();
+        let info = PilInfo::new(&pil, &stark_struct, ImPolsSelection::Greedy).unwrap();
+
+        let mut pil = pil;
+        let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
+        const_pol.load("data/fib.const.gl").unwrap();
+        let mut cm_pol = PolsArray::new(&pil, PolKind::Commit);
+        cm_pol.load("data/fib.cm.gl").unwrap();
+        let config = ProverConfig::default();
+        let setup =
+            StarkSetup::<MerkleTreeGL>::new(&const_pol, &mut pil, &stark_struct, None, &config)
+                .unwrap();
+        let proof = StarkProof::<MerkleTreeGL>::stark_gen::<TranscriptGL>(
+            cm_pol,
+            const_pol,
+            &setup.const_tree,
+            &setup.starkinfo,
+            &setup.program,
+            &pil,
+            &stark_struct,
+            "",
+        )
+        .unwrap();
+        let proof = serde_json::to_value(&proof).unwrap();
+        assert_eq!(info.proof_size, elements(&proof) * 8);
+    }
+
+    #[test]
+    fn test_pil_info() {
+        let pil = load_json::<PIL>("data/plookup.pil.json").unwrap();
+        let stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
+        let info = PilInfo::new(&pil, &stark_struct, ImPolsSelection::Greedy).unwrap();
+
+        let mut pil2 = pil.clone();
+        let (starkinfo, _) =
+            StarkInfo::new(&mut pil2, &stark_struct, None, ImPolsSelection::Greedy).unwrap();
+        assert_eq!(info.n_cm1, starkinfo.n_cm1);
+        assert_eq!(info.q_deg, starkinfo.q_deg);
+        assert_eq!(info.n_evals, starkinfo.ev_map.len());
+        assert_eq!(
+            info.identities.len(),
+            pil.polIdentities.len()
+                + pil.plookupIdentities.len()
+                + pil.permutationIdentities.as_ref().map_or(0, |p| p.len())
+                + pil.connectionIdentities.as_ref().map_or(0, |c| c.len())
+        );
+        assert!(info.identities.iter().any(|id| id.kind == "plookup"));
+        assert!(info.proof_size > 0 && info.peak_memory > info.proof_size);
+
+        let mut table = vec![];
+        info.write_table(&mut table).unwrap();
+        assert!(String::from_utf8(table).unwrap().contains("q_deg"));
+        let mut json = vec![];
+        pil_info(
+            "data/plookup.pil.json",
+            "data/starkStruct.json",
+            ImPolsSelection::Greedy,
+            true,
+            &mut json,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["q_deg"], info.q_deg);
+    }
+}
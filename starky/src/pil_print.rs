@@ -0,0 +1,440 @@
+#![allow(non_snake_case)]
+//! Renders a compiled `PIL` back to PIL source, e.g. to read or diff the output of
+//! compressor12 or of `recursion::pilcom::export`.
+//!
+//! Columns and intermediates take their names from `references`, qualified when used out of
+//! their namespace. An unnamed expression is inlined, unless it is shared or shifted, then it
+//! is declared as the intermediate `__e<id>`. Identities are annotated with their source line.
+use crate::types::{load_json, Expression, PIL};
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Write;
+
+const GLOBAL: &str = "Global";
+
+/// Binding strength of the rendered operators.
+const PREC_ADD: usize = 1;
+const PREC_MUL: usize = 2;
+const PREC_NEG: usize = 3;
+const PREC_ATOM: usize = 4;
+
+#[derive(Default)]
+struct Namespace {
+    pol_deg: usize,
+    constants: Vec<String>,
+    commits: Vec<String>,
+    declarations: Vec<String>,
+    identities: Vec<String>,
+}
+
+struct Printer<'a> {
+    pil: &'a PIL,
+    /// (cm or const, id) to (namespace, name)
+    columns: HashMap<(&'static str, usize), (String, String)>,
+    /// expression id of the named intermediates to (namespace, name)
+    ims: HashMap<usize, (String, String)>,
+    /// unnamed expressions declared as `__e<id>`
+    shared: BTreeSet<usize>,
+}
+
+fn split_name(name: &str) -> (String, String) {
+    match name.split_once('.') {
+        Some((ns, n)) => (ns.to_string(), n.to_string()),
+        None => (GLOBAL.to_string(), name.to_string()),
+    }
+}
+
+fn paren(s: (String, usize), prec: usize) -> String {
+    if s.1 < prec {
+        format!("({})", s.0)
+    } else {
+        s.0
+    }
+}
+
+impl<'a> Printer<'a> {
+    fn new(pil: &'a PIL) -> Self {
+        let mut columns = HashMap::new();
+        let mut ims = HashMap::new();
+        for (name, r) in pil.references.iter() {
+            let (ns, n) = split_name(name);
+            let len = if r.isArray { r.len.unwrap_or(0) } else { 1 };
+            for i in 0..len {
+                let n = if r.isArray { format!("{}[{}]", n, i) } else { n.clone() };
+                match r.type_.as_str() {
+                    "cmP" => columns.insert(("cm", r.id + i), (ns.clone(), n)),
+                    "constP" => columns.insert(("const", r.id + i), (ns.clone(), n)),
+                    _ => ims.insert(r.id + i, (ns.clone(), n)),
+                };
+            }
+        }
+        let mut printer = Printer { pil, columns, ims, shared: BTreeSet::new() };
+        printer.find_shared();
+        printer
+    }
+
+    /// Expression ids rendered from the identities, the publics and the named intermediates.
+    fn roots(&self) -> Vec<usize> {
+        let pil = self.pil;
+        let mut roots: Vec<usize> = pil.polIdentities.iter().map(|pi| pi.e).collect();
+        for pi in pil.plookupIdentities.iter() {
+            roots.extend(pi.f.iter().chain(pi.t.iter()).flatten());
+            roots.extend(pi.selF.iter().chain(pi.selT.iter()));
+        }
+        for pi in pil.permutationIdentities.iter().flatten() {
+            roots.extend(pi.f.iter().chain(pi.t.iter()).flatten());
+            roots.extend(pi.selF.iter().chain(pi.selT.iter()));
+        }
+        for ci in pil.connectionIdentities.iter().flatten() {
+            roots.extend(ci.pols.iter().chain(ci.connections.iter()).flatten());
+        }
+        let mut ims: Vec<usize> = self.ims.keys().copied().collect();
+        ims.sort();
+        roots.extend(ims);
+        roots
+    }
+
+    fn find_shared(&mut self) {
+        fn count(
+            pil: &PIL,
+            e: &Expression,
+            refs: &mut HashMap<usize, usize>,
+            shifted: &mut BTreeSet<usize>,
+        ) {
+            if e.op.as_str() == "exp" {
+                let id = e.id.unwrap();
+                if e.next() {
+                    shifted.insert(id);
+                }
+                *refs.entry(id).or_insert(0) += 1;
+                if refs[&id] == 1 {
+                    count(pil, &pil.expressions[id], refs, shifted);
+                }
+            } else {
+                for v in e.values.iter().flatten() {
+                    count(pil, v, refs, shifted);
+                }
+            }
+        }
+
+        let mut refs = HashMap::new();
+        let mut shifted = BTreeSet::new();
+        for id in self.roots() {
+            count(self.pil, &self.pil.expressions[id], &mut refs, &mut shifted);
+        }
+        self.shared = refs
+            .into_iter()
+            .filter(|(id, n)| *n > 1 || shifted.contains(id))
+            .map(|(id, _)| id)
+            .filter(|id| !self.ims.contains_key(id))
+            .collect();
+    }
+
+    /// Namespace of the first column of an expression.
+    fn namespace_of(&self, e: &Expression) -> Option<String> {
+        match e.op.as_str() {
+            "cm" | "const" => {
+                let kind = if e.op.as_str() == "cm" { "cm" } else { "const" };
+                self.columns.get(&(kind, e.id.unwrap())).map(|(ns, _)| ns.clone())
+            }
+            "exp" => {
+                let id = e.id.unwrap();
+                match self.ims.get(&id) {
+                    Some((ns, _)) => Some(ns.clone()),
+                    None => self.namespace_of(&self.pil.expressions[id]),
+                }
+            }
+            _ => e.values.iter().flatten().find_map(|v| self.namespace_of(v)),
+        }
+    }
+
+    fn name(&self, named: Option<&(String, String)>, fallback: String, ns: &str) -> String {
+        match named {
+            Some((n_ns, n)) if n_ns == ns => n.clone(),
+            Some((n_ns, n)) => format!("{}.{}", n_ns, n),
+            None => fallback,
+        }
+    }
+
+    fn render(&self, e: &Expression, ns: &str) -> (String, usize) {
+        let values = e.values.as_deref().unwrap_or_default();
+        let next = if e.next() { "'" } else { "" };
+        match e.op.as_str() {
+            "add" => (
+                format!(
+                    "{} + {}",
+                    paren(self.render(&values[0], ns), PREC_ADD),
+                    paren(self.render(&values[1], ns), PREC_ADD)
+                ),
+                PREC_ADD,
+            ),
+            "sub" => (
+                format!(
+                    "{} - {}",
+                    paren(self.render(&values[0], ns), PREC_ADD),
+                    paren(self.render(&values[1], ns), PREC_ADD + 1)
+                ),
+                PREC_ADD,
+            ),
+            "mul" => (
+                format!(
+                    "{} * {}",
+                    paren(self.render(&values[0], ns), PREC_MUL),
+                    paren(self.render(&values[1], ns), PREC_MUL)
+                ),
+                PREC_MUL,
+            ),
+            "muladd" => (
+                format!(
+                    "{} * {} + {}",
+                    paren(self.render(&values[0], ns), PREC_MUL),
+                    paren(self.render(&values[1], ns), PREC_MUL),
+                    paren(self.render(&values[2], ns), PREC_ADD)
+                ),
+                PREC_ADD,
+            ),
+            "addc" => (
+                format!(
+                    "{} + {}",
+                    paren(self.render(&values[0], ns), PREC_ADD),
+                    e.value.clone().unwrap_or_default()
+                ),
+                PREC_ADD,
+            ),
+            "mulc" => (
+                format!(
+                    "{} * {}",
+                    paren(self.render(&values[0], ns), PREC_MUL),
+                    e.value.clone().unwrap_or_default()
+                ),
+                PREC_MUL,
+            ),
+            "neg" => (format!("-{}", paren(self.render(&values[0], ns), PREC_NEG)), PREC_NEG),
+            "cm" | "const" => {
+                let kind = if e.op.as_str() == "cm" { "cm" } else { "const" };
+                let id = e.id.unwrap();
+                let fallback = format!("{}{}", kind, id);
+                (
+                    format!("{}{}", self.name(self.columns.get(&(kind, id)), fallback, ns), next),
+                    PREC_ATOM,
+                )
+            }
+            "exp" => {
+                let id = e.id.unwrap();
+                if self.ims.contains_key(&id) || self.shared.contains(&id) {
+                    let fallback = format!("__e{}", id);
+                    (format!("{}{}", self.name(self.ims.get(&id), fallback, ns), next), PREC_ATOM)
+                } else {
+                    self.render(&self.pil.expressions[id], ns)
+                }
+            }
+            "number" => (e.value.clone().unwrap_or_default(), PREC_ATOM),
+            "public" => (format!(":{}", self.pil.publics[e.id.unwrap()].name), PREC_ATOM),
+            op => {
+                (format!("{}{}", op, e.id.map(|id| id.to_string()).unwrap_or_default()), PREC_ATOM)
+            }
+        }
+    }
+
+    fn render_list(&self, ids: &Option<Vec<usize>>, ns: &str) -> String {
+        let items: Vec<String> =
+            ids.iter().flatten().map(|id| self.render(&self.pil.expressions[*id], ns).0).collect();
+        format!("{{ {} }}", items.join(", "))
+    }
+
+    fn render_sel(&self, sel: Option<usize>, ns: &str) -> String {
+        match sel {
+            Some(id) => {
+                format!("{} ", paren(self.render(&self.pil.expressions[id], ns), PREC_ATOM))
+            }
+            None => "".to_string(),
+        }
+    }
+
+    fn print<W: Write>(&self, w: &mut W) -> Result<()> {
+        let pil = self.pil;
+        let mut namespaces: BTreeMap<String, Namespace> = BTreeMap::new();
+        // namespaces in the order of their first constant or commitment
+        let mut order: Vec<(usize, usize, String)> = vec![];
+        let mut refs: Vec<_> = pil.references.iter().collect();
+        refs.sort_by_key(|(name, r)| (r.type_.clone(), r.id, *name));
+        for (name, r) in refs {
+            let (ns_name, n) = split_name(name);
+            let rank = match r.type_.as_str() {
+                "constP" => 0,
+                "cmP" => 1,
+                _ => 2,
+            };
+            if !namespaces.contains_key(&ns_name) {
+                order.push((rank, r.id, ns_name.clone()));
+            }
+            let ns = namespaces.entry(ns_name.clone()).or_default();
+            ns.pol_deg = r.polDeg;
+            let decl = if r.isArray { format!("{}[{}]", n, r.len.unwrap_or(0)) } else { n };
+            match r.type_.as_str() {
+                "constP" => ns.constants.push(decl),
+                "cmP" => ns.commits.push(decl),
+                _ => {
+                    let e = self.render(&pil.expressions[r.id], &ns_name).0;
+                    ns.declarations.push(format!("pol {} = {};", decl, e));
+                }
+            }
+        }
+        order.sort();
+        let first = order.first().map_or(GLOBAL.to_string(), |o| o.2.clone());
+        let ns_of = |e: &Expression| self.namespace_of(e).unwrap_or_else(|| first.clone());
+
+        for id in self.shared.iter() {
+            let e = &pil.expressions[*id];
+            let ns_name = ns_of(e);
+            let body = self.render(e, &ns_name).0;
+            let ns = namespaces.entry(ns_name).or_default();
+            ns.declarations.push(format!("pol __e{} = {};", id, body));
+        }
+        for p in pil.publics.iter() {
+            let kind = match p.polType.as_str() {
+                "cmP" => Some("cm"),
+                "constP" => Some("const"),
+                _ => None,
+            };
+            let named = match kind {
+                Some(kind) => self.columns.get(&(kind, p.polId)),
+                None => self.ims.get(&p.polId),
+            };
+            let ns_name = named.map_or(first.clone(), |(ns, _)| ns.clone());
+            let column = self.name(named, format!("{}{}", p.polType, p.polId), &ns_name);
+            let ns = namespaces.entry(ns_name).or_default();
+            ns.declarations.push(format!("public {} = {}({});", p.name, column, p.idx));
+        }
+
+        let mut identity = |e: &Expression, body: &dyn Fn(&str) -> String, at: String| {
+            let ns_name = ns_of(e);
+            let line = format!("{}; // {}", body(&ns_name), at);
+            namespaces.entry(ns_name).or_default().identities.push(line);
+        };
+        for pi in pil.polIdentities.iter() {
+            let e = &pil.expressions[pi.e];
+            let body = |ns: &str| match e.op.as_str() {
+                // pilcom compiles `a = b` to `a - b`
+                "sub" => {
+                    let values = e.values.as_ref().unwrap();
+                    format!("{} = {}", self.render(&values[0], ns).0, self.render(&values[1], ns).0)
+                }
+                _ => format!("{} = 0", self.render(e, ns).0),
+            };
+            identity(e, &body, format!("{}:{}", pi.fileName, pi.line));
+        }
+        let first_exp = |ids: &Option<Vec<usize>>| {
+            ids.iter()
+                .flatten()
+                .next()
+                .map_or(Expression::new("number".to_string(), 0, None, None, None), |id| {
+                    pil.expressions[*id].clone()
+                })
+        };
+        for pi in pil.plookupIdentities.iter() {
+            let body = |ns: &str| {
+                format!(
+                    "{}{} in {}{}",
+                    self.render_sel(pi.selF, ns),
+                    self.render_list(&pi.f, ns),
+                    self.render_sel(pi.selT, ns),
+                    self.render_list(&pi.t, ns)
+                )
+            };
+            identity(&first_exp(&pi.f), &body, format!("{}:{}", pi.fileName, pi.line));
+        }
+        for pi in pil.permutationIdentities.iter().flatten() {
+            let body = |ns: &str| {
+                format!(
+                    "{}{} is {}{}",
+                    self.render_sel(pi.selF, ns),
+                    self.render_list(&pi.f, ns),
+                    self.render_sel(pi.selT, ns),
+                    self.render_list(&pi.t, ns)
+                )
+            };
+            identity(&first_exp(&pi.f), &body, format!("{}:{}", pi.fileName, pi.line));
+        }
+        for ci in pil.connectionIdentities.iter().flatten() {
+            let body = |ns: &str| {
+                format!(
+                    "{} connect {}",
+                    self.render_list(&ci.pols, ns),
+                    self.render_list(&ci.connections, ns)
+                )
+            };
+            identity(&first_exp(&ci.pols), &body, format!("{}:{}", ci.fileName, ci.line));
+        }
+
+        let mut names: Vec<String> = order.into_iter().map(|o| o.2).collect();
+        names.extend(namespaces.keys().filter(|k| !names.contains(k)).cloned().collect::<Vec<_>>());
+        for (i, name) in names.iter().enumerate() {
+            let ns = &namespaces[name];
+            if i > 0 {
+                writeln!(w)?;
+            }
+            writeln!(w, "namespace {}({});", name, ns.pol_deg)?;
+            if !ns.constants.is_empty() {
+                writeln!(w, "    pol constant {};", ns.constants.join(", "))?;
+            }
+            if !ns.commits.is_empty() {
+                writeln!(w, "    pol commit {};", ns.commits.join(", "))?;
+            }
+            for line in ns.declarations.iter().chain(ns.identities.iter()) {
+                writeln!(w, "    {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes `pil` as PIL source.
+pub fn print_pil<W: Write>(pil: &PIL, w: &mut W) -> Result<()> {
+    Printer::new(pil).print(w)
+}
+
+/// Writes the PIL source of a pil.json.
+pub fn pil_print<W: Write>(pil_file: &str, mut writer: W) -> Result<()> {
+    let pil = load_json::<PIL>(pil_file)?;
+    print_pil(&pil, &mut writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pil_print::*;
+
+    fn print(pil_file: &str) -> String {
+        let mut out = vec![];
+        pil_print(pil_file, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_pil_print_fibonacci() {
+        let source = print("data/fib.pil.json");
+        assert!(source.starts_with("namespace Fibonacci(1024);\n"), "{}", source);
+        assert!(source.contains("    pol constant L1, LLAST;\n"), "{}", source);
+        assert!(source.contains("    pol commit l1, l2;\n"), "{}", source);
+        assert!(source.contains("    pol next = l1 * l1 + l2 * l2;\n"), "{}", source);
+        assert!(source.contains("    public in1 = l2c(0);\n"), "{}", source);
+        assert!(
+            source.contains("    (l2' - l1) * (1 - LLAST) = 0; // fibonacci.pil:13\n"),
+            "{}",
+            source
+        );
+        // deterministic, so two files are diffed by their source
+        assert_eq!(source, print("data/fib.pil.json"));
+    }
+
+    #[test]
+    fn test_pil_print_identities() {
+        let source = print("data/plookup.pil.json");
+        assert!(source.contains(" in "), "{}", source);
+        assert!(source.contains("// plookup.pil:9"), "{}", source);
+        let source = print("data/connection.pil.json");
+        assert!(source.contains(" connect "), "{}", source);
+        let source = print("data/pe.pil.json");
+        assert!(source.contains(" is "), "{}", source);
+    }
+}
@@ -0,0 +1,516 @@
+#![allow(non_snake_case)]
+//! Structural checks of a `PIL` before setup, so a malformed pil.json is reported by reference
+//! and identity location instead of panicking in `starkinfo_map` or `PolsArray::get_pol_id`.
+use crate::types::{Expression, PIL};
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Identities of a higher degree are reported, as they need intermediate polynomials or a
+/// larger blowup.
+pub const MAX_IDENTITY_DEGREE: i32 = 3;
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PilReport {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl PilReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Fails with all the errors, otherwise returns the warnings.
+    pub fn into_result(self) -> Result<Vec<String>> {
+        if !self.errors.is_empty() {
+            bail!("Invalid PIL:\n{}", self.errors.join("\n"));
+        }
+        Ok(self.warnings)
+    }
+}
+
+struct Validator<'a> {
+    pil: &'a PIL,
+    report: PilReport,
+    /// (cmP or constP, id) to `ns.name[i]`
+    names: HashMap<(&'static str, usize), String>,
+    /// Expression id to the `fileName:line` of the first identity reaching it.
+    locations: HashMap<usize, String>,
+}
+
+impl Validator<'_> {
+    fn column(&self, type_: &'static str, id: usize) -> String {
+        match self.names.get(&(type_, id)) {
+            Some(name) => name.clone(),
+            None => format!("{} {}", type_, id),
+        }
+    }
+
+    /// `expression id`, after the location of the identity reaching it.
+    fn expression_at(&self, id: usize) -> String {
+        match self.locations.get(&id) {
+            Some(at) => format!("{}: expression {}", at, id),
+            None => format!("expression {}", id),
+        }
+    }
+
+    fn check_references(&mut self) {
+        let pil = self.pil;
+        let mut refs: Vec<_> = pil.references.iter().collect();
+        refs.sort_by_key(|(name, _)| *name);
+        let pol_deg = match refs.first() {
+            Some((_, r)) => r.polDeg,
+            None => {
+                self.report.errors.push("PIL has no references".to_string());
+                return;
+            }
+        };
+        for (name, r) in refs {
+            if r.polDeg != pol_deg {
+                self.report.errors.push(format!(
+                    "Reference {}: polDeg {} differs from {}",
+                    name, r.polDeg, pol_deg
+                ));
+            }
+            let len = if r.isArray { r.len.unwrap_or(0) } else { 1 };
+            let (type_, n): (&'static str, usize) = match r.type_.as_str() {
+                "cmP" => ("cmP", pil.nCommitments),
+                "constP" => ("constP", pil.nConstants),
+                "imP" => ("imP", pil.expressions.len()),
+                _ => {
+                    self.report
+                        .errors
+                        .push(format!("Reference {}: unknown type {}", name, r.type_));
+                    continue;
+                }
+            };
+            if r.id + len > n {
+                self.report.errors.push(format!(
+                    "Reference {}: {} {}..{} out of range, {} defined",
+                    name,
+                    type_,
+                    r.id,
+                    r.id + len,
+                    n
+                ));
+                continue;
+            }
+            for i in 0..len {
+                let column = if r.isArray { format!("{}[{}]", name, i) } else { name.clone() };
+                self.names.insert((type_, r.id + i), column);
+            }
+        }
+    }
+
+    fn check_expression(&mut self, e: &Expression, at: &str) {
+        let pil = self.pil;
+        let n_values = match e.op.as_str() {
+            "add" | "sub" | "mul" => 2,
+            "muladd" => 3,
+            "neg" | "addc" | "mulc" => 1,
+            "number" | "challenge" | "eval" | "x" => 0,
+            "cm" | "const" | "exp" | "public" => {
+                let (n, what) = match e.op.as_str() {
+                    "cm" => (pil.nCommitments, "commitment"),
+                    "const" => (pil.nConstants, "constant"),
+                    "exp" => (pil.expressions.len(), "expression"),
+                    _ => (pil.publics.len(), "public"),
+                };
+                match e.id {
+                    Some(id) if id < n => {}
+                    id => self
+                        .report
+                        .errors
+                        .push(format!("{}: {} id {:?} out of range, {} defined", at, what, id, n)),
+                }
+                0
+            }
+            _ => {
+                self.report.errors.push(format!("{}: unknown op {}", at, e.op));
+                return;
+            }
+        };
+        let values = e.values.as_deref().unwrap_or_default();
+        if values.len() < n_values {
+            self.report.errors.push(format!(
+                "{}: {} expects {} operands, found {}",
+                at,
+                e.op,
+                n_values,
+                values.len()
+            ));
+        }
+        for v in values.iter() {
+            self.check_expression(v, at);
+        }
+    }
+
+    /// Reports the expressions which reference themselves through `exp`.
+    fn check_cycles(&mut self) {
+        fn visit(pil: &PIL, e: &Expression, state: &mut [u8], cycles: &mut HashSet<usize>) {
+            if e.op.as_str() == "exp" {
+                let id = match e.id {
+                    Some(id) if id < state.len() => id,
+                    _ => return,
+                };
+                match state[id] {
+                    1 => {
+                        cycles.insert(id);
+                    }
+                    0 => {
+                        state[id] = 1;
+                        visit(pil, &pil.expressions[id], state, cycles);
+                        state[id] = 2;
+                    }
+                    _ => {}
+                }
+            } else if let Some(values) = e.values.as_ref() {
+                for v in values.iter() {
+                    visit(pil, v, state, cycles);
+                }
+            }
+        }
+
+        let pil = self.pil;
+        let mut state = vec![0u8; pil.expressions.len()];
+        let mut cycles = HashSet::new();
+        for id in 0..pil.expressions.len() {
+            if state[id] == 0 {
+                state[id] = 1;
+                visit(pil, &pil.expressions[id], &mut state, &mut cycles);
+                state[id] = 2;
+            }
+        }
+        let mut cycles: Vec<usize> = cycles.into_iter().collect();
+        cycles.sort();
+        for id in cycles {
+            let at = self.expression_at(id);
+            self.report.errors.push(format!("{}: references itself", at));
+        }
+    }
+
+    fn check_exp_ids(&mut self, ids: &[usize], at: &str) {
+        for id in ids.iter() {
+            if *id >= self.pil.expressions.len() {
+                self.report.errors.push(format!(
+                    "{}: expression id {} out of range, {} defined",
+                    at,
+                    id,
+                    self.pil.expressions.len()
+                ));
+            }
+        }
+    }
+
+    /// The location of the first identity reaching each expression, directly or through `exp`.
+    fn locations(&self) -> HashMap<usize, String> {
+        fn exp_ids(e: &Expression, out: &mut Vec<usize>) {
+            if e.op.as_str() == "exp" {
+                out.extend(e.id);
+            }
+            for v in e.values.iter().flatten() {
+                exp_ids(v, out);
+            }
+        }
+
+        let n = self.pil.expressions.len();
+        let mut locations = HashMap::new();
+        for (at, ids) in self.identities() {
+            let mut stack = ids;
+            while let Some(id) = stack.pop() {
+                if id < n && !locations.contains_key(&id) {
+                    locations.insert(id, at.clone());
+                    exp_ids(&self.pil.expressions[id], &mut stack);
+                }
+            }
+        }
+        locations
+    }
+
+    /// The expression ids of every identity, with its location.
+    fn identities(&self) -> Vec<(String, Vec<usize>)> {
+        let pil = self.pil;
+        let mut identities = vec![];
+        for pi in pil.polIdentities.iter() {
+            identities.push((format!("{}:{}", pi.fileName, pi.line), vec![pi.e]));
+        }
+        let lookup = |f: &Option<Vec<usize>>, t: &Option<Vec<usize>>, sels: [Option<usize>; 2]| {
+            let mut ids: Vec<usize> = f.iter().chain(t.iter()).flatten().copied().collect();
+            ids.extend(sels.iter().flatten());
+            ids
+        };
+        for pi in pil.plookupIdentities.iter() {
+            let ids = lookup(&pi.f, &pi.t, [pi.selF, pi.selT]);
+            identities.push((format!("{}:{}", pi.fileName, pi.line), ids));
+        }
+        for pi in pil.permutationIdentities.iter().flatten() {
+            let ids = lookup(&pi.f, &pi.t, [pi.selF, pi.selT]);
+            identities.push((format!("{}:{}", pi.fileName, pi.line), ids));
+        }
+        for ci in pil.connectionIdentities.iter().flatten() {
+            let ids = lookup(&ci.pols, &ci.connections, [None, None]);
+            identities.push((format!("{}:{}", ci.fileName, ci.line), ids));
+        }
+        identities
+    }
+
+    fn check_identities(&mut self) {
+        let pil = self.pil;
+        for (at, ids) in self.identities() {
+            self.check_exp_ids(&ids, &at);
+        }
+        let lengths = pil
+            .plookupIdentities
+            .iter()
+            .map(|pi| (&pi.f, &pi.t, &pi.fileName, pi.line, "plookup"))
+            .chain(
+                pil.permutationIdentities
+                    .iter()
+                    .flatten()
+                    .map(|pi| (&pi.f, &pi.t, &pi.fileName, pi.line, "permutation")),
+            )
+            .chain(
+                pil.connectionIdentities
+                    .iter()
+                    .flatten()
+                    .map(|ci| (&ci.pols, &ci.connections, &ci.fileName, ci.line, "connection")),
+            );
+        for (f, t, fileName, line, kind) in lengths {
+            let (f, t) = (f.as_ref().map_or(0, |f| f.len()), t.as_ref().map_or(0, |t| t.len()));
+            if f != t {
+                self.report.errors.push(format!(
+                    "{}:{}: {} identity with {} and {} expressions",
+                    fileName, line, kind, f, t
+                ));
+            }
+        }
+    }
+
+    fn check_publics(&mut self) {
+        let pil = self.pil;
+        let pol_deg = pil.references.values().next().map_or(0, |r| r.polDeg);
+        for (i, p) in pil.publics.iter().enumerate() {
+            let n = match p.polType.as_str() {
+                "cmP" => pil.nCommitments,
+                "constP" => pil.nConstants,
+                "imP" => pil.expressions.len(),
+                _ => {
+                    self.report
+                        .errors
+                        .push(format!("Public {}: unknown polType {}", p.name, p.polType));
+                    continue;
+                }
+            };
+            if p.polId >= n {
+                self.report.errors.push(format!(
+                    "Public {}: {} {} is missing, {} defined",
+                    p.name, p.polType, p.polId, n
+                ));
+            }
+            if p.idx >= pol_deg {
+                self.report.errors.push(format!(
+                    "Public {}: row {} out of range, polDeg {}",
+                    p.name, p.idx, pol_deg
+                ));
+            }
+            if p.id != i {
+                self.report
+                    .errors
+                    .push(format!("Public {}: id {} at position {}", p.name, p.id, i));
+            }
+        }
+    }
+
+    /// Columns referenced by `e`, following `exp` through `visited`.
+    fn columns(
+        &self,
+        e: &Expression,
+        visited: &mut HashSet<usize>,
+        out: &mut HashSet<(&'static str, usize)>,
+    ) {
+        match e.op.as_str() {
+            "cm" => {
+                out.insert(("cmP", e.id.unwrap()));
+            }
+            "const" => {
+                out.insert(("constP", e.id.unwrap()));
+            }
+            "exp" => {
+                let id = e.id.unwrap();
+                if visited.insert(id) {
+                    self.columns(&self.pil.expressions[id], visited, out);
+                }
+            }
+            _ => {
+                for v in e.values.iter().flatten() {
+                    self.columns(v, visited, out);
+                }
+            }
+        }
+    }
+
+    fn check_usage(&mut self) {
+        let pil = self.pil;
+        let mut used = HashSet::new();
+        let mut visited = HashSet::new();
+        for (id, e) in pil.expressions.iter().enumerate() {
+            if visited.insert(id) {
+                self.columns(e, &mut visited, &mut used);
+            }
+        }
+        let mut constrained = HashSet::new();
+        let mut visited = HashSet::new();
+        for (_, ids) in self.identities() {
+            for id in ids {
+                self.columns(&pil.expressions[id], &mut visited, &mut constrained);
+            }
+        }
+        for p in pil.publics.iter().filter(|p| p.polType.as_str() == "cmP") {
+            used.insert(("cmP", p.polId));
+        }
+
+        for (type_, n) in [("cmP", pil.nCommitments), ("constP", pil.nConstants)] {
+            for id in 0..n {
+                if !used.contains(&(type_, id)) {
+                    let column = self.column(type_, id);
+                    self.report.warnings.push(format!("{} is unused", column));
+                } else if type_ == "cmP" && !constrained.contains(&(type_, id)) {
+                    let column = self.column(type_, id);
+                    self.report.warnings.push(format!("{} is unconstrained", column));
+                }
+            }
+        }
+
+        let mut degrees = HashMap::new();
+        for pi in pil.polIdentities.iter() {
+            let degree = exp_degree(pil, &pil.expressions[pi.e], &mut degrees);
+            if degree > MAX_IDENTITY_DEGREE {
+                self.report.warnings.push(format!(
+                    "{}:{}: identity of degree {}, above {}",
+                    pi.fileName, pi.line, degree, MAX_IDENTITY_DEGREE
+                ));
+            }
+        }
+    }
+}
+
+/// The degree of `e`, as `starkinfo_cp_prover::get_exp_dim`, with the degrees of the `exp`
+/// it reaches kept in `degrees`, shared across the identities of a PIL.
+pub fn exp_degree(pil: &PIL, e: &Expression, degrees: &mut HashMap<usize, i32>) -> i32 {
+    let mut degree = |v: &Expression| exp_degree(pil, v, degrees);
+    let values = e.values.as_deref().unwrap_or_default();
+    match e.op.as_str() {
+        "add" | "sub" | "addc" | "mulc" | "neg" => values.iter().map(degree).fold(1, i32::max),
+        "mul" => degree(&values[0]) + degree(&values[1]),
+        "muladd" => i32::max(degree(&values[0]) + degree(&values[1]), degree(&values[2])),
+        "cm" | "const" | "x" => 1,
+        "exp" => {
+            let id = e.id.unwrap();
+            if let Some(d) = degrees.get(&id) {
+                return *d;
+            }
+            let d = exp_degree(pil, &pil.expressions[id], degrees);
+            degrees.insert(id, d);
+            d
+        }
+        "number" | "public" | "challenge" | "eval" => 0,
+        _ => panic!("Exp op not defined: {}", e.op),
+    }
+}
+
+fn check_structure(pil: &PIL) -> Validator<'_> {
+    let mut v = Validator {
+        pil,
+        report: PilReport::default(),
+        names: HashMap::new(),
+        locations: HashMap::new(),
+    };
+    v.locations = v.locations();
+    v.check_references();
+    for (id, e) in pil.expressions.iter().enumerate() {
+        let at = v.expression_at(id);
+        v.check_expression(e, &at);
+    }
+    v.check_cycles();
+    v.check_identities();
+    v.check_publics();
+    v
+}
+
+/// Checks the references, expressions, identities and publics of `pil`, collecting every
+/// error, the ones of an expression at the identity reaching it.
+pub fn check(pil: &PIL) -> Result<()> {
+    check_structure(pil).report.into_result()?;
+    Ok(())
+}
+
+/// The errors of `check` and the warnings of the usage and degrees, only computed for a
+/// structurally valid PIL.
+pub fn validate(pil: &PIL) -> PilReport {
+    let mut v = check_structure(pil);
+    if v.report.is_ok() {
+        v.check_usage();
+    }
+    v.report
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pil_validate::*;
+    use crate::starkinfo_cp_prover::get_exp_dim;
+    use crate::types::load_json;
+
+    #[test]
+    fn test_validate_examples() {
+        for pil_file in [
+            "data/fib.pil.json",
+            "data/pe.pil.json",
+            "data/plookup.pil.json",
+            "data/connection.pil.json",
+        ] {
+            let pil = load_json::<PIL>(pil_file).unwrap();
+            let report = validate(&pil);
+            assert!(report.is_ok(), "{}: {:?}", pil_file, report.errors);
+            let mut degrees = HashMap::new();
+            for e in pil.expressions.iter() {
+                assert_eq!(exp_degree(&pil, e, &mut degrees), get_exp_dim(&pil, e), "{}", pil_file);
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_errors() {
+        let mut pil = load_json::<PIL>("data/fib.pil.json").unwrap();
+        let n_cm = pil.nCommitments;
+        pil.expressions[0].values = Some(vec![
+            Expression::new("cm".to_string(), 1, Some(n_cm), None, None),
+            Expression::new("exp".to_string(), 1, Some(0), None, None),
+        ]);
+        pil.expressions[0].op = "add".to_string();
+        pil.references.values_mut().next().unwrap().polDeg += 1;
+        pil.publics[1].polId = n_cm;
+        // reached through the exp of the identity of fibonacci.pil:17
+        pil.expressions[2].op = "div".to_string();
+
+        let report = validate(&pil);
+        let errors = report.errors.join("\n");
+        assert!(errors.contains("commitment id"), "{}", errors);
+        assert!(errors.contains("references itself"), "{}", errors);
+        assert!(errors.contains("fibonacci.pil:17: expression 2: unknown op div"), "{}", errors);
+        assert!(check(&pil).is_err());
+        assert!(errors.contains("polDeg"), "{}", errors);
+        assert!(errors.contains(&format!("Public {}", pil.publics[1].name)), "{}", errors);
+        assert!(report.into_result().is_err());
+    }
+
+    #[test]
+    fn test_validate_warnings() {
+        let mut pil = load_json::<PIL>("data/fib.pil.json").unwrap();
+        // a commitment nothing references
+        pil.nCommitments += 1;
+        let report = validate(&pil);
+        assert!(report.is_ok());
+        assert!(report.warnings.iter().any(|w| w.contains("unused")), "{:?}", report.warnings);
+    }
+}
@@ -0,0 +1,450 @@
+#![allow(non_snake_case)]
+//! Readers and writers of trace files, besides pil-stark's raw row-major layout, see
+//! [`TraceFormat`].
+use crate::polsarray::{Pol, PolKind, PolsArray};
+use crate::types::{load_json, PIL};
+use anyhow::{anyhow, bail, Result};
+use fields::field_gl::{Fr as FGL, MODULUS};
+use serde_cbor::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::str::FromStr;
+
+/// Encoding of a trace file.
+pub trait PolsFormat {
+    /// Fills `pols`, which must have been built from the PIL of the trace.
+    fn load(&self, pols: &mut PolsArray, fileName: &str) -> Result<()>;
+
+    fn save(&self, pols: &PolsArray, fileName: &str) -> Result<()>;
+}
+
+/// pil-stark's layout, little-endian u64 words, row by row.
+pub struct RowMajor;
+
+/// Little-endian u64 words, polynomial by polynomial.
+pub struct ColMajor;
+
+/// One column per polynomial, named in the header, with a leading `Row` column. Values are
+/// decimal or `0x` prefixed hex.
+pub struct Csv;
+
+/// An object mapping the polynomial names to their values.
+pub struct Json;
+
+/// powdr's fixed/witness column dump, a CBOR list of `(name, values)` pairs.
+pub struct Powdr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    RowMajor,
+    ColMajor,
+    Csv,
+    Json,
+    Powdr,
+}
+
+impl TraceFormat {
+    pub fn codec(&self) -> Box<dyn PolsFormat> {
+        match self {
+            TraceFormat::RowMajor => Box::new(RowMajor),
+            TraceFormat::ColMajor => Box::new(ColMajor),
+            TraceFormat::Csv => Box::new(Csv),
+            TraceFormat::Json => Box::new(Json),
+            TraceFormat::Powdr => Box::new(Powdr),
+        }
+    }
+}
+
+impl FromStr for TraceFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "raw" | "row" | "rowmajor" => Ok(TraceFormat::RowMajor),
+            "col" | "colmajor" => Ok(TraceFormat::ColMajor),
+            "csv" => Ok(TraceFormat::Csv),
+            "json" => Ok(TraceFormat::Json),
+            "powdr" => Ok(TraceFormat::Powdr),
+            _ => bail!("Invalid trace format {}, expect raw, col, csv, json or powdr", s),
+        }
+    }
+}
+
+impl FromStr for PolKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "cm" | "commit" => Ok(PolKind::Commit),
+            "const" | "constant" => Ok(PolKind::Constant),
+            _ => bail!("Invalid polynomial kind {}, expect cm or const", s),
+        }
+    }
+}
+
+/// The name of a polynomial in the text formats, `ns.name` or `ns.name[i]` for arrays.
+pub fn pol_name(pol: &Pol) -> String {
+    match pol.idx {
+        Some(i) => format!("{}[{}]", pol.name, i),
+        None => pol.name.clone(),
+    }
+}
+
+fn pol_ids(pols: &PolsArray) -> HashMap<String, usize> {
+    pols.defArray.iter().map(|p| (pol_name(p), p.id)).collect()
+}
+
+fn to_fgl(v: u64) -> Result<FGL> {
+    if v >= MODULUS.0[0] {
+        bail!("{} is not less than the modulus", v);
+    }
+    Ok(FGL::from(v))
+}
+
+fn parse_value(s: &str) -> Result<FGL> {
+    let s = s.trim();
+    let v = match s.strip_prefix("0x") {
+        Some(h) => u64::from_str_radix(h, 16)?,
+        None => s.parse::<u64>()?,
+    };
+    to_fgl(v)
+}
+
+/// Sets the column `name`, checking it belongs to the PIL and has a value per row.
+fn set_column(
+    pols: &mut PolsArray,
+    ids: &HashMap<String, usize>,
+    name: &str,
+    values: Vec<FGL>,
+) -> Result<()> {
+    let id = *ids.get(name).ok_or_else(|| anyhow!("Unknown polynomial {}", name))?;
+    if values.len() != pols.n {
+        bail!("Invalid length of {}: {} rows, expected {}", name, values.len(), pols.n);
+    }
+    pols.array[id] = values;
+    Ok(())
+}
+
+fn check_all_set(pols: &PolsArray, set: &[bool]) -> Result<()> {
+    if let Some(id) = set.iter().position(|s| !s) {
+        bail!("Missing polynomial {}", pol_name(&pols.defArray[id]));
+    }
+    Ok(())
+}
+
+impl PolsFormat for RowMajor {
+    fn load(&self, pols: &mut PolsArray, fileName: &str) -> Result<()> {
+        pols.load(fileName)
+    }
+
+    fn save(&self, pols: &PolsArray, fileName: &str) -> Result<()> {
+        pols.save(BufWriter::new(File::create(fileName)?))
+    }
+}
+
+impl PolsFormat for ColMajor {
+    fn load(&self, pols: &mut PolsArray, fileName: &str) -> Result<()> {
+        let f = File::open(fileName)?;
+        let expected = (pols.nPols * pols.n * 8) as u64;
+        if f.metadata()?.len() != expected {
+            bail!(
+                "Invalid size of {}: {} bytes, expected {} pols x {} rows x 8 bytes",
+                fileName,
+                f.metadata()?.len(),
+                pols.nPols,
+                pols.n
+            );
+        }
+        let mut reader = BufReader::new(f);
+        let mut word = [0u8; 8];
+        for j in 0..pols.nPols {
+            for i in 0..pols.n {
+                reader.read_exact(&mut word)?;
+                pols.array[j][i] = to_fgl(u64::from_le_bytes(word))
+                    .map_err(|e| anyhow!("row {}, pol {}: {}", i, j, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn save(&self, pols: &PolsArray, fileName: &str) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(fileName)?);
+        for col in pols.array.iter() {
+            for v in col.iter() {
+                writer.write_all(&v.as_int().to_le_bytes())?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl PolsFormat for Csv {
+    fn load(&self, pols: &mut PolsArray, fileName: &str) -> Result<()> {
+        let mut lines = BufReader::new(File::open(fileName)?).lines();
+        let header = lines.next().ok_or_else(|| anyhow!("Empty csv file {}", fileName))??;
+        let mut names: Vec<&str> = header.split(',').map(|s| s.trim()).collect();
+        let with_row = names.first() == Some(&"Row");
+        if with_row {
+            names.remove(0);
+        }
+
+        let mut columns: Vec<Vec<FGL>> = vec![Vec::with_capacity(pols.n); names.len()];
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let values: Vec<&str> = line.split(',').skip(with_row as usize).collect();
+            if values.len() != names.len() {
+                bail!("Invalid number of values at row {} of {}", i, fileName);
+            }
+            for (k, v) in values.iter().enumerate() {
+                let v = parse_value(v).map_err(|e| anyhow!("row {}, {}: {}", i, names[k], e))?;
+                columns[k].push(v);
+            }
+        }
+
+        let ids = pol_ids(pols);
+        let mut set = vec![false; pols.nPols];
+        for (name, values) in names.iter().zip(columns) {
+            set_column(pols, &ids, name, values)?;
+            set[ids[*name]] = true;
+        }
+        check_all_set(pols, &set)
+    }
+
+    fn save(&self, pols: &PolsArray, fileName: &str) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(fileName)?);
+        let names: Vec<String> = pols.defArray.iter().map(pol_name).collect();
+        writeln!(writer, "Row,{}", names.join(","))?;
+        for i in 0..pols.n {
+            write!(writer, "{}", i)?;
+            for col in pols.array.iter() {
+                write!(writer, ",{}", col[i].as_int())?;
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl PolsFormat for Json {
+    fn load(&self, pols: &mut PolsArray, fileName: &str) -> Result<()> {
+        let columns = load_json::<HashMap<String, Vec<u64>>>(fileName)?;
+        let ids = pol_ids(pols);
+        let mut set = vec![false; pols.nPols];
+        for (name, values) in columns {
+            let values = values
+                .into_iter()
+                .map(to_fgl)
+                .collect::<Result<Vec<_>>>()
+                .map_err(|e| anyhow!("{}: {}", name, e))?;
+            set_column(pols, &ids, &name, values)?;
+            set[ids[&name]] = true;
+        }
+        check_all_set(pols, &set)
+    }
+
+    fn save(&self, pols: &PolsArray, fileName: &str) -> Result<()> {
+        let columns: HashMap<String, Vec<u64>> = pols
+            .defArray
+            .iter()
+            .map(|p| (pol_name(p), pols.array[p.id].iter().map(|v| v.as_int()).collect()))
+            .collect();
+        serde_json::to_writer(BufWriter::new(File::create(fileName)?), &columns)?;
+        Ok(())
+    }
+}
+
+/// powdr names its columns `ns::name`, the PIL exported from powdr uses `ns.name`.
+fn powdr_name(name: &str) -> String {
+    if name.contains('.') {
+        name.to_string()
+    } else if let Some(last) = name.rfind("::") {
+        format!("{}.{}", &name[..last], &name[last + 2..])
+    } else {
+        name.to_string()
+    }
+}
+
+/// Field elements are dumped either as integers, or as their little-endian bytes.
+fn powdr_value(v: &Value) -> Result<FGL> {
+    let bytes: Vec<u8> = match v {
+        Value::Integer(i) => return to_fgl(u64::try_from(*i)?),
+        Value::Bytes(b) => b.clone(),
+        Value::Array(a) => a
+            .iter()
+            .map(|b| match b {
+                Value::Integer(i) => Ok(u8::try_from(*i)?),
+                _ => bail!("Invalid byte {:?}", b),
+            })
+            .collect::<Result<_>>()?,
+        _ => bail!("Invalid field element {:?}", v),
+    };
+    if bytes.len() > 8 && bytes[8..].iter().any(|b| *b != 0) {
+        bail!("Invalid field element {:?}", bytes);
+    }
+    let mut word = [0u8; 8];
+    let len = std::cmp::min(bytes.len(), 8);
+    word[..len].copy_from_slice(&bytes[..len]);
+    to_fgl(u64::from_le_bytes(word))
+}
+
+impl PolsFormat for Powdr {
+    fn load(&self, pols: &mut PolsArray, fileName: &str) -> Result<()> {
+        let columns: Vec<(String, Vec<Value>)> =
+            serde_cbor::from_reader(BufReader::new(File::open(fileName)?))?;
+        let ids = pol_ids(pols);
+        let mut set = vec![false; pols.nPols];
+        for (name, values) in columns {
+            let name = powdr_name(&name);
+            if !ids.contains_key(&name) {
+                log::debug!("Skip column {} of {}, not in the PIL", name, fileName);
+                continue;
+            }
+            let values = values
+                .iter()
+                .map(powdr_value)
+                .collect::<Result<Vec<_>>>()
+                .map_err(|e| anyhow!("{}: {}", name, e))?;
+            set_column(pols, &ids, &name, values)?;
+            set[ids[&name]] = true;
+        }
+        check_all_set(pols, &set)
+    }
+
+    fn save(&self, pols: &PolsArray, fileName: &str) -> Result<()> {
+        let columns: Vec<(String, Vec<u64>)> = pols
+            .defArray
+            .iter()
+            .map(|p| (pol_name(p), pols.array[p.id].iter().map(|v| v.as_int()).collect()))
+            .collect();
+        serde_cbor::to_writer(BufWriter::new(File::create(fileName)?), &columns)?;
+        Ok(())
+    }
+}
+
+/// Converts a trace of `pil_file` from one format to another.
+pub fn pols_convert(
+    pil_file: &str,
+    kind: &str,
+    input_file: &str,
+    input_format: &str,
+    output_file: &str,
+    output_format: &str,
+) -> Result<()> {
+    let pil = load_json::<PIL>(pil_file)?;
+    let mut pols = PolsArray::new(&pil, kind.parse()?);
+    TraceFormat::from_str(input_format)?.codec().load(&mut pols, input_file)?;
+    TraceFormat::from_str(output_format)?.codec().save(&pols, output_file)
+}
+
+/// Prints rows `[start, end)` of the polynomial `name`, `ns.name` or `ns.name[i]`.
+#[allow(clippy::too_many_arguments)]
+pub fn pols_dump<W: Write>(
+    pil_file: &str,
+    kind: &str,
+    input_file: &str,
+    input_format: &str,
+    name: &str,
+    start: usize,
+    end: Option<usize>,
+    mut writer: W,
+) -> Result<()> {
+    let pil = load_json::<PIL>(pil_file)?;
+    let mut pols = PolsArray::new(&pil, kind.parse()?);
+    TraceFormat::from_str(input_format)?.codec().load(&mut pols, input_file)?;
+
+    let id = *pol_ids(&pols).get(name).ok_or_else(|| anyhow!("Unknown polynomial {}", name))?;
+    let end = std::cmp::min(end.unwrap_or(pols.n), pols.n);
+    for i in start..end {
+        writeln!(writer, "{}[{}] = {}", name, i, pols.array[id][i].as_int())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types;
+
+    fn fib_pols() -> (PIL, PolsArray) {
+        let pil = types::load_json::<PIL>("data/fib.pil.json").unwrap();
+        let mut pols = PolsArray::new(&pil, PolKind::Commit);
+        pols.load("data/fib.cm").unwrap();
+        (pil, pols)
+    }
+
+    #[test]
+    fn test_pols_format_roundtrip() {
+        let (pil, pols) = fib_pols();
+        let dir = std::env::temp_dir();
+        for (format, ext) in [
+            (TraceFormat::RowMajor, "raw"),
+            (TraceFormat::ColMajor, "col"),
+            (TraceFormat::Csv, "csv"),
+            (TraceFormat::Json, "json"),
+            (TraceFormat::Powdr, "bin"),
+        ] {
+            let path = dir.join(format!("starky_fib_cm.{ext}"));
+            let path = path.to_str().unwrap();
+            format.codec().save(&pols, path).unwrap();
+
+            let mut actual = PolsArray::new(&pil, PolKind::Commit);
+            format.codec().load(&mut actual, path).unwrap();
+            assert_eq!(actual.array, pols.array, "{:?}", format);
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_powdr_names_and_values() {
+        let (pil, pols) = fib_pols();
+        // powdr style names and byte encoded values, plus a column unknown to the PIL
+        let mut columns: Vec<(String, Vec<Value>)> = pols
+            .defArray
+            .iter()
+            .map(|p| {
+                let name = pol_name(p).replacen('.', "::", 1);
+                let values = pols.array[p.id]
+                    .iter()
+                    .map(|v| Value::Bytes(v.as_int().to_le_bytes().to_vec()))
+                    .collect();
+                (name, values)
+            })
+            .collect();
+        columns.push(("Fibonacci::extra".to_string(), vec![Value::Integer(0); pols.n]));
+
+        let path = std::env::temp_dir().join("starky_fib_powdr.bin");
+        let path = path.to_str().unwrap();
+        serde_cbor::to_writer(File::create(path).unwrap(), &columns).unwrap();
+        let mut actual = PolsArray::new(&pil, PolKind::Commit);
+        Powdr.load(&mut actual, path).unwrap();
+        assert_eq!(actual.array, pols.array);
+
+        // missing column
+        columns.remove(0);
+        serde_cbor::to_writer(File::create(path).unwrap(), &columns).unwrap();
+        assert!(Powdr.load(&mut actual, path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_pols_dump() {
+        let (_, pols) = fib_pols();
+        let name = pol_name(&pols.defArray[0]);
+        let mut out = vec![];
+        pols_dump("data/fib.pil.json", "cm", "data/fib.cm", "raw", &name, 1, Some(3), &mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let expect = format!(
+            "{name}[1] = {}\n{name}[2] = {}\n",
+            pols.array[0][1].as_int(),
+            pols.array[0][2].as_int()
+        );
+        assert_eq!(out, expect);
+    }
+}
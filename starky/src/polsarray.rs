@@ -1,7 +1,8 @@
 #![allow(non_snake_case)]
 use crate::{traits::FieldExtension, types::PIL};
-use anyhow::Result;
-use fields::field_gl::Fr as FGL;
+use anyhow::{bail, Result};
+use fields::field_gl::{Fr as FGL, MODULUS};
+use memmap2::Mmap;
 use profiler_macro::time_profiler;
 use rayon::prelude::*;
 use std::collections::HashMap;
@@ -27,7 +28,7 @@ pub struct Pol {
     pub elementType: Option<String>, // "field, s8, s16, s32, s64, u16, u8"
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Clone, Copy)]
 pub enum PolKind {
     Commit,
     Constant,
@@ -138,6 +139,7 @@ impl PolsArray {
         let mut f = File::open(fileName)?;
         let maxBufferSize = 1024 * 1024 * 32;
         let totalSize = self.nPols * self.n;
+        check_file_size(fileName, f.metadata()?.len(), self.nPols, self.n)?;
         let mut buff8: Vec<u8> = vec![0u8; std::cmp::min(totalSize, maxBufferSize) * 8];
 
         let mut i = 0;
@@ -215,21 +217,160 @@ impl PolsArray {
         }
         Ok(())
     }
+}
 
-    pub fn write_buff<F: FieldExtension>(&self) -> Vec<F> {
-        let mut buff: Vec<F> = vec![F::ZERO; self.n * self.nPols];
-        buff.par_chunks_mut(self.nPols).enumerate().for_each(|(i, chunk)| {
-            for j in 0..self.nPols {
-                chunk[j] = F::from(self.array[j][i]);
+impl PolsRows for PolsArray {
+    fn n_pols(&self) -> usize {
+        self.nPols
+    }
+
+    fn n(&self) -> usize {
+        self.n
+    }
+
+    #[inline(always)]
+    fn get_row(&self, i: usize, j: usize) -> FGL {
+        self.array[j][i]
+    }
+}
+
+/// Row-major access to the evaluations of `n_pols` polynomials over `n` rows.
+pub trait PolsRows: Sync {
+    fn n_pols(&self) -> usize;
+
+    fn n(&self) -> usize;
+
+    /// Returns the j-th polynomial at row i.
+    fn get_row(&self, i: usize, j: usize) -> FGL;
+
+    /// Copies the polynomials into a row-major buffer, `buff[i * n_pols + j]` is the j-th
+    /// polynomial at row i.
+    fn write_buff<F: FieldExtension>(&self) -> Vec<F> {
+        let nPols = self.n_pols();
+        let mut buff: Vec<F> = vec![F::ZERO; self.n() * nPols];
+        if nPols == 0 {
+            return buff;
+        }
+        buff.par_chunks_mut(nPols).enumerate().for_each(|(i, chunk)| {
+            for j in 0..nPols {
+                chunk[j] = F::from(self.get_row(i, j));
             }
         });
         buff
     }
 }
 
+impl<P: PolsRows> PolsRows for &P {
+    fn n_pols(&self) -> usize {
+        (*self).n_pols()
+    }
+
+    fn n(&self) -> usize {
+        (*self).n()
+    }
+
+    #[inline(always)]
+    fn get_row(&self, i: usize, j: usize) -> FGL {
+        (*self).get_row(i, j)
+    }
+}
+
+/// Read-only view of a `.const`/`.cm` file mapped into memory.
+///
+/// The file is a row-major sequence of little-endian u64 words, `nPols` per row. The mapping
+/// is validated once on open, rows are then read in place without loading the whole trace.
+#[derive(Debug)]
+pub struct PolsMmap {
+    pub nPols: usize,
+    pub n: usize,
+    mmap: Option<Mmap>,
+}
+
+impl PolsMmap {
+    #[time_profiler("mmap_pols_array")]
+    pub fn open(fileName: &str, nPols: usize, n: usize) -> Result<Self> {
+        let f = File::open(fileName)?;
+        check_file_size(fileName, f.metadata()?.len(), nPols, n)?;
+        // an empty mapping is rejected by mmap(2)
+        let mmap = if nPols * n > 0 { Some(unsafe { Mmap::map(&f)? }) } else { None };
+        let pols = PolsMmap { nPols, n, mmap };
+
+        if let Some(pos) = pols.as_slice().par_iter().position_any(|v| *v >= MODULUS.0[0]) {
+            bail!(
+                "Invalid element in {}: row {}, pol {}, {} is not less than the modulus",
+                fileName,
+                pos / nPols,
+                pos % nPols,
+                pols.as_slice()[pos]
+            );
+        }
+        Ok(pols)
+    }
+
+    /// Maps the committed or constant polynomials of `pil`.
+    pub fn from_pil(fileName: &str, pil: &PIL, kind: PolKind) -> Result<Self> {
+        let (nPols, type_) = match kind {
+            PolKind::Commit => (pil.nCommitments, "cmP"),
+            PolKind::Constant => (pil.nConstants, "constP"),
+        };
+        let n = match pil.references.values().find(|r| r.type_ == type_) {
+            Some(r) => r.polDeg,
+            None => 0,
+        };
+        Self::open(fileName, nPols, n)
+    }
+
+    /// The raw words of the file, `nPols * n` canonical values.
+    pub fn as_slice(&self) -> &[u64] {
+        match &self.mmap {
+            // mmap is page aligned, and the length has been checked on open
+            Some(mmap) => unsafe {
+                std::slice::from_raw_parts(mmap.as_ptr() as *const u64, self.nPols * self.n)
+            },
+            None => &[],
+        }
+    }
+
+    /// The canonical values of row i.
+    #[inline(always)]
+    pub fn row(&self, i: usize) -> &[u64] {
+        &self.as_slice()[i * self.nPols..(i + 1) * self.nPols]
+    }
+}
+
+impl PolsRows for PolsMmap {
+    fn n_pols(&self) -> usize {
+        self.nPols
+    }
+
+    fn n(&self) -> usize {
+        self.n
+    }
+
+    #[inline(always)]
+    fn get_row(&self, i: usize, j: usize) -> FGL {
+        FGL::from(self.as_slice()[i * self.nPols + j])
+    }
+}
+
+fn check_file_size(fileName: &str, size: u64, nPols: usize, n: usize) -> Result<()> {
+    let expected = (nPols * n * std::mem::size_of::<u64>()) as u64;
+    if size != expected {
+        bail!(
+            "Invalid size of {}: {} bytes, expected {} pols x {} rows x 8 bytes",
+            fileName,
+            size,
+            nPols,
+            n
+        );
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::f3g::F3G;
     use crate::types;
 
     #[test]
@@ -241,8 +382,44 @@ pub mod tests {
         cp.save(file).unwrap();
 
         let mut cmp = PolsArray::new(&pil, PolKind::Commit);
-        cmp.load("data/fib.exec").unwrap();
-        let file = File::create("data/fib.exec.cp").unwrap();
+        cmp.load("data/fib.cm").unwrap();
+        let file = File::create("data/fib.cm.cp").unwrap();
         cmp.save(file).unwrap();
     }
+
+    #[test]
+    fn test_mmap_polsarray() {
+        let pil = types::load_json::<PIL>("data/fib.pil.json").unwrap();
+        let mut cp = PolsArray::new(&pil, PolKind::Constant);
+        cp.load("data/fib.const").unwrap();
+
+        let mp = PolsMmap::from_pil("data/fib.const", &pil, PolKind::Constant).unwrap();
+        assert_eq!((mp.nPols, mp.n), (cp.nPols, cp.n));
+        for i in 0..cp.n {
+            for j in 0..cp.nPols {
+                assert_eq!(mp.get_row(i, j), cp.array[j][i]);
+                assert_eq!(FGL::from(mp.row(i)[j]), cp.array[j][i]);
+            }
+        }
+        let expect: Vec<F3G> = cp.write_buff();
+        let actual: Vec<F3G> = mp.write_buff();
+        assert_eq!(expect, actual);
+
+        // the size of the file doesn't match the pil
+        assert!(PolsMmap::open("data/fib.const", cp.nPols, cp.n * 2).is_err());
+        let mut cp2 = PolsArray::new(&pil, PolKind::Commit);
+        assert!(cp2.load("data/fib.pil.json").is_err());
+    }
+
+    #[test]
+    fn test_mmap_non_canonical() {
+        let path = std::env::temp_dir().join("starky_non_canonical.cm");
+        let words: Vec<u64> = vec![1, 2, 3, MODULUS.0[0], 5, 6];
+        let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        std::fs::write(&path, bytes).unwrap();
+
+        let err = PolsMmap::open(path.to_str().unwrap(), 2, 3).unwrap_err();
+        assert!(err.to_string().contains("row 1, pol 1"));
+        std::fs::remove_file(path).unwrap();
+    }
 }
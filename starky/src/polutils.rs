@@ -1,5 +1,7 @@
 use crate::constant::SHIFT;
 use crate::fft::FFT;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::traits::FieldExtension;
 
 pub fn pol_mul_axi<F: FieldExtension>(p: &mut [F], init: F, acc: &F) {
@@ -1,3 +1,6 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
 #[allow(clippy::type_complexity)]
 pub fn constants() -> (Vec<u64>, Vec<u64>, Vec<u64>, Vec<u64>) {
     let c_str: Vec<u64> = vec![
@@ -2,6 +2,8 @@
 
 use crate::constant::POSEIDON_CONSTANTS_OPT;
 use crate::poseidon_constants_opt as constants;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use anyhow::bail;
 use anyhow::Result;
 use fields::field_gl::Fr as FGL;
@@ -3,10 +3,12 @@ use crate::{
     merklehash_bls12381::MerkleTreeBLS12381,
     merklehash_bn128::MerkleTreeBN128,
     pil2circom,
-    polsarray::{PolKind, PolsArray},
+    polsarray::{PolKind, PolsMmap},
+    prover_config::ProverConfig,
     stark_gen::StarkProof,
     stark_setup::StarkSetup,
     stark_verify::stark_verify,
+    starkinfo::ImPolsSelection,
     traits::{MerkleTree, Transcript},
     transcript::TranscriptGL,
     transcript_bls12381::TranscriptBLS128,
@@ -38,13 +40,45 @@ pub fn stark_prove(
     circom_file: &str,
     zkin: &str,
     prover_addr: &str,
+    im_pols: ImPolsSelection,
+    config: &ProverConfig,
 ) -> Result<()> {
-    let mut pil = load_json::<PIL>(pil_file)?;
-    let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
-    const_pol.load(const_pol_file)?;
+    config.install(|| {
+        stark_prove_inner(
+            stark_struct,
+            pil_file,
+            norm_stage,
+            skip_main,
+            agg_stage,
+            const_pol_file,
+            cm_pol_file,
+            circom_file,
+            zkin,
+            prover_addr,
+            im_pols,
+            config,
+        )
+    })
+}
 
-    let mut cm_pol = PolsArray::new(&pil, PolKind::Commit);
-    cm_pol.load(cm_pol_file)?;
+#[allow(clippy::too_many_arguments)]
+fn stark_prove_inner(
+    stark_struct: &str,
+    pil_file: &str,
+    norm_stage: bool,
+    skip_main: bool,
+    agg_stage: bool,
+    const_pol_file: &str,
+    cm_pol_file: &str,
+    circom_file: &str,
+    zkin: &str,
+    prover_addr: &str,
+    im_pols: ImPolsSelection,
+    config: &ProverConfig,
+) -> Result<()> {
+    let mut pil = load_json::<PIL>(pil_file)?;
+    let const_pol = PolsMmap::from_pil(const_pol_file, &pil, PolKind::Constant)?;
+    let cm_pol = PolsMmap::from_pil(cm_pol_file, &pil, PolKind::Commit)?;
 
     let stark_struct = load_json::<StarkStruct>(stark_struct)?;
     let circom_file_writer = File::create(circom_file)?;
@@ -61,6 +95,8 @@ pub fn stark_prove(
             circom_file_writer,
             zkin_writer,
             prover_addr,
+            im_pols,
+            config,
         ),
         "BLS12381" => prove::<Fr_BLS12381, MerkleTreeBLS12381, TranscriptBLS128, _>(
             &mut pil,
@@ -73,6 +109,8 @@ pub fn stark_prove(
             circom_file_writer,
             zkin_writer,
             prover_addr,
+            im_pols,
+            config,
         ),
         "GL" => prove::<FGL, MerkleTreeGL, TranscriptGL, _>(
             &mut pil,
@@ -85,6 +123,8 @@ pub fn stark_prove(
             circom_file_writer,
             zkin_writer,
             prover_addr,
+            im_pols,
+            config,
         ),
         _ => panic!("Invalid hashtype {}", stark_struct.verificationHashType),
     }
@@ -99,8 +139,8 @@ fn prove<
     W: Write,
 >(
     pil: &mut PIL,
-    const_pol: PolsArray,
-    cm_pol: PolsArray,
+    const_pol: PolsMmap,
+    cm_pol: PolsMmap,
     stark_struct: &StarkStruct,
     agg_stage: bool,
     norm_stage: bool,
@@ -108,8 +148,11 @@ fn prove<
     mut circom_file_writer: W,
     mut zkin_writer: W,
     prover_addr: &str,
+    im_pols: ImPolsSelection,
+    config: &ProverConfig,
 ) -> Result<()> {
-    let mut setup = StarkSetup::<M>::new(&const_pol, pil, stark_struct, None)?;
+    let mut setup =
+        StarkSetup::<M>::with_im_pols(&const_pol, pil, stark_struct, None, im_pols, config)?;
     let starkproof = StarkProof::<M>::stark_gen::<T>(
         cm_pol,
         const_pol,
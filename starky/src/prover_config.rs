@@ -0,0 +1,2 @@
+//! `ProverConfig` lives in the `prover_config` crate, shared with `groth16`.
+pub use ::prover_config::ProverConfig;
@@ -1,22 +1,26 @@
 // input json of plonk
 #![allow(non_snake_case)]
 use crate::f3g::F3G;
+#[cfg(feature = "std")]
 use crate::f5g::F5G;
 use crate::fri::FRIProof;
 use crate::fri::Query;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::stark_gen::StarkProof;
 use crate::traits::FieldExtension;
 use crate::traits::{MTNodeType, MerkleTree};
+use core::any::TypeId;
+use core::fmt;
+use core::marker::PhantomData;
 use fields::field_gl::Fr as FGL;
 use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 use serde::{
     de::{self, MapAccess, SeqAccess, Visitor},
     Deserialize, Deserializer,
 };
-use std::any::TypeId;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::fmt;
-use std::marker::PhantomData;
 
 impl Serialize for F3G {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -58,9 +62,12 @@ impl<'de> Deserialize<'de> for F3G {
             {
                 let mut entries = Vec::new();
                 while let Some(entry) = seq.next_element::<String>()? {
-                    let entry: u64 = entry.parse().unwrap();
+                    let entry: u64 = entry.parse().map_err(de::Error::custom)?;
                     entries.push(FGL::from(entry));
                 }
+                if entries.len() != 3 {
+                    return Err(de::Error::invalid_length(entries.len(), &self));
+                }
                 Ok(F3G::from_vec(entries))
             }
 
@@ -68,7 +75,7 @@ impl<'de> Deserialize<'de> for F3G {
             where
                 E: de::Error,
             {
-                let ien: u64 = s.parse().unwrap();
+                let ien: u64 = s.parse().map_err(de::Error::custom)?;
                 Ok(F3G::from(ien))
             }
         }
@@ -76,6 +83,7 @@ impl<'de> Deserialize<'de> for F3G {
     }
 }
 
+#[cfg(feature = "std")]
 impl Serialize for F5G {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -96,6 +104,7 @@ impl Serialize for F5G {
     }
 }
 
+#[cfg(feature = "std")]
 impl<'de> Deserialize<'de> for F5G {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -143,8 +152,8 @@ impl<M: MerkleTree> Serialize for StarkProof<M> {
         let len = 16 + (self.fri_proof.queries.len() - 1) * 3;
         let mut map = serializer.serialize_map(Some(len))?;
 
-        if self.rootC.is_some() {
-            map.serialize_entry("rootC", &self.rootC.unwrap())?;
+        if let Some(root) = &self.rootC {
+            map.serialize_entry("rootC", root)?;
         }
 
         map.serialize_entry("root1", &self.root1)?;
@@ -264,6 +273,16 @@ impl<M: MerkleTree> Serialize for StarkProof<M> {
     }
 }
 
+/// Deserializes the entry `key` of a proof, an error instead of a panic when it is missing or
+/// malformed, as the proof may come from an untrusted caller.
+fn field<T: de::DeserializeOwned, E: de::Error>(
+    map: &HashMap<String, serde_json::Value>,
+    key: &str,
+) -> Result<T, E> {
+    let value = map.get(key).ok_or_else(|| E::custom(format!("missing field `{key}`")))?;
+    serde_json::from_value(value.clone()).map_err(E::custom)
+}
+
 impl<'de, T: MerkleTree + Default> Deserialize<'de> for StarkProof<T> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -287,36 +306,24 @@ impl<'de, T: MerkleTree + Default> Deserialize<'de> for StarkProof<T> {
                 while let Some((key, value)) = access.next_entry()? {
                     map.insert(key, value);
                 }
-                let mut sp: StarkProof<MT> = Default::default();
-                let root: MT::MTNode =
-                    serde_json::from_value(map.get("root1").unwrap().clone()).unwrap();
-                sp.root1 = root;
-
-                let root: MT::MTNode =
-                    serde_json::from_value(map.get("root2").unwrap().clone()).unwrap();
-                sp.root2 = root;
-
-                let root: MT::MTNode =
-                    serde_json::from_value(map.get("root3").unwrap().clone()).unwrap();
-                sp.root3 = root;
-
-                let root: MT::MTNode =
-                    serde_json::from_value(map.get("root4").unwrap().clone()).unwrap();
-                sp.root4 = root;
-
-                let root = map.get("rootC");
-                if let Some(root_value) = root {
-                    let root: MT::MTNode = serde_json::from_value(root_value.clone()).unwrap();
-                    sp.rootC = Some(root);
+                let mut sp: StarkProof<MT> = StarkProof {
+                    root1: field(&map, "root1")?,
+                    root2: field(&map, "root2")?,
+                    root3: field(&map, "root3")?,
+                    root4: field(&map, "root4")?,
+                    ..Default::default()
+                };
+
+                if map.contains_key("rootC") {
+                    sp.rootC = Some(field(&map, "rootC")?);
                 }
 
-                let prover_addr = map.get("proverAddr");
-                if let Some(prover_addr_value) = prover_addr {
-                    sp.prover_addr = serde_json::from_value(prover_addr_value.clone()).unwrap();
+                if map.contains_key("proverAddr") {
+                    sp.prover_addr = field(&map, "proverAddr")?;
                 }
-                sp.evals = serde_json::from_value(map.get("evals").unwrap().clone()).unwrap();
+                sp.evals = field(&map, "evals")?;
 
-                sp.publics = serde_json::from_value(map.get("publics").unwrap().clone()).unwrap();
+                sp.publics = field(&map, "publics")?;
 
                 let mut fri_proof: FRIProof<MT::ExtendField, MT> = FRIProof::default();
 
@@ -341,15 +348,13 @@ impl<'de, T: MerkleTree + Default> Deserialize<'de> for StarkProof<T> {
                 let mut s0_siblings_all: Vec<Vec<Vec<Vec<MT::MTNode>>>> = vec![];
                 // handle queries[0]
                 for j in ["1", "2", "3", "4", "C"] {
-                    let key = map.get(&format!("s0_vals{j}"));
-                    if key.is_none() {
+                    if !map.contains_key(&format!("s0_vals{j}")) {
                         log::info!("skip s0_vals{}", j);
                         s0_vals_all.push(vec![]);
                         s0_siblings_all.push(vec![]);
                         continue;
                     }
-                    let s0_vals: Vec<Vec<F3G>> =
-                        serde_json::from_value(key.unwrap().clone()).unwrap();
+                    let s0_vals: Vec<Vec<F3G>> = field(&map, &format!("s0_vals{j}"))?;
                     let s0_vals: Vec<Vec<FGL>> = s0_vals
                         .iter()
                         .map(|e| {
@@ -364,9 +369,11 @@ impl<'de, T: MerkleTree + Default> Deserialize<'de> for StarkProof<T> {
                         })
                         .collect();
 
-                    let key = map.get(&format!("s0_siblings{j}"));
                     let s0_siblings: Vec<Vec<Vec<MT::MTNode>>> =
-                        serde_json::from_value(key.unwrap().clone()).unwrap();
+                        field(&map, &format!("s0_siblings{j}"))?;
+                    if s0_siblings.len() != s0_vals.len() {
+                        return Err(de::Error::invalid_length(s0_siblings.len(), &self));
+                    }
                     s0_vals_all.push(s0_vals);
                     s0_siblings_all.push(s0_siblings);
                 }
@@ -408,12 +415,9 @@ impl<'de, T: MerkleTree + Default> Deserialize<'de> for StarkProof<T> {
 
                 // handle query 1 to num_query
                 for i in 1..=num_query {
-                    let key = map.get(&format!("s{i}_root"));
-                    let root: MT::MTNode = serde_json::from_value(key.unwrap().clone()).unwrap();
-                    fri_proof.queries[i].root = root;
+                    fri_proof.queries[i].root = field(&map, &format!("s{i}_root"))?;
 
-                    let key = map.get(&format!("s{i}_vals"));
-                    let val: Vec<Vec<F3G>> = serde_json::from_value(key.unwrap().clone()).unwrap();
+                    let val: Vec<Vec<F3G>> = field(&map, &format!("s{i}_vals"))?;
                     let vals: Vec<Vec<FGL>> = val
                         .iter()
                         .map(|e| {
@@ -428,9 +432,10 @@ impl<'de, T: MerkleTree + Default> Deserialize<'de> for StarkProof<T> {
                         })
                         .collect();
 
-                    let key = map.get(&format!("s{i}_siblings"));
-                    let sibs: Vec<Vec<Vec<MT::MTNode>>> =
-                        serde_json::from_value(key.unwrap().clone()).unwrap();
+                    let sibs: Vec<Vec<Vec<MT::MTNode>>> = field(&map, &format!("s{i}_siblings"))?;
+                    if vals.len() != num_pol_queries || sibs.len() != num_pol_queries {
+                        return Err(de::Error::invalid_length(vals.len().min(sibs.len()), &self));
+                    }
                     fri_proof.queries[i].pol_queries = vec![vec![]; num_pol_queries];
                     for q in 0..num_pol_queries {
                         let node_to_bf = crate::traits::mt_node_to_basefield::<MT>(&sibs[q]);
@@ -439,8 +444,7 @@ impl<'de, T: MerkleTree + Default> Deserialize<'de> for StarkProof<T> {
                 }
 
                 // handle finalPol
-                let key = map.get("finalPol");
-                fri_proof.last = serde_json::from_value(key.unwrap().clone()).unwrap();
+                fri_proof.last = field(&map, "finalPol")?;
                 sp.fri_proof = fri_proof;
                 Ok(sp)
             }
@@ -461,6 +465,7 @@ mod tests {
     use crate::merklehash_bn128::MerkleTreeBN128;
     use crate::polsarray::PolKind;
     use crate::polsarray::PolsArray;
+    use crate::prover_config::ProverConfig;
     use crate::serializer::StarkProof;
     use crate::stark_setup::StarkSetup;
     use crate::traits::FieldExtension;
@@ -558,8 +563,14 @@ mod tests {
         cm_pol.load("data/fib.cm").unwrap();
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
 
-        let setup =
-            StarkSetup::<MerkleTreeBN128>::new(&const_pol, &mut pil, &stark_struct, None).unwrap();
+        let setup = StarkSetup::<MerkleTreeBN128>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
         //let fr_root: Fr = Fr(setup.const_root.as_scalar::<Fr>());
 
         let starkproof = StarkProof::<MerkleTreeBN128>::stark_gen::<TranscriptBN128>(
@@ -607,8 +618,14 @@ mod tests {
         cm_pol.load("data/fib.cm.gl").unwrap();
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json.gl").unwrap();
 
-        let setup =
-            StarkSetup::<MerkleTreeGL>::new(&const_pol, &mut pil, &stark_struct, None).unwrap();
+        let setup = StarkSetup::<MerkleTreeGL>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
         //let fr_root: Fr = Fr(setup.const_root.as_scalar::<Fr>());
 
         let starkproof = StarkProof::<MerkleTreeGL>::stark_gen::<TranscriptGL>(
@@ -657,9 +674,14 @@ mod tests {
         cm_pol.load("data/fib.cm").unwrap();
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json.bls12381").unwrap();
 
-        let setup =
-            StarkSetup::<MerkleTreeBLS12381>::new(&const_pol, &mut pil, &stark_struct, None)
-                .unwrap();
+        let setup = StarkSetup::<MerkleTreeBLS12381>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
         //let fr_root: Fr = Fr(setup.const_root.as_scalar::<Fr>());
 
         let starkproof = StarkProof::<MerkleTreeBLS12381>::stark_gen::<TranscriptBLS128>(
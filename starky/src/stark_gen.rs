@@ -1,23 +1,45 @@
 #![allow(non_snake_case, dead_code)]
 #![allow(clippy::needless_range_loop)]
 
-use crate::constant::{get_max_workers, MAX_OPS_PER_THREAD, MG, MIN_OPS_PER_THREAD, SHIFT};
+#[cfg(feature = "std")]
+use crate::constant::{
+    get_max_workers, max_ops_per_thread, min_ops_per_thread, MG, SHIFT, SHIFT_INV,
+};
+#[cfg(feature = "std")]
 use crate::fft::FFT;
-use crate::fft_p::{fft, ifft, interpolate};
+#[cfg(feature = "std")]
+use crate::fiat_shamir::init_transcript;
 use crate::fri::FRIProof;
+#[cfg(feature = "std")]
 use crate::fri::FRI;
 use crate::helper::pretty_print_array;
+#[cfg(feature = "std")]
 use crate::interpreter::compile_code;
-use crate::polsarray::PolsArray;
+#[cfg(feature = "std")]
+use crate::ntt_gl::{coset_lde_rows, intt, ntt};
+#[cfg(feature = "std")]
+use crate::polsarray::PolsRows;
+#[cfg(feature = "std")]
 use crate::polutils::batch_inverse;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+#[cfg(feature = "std")]
 use crate::starkinfo::{Program, StarkInfo};
+#[cfg(feature = "std")]
 use crate::starkinfo_codegen::{Polynom, Segment};
-use crate::traits::{FieldExtension, MTNodeType, MerkleTree, Transcript};
+use crate::traits::{FieldExtension, MerkleTree};
+#[cfg(feature = "std")]
+use crate::traits::{MTNodeType, Transcript};
+#[cfg(feature = "std")]
 use crate::types::{StarkStruct, PIL};
+#[cfg(feature = "std")]
 use anyhow::Result;
 use fields::field_gl::Fr as FGL;
+#[cfg(feature = "std")]
 use profiler_macro::time_profiler;
+#[cfg(feature = "std")]
 use rayon::prelude::*;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
 
 pub struct StarkContext<F: FieldExtension> {
@@ -60,8 +82,8 @@ pub struct StarkContext<F: FieldExtension> {
     pub consts: Vec<FGL>,
 }
 
-impl<F: FieldExtension> std::fmt::Debug for StarkContext<F> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<F: FieldExtension> core::fmt::Debug for StarkContext<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         writeln!(f, "n {}", self.N)?;
         writeln!(f, "nBits {}", self.nbits)?;
         writeln!(f, "nBitsExt {}", self.nbits_ext)?;
@@ -187,12 +209,13 @@ pub struct StarkProof<M: MerkleTree> {
     pub prover_addr: String,
 }
 
+#[cfg(feature = "std")]
 impl<M: MerkleTree> StarkProof<M> {
     #[allow(clippy::too_many_arguments, clippy::type_complexity)]
     #[time_profiler()]
     pub fn stark_gen<T: Transcript>(
-        cm_pols: PolsArray,
-        const_pols: PolsArray,
+        cm_pols: impl PolsRows,
+        const_pols: impl PolsRows,
         const_tree: &M,
         starkinfo: &StarkInfo,
         program: &Program,
@@ -212,8 +235,8 @@ impl<M: MerkleTree> StarkProof<M> {
         let mut n_cm = starkinfo.n_cm1;
 
         log::trace!("Alloc context memory");
-        ctx.cm1_n = cm_pols.write_buff();
-        drop(cm_pols);
+        assert_eq!((cm_pols.n(), cm_pols.n_pols()), (ctx.N, starkinfo.map_sectionsN.cm1_n));
+        assert_eq!((const_pols.n(), const_pols.n_pols()), (ctx.N, starkinfo.n_constants));
 
         ctx.cm2_n = vec![M::ExtendField::ZERO; (starkinfo.map_sectionsN.cm2_n) * ctx.N];
         ctx.cm3_n = vec![M::ExtendField::ZERO; (starkinfo.map_sectionsN.cm3_n) * ctx.N];
@@ -248,21 +271,27 @@ impl<M: MerkleTree> StarkProof<M> {
 
         ctx.Zi = build_Zh_Inv::<M::ExtendField>(ctx.nbits, extend_bits, 0);
 
-        log::trace!("Convert const pols to array");
-        ctx.const_n = const_pols.write_buff();
         const_tree.to_extend(&mut ctx.const_2ns);
-        drop(const_pols);
 
         ctx.publics = vec![M::ExtendField::ZERO; starkinfo.publics.len()];
         for (i, pe) in starkinfo.publics.iter().enumerate() {
             if pe.polType.as_str() == "cmP" {
-                ctx.publics[i] = ctx.cm1_n[pe.idx * starkinfo.map_sectionsN.cm1_n + pe.polId];
+                ctx.publics[i] = M::ExtendField::from(cm_pols.get_row(pe.idx, pe.polId));
             } else if pe.polType.as_str() == "imP" {
+                // the rows idx and idx + 1 of the trace, evaluated at 0
+                let mut pub_ctx = StarkContext::<M::ExtendField> {
+                    N: ctx.N,
+                    nbits: ctx.nbits,
+                    nbits_ext: ctx.nbits_ext,
+                    cm1_n: trace_rows(&cm_pols, pe.idx, 2),
+                    const_n: trace_rows(&const_pols, pe.idx, 2),
+                    ..Default::default()
+                };
                 ctx.publics[i] = Self::calculate_exp_at_point::<M::ExtendField>(
-                    &mut ctx,
+                    &mut pub_ctx,
                     starkinfo,
                     &program.publics_code[i],
-                    pe.idx,
+                    0,
                 );
             } else {
                 panic!("Invalid public type {}", pe.polType);
@@ -270,6 +299,7 @@ impl<M: MerkleTree> StarkProof<M> {
         }
 
         let mut transcript = T::new();
+        init_transcript(&mut transcript, stark_struct, starkinfo, &const_tree.root())?;
         for i in 0..starkinfo.publics.len() {
             let b =
                 ctx.publics[i].as_elements().iter().map(|e| vec![*e]).collect::<Vec<Vec<FGL>>>();
@@ -279,7 +309,7 @@ impl<M: MerkleTree> StarkProof<M> {
         //Do pre-allocation
         let mut result = vec![M::ExtendField::ZERO; (1 << stark_struct.nBitsExt) * 8];
         log::trace!("Merkelizing 1....");
-        let tree1 = extend_and_merkelize::<M>(&mut ctx, starkinfo, "cm1_n", &mut result)?;
+        let tree1 = extend_and_merkelize_rows::<M>(&cm_pols, ctx.nbits, ctx.nbits_ext)?;
         tree1.to_extend(&mut ctx.cm1_2ns);
 
         log::trace!(
@@ -295,7 +325,14 @@ impl<M: MerkleTree> StarkProof<M> {
         log::trace!("challenge[0] {}", ctx.challenge[0]);
         log::trace!("challenge[1] {}", ctx.challenge[1]);
 
-        calculate_exps_parallel(&mut ctx, starkinfo, &program.step2prev, "n", "step2prev");
+        calculate_exps_parallel(
+            &mut ctx,
+            starkinfo,
+            &program.step2prev,
+            "n",
+            "step2prev",
+            (&cm_pols, &const_pols),
+        );
 
         for pu in starkinfo.pu_ctx.iter() {
             let f_pol = get_pol(&mut ctx, starkinfo, starkinfo.exp2pol[&pu.f_exp_id]);
@@ -324,7 +361,14 @@ impl<M: MerkleTree> StarkProof<M> {
         log::trace!("challenge[2] {}", ctx.challenge[2]);
         log::trace!("challenge[3] {}", ctx.challenge[3]);
 
-        calculate_exps_parallel(&mut ctx, starkinfo, &program.step3prev, "n", "step3prev");
+        calculate_exps_parallel(
+            &mut ctx,
+            starkinfo,
+            &program.step3prev,
+            "n",
+            "step3prev",
+            (&cm_pols, &const_pols),
+        );
 
         for (i, pu) in starkinfo.pu_ctx.iter().enumerate() {
             log::trace!("Calculating z for plookup {}", i);
@@ -352,7 +396,14 @@ impl<M: MerkleTree> StarkProof<M> {
             n_cm += 1;
         }
 
-        calculate_exps_parallel(&mut ctx, starkinfo, &program.step3, "n", "step3");
+        calculate_exps_parallel(
+            &mut ctx,
+            starkinfo,
+            &program.step3,
+            "n",
+            "step3",
+            (&cm_pols, &const_pols),
+        );
 
         log::trace!("Merkelizing 3....");
 
@@ -369,15 +420,24 @@ impl<M: MerkleTree> StarkProof<M> {
         // 4. Compute C Polynomial
         ctx.challenge[4] = transcript.get_field(); // vc
 
-        calculate_exps_parallel(&mut ctx, starkinfo, &program.step42ns, "2ns", "step4");
+        calculate_exps_parallel(
+            &mut ctx,
+            starkinfo,
+            &program.step42ns,
+            "2ns",
+            "step4",
+            (&cm_pols, &const_pols),
+        );
 
         log::trace!("Calculate c polynomial");
-        let mut qq1 = vec![M::ExtendField::ZERO; ctx.q_2ns.len()];
-        let mut qq2 = vec![M::ExtendField::ZERO; starkinfo.q_dim * ctx.Next * starkinfo.q_deg];
-        ifft(&ctx.q_2ns, starkinfo.q_dim, ctx.nbits_ext, &mut qq1);
+        // the q_dim components of q are base field elements, transformed by the GL NTT engine
+        let q_be: Vec<FGL> = ctx.q_2ns.par_iter().map(|e| e.to_be()).collect();
+        let mut qq1 = vec![FGL::ZERO; q_be.len()];
+        let mut qq2 = vec![FGL::ZERO; starkinfo.q_dim * ctx.Next * starkinfo.q_deg];
+        intt(&q_be, starkinfo.q_dim, ctx.nbits_ext, &mut qq1);
 
-        let mut cur_s = M::ExtendField::ONE;
-        let shift_inv = (M::ExtendField::inv(&shift_ext)).exp(ctx.N);
+        let mut cur_s = FGL::ONE;
+        let shift_inv = SHIFT_INV.exp(ctx.N as u64);
 
         log::trace!("Calculate qq2");
         for p in 0..starkinfo.q_deg {
@@ -387,12 +447,14 @@ impl<M: MerkleTree> StarkProof<M> {
                         qq1[p * ctx.N * starkinfo.q_dim + i * starkinfo.q_dim + k] * cur_s;
                 }
             }
-            cur_s *= shift_inv;
+            cur_s = cur_s * shift_inv;
         }
 
         // powdr may produce constant polynomial only
         if starkinfo.q_deg > 0 {
-            fft(&qq2, starkinfo.q_dim * starkinfo.q_deg, ctx.nbits_ext, &mut ctx.cm4_2ns);
+            let mut cm4_be = vec![FGL::ZERO; ctx.cm4_2ns.len()];
+            ntt(&qq2, starkinfo.q_dim * starkinfo.q_deg, ctx.nbits_ext, &mut cm4_be);
+            ctx.cm4_2ns.par_iter_mut().zip(cm4_be).for_each(|(out, e)| *out = e.into());
         }
 
         log::trace!("Merkelizing 4....");
@@ -520,7 +582,14 @@ impl<M: MerkleTree> StarkProof<M> {
                 xxwx[1] = vw[1];
                 xxwx[2] = vw[2];
             });
-        calculate_exps_parallel(&mut ctx, starkinfo, &program.step52ns, "2ns", "step5");
+        calculate_exps_parallel(
+            &mut ctx,
+            starkinfo,
+            &program.step52ns,
+            "2ns",
+            "step5",
+            (&cm_pols, &const_pols),
+        );
 
         let mut fri_pol = vec![M::ExtendField::ZERO; N << extend_bits];
         fri_pol.par_iter_mut().enumerate().for_each(|(i, o)| {
@@ -572,6 +641,7 @@ impl<M: MerkleTree> StarkProof<M> {
     }
 }
 
+#[cfg(feature = "std")]
 pub fn build_Zh_Inv<T: FieldExtension>(
     nBits: usize,
     extend_bits: usize,
@@ -591,6 +661,7 @@ pub fn build_Zh_Inv<T: FieldExtension>(
     Box::new(move |i: usize| ZHInv[(i + offset) % ZHInv.len()])
 }
 
+#[cfg(feature = "std")]
 fn set_pol<F: FieldExtension>(
     ctx: &mut StarkContext<F>,
     starkinfo: &StarkInfo,
@@ -621,6 +692,7 @@ fn set_pol<F: FieldExtension>(
     }
 }
 
+#[cfg(feature = "std")]
 #[time_profiler("calculate_H1H2")]
 fn calculate_H1H2<F: FieldExtension>(f: Vec<F>, t: Vec<F>) -> (Vec<F>, Vec<F>) {
     let mut idx_t: HashMap<F, usize> = HashMap::with_capacity(t.len());
@@ -650,6 +722,7 @@ fn calculate_H1H2<F: FieldExtension>(f: Vec<F>, t: Vec<F>) -> (Vec<F>, Vec<F>) {
     (h1, h2)
 }
 
+#[cfg(feature = "std")]
 fn calculate_Z<F: FieldExtension>(num: Vec<F>, den: Vec<F>) -> Vec<F> {
     let N = num.len();
     assert_eq!(N, den.len());
@@ -665,6 +738,7 @@ fn calculate_Z<F: FieldExtension>(num: Vec<F>, den: Vec<F>) -> Vec<F> {
     z
 }
 
+#[cfg(feature = "std")]
 fn get_pol_ref<'a, F: FieldExtension>(
     ctx: &'a mut StarkContext<F>,
     starkinfo: &StarkInfo,
@@ -680,6 +754,7 @@ fn get_pol_ref<'a, F: FieldExtension>(
     }
 }
 
+#[cfg(feature = "std")]
 pub fn get_pol<F: FieldExtension>(
     ctx: &mut StarkContext<F>,
     starkinfo: &StarkInfo,
@@ -706,6 +781,28 @@ pub fn get_pol<F: FieldExtension>(
     res
 }
 
+/// `extend_and_merkelize` of the rows of the trace, read in place.
+#[cfg(feature = "std")]
+#[time_profiler("extend_and_merkelize_rows")]
+pub fn extend_and_merkelize_rows<M: MerkleTree>(
+    pols: &impl PolsRows,
+    nbits: usize,
+    nbits_ext: usize,
+) -> Result<M> {
+    let n_pols = pols.n_pols();
+    let mut p_be = vec![FGL::ZERO; (1 << nbits_ext) * n_pols];
+    let read_row = |r: usize, out: &mut [FGL]| {
+        for (j, e) in out.iter_mut().enumerate() {
+            *e = pols.get_row(r, j);
+        }
+    };
+    coset_lde_rows(read_row, n_pols, nbits, &mut p_be, nbits_ext);
+    let mut tree = M::new();
+    tree.merkelize(p_be, n_pols, 1 << nbits_ext)?;
+    Ok(tree)
+}
+
+#[cfg(feature = "std")]
 #[time_profiler("extend_and_merkelize")]
 pub fn extend_and_merkelize<M: MerkleTree>(
     ctx: &mut StarkContext<M::ExtendField>,
@@ -720,17 +817,22 @@ pub fn extend_and_merkelize<M: MerkleTree>(
     let curr_size = (1 << nBitsExt) * n_pols;
     result.resize(curr_size, M::ExtendField::ZERO);
 
-    let p = ctx.get_mut(section_name);
-    interpolate(p, n_pols, nBits, result, nBitsExt);
-    let mut p_be = vec![FGL::ZERO; result.len()];
-    p_be.par_iter_mut().zip(result).for_each(|(be_out, f3g_in)| {
-        *be_out = f3g_in.to_be();
-    });
+    // the sections hold base field elements, extended by the GL NTT engine
+    let p = &*ctx.get_mut(section_name);
+    let read_row = |r: usize, out: &mut [FGL]| {
+        for (j, e) in out.iter_mut().enumerate() {
+            *e = p[r * n_pols + j].to_be();
+        }
+    };
+    let mut p_be = vec![FGL::ZERO; curr_size];
+    coset_lde_rows(read_row, n_pols, nBits, &mut p_be, nBitsExt);
+    result.par_iter_mut().zip(&p_be).for_each(|(out, e)| *out = M::ExtendField::from(*e));
     let mut tree = M::new();
     tree.merkelize(p_be, n_pols, 1 << nBitsExt)?;
     Ok(tree)
 }
 
+#[cfg(feature = "std")]
 #[time_profiler("merkelize")]
 pub fn merkelize<M: MerkleTree>(
     ctx: &mut StarkContext<M::ExtendField>,
@@ -749,6 +851,7 @@ pub fn merkelize<M: MerkleTree>(
     Ok(tree)
 }
 
+#[cfg(feature = "std")]
 pub fn calculate_exps<F: FieldExtension>(
     ctx: &mut StarkContext<F>,
     starkinfo: &StarkInfo,
@@ -782,6 +885,7 @@ pub fn calculate_exps<F: FieldExtension>(
     }
 }
 
+#[cfg(feature = "std")]
 #[time_profiler()]
 pub fn calculate_exps_parallel<F: FieldExtension>(
     ctx: &mut StarkContext<F>,
@@ -789,6 +893,7 @@ pub fn calculate_exps_parallel<F: FieldExtension>(
     seg: &Segment,
     _dom: &str,
     step: &str,
+    trace: (&impl PolsRows, &impl PolsRows),
 ) {
     #[derive(Debug)]
     struct ExecItem {
@@ -885,11 +990,11 @@ pub fn calculate_exps_parallel<F: FieldExtension>(
     let next = if dom == "n" { 1 } else { 1 << extend_bits };
 
     let mut n_per_thread = (n - 1) / get_max_workers() + 1;
-    if n_per_thread > MAX_OPS_PER_THREAD {
-        n_per_thread = MAX_OPS_PER_THREAD
+    if n_per_thread > max_ops_per_thread() {
+        n_per_thread = max_ops_per_thread()
     };
-    if n_per_thread < MIN_OPS_PER_THREAD {
-        n_per_thread = MIN_OPS_PER_THREAD
+    if n_per_thread < min_ops_per_thread() {
+        n_per_thread = min_ops_per_thread()
     };
 
     let mut ctx_chunks: Vec<StarkContext<F>> = vec![];
@@ -920,6 +1025,10 @@ pub fn calculate_exps_parallel<F: FieldExtension>(
                 for j in 0..(next * si.width) {
                     tmp[cur_n * si.width + j] = ori_sec[((i + cur_n) % n) * si.width + j]
                 }
+            } else if si.name.as_str() == "cm1_n" {
+                tmp_ctx.cm1_n = trace_rows(trace.0, i, cur_n + next);
+            } else if si.name.as_str() == "const_n" {
+                tmp_ctx.const_n = trace_rows(trace.1, i, cur_n + next);
             } else {
                 let tmp = tmp_ctx.get_mut(si.name.as_str());
                 // for field extension GL(p^3)
@@ -962,15 +1071,33 @@ pub fn calculate_exps_parallel<F: FieldExtension>(
     }
 }
 
+/// Rows `start..start + len` of the trace, wrapping around at its end.
+#[cfg(feature = "std")]
+fn trace_rows<F: FieldExtension>(pols: &impl PolsRows, start: usize, len: usize) -> Vec<F> {
+    let (n, n_pols) = (pols.n(), pols.n_pols());
+    let mut buff = vec![F::ZERO; len * n_pols];
+    if n_pols == 0 {
+        return buff;
+    }
+    buff.par_chunks_mut(n_pols).enumerate().for_each(|(k, row)| {
+        for (j, e) in row.iter_mut().enumerate() {
+            *e = F::from(pols.get_row((start + k) % n, j));
+        }
+    });
+    buff
+}
+
 #[cfg(test)]
 pub mod tests {
     use crate::field_bn128::Fr;
     use crate::merklehash::MerkleTreeGL;
     use crate::merklehash_bn128::MerkleTreeBN128;
     use crate::polsarray::{PolKind, PolsArray};
+    use crate::prover_config::ProverConfig;
     use crate::stark_gen::StarkProof;
     use crate::stark_setup::StarkSetup;
     use crate::stark_verify::stark_verify;
+    use crate::starkinfo::ImPolsSelection;
     use crate::traits::MTNodeType;
     use crate::transcript::TranscriptGL;
     use crate::transcript_bn128::TranscriptBN128;
@@ -994,8 +1121,14 @@ pub mod tests {
 
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
 
-        let setup =
-            StarkSetup::<MerkleTreeBN128>::new(&const_pol, &mut pil, &stark_struct, None).unwrap();
+        let setup = StarkSetup::<MerkleTreeBN128>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
         let serialized = serde_json::to_string(&setup).unwrap();
         let setup: StarkSetup<MerkleTreeBN128> = serde_json::from_str(&serialized).unwrap();
 
@@ -1038,8 +1171,14 @@ pub mod tests {
         cm_pol.load("data/pe.cm").unwrap();
 
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
-        let setup =
-            StarkSetup::<MerkleTreeBN128>::new(&const_pol, &mut pil, &stark_struct, None).unwrap();
+        let setup = StarkSetup::<MerkleTreeBN128>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
         let serialized = serde_json::to_string(&setup).unwrap();
         let setup: StarkSetup<MerkleTreeBN128> = serde_json::from_str(&serialized).unwrap();
 
@@ -1077,8 +1216,14 @@ pub mod tests {
         let mut cm_pol = PolsArray::new(&pil, PolKind::Commit);
         cm_pol.load("data/plookup.cm").unwrap();
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
-        let setup =
-            StarkSetup::<MerkleTreeBN128>::new(&const_pol, &mut pil, &stark_struct, None).unwrap();
+        let setup = StarkSetup::<MerkleTreeBN128>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
         let serialized = serde_json::to_string(&setup).unwrap();
         let setup: StarkSetup<MerkleTreeBN128> = serde_json::from_str(&serialized).unwrap();
         let starkproof = StarkProof::<MerkleTreeBN128>::stark_gen::<TranscriptBN128>(
@@ -1114,8 +1259,14 @@ pub mod tests {
         let mut cm_pol = PolsArray::new(&pil, PolKind::Commit);
         cm_pol.load("data/connection.cm").unwrap();
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
-        let setup_ =
-            StarkSetup::<MerkleTreeBN128>::new(&const_pol, &mut pil, &stark_struct, None).unwrap();
+        let setup_ = StarkSetup::<MerkleTreeBN128>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
 
         let serialized = serde_json::to_string(&setup_).unwrap();
         let setup: StarkSetup<MerkleTreeBN128> = serde_json::from_str(&serialized).unwrap();
@@ -1153,8 +1304,14 @@ pub mod tests {
         let mut cm_pol = PolsArray::new(&pil, PolKind::Commit);
         cm_pol.load("data/plookup.cm.gl").unwrap();
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json.gl").unwrap();
-        let setup_ =
-            StarkSetup::<MerkleTreeGL>::new(&const_pol, &mut pil, &stark_struct, None).unwrap();
+        let setup_ = StarkSetup::<MerkleTreeGL>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
 
         let serialized = serde_json::to_string(&setup_).unwrap();
         let setup: StarkSetup<MerkleTreeGL> = serde_json::from_str(&serialized).unwrap();
@@ -1193,4 +1350,96 @@ pub mod tests {
         .unwrap();
         assert!(result);
     }
+
+    #[test]
+    fn test_stark_strong_fiat_shamir() {
+        let mut pil = load_json::<PIL>("data/plookup.pil.json.gl").unwrap();
+        let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
+        const_pol.load("data/plookup.const.gl").unwrap();
+        let mut cm_pol = PolsArray::new(&pil, PolKind::Commit);
+        cm_pol.load("data/plookup.cm.gl").unwrap();
+        let weak = load_json::<StarkStruct>("data/starkStruct.json.gl").unwrap();
+        let mut stark_struct = weak.clone();
+        stark_struct.fiatShamirVersion = crate::fiat_shamir::FIAT_SHAMIR_STRONG;
+        let setup = StarkSetup::<MerkleTreeGL>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
+
+        let starkproof = StarkProof::<MerkleTreeGL>::stark_gen::<TranscriptGL>(
+            cm_pol,
+            const_pol,
+            &setup.const_tree,
+            &setup.starkinfo,
+            &setup.program,
+            &pil,
+            &stark_struct,
+            "273030697313060285579891744179749754319274977764",
+        )
+        .unwrap();
+        let result = stark_verify::<MerkleTreeGL, TranscriptGL>(
+            &starkproof,
+            &setup.const_root,
+            &setup.starkinfo,
+            &stark_struct,
+            &setup.program,
+        )
+        .unwrap();
+        assert!(result);
+
+        // the challenges differ without the circuit digest and the constant root
+        let result = stark_verify::<MerkleTreeGL, TranscriptGL>(
+            &starkproof,
+            &setup.const_root,
+            &setup.starkinfo,
+            &weak,
+            &setup.program,
+        );
+        assert!(!matches!(result, Ok(true)));
+    }
+
+    #[test]
+    fn test_stark_optimal_im_pols() {
+        let mut pil = load_json::<PIL>("data/connection.pil.json").unwrap();
+        let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
+        const_pol.load("data/connection.const").unwrap();
+        let mut cm_pol = PolsArray::new(&pil, PolKind::Commit);
+        cm_pol.load("data/connection.cm").unwrap();
+        let stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
+
+        let setup = StarkSetup::<MerkleTreeBN128>::with_im_pols(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            ImPolsSelection::Optimal,
+            &ProverConfig::default(),
+        )
+        .unwrap();
+
+        let starkproof = StarkProof::<MerkleTreeBN128>::stark_gen::<TranscriptBN128>(
+            cm_pol,
+            const_pol,
+            &setup.const_tree,
+            &setup.starkinfo,
+            &setup.program,
+            &pil,
+            &stark_struct,
+            "273030697313060285579891744179749754319274977764",
+        )
+        .unwrap();
+        let result = stark_verify::<MerkleTreeBN128, TranscriptBN128>(
+            &starkproof,
+            &setup.const_root,
+            &setup.starkinfo,
+            &stark_struct,
+            &setup.program,
+        )
+        .unwrap();
+        assert!(result);
+    }
 }
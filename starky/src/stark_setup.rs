@@ -1,13 +1,12 @@
 #![allow(non_snake_case, dead_code)]
-use crate::fft_p::interpolate;
-use crate::polsarray::PolsArray;
-use crate::starkinfo::{self, Program, StarkInfo};
-use crate::traits::{FieldExtension, MerkleTree};
+use crate::polsarray::PolsRows;
+use crate::prover_config::ProverConfig;
+use crate::stark_gen::extend_and_merkelize_rows;
+use crate::starkinfo::{self, ImPolsSelection, Program, StarkInfo};
+use crate::traits::MerkleTree;
 use crate::types::{StarkStruct, PIL};
 use anyhow::Result;
-use fields::field_gl::Fr as FGL;
 use profiler_macro::time_profiler;
-use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Default, Serialize, Deserialize)]
@@ -24,39 +23,50 @@ pub struct StarkSetup<M: MerkleTree> {
 impl<M: MerkleTree> StarkSetup<M> {
     // global_l1: https://github.com/0xEigenLabs/eigen-zkvm/pull/91
     #[time_profiler("stark_setup")]
-    pub fn new(
-        const_pol: &PolsArray,
+    pub fn new<P: PolsRows>(
+        const_pol: &P,
         pil: &mut PIL,
         stark_struct: &StarkStruct,
         global_l1: Option<String>,
+        config: &ProverConfig,
+    ) -> Result<StarkSetup<M>> {
+        Self::with_im_pols(
+            const_pol,
+            pil,
+            stark_struct,
+            global_l1,
+            ImPolsSelection::default(),
+            config,
+        )
+    }
+
+    /// Setup selecting the intermediate polynomials by `im_pols`.
+    pub fn with_im_pols<P: PolsRows>(
+        const_pol: &P,
+        pil: &mut PIL,
+        stark_struct: &StarkStruct,
+        global_l1: Option<String>,
+        im_pols: ImPolsSelection,
+        config: &ProverConfig,
+    ) -> Result<StarkSetup<M>> {
+        config.install(|| Self::setup(const_pol, pil, stark_struct, global_l1, im_pols))
+    }
+
+    fn setup<P: PolsRows>(
+        const_pol: &P,
+        pil: &mut PIL,
+        stark_struct: &StarkStruct,
+        global_l1: Option<String>,
+        im_pols: ImPolsSelection,
     ) -> Result<StarkSetup<M>> {
         let nBits = stark_struct.nBits;
         let nBitsExt = stark_struct.nBitsExt;
-        assert_eq!(const_pol.nPols, pil.nConstants);
-
-        log::trace!("Write const pol buff and interpolate");
-        let const_buff = const_pol.write_buff();
-        //extend and merkelize
-        let mut const_pols_array_e = vec![M::ExtendField::ZERO; (1 << nBitsExt) * pil.nConstants];
-        let mut const_pols_array_e_be = vec![FGL::ZERO; (1 << nBitsExt) * pil.nConstants];
-
-        interpolate(&const_buff, pil.nConstants, nBits, &mut const_pols_array_e, nBitsExt);
-
-        const_pols_array_e_be.par_iter_mut().zip(const_pols_array_e).for_each(
-            |(be_out, f3g_in)| {
-                *be_out = f3g_in.to_be();
-            },
-        );
-
-        let mut const_tree = M::new();
-        log::trace!("Merkelize const tree");
-        const_tree.merkelize(
-            const_pols_array_e_be,
-            const_pol.nPols,
-            const_pol.n << (nBitsExt - nBits),
-        )?;
-
-        let starkinfo = starkinfo::StarkInfo::new(pil, stark_struct, global_l1)?;
+        assert_eq!(const_pol.n_pols(), pil.nConstants);
+
+        log::trace!("Extend and merkelize const tree");
+        let const_tree = extend_and_merkelize_rows::<M>(const_pol, nBits, nBitsExt)?;
+
+        let starkinfo = starkinfo::StarkInfo::new(pil, stark_struct, global_l1, im_pols)?;
         Ok(StarkSetup {
             const_root: const_tree.root(),
             const_tree,
@@ -69,6 +79,7 @@ impl<M: MerkleTree> StarkSetup<M> {
 #[cfg(test)]
 pub mod tests {
     use crate::polsarray::{PolKind, PolsArray};
+    use crate::prover_config::ProverConfig;
     use crate::stark_setup::StarkSetup;
     use crate::types::{load_json, StarkStruct, PIL};
 
@@ -87,8 +98,14 @@ pub mod tests {
         const_pol.load("data/fib.const").unwrap();
 
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
-        let setup =
-            StarkSetup::<MerkleTreeBN128>::new(&const_pol, &mut pil, &stark_struct, None).unwrap();
+        let setup = StarkSetup::<MerkleTreeBN128>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
         let root: Fr = Fr(setup.const_root.as_scalar::<Fr>());
 
         let expect_root =
@@ -103,8 +120,14 @@ pub mod tests {
         const_pol.load("data/fib.const.gl").unwrap();
 
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json.gl").unwrap();
-        let setup =
-            StarkSetup::<MerkleTreeGL>::new(&const_pol, &mut pil, &stark_struct, None).unwrap();
+        let setup = StarkSetup::<MerkleTreeGL>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
 
         let expect_root = ElementDigest::<4, FGL>::new(&[
             FGL::from(15302509084042343527u64),
@@ -122,8 +145,14 @@ pub mod tests {
         const_pol.load("data/fib.const").unwrap();
 
         let stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
-        let data =
-            StarkSetup::<MerkleTreeBN128>::new(&const_pol, &mut pil, &stark_struct, None).unwrap();
+        let data = StarkSetup::<MerkleTreeBN128>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
 
         let serialized = serde_json::to_string(&data).unwrap();
         println!("Serialized: {serialized}");
@@ -3,6 +3,7 @@
 use crate::constant::{MG, SHIFT};
 use crate::digest::ElementDigest;
 use crate::f3g::F3G;
+use crate::fiat_shamir::{circuit_digest, FIAT_SHAMIR_STRONG};
 use crate::pil2circom::StarkOption;
 use crate::starkinfo::{Program, StarkInfo};
 use crate::starkinfo_codegen::Node;
@@ -10,6 +11,7 @@ use crate::starkinfo_codegen::Section;
 use crate::traits::FieldExtension;
 use crate::traits::MTNodeType;
 use crate::types::{StarkStruct, PIL};
+use anyhow::Result;
 use profiler_macro::time_profiler;
 
 fn header(options: &StarkOption) -> String {
@@ -809,7 +811,7 @@ fn stark_verifier<F: ff::PrimeField + Default>(
     stark_struct: &StarkStruct,
     const_root: &ElementDigest<4, F>,
     options: &StarkOption,
-) -> String {
+) -> Result<String> {
     let mut res = format!(
         r#"
 template StarkVerifier() {{
@@ -991,6 +993,13 @@ template StarkVerifier() {{
     ///////////
 
     let mut transcript = Transcript::new();
+    if stark_struct.fiatShamirVersion == FIAT_SHAMIR_STRONG {
+        let digest = circuit_digest(stark_struct, starkinfo)?;
+        for e in digest.iter() {
+            transcript._add1(&e.as_int().to_string());
+        }
+        transcript.put("rootC", 4);
+    }
     transcript.put("publics", pil.publics.len() as i32);
     transcript.put("root1", 4);
     transcript.getField("challenges[0]", 3);
@@ -2116,7 +2125,7 @@ component main {public [publics]}= StarkVerifier();
 "#,
         );
     }
-    res
+    Ok(res)
 }
 
 // Support goldilocks
@@ -2127,11 +2136,31 @@ pub fn render<F: ff::PrimeField + Default>(
     stark_struct: &StarkStruct,
     const_root: &ElementDigest<4, F>,
     options: &StarkOption,
-) -> String {
+) -> Result<String> {
     let mut res = header(options);
     res.push_str(&verify_evaluations(starkinfo, prorgam, pil, stark_struct));
     res.push_str(&verify_query(starkinfo, prorgam, stark_struct));
     res.push_str(&map_values(starkinfo));
-    res.push_str(&stark_verifier(starkinfo, pil, stark_struct, const_root, options));
-    res
+    res.push_str(&stark_verifier(starkinfo, pil, stark_struct, const_root, options)?);
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_header_is_bundled() {
+        let mut options = StarkOption {
+            enable_input: false,
+            verkey_input: false,
+            agg_stage: false,
+            skip_main: false,
+        };
+        for agg_stage in [false, true] {
+            options.agg_stage = agg_stage;
+            let unbundled = stark_circuits::unbundled_includes("GL", &header(&options)).unwrap();
+            assert!(unbundled.is_empty(), "{unbundled:?}");
+        }
+    }
 }
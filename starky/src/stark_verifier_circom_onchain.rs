@@ -3,6 +3,7 @@
 use crate::constant::{MG, SHIFT};
 use crate::digest::ElementDigest;
 use crate::f3g::F3G;
+use crate::fiat_shamir::{circuit_digest, FIAT_SHAMIR_STRONG};
 use crate::pil2circom::StarkOption;
 use crate::starkinfo::{Program, StarkInfo};
 use crate::starkinfo_codegen::Node;
@@ -10,6 +11,7 @@ use crate::starkinfo_codegen::Section;
 use crate::traits::FieldExtension;
 use crate::traits::MTNodeType;
 use crate::types::{StarkStruct, PIL};
+use anyhow::Result;
 use profiler_macro::time_profiler;
 
 fn header() -> String {
@@ -815,7 +817,7 @@ fn stark_verifier<F: ff::PrimeField + Default>(
     stark_struct: &StarkStruct,
     const_root: &ElementDigest<4, F>,
     options: &StarkOption,
-) -> String {
+) -> Result<String> {
     let mut res = format!(
         r#"
 template StarkVerifier() {{
@@ -996,6 +998,13 @@ template StarkVerifier() {{
     ///////////
 
     let mut transcript = Transcript::new(stark_struct.clone());
+    if stark_struct.fiatShamirVersion == FIAT_SHAMIR_STRONG {
+        let digest = circuit_digest(stark_struct, starkinfo)?;
+        for e in digest.iter() {
+            transcript._add1(&e.as_int().to_string());
+        }
+        transcript.put("rootC", -1);
+    }
     transcript.put("publics", pil.publics.len() as i32);
     transcript.put("root1", -1);
     transcript.getField("challenges[0]", 3);
@@ -1823,7 +1832,7 @@ component main {} = Main();
             if options.verkey_input { "{public [rootC]}" } else { "" }
         ));
     }
-    res
+    Ok(res)
 }
 
 // Suport bn128 && bls12381
@@ -1834,11 +1843,27 @@ pub fn render<F: ff::PrimeField + Default>(
     stark_struct: &StarkStruct,
     const_root: &ElementDigest<4, F>,
     options: &StarkOption,
-) -> String {
+) -> Result<String> {
     let mut res = header();
     res.push_str(&verify_evaluations(starkinfo, prorgam, pil, stark_struct));
     res.push_str(&verify_query(starkinfo, prorgam, stark_struct));
     res.push_str(&map_values(starkinfo));
-    res.push_str(&stark_verifier(starkinfo, pil, stark_struct, const_root, options));
-    res
+    res.push_str(&stark_verifier(starkinfo, pil, stark_struct, const_root, options)?);
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_header_is_bundled() {
+        // circomlib's
+        for hash_type in ["BN128", "BLS12381"] {
+            assert_eq!(
+                stark_circuits::unbundled_includes(hash_type, &header()).unwrap(),
+                vec!["bitify.circom", "sha256/sha256.circom"]
+            );
+        }
+    }
 }
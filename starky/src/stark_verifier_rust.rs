@@ -0,0 +1,905 @@
+//! Renders the verifier of a GL STARK as a standalone Rust module, the counterpart of the circom
+//! renderers for native verification.
+//!
+//! The module depends on `core` and `alloc` only. The constraint check of `verifier_code` and
+//! `verifier_query_code` is unrolled into straight-line functions, and the FRI steps, the
+//! circuit digest and the constant root are baked in. It exposes `pub fn verify(proof: &[u64])
+//! -> bool` over the proof layout of `proof_words`.
+use crate::constant::{MG, POSEIDON_CONSTANTS_OPT};
+use crate::digest::ElementDigest;
+use crate::f3g::F3G;
+use crate::fiat_shamir::{circuit_digest, FIAT_SHAMIR_STRONG};
+use crate::merklehash::MerkleTreeGL;
+use crate::polsarray::{PolKind, PolsArray};
+use crate::prover_config::ProverConfig;
+use crate::stark_gen::StarkProof;
+use crate::stark_setup::StarkSetup;
+use crate::starkinfo::{Program, StarkInfo};
+use crate::starkinfo_codegen::{Node, Section};
+use crate::traits::{FieldExtension, MTNodeType};
+use crate::types::{load_json, parse_pil_number, StarkStruct, PIL};
+use anyhow::{bail, Result};
+use fields::field_gl::Fr as FGL;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Field, Poseidon, transcript, Merkle and FRI helpers of the generated module, as in
+/// `stark_verify` for `MerkleTreeGL` and `TranscriptGL`.
+const RUNTIME: &str = r#"
+const P: u64 = 0xffff_ffff_0000_0001;
+const SHIFT: u64 = 49;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Fp(u64);
+
+impl Fp {
+    const ZERO: Fp = Fp(0);
+    const ONE: Fp = Fp(1);
+
+    fn add(self, b: Fp) -> Fp {
+        let (s, c) = self.0.overflowing_add(b.0);
+        Fp(if c || s >= P { s.wrapping_sub(P) } else { s })
+    }
+
+    fn sub(self, b: Fp) -> Fp {
+        Fp(if self.0 >= b.0 { self.0 - b.0 } else { self.0 + (P - b.0) })
+    }
+
+    fn mul(self, b: Fp) -> Fp {
+        Fp(reduce128(self.0 as u128 * b.0 as u128))
+    }
+
+    fn pow(self, mut e: u64) -> Fp {
+        let (mut r, mut b) = (Fp::ONE, self);
+        while e > 0 {
+            if e & 1 == 1 {
+                r = r.mul(b);
+            }
+            b = b.mul(b);
+            e >>= 1;
+        }
+        r
+    }
+
+    fn inv(self) -> Fp {
+        self.pow(P - 2)
+    }
+}
+
+fn reduce128(x: u128) -> u64 {
+    let (lo, hi) = (x as u64, (x >> 64) as u64);
+    let (hi_hi, hi_lo) = (hi >> 32, hi & 0xffff_ffff);
+    let (mut t0, borrow) = lo.overflowing_sub(hi_hi);
+    if borrow {
+        t0 = t0.wrapping_sub(0xffff_ffff);
+    }
+    let (mut t1, carry) = t0.overflowing_add(hi_lo * 0xffff_ffff);
+    if carry {
+        t1 = t1.wrapping_add(0xffff_ffff);
+    }
+    if t1 >= P {
+        t1 - P
+    } else {
+        t1
+    }
+}
+
+/// The cubic extension by x^3 = x + 1, the base field embedded as (a, 0, 0).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct F3([Fp; 3]);
+
+impl F3 {
+    const ZERO: F3 = F3([Fp::ZERO; 3]);
+    const ONE: F3 = F3([Fp::ONE, Fp::ZERO, Fp::ZERO]);
+
+    fn pow(self, mut e: usize) -> F3 {
+        let (mut r, mut b) = (F3::ONE, self);
+        while e > 0 {
+            if e & 1 == 1 {
+                r = r * b;
+            }
+            b = b * b;
+            e >>= 1;
+        }
+        r
+    }
+
+    fn inv(self) -> F3 {
+        let [a, b, c] = self.0;
+        let (aa, ac, ba, bb, bc, cc) = (a.mul(a), a.mul(c), b.mul(a), b.mul(b), b.mul(c), c.mul(c));
+        let (aaa, aac, abc, abb) = (aa.mul(a), aa.mul(c), ba.mul(c), ba.mul(b));
+        let (acc, bbb, bcc, ccc) = (ac.mul(c), bb.mul(b), bc.mul(c), cc.mul(c));
+        let t = Fp::ZERO
+            .sub(aaa)
+            .sub(aac)
+            .sub(aac)
+            .add(abc)
+            .add(abc)
+            .add(abc)
+            .add(abb)
+            .sub(acc)
+            .sub(bbb)
+            .add(bcc)
+            .sub(ccc);
+        let t = t.inv();
+        let i1 = Fp::ZERO.sub(aa).sub(ac).sub(ac).add(bc).add(bb).sub(cc).mul(t);
+        let i2 = ba.sub(cc).mul(t);
+        let i3 = Fp::ZERO.sub(bb).add(ac).add(cc).mul(t);
+        F3([i1, i2, i3])
+    }
+}
+
+impl From<Fp> for F3 {
+    fn from(v: Fp) -> F3 {
+        F3([v, Fp::ZERO, Fp::ZERO])
+    }
+}
+
+impl core::ops::Add for F3 {
+    type Output = F3;
+    fn add(self, b: F3) -> F3 {
+        F3([self.0[0].add(b.0[0]), self.0[1].add(b.0[1]), self.0[2].add(b.0[2])])
+    }
+}
+
+impl core::ops::Sub for F3 {
+    type Output = F3;
+    fn sub(self, b: F3) -> F3 {
+        F3([self.0[0].sub(b.0[0]), self.0[1].sub(b.0[1]), self.0[2].sub(b.0[2])])
+    }
+}
+
+impl core::ops::Mul for F3 {
+    type Output = F3;
+    fn mul(self, b: F3) -> F3 {
+        let (a, b) = (self.0, b.0);
+        let aa = a[0].add(a[1]).mul(b[0].add(b[1]));
+        let bb = a[0].add(a[2]).mul(b[0].add(b[2]));
+        let cc = a[1].add(a[2]).mul(b[1].add(b[2]));
+        let (dd, ee, ff) = (a[0].mul(b[0]), a[1].mul(b[1]), a[2].mul(b[2]));
+        let gg = dd.sub(ee);
+        F3([cc.add(gg).sub(ff), aa.add(cc).sub(ee).sub(ee).sub(dd), bb.sub(gg)])
+    }
+}
+
+impl core::ops::Div for F3 {
+    type Output = F3;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, b: F3) -> F3 {
+        self * b.inv()
+    }
+}
+
+type Node = [Fp; 4];
+
+fn pow7(x: Fp) -> Fp {
+    let x2 = x.mul(x);
+    let x3 = x2.mul(x);
+    x3.mul(x3).mul(x)
+}
+
+fn mds(s: &[Fp; 12], m: &[[u64; 12]; 12]) -> [Fp; 12] {
+    let mut out = [Fp::ZERO; 12];
+    for (i, o) in out.iter_mut().enumerate() {
+        for j in 0..12 {
+            *o = o.add(Fp(m[j][i]).mul(s[j]));
+        }
+    }
+    out
+}
+
+fn poseidon(inp: &[Fp; 8], cap: &Node) -> [Fp; 12] {
+    const T: usize = 12;
+    const N_ROUNDS_P: usize = 22;
+    let c = |i: usize| Fp(POSEIDON_C[i]);
+    let mut s = [Fp::ZERO; T];
+    s[..8].copy_from_slice(inp);
+    s[8..].copy_from_slice(cap);
+    for i in 0..T {
+        s[i] = s[i].add(c(i));
+    }
+    for r in 0..3 {
+        for i in 0..T {
+            s[i] = pow7(s[i]).add(c((r + 1) * T + i));
+        }
+        s = mds(&s, &POSEIDON_M);
+    }
+    for i in 0..T {
+        s[i] = pow7(s[i]).add(c(4 * T + i));
+    }
+    s = mds(&s, &POSEIDON_P);
+    for r in 0..N_ROUNDS_P {
+        s[0] = pow7(s[0]).add(c(5 * T + r));
+        let mut s0 = Fp::ZERO;
+        for j in 0..T {
+            s0 = s0.add(Fp(POSEIDON_S[(T * 2 - 1) * r + j]).mul(s[j]));
+        }
+        for k in 1..T {
+            s[k] = s[k].add(Fp(POSEIDON_S[(T * 2 - 1) * r + T + k - 1]).mul(s[0]));
+        }
+        s[0] = s0;
+    }
+    for r in 0..3 {
+        for i in 0..T {
+            s[i] = pow7(s[i]).add(c(5 * T + N_ROUNDS_P + r * T + i));
+        }
+        s = mds(&s, &POSEIDON_M);
+    }
+    for x in s.iter_mut() {
+        *x = pow7(*x);
+    }
+    mds(&s, &POSEIDON_M)
+}
+
+fn poseidon4(inp: &[Fp; 8], cap: &Node) -> Node {
+    let s = poseidon(inp, cap);
+    [s[0], s[1], s[2], s[3]]
+}
+
+struct Transcript {
+    state: Node,
+    pending: Vec<Fp>,
+    out: Vec<Fp>,
+}
+
+impl Transcript {
+    fn new() -> Self {
+        Transcript { state: [Fp::ZERO; 4], pending: Vec::new(), out: Vec::new() }
+    }
+
+    fn update_state(&mut self) {
+        let mut inp = [Fp::ZERO; 8];
+        inp[..self.pending.len()].copy_from_slice(&self.pending);
+        let out = poseidon(&inp, &self.state);
+        self.pending.clear();
+        self.state.copy_from_slice(&out[..4]);
+        self.out = out.to_vec();
+    }
+
+    fn put(&mut self, e: Fp) {
+        self.out.clear();
+        self.pending.push(e);
+        if self.pending.len() == 8 {
+            self.update_state();
+        }
+    }
+
+    fn put_all(&mut self, es: &[Fp]) {
+        for e in es {
+            self.put(*e);
+        }
+    }
+
+    fn get_fields1(&mut self) -> Fp {
+        if self.out.is_empty() {
+            self.update_state();
+        }
+        self.out.remove(0)
+    }
+
+    fn get_field(&mut self) -> F3 {
+        F3([self.get_fields1(), self.get_fields1(), self.get_fields1()])
+    }
+
+    fn get_permutations(&mut self, n: usize, nbits: usize) -> Vec<usize> {
+        let n_fields = (n * nbits - 1) / 63 + 1;
+        let fields: Vec<u64> = (0..n_fields).map(|_| self.get_fields1().0).collect();
+        let (mut cur_field, mut cur_bit) = (0, 0);
+        let mut res = Vec::with_capacity(n);
+        for _ in 0..n {
+            let mut a = 0usize;
+            for j in 0..nbits {
+                if (fields[cur_field] >> cur_bit) & 1 == 1 {
+                    a += 1 << j;
+                }
+                cur_bit += 1;
+                if cur_bit == 63 {
+                    cur_bit = 0;
+                    cur_field += 1;
+                }
+            }
+            res.push(a);
+        }
+        res
+    }
+}
+
+fn sponge(vals: &[Fp]) -> Node {
+    let mut st = [Fp::ZERO; 4];
+    if vals.len() <= 4 {
+        st[..vals.len()].copy_from_slice(vals);
+        return st;
+    }
+    for chunk in vals.chunks(8) {
+        let mut inp = [Fp::ZERO; 8];
+        inp[..chunk.len()].copy_from_slice(chunk);
+        st = poseidon4(&inp, &st);
+    }
+    st
+}
+
+fn linear_hash(vals: &[Fp]) -> Node {
+    if vals.len() <= 4 {
+        return sponge(vals);
+    }
+    let bs = core::cmp::max(8, vals.len().div_ceil(4));
+    let hashes: Vec<Fp> = vals.chunks(bs).flat_map(sponge).collect();
+    sponge(&hashes)
+}
+
+fn merkle_root(vals: &[Fp], path: &[Node], mut idx: usize) -> Node {
+    let mut value = linear_hash(vals);
+    for sibling in path {
+        let mut inp = [Fp::ZERO; 8];
+        let (left, right) = if idx & 1 == 0 { (&value, sibling) } else { (sibling, &value) };
+        inp[..4].copy_from_slice(left);
+        inp[4..].copy_from_slice(right);
+        value = poseidon4(&inp, &[Fp::ZERO; 4]);
+        idx >>= 1;
+    }
+    value
+}
+
+fn ifft(e: &[F3]) -> Vec<F3> {
+    let n = e.len();
+    if n <= 1 {
+        return e.to_vec();
+    }
+    let w_inv = F3::from(Fp(MG[n.trailing_zeros() as usize]).inv());
+    let n_inv = F3::from(Fp(n as u64).inv());
+    (0..n)
+        .map(|j| {
+            let wj = w_inv.pow(j);
+            let (mut acc, mut x) = (F3::ZERO, F3::ONE);
+            for ei in e {
+                acc = acc + *ei * x;
+                x = x * wj;
+            }
+            acc * n_inv
+        })
+        .collect()
+}
+
+fn eval_pol(p: &[F3], x: F3) -> F3 {
+    p.iter().rev().fold(F3::ZERO, |acc, c| acc * x + *c)
+}
+
+struct Reader<'a> {
+    words: &'a [u64],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn len(&mut self) -> Option<usize> {
+        let n = *self.words.get(self.pos)? as usize;
+        self.pos += 1;
+        (n <= self.words.len() - self.pos).then_some(n)
+    }
+
+    fn fp(&mut self) -> Option<Fp> {
+        let w = *self.words.get(self.pos)?;
+        self.pos += 1;
+        (w < P).then_some(Fp(w))
+    }
+
+    fn f3(&mut self) -> Option<F3> {
+        Some(F3([self.fp()?, self.fp()?, self.fp()?]))
+    }
+
+    fn node(&mut self) -> Option<Node> {
+        Some([self.fp()?, self.fp()?, self.fp()?, self.fp()?])
+    }
+
+    fn fps(&mut self) -> Option<Vec<Fp>> {
+        let n = self.len()?;
+        (0..n).map(|_| self.fp()).collect()
+    }
+
+    fn f3s(&mut self) -> Option<Vec<F3>> {
+        let n = self.len()?;
+        (0..n).map(|_| self.f3()).collect()
+    }
+
+    fn nodes(&mut self) -> Option<Vec<Node>> {
+        let n = self.len()?;
+        (0..n).map(|_| self.node()).collect()
+    }
+}
+
+struct Group {
+    vals: Vec<Fp>,
+    path: Vec<Node>,
+}
+
+struct Proof {
+    publics: Vec<F3>,
+    roots: [Node; 4],
+    evals: Vec<F3>,
+    fri_roots: Vec<Node>,
+    last: Vec<F3>,
+    /// The groups of each query of each FRI step.
+    queries: Vec<Vec<Vec<Group>>>,
+}
+
+fn read_proof(words: &[u64]) -> Option<Proof> {
+    let mut r = Reader { words, pos: 0 };
+    let publics: Vec<F3> = r.fps()?.into_iter().map(F3::from).collect();
+    let roots = [r.node()?, r.node()?, r.node()?, r.node()?];
+    let evals = r.f3s()?;
+    let fri_roots = (1..STEPS.len()).map(|_| r.node()).collect::<Option<Vec<_>>>()?;
+    let last = r.f3s()?;
+    if publics.len() != N_PUBLICS
+        || evals.len() != N_EVALS
+        || last.len() != 1 << STEPS[STEPS.len() - 1]
+    {
+        return None;
+    }
+    let mut queries = Vec::with_capacity(STEPS.len());
+    for si in 0..STEPS.len() {
+        let mut step = Vec::with_capacity(N_QUERIES);
+        for _ in 0..N_QUERIES {
+            let n_groups = r.len()?;
+            let groups = (0..n_groups)
+                .map(|_| Some(Group { vals: r.fps()?, path: r.nodes()? }))
+                .collect::<Option<Vec<_>>>()?;
+            let widths_ok = if si == 0 {
+                groups.len() == 5 && groups.iter().zip(TREE_WIDTHS).all(|(g, w)| g.vals.len() >= w)
+            } else {
+                groups.len() == 1 && groups[0].vals.len() == 3 << (STEPS[si - 1] - STEPS[si])
+            };
+            if !widths_ok {
+                return None;
+            }
+            step.push(groups);
+        }
+        queries.push(step);
+    }
+    (r.pos == words.len()).then_some(Proof { publics, roots, evals, fri_roots, last, queries })
+}
+
+struct Ctx<'a> {
+    challenges: [F3; 8],
+    evals: &'a [F3],
+    publics: &'a [F3],
+    z: F3,
+    zp: F3,
+    tree1: &'a [Fp],
+    tree2: &'a [Fp],
+    tree3: &'a [Fp],
+    tree4: &'a [Fp],
+    consts: &'a [Fp],
+    x_div_x_sub_xi: F3,
+    x_div_x_sub_wxi: F3,
+}
+
+/// Verifies a proof in the layout of `starky::stark_verifier_rust::proof_words`.
+pub fn verify(proof: &[u64]) -> bool {
+    match read_proof(proof) {
+        Some(proof) => verify_proof(&proof),
+        None => false,
+    }
+}
+
+fn verify_proof(p: &Proof) -> bool {
+    let mut t = Transcript::new();
+    if let Some(digest) = CIRCUIT_DIGEST {
+        digest.iter().chain(CONST_ROOT.iter()).for_each(|e| t.put(Fp(*e)));
+    }
+    for public in p.publics.iter() {
+        t.put(public.0[0]);
+    }
+    let mut ch = [F3::ZERO; 8];
+    t.put_all(&p.roots[0]);
+    ch[0] = t.get_field();
+    ch[1] = t.get_field();
+    t.put_all(&p.roots[1]);
+    ch[2] = t.get_field();
+    ch[3] = t.get_field();
+    t.put_all(&p.roots[2]);
+    ch[4] = t.get_field();
+    t.put_all(&p.roots[3]);
+    ch[7] = t.get_field();
+    for e in p.evals.iter() {
+        t.put_all(&e.0);
+    }
+    ch[5] = t.get_field();
+    ch[6] = t.get_field();
+
+    let xi = ch[7];
+    let x_n = xi.pow(1 << N_BITS);
+    let z = x_n - F3::ONE;
+    let zp = (xi * F3::from(Fp(MG[N_BITS]))).pow(1 << N_BITS) - F3::ONE;
+    let mut c = Ctx {
+        challenges: ch,
+        evals: &p.evals,
+        publics: &p.publics,
+        z,
+        zp,
+        tree1: &[],
+        tree2: &[],
+        tree3: &[],
+        tree4: &[],
+        consts: &[],
+        x_div_x_sub_xi: F3::ZERO,
+        x_div_x_sub_wxi: F3::ZERO,
+    };
+
+    let res = verifier_code(&c);
+    let (mut q, mut x_acc) = (F3::ZERO, F3::ONE);
+    for e in Q_EVALS {
+        q = q + x_acc * p.evals[e];
+        x_acc = x_acc * x_n;
+    }
+    if res != q * z {
+        return false;
+    }
+
+    let n_steps = STEPS.len();
+    let mut special_x = Vec::with_capacity(n_steps);
+    for si in 0..n_steps {
+        special_x.push(t.get_field());
+        if si < n_steps - 1 {
+            t.put_all(&p.fri_roots[si]);
+        } else {
+            for e in p.last.iter() {
+                t.put_all(&e.0);
+            }
+        }
+    }
+    let mut ys = t.get_permutations(N_QUERIES, STEPS[0]);
+    let const_root = CONST_ROOT.map(Fp);
+    let mut pol_bits = N_BITS_EXT;
+    let mut shift = F3::from(Fp(SHIFT));
+    for si in 0..n_steps {
+        let reduction_bits = pol_bits - STEPS[si];
+        for i in 0..N_QUERIES {
+            let groups = &p.queries[si][i];
+            let pgroup_e = if si == 0 {
+                let roots = [&p.roots[0], &p.roots[1], &p.roots[2], &p.roots[3], &const_root];
+                for (g, root) in groups.iter().zip(roots) {
+                    if merkle_root(&g.vals, &g.path, ys[i]) != *root {
+                        return false;
+                    }
+                }
+                c.tree1 = &groups[0].vals;
+                c.tree2 = &groups[1].vals;
+                c.tree3 = &groups[2].vals;
+                c.tree4 = &groups[3].vals;
+                c.consts = &groups[4].vals;
+                let x = F3::from(Fp(SHIFT)) * F3::from(Fp(MG[N_BITS_EXT])).pow(ys[i]);
+                c.x_div_x_sub_xi = x / (x - xi);
+                c.x_div_x_sub_wxi = x / (x - xi * F3::from(Fp(MG[N_BITS])));
+                alloc::vec![verifier_query_code(&c)]
+            } else {
+                let g = &groups[0];
+                if merkle_root(&g.vals, &g.path, ys[i]) != p.fri_roots[si - 1] {
+                    return false;
+                }
+                g.vals.chunks(3).map(|v| F3([v[0], v[1], v[2]])).collect()
+            };
+
+            let pgroup_c = ifft(&pgroup_e);
+            let sinv = (shift * F3::from(Fp(MG[pol_bits])).pow(ys[i])).inv();
+            let ev = eval_pol(&pgroup_c, special_x[si] * sinv);
+            if si < n_steps - 1 {
+                let group_idx = ys[i] / (1 << STEPS[si + 1]);
+                let next = &p.queries[si + 1][i][0].vals[group_idx * 3..];
+                if ev != F3([next[0], next[1], next[2]]) {
+                    return false;
+                }
+            } else if ev != p.last[ys[i]] {
+                return false;
+            }
+        }
+
+        pol_bits = STEPS[si];
+        for _ in 0..reduction_bits {
+            shift = shift * shift;
+        }
+        if si < n_steps - 1 {
+            for y in ys.iter_mut() {
+                *y %= 1 << STEPS[si + 1];
+            }
+        }
+    }
+
+    let max_deg =
+        if pol_bits < N_BITS_EXT - N_BITS { 0 } else { 1 << (pol_bits - (N_BITS_EXT - N_BITS)) };
+    ifft(&p.last).iter().skip(max_deg + 1).all(|e| *e == F3::ZERO)
+}
+"#;
+
+fn u64_list(vals: impl IntoIterator<Item = u64>) -> String {
+    vals.into_iter().map(|v| format!("0x{:016x}", v)).collect::<Vec<_>>().join(", ")
+}
+
+fn fgl_list(vals: &[FGL]) -> String {
+    u64_list(vals.iter().map(|v| v.as_int()))
+}
+
+fn fgl_matrix(rows: &[Vec<FGL>]) -> String {
+    rows.iter().map(|r| format!("    [{}],\n", fgl_list(r))).collect()
+}
+
+/// Rust expression of a reference of the verifier code.
+fn get_ref(r: &Node) -> String {
+    let tree = |tree: &str| match r.dim {
+        1 => format!("F3::from(c.{}[{}])", tree, r.tree_pos),
+        _ => format!(
+            "F3([c.{tree}[{}], c.{tree}[{}], c.{tree}[{}]])",
+            r.tree_pos,
+            r.tree_pos + 1,
+            r.tree_pos + 2
+        ),
+    };
+    match r.type_.as_str() {
+        "tmp" => format!("t{}", r.id),
+        "tree1" | "tree2" | "tree3" | "tree4" => tree(r.type_.as_str()),
+        "const" => format!("F3::from(c.consts[{}])", r.id),
+        "eval" => format!("c.evals[{}]", r.id),
+        "number" => {
+            format!("F3::from(Fp(0x{:016x}))", parse_pil_number(r.value.as_ref().unwrap()))
+        }
+        "public" => format!("c.publics[{}]", r.id),
+        "challenge" => format!("c.challenges[{}]", r.id),
+        "xDivXSubXi" => "c.x_div_x_sub_xi".to_string(),
+        "xDivXSubWXi" => "c.x_div_x_sub_wxi".to_string(),
+        "x" => "c.challenges[7]".to_string(),
+        "Z" if r.prime => "c.zp".to_string(),
+        "Z" => "c.z".to_string(),
+        _ => panic!("Invalid reference type, get: {}", r.type_),
+    }
+}
+
+/// The straight-line function of a verifier code.
+fn render_code(name: &str, code: &[Section]) -> Result<String> {
+    let mut body = String::new();
+    for ci in code {
+        if ci.dest.type_ != "tmp" {
+            bail!("Invalid reference type set: {}", ci.dest.type_);
+        }
+        let src: Vec<String> = ci.src.iter().map(get_ref).collect();
+        let e = match ci.op.as_str() {
+            "add" => format!("{} + {}", src[0], src[1]),
+            "sub" => format!("{} - {}", src[0], src[1]),
+            "mul" => format!("{} * {}", src[0], src[1]),
+            "muladd" => format!("{} * {} + {}", src[0], src[1], src[2]),
+            "copy" => src[0].clone(),
+            _ => bail!("Invalid op: {}", ci.op),
+        };
+        writeln!(body, "    let t{} = {};", ci.dest.id, e)?;
+    }
+    let res = match code.last() {
+        Some(ci) => get_ref(&ci.dest),
+        None => "F3::ZERO".to_string(),
+    };
+    Ok(format!("#[allow(unused_variables)]\nfn {name}(c: &Ctx) -> F3 {{\n{body}    {res}\n}}\n"))
+}
+
+/// The values of the query groups of step 0 the query code reads at least.
+fn tree_widths(code: &[Section]) -> [usize; 5] {
+    let mut widths = [0; 5];
+    for r in code.iter().flat_map(|ci| ci.src.iter()) {
+        let (i, end) = match r.type_.as_str() {
+            "tree1" => (0, r.tree_pos + r.dim),
+            "tree2" => (1, r.tree_pos + r.dim),
+            "tree3" => (2, r.tree_pos + r.dim),
+            "tree4" => (3, r.tree_pos + r.dim),
+            "const" => (4, r.id + 1),
+            _ => continue,
+        };
+        widths[i] = widths[i].max(end);
+    }
+    widths
+}
+
+/// Renders the verifier module of a GL STARK.
+pub fn render(
+    starkinfo: &mut StarkInfo,
+    program: &mut Program,
+    stark_struct: &StarkStruct,
+    const_root: &ElementDigest<4, FGL>,
+) -> Result<String> {
+    crate::fiat_shamir::check_version(stark_struct)?;
+    if stark_struct.verificationHashType != "GL" {
+        bail!("The Rust verifier supports GL, not {}", stark_struct.verificationHashType);
+    }
+    let digest = match stark_struct.fiatShamirVersion {
+        FIAT_SHAMIR_STRONG => {
+            format!("Some([{}])", fgl_list(&circuit_digest(stark_struct, starkinfo)?))
+        }
+        _ => "None".to_string(),
+    };
+    starkinfo.set_code_dimensions_first(&mut program.verifier_code)?;
+    starkinfo.set_code_dimensions_first(&mut program.verifier_query_code)?;
+
+    let q_evals = (0..starkinfo.q_deg)
+        .map(|i| Ok(*starkinfo.ev_idx.get("cm", 0, starkinfo.qs[i]).unwrap()))
+        .collect::<Result<Vec<usize>>>()?;
+    let steps: Vec<String> = stark_struct.steps.iter().map(|s| s.nBits.to_string()).collect();
+    let poseidon = &*POSEIDON_CONSTANTS_OPT;
+
+    let mut res = String::new();
+    writeln!(res, "//! STARK verifier generated by starky, do not edit.")?;
+    writeln!(res, "//!")?;
+    writeln!(
+        res,
+        "//! `verify` checks a proof in the layout of `starky::stark_verifier_rust::proof_words`."
+    )?;
+    writeln!(res, "extern crate alloc;")?;
+    writeln!(res, "use alloc::vec::Vec;\n")?;
+    writeln!(res, "const N_BITS: usize = {};", stark_struct.nBits)?;
+    writeln!(res, "const N_BITS_EXT: usize = {};", stark_struct.nBitsExt)?;
+    writeln!(res, "const N_QUERIES: usize = {};", stark_struct.nQueries)?;
+    writeln!(res, "const STEPS: [usize; {}] = [{}];", steps.len(), steps.join(", "))?;
+    writeln!(res, "const N_PUBLICS: usize = {};", starkinfo.n_publics)?;
+    writeln!(res, "const N_EVALS: usize = {};", starkinfo.ev_map.len())?;
+    let widths = tree_widths(&program.verifier_query_code.first);
+    writeln!(res, "const TREE_WIDTHS: [usize; 5] = {:?};", widths)?;
+    writeln!(res, "const Q_EVALS: [usize; {}] = {:?};", q_evals.len(), q_evals)?;
+    writeln!(res, "const CONST_ROOT: [u64; 4] = [{}];", fgl_list(const_root.as_elements()))?;
+    writeln!(res, "const CIRCUIT_DIGEST: Option<[u64; 4]> = {};", digest)?;
+    writeln!(res, "const MG: [u64; {}] = [{}];", MG.0.len(), fgl_list(&MG.0))?;
+    writeln!(res, "const POSEIDON_C: [u64; {}] = [{}];", poseidon.c.len(), fgl_list(&poseidon.c))?;
+    writeln!(res, "const POSEIDON_S: [u64; {}] = [{}];", poseidon.s.len(), fgl_list(&poseidon.s))?;
+    writeln!(res, "const POSEIDON_M: [[u64; 12]; 12] = [\n{}];", fgl_matrix(&poseidon.m))?;
+    writeln!(res, "const POSEIDON_P: [[u64; 12]; 12] = [\n{}];", fgl_matrix(&poseidon.p))?;
+    res.push_str(RUNTIME);
+    res.push('\n');
+    res.push_str(&render_code("verifier_code", &program.verifier_code.first)?);
+    res.push('\n');
+    res.push_str(&render_code("verifier_query_code", &program.verifier_query_code.first)?);
+    Ok(res)
+}
+
+fn f3_words(e: &F3G, words: &mut Vec<u64>) {
+    let elements = e.as_elements();
+    words.extend((0..3).map(|i| elements.get(i).map_or(0, |v| v.as_int())));
+}
+
+/// The words of a proof for the generated `verify`: the publics, root1-4, the evaluations, the
+/// roots of the FRI steps after the first, the last FRI polynomial, then for each step and
+/// query the (values, Merkle path) groups. Vectors are prefixed by their length.
+pub fn proof_words(proof: &StarkProof<MerkleTreeGL>) -> Vec<u64> {
+    let mut words = vec![proof.publics.len() as u64];
+    words.extend(proof.publics.iter().map(|p| p.as_elements()[0].as_int()));
+    for root in [&proof.root1, &proof.root2, &proof.root3, &proof.root4] {
+        words.extend(root.as_elements().iter().map(|e| e.as_int()));
+    }
+    words.push(proof.evals.len() as u64);
+    proof.evals.iter().for_each(|e| f3_words(e, &mut words));
+    for query in proof.fri_proof.queries.iter().skip(1) {
+        words.extend(query.root.as_elements().iter().map(|e| e.as_int()));
+    }
+    words.push(proof.fri_proof.last.len() as u64);
+    proof.fri_proof.last.iter().for_each(|e| f3_words(e, &mut words));
+    for query in proof.fri_proof.queries.iter() {
+        for groups in query.pol_queries.iter() {
+            words.push(groups.len() as u64);
+            for (vals, path) in groups.iter() {
+                words.push(vals.len() as u64);
+                words.extend(vals.iter().map(|e| e.as_int()));
+                words.push(path.len() as u64);
+                words.extend(path.iter().flatten().map(|e| e.as_int()));
+            }
+        }
+    }
+    words
+}
+
+/// Writes the words of a proof, one per line.
+pub fn write_proof_words<W: Write>(proof: &StarkProof<MerkleTreeGL>, mut writer: W) -> Result<()> {
+    for w in proof_words(proof) {
+        writeln!(writer, "{}", w)?;
+    }
+    Ok(())
+}
+
+/// Sets a GL PIL up and writes its Rust verifier, and the words of the proof of `zkin`, a
+/// proof written by `stark_prove`, if any.
+pub fn generate(
+    stark_struct: &str,
+    pil_file: &str,
+    const_pol_file: &str,
+    zkin: Option<&str>,
+    rust_file: &str,
+    config: &ProverConfig,
+) -> Result<()> {
+    let mut pil = load_json::<PIL>(pil_file)?;
+    let stark_struct = load_json::<StarkStruct>(stark_struct)?;
+    let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
+    const_pol.load(const_pol_file)?;
+    let mut setup =
+        StarkSetup::<MerkleTreeGL>::new(&const_pol, &mut pil, &stark_struct, None, config)?;
+    let code = render(&mut setup.starkinfo, &mut setup.program, &stark_struct, &setup.const_root)?;
+    std::fs::write(rust_file, code)?;
+    if let Some(zkin) = zkin {
+        let proof = load_json::<StarkProof<MerkleTreeGL>>(zkin)?;
+        let words = std::fs::File::create(format!("{rust_file}.proof"))?;
+        write_proof_words(&proof, std::io::BufWriter::new(words))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stark_verifier_rust::*;
+    use crate::stark_verify::stark_verify;
+    use crate::transcript::TranscriptGL;
+    use std::process::Command;
+
+    const MAIN: &str = r#"
+mod verifier;
+
+fn main() {
+    let words = std::fs::read_to_string(std::env::args().nth(1).unwrap()).unwrap();
+    let words: Vec<u64> = words.split_whitespace().map(|w| w.parse().unwrap()).collect();
+    std::process::exit(if verifier::verify(&words) { 0 } else { 1 });
+}
+"#;
+
+    #[test]
+    fn test_stark_verifier_rust() {
+        let mut pil = load_json::<PIL>("data/plookup.pil.json.gl").unwrap();
+        let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
+        const_pol.load("data/plookup.const.gl").unwrap();
+        let mut cm_pol = PolsArray::new(&pil, PolKind::Commit);
+        cm_pol.load("data/plookup.cm.gl").unwrap();
+        let mut stark_struct = load_json::<StarkStruct>("data/starkStruct.json.gl").unwrap();
+        stark_struct.fiatShamirVersion = FIAT_SHAMIR_STRONG;
+        let mut setup = StarkSetup::<MerkleTreeGL>::new(
+            &const_pol,
+            &mut pil,
+            &stark_struct,
+            None,
+            &ProverConfig::default(),
+        )
+        .unwrap();
+        let proof = StarkProof::<MerkleTreeGL>::stark_gen::<TranscriptGL>(
+            cm_pol,
+            const_pol,
+            &setup.const_tree,
+            &setup.starkinfo,
+            &setup.program,
+            &pil,
+            &stark_struct,
+            "273030697313060285579891744179749754319274977764",
+        )
+        .unwrap();
+        assert!(stark_verify::<MerkleTreeGL, TranscriptGL>(
+            &proof,
+            &setup.const_root,
+            &setup.starkinfo,
+            &stark_struct,
+            &setup.program,
+        )
+        .unwrap());
+
+        let code =
+            render(&mut setup.starkinfo, &mut setup.program, &stark_struct, &setup.const_root)
+                .unwrap();
+        let dir = std::env::temp_dir().join("starky_verifier_rust");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("verifier.rs"), code).unwrap();
+        std::fs::write(dir.join("main.rs"), MAIN).unwrap();
+        let words = proof_words(&proof);
+        write_proof_words(&proof, std::fs::File::create(dir.join("proof")).unwrap()).unwrap();
+        // an evaluation changed
+        let mut bad = words.clone();
+        bad[1 + proof.publics.len() + 16 + 1] ^= 1;
+        let bad: Vec<String> = bad.iter().map(|w| w.to_string()).collect();
+        std::fs::write(dir.join("bad"), bad.join("\n")).unwrap();
+
+        let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let status = Command::new(rustc)
+            .args(["--edition", "2021", "-O", "-o"])
+            .arg(dir.join("verify"))
+            .arg(dir.join("main.rs"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let run = |proof: &str| Command::new(dir.join("verify")).arg(dir.join(proof)).status();
+        assert!(run("proof").unwrap().success());
+        assert!(!run("bad").unwrap().success());
+    }
+}
@@ -1,6 +1,9 @@
 #![allow(dead_code, clippy::type_complexity)]
 use crate::constant::{MG, SHIFT};
+use crate::fiat_shamir::init_transcript;
 use crate::fri::FRI;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::stark_gen::StarkContext;
 use crate::stark_gen::StarkProof;
 use crate::starkinfo::Program;
@@ -13,10 +16,12 @@ use crate::types::parse_pil_number;
 use crate::types::StarkStruct;
 use anyhow::{bail, Result};
 use fields::field_gl::Fr as FGL;
+#[cfg(feature = "std")]
 use profiler_macro::time_profiler;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
 
-#[time_profiler("stark_verify")]
+#[cfg_attr(feature = "std", time_profiler("stark_verify"))]
 pub fn stark_verify<M: MerkleTree, T: Transcript>(
     proof: &StarkProof<M>,
     const_root: &M::MTNode,
@@ -34,6 +39,8 @@ pub fn stark_verify<M: MerkleTree, T: Transcript>(
     ctx.evals.clone_from(&proof.evals);
     ctx.publics.clone_from(&proof.publics);
 
+    init_transcript(&mut transcript, stark_struct, starkinfo, const_root)?;
+
     for i in 0..proof.publics.len() {
         let b = ctx.publics[i].as_elements().iter().map(|e| vec![*e]).collect::<Vec<Vec<FGL>>>();
         transcript.put(&b[..])?;
@@ -1,15 +1,23 @@
 #![allow(non_snake_case)]
 
+#[cfg(feature = "std")]
 use crate::expressionops::ExpressionOps as E;
-use crate::starkinfo_codegen::{
-    build_code, iterate_code, pil_code_gen, Context, ContextF, EVIdx, Index, IndexVec, Node,
-    PolType, Segment,
-};
-use crate::types::{Expression, Public, StarkStruct, PIL};
+#[cfg(feature = "std")]
+use crate::pil_validate::check;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+#[cfg(feature = "std")]
+use crate::starkinfo_codegen::{build_code, iterate_code, pil_code_gen, Context, ContextF};
+use crate::starkinfo_codegen::{EVIdx, Index, IndexVec, Node, PolType, Segment};
+use crate::types::Public;
+#[cfg(feature = "std")]
+use crate::types::{Expression, StarkStruct, PIL};
 use anyhow::{bail, Result};
+use core::fmt;
+use core::str::FromStr;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::fmt;
 
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub struct PCCTX {
@@ -43,6 +51,46 @@ impl fmt::Display for Program {
     }
 }
 
+/// How the intermediate polynomials of the constraint polynomial are selected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImPolsSelection {
+    /// pil-stark's recursive walk.
+    #[default]
+    Greedy,
+    /// Branch and bound for each max degree, seeded by the greedy choice, minimizing the number
+    /// of committed columns.
+    Optimal,
+}
+
+impl FromStr for ImPolsSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "greedy" => Ok(ImPolsSelection::Greedy),
+            "optimal" => Ok(ImPolsSelection::Optimal),
+            _ => bail!("Invalid intermediate polynomials selection {}", s),
+        }
+    }
+}
+
+/// An alternative considered for the intermediate polynomials, at a max constraint degree.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImPolsChoice {
+    pub max_deg: usize,
+    pub im_exps: Vec<usize>,
+    pub q_deg: usize,
+    /// false if the search ran out of budget before proving the choice minimal
+    pub exhaustive: bool,
+}
+
+impl ImPolsChoice {
+    /// Committed columns added by the choice, the intermediate polynomials and the q chunks.
+    pub fn width(&self) -> usize {
+        self.im_exps.len() + self.q_deg
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct StarkInfo {
     pub var_pol_map: Vec<PolType>,
@@ -56,6 +104,9 @@ pub struct StarkInfo {
     pub ci_ctx: Vec<PCCTX>,
     pub n_constants: usize,
     pub n_publics: usize,
+    /// The [`crate::fiat_shamir::pil_hash`] of the PIL, bound by the Fiat-Shamir version 1.
+    #[serde(default)]
+    pub pil_hash: [u64; 4],
     pub c_exp: usize,
 
     pub im_exps: HashMap<usize, bool>,
@@ -63,6 +114,8 @@ pub struct StarkInfo {
     pub q_dim: usize,
     pub im_exps_list: Vec<usize>,
     pub im_exp2cm: HashMap<usize, usize>,
+    #[serde(skip)]
+    pub im_pols_choices: Vec<ImPolsChoice>,
 
     pub qs: Vec<usize>,
     pub exps_2ns: Vec<usize>,
@@ -156,12 +209,15 @@ impl fmt::Display for StarkInfo {
     }
 }
 
+#[cfg(feature = "std")]
 impl StarkInfo {
     pub fn new(
         pil: &mut PIL,
         stark_struct: &StarkStruct,
         global_l1: Option<String>,
+        im_pols: ImPolsSelection,
     ) -> Result<(StarkInfo, Program)> {
+        check(pil)?;
         let pil_deg = pil.references.values().next().unwrap().polDeg;
 
         let stark_deg = 2usize.pow(stark_struct.nBits as u32);
@@ -181,6 +237,7 @@ impl StarkInfo {
             ci_ctx: Vec::new(),
             n_constants: pil.nConstants,
             n_publics: pil.publics.len(),
+            pil_hash: crate::fiat_shamir::pil_hash(pil)?.map(|e| e.as_int()),
             exp2pol: HashMap::new(),
             n_cm1: 0,
             n_cm2: 0,
@@ -196,6 +253,7 @@ impl StarkInfo {
             im_exps: HashMap::new(),
             im_exps_list: Vec::new(),
             im_exp2cm: HashMap::new(),
+            im_pols_choices: Vec::new(),
             qs: Vec::new(),
             exps_2ns: Vec::new(),
             exps_n: Vec::new(),
@@ -247,6 +305,7 @@ impl StarkInfo {
             pil,
             stark_struct,
             &mut program,
+            im_pols,
         )?;
 
         let mut ctx = Context { tmp_used: 0, code: vec![], calculated: HashMap::new(), exp_id: 0 };
@@ -1,17 +1,27 @@
 #![allow(dead_code, non_snake_case)]
+#[cfg(feature = "std")]
 use crate::expressionops::ExpressionOps;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+#[cfg(feature = "std")]
 use crate::starkinfo::StarkInfo;
+#[cfg(feature = "std")]
 use crate::traits::FieldExtension;
+#[cfg(feature = "std")]
 use crate::types::Expression;
+#[cfg(feature = "std")]
 use crate::types::PIL;
+#[cfg(feature = "std")]
 use anyhow::{bail, Result};
+use core::fmt;
 use serde::ser::SerializeSeq;
 use serde::Deserializer;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::fmt;
 
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct Context {
     pub exp_id: usize,
@@ -20,6 +30,7 @@ pub struct Context {
     pub calculated: HashMap<(&'static str, usize), bool>,
 }
 
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct ContextC {
     pub exp_id: usize,
@@ -27,6 +38,7 @@ pub struct ContextC {
     pub code: Vec<Section>,
 }
 
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct ContextF<'a> {
     pub exp_map: HashMap<(usize, usize), usize>,
@@ -36,6 +48,7 @@ pub struct ContextF<'a> {
     pub starkinfo: &'a mut StarkInfo,
 }
 
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct Code {
     pub exp_id: usize,
@@ -79,7 +92,7 @@ pub struct Section {
     pub src: Vec<Node>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Segment {
     pub first: Vec<Section>,
     pub i: Vec<Section>,
@@ -224,6 +237,7 @@ pub struct PolType {
     pub exp_id: usize,
 }
 
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct Polynom<'a, F: FieldExtension> {
     pub buffer: &'a mut Vec<F>,
@@ -241,14 +255,15 @@ pub struct EVIdx {
     pub const_: HashMap<(usize, usize), usize>,
 }
 
-fn serialize_map<S, K: Serialize, V: Serialize>(
+fn serialize_map<S, K: Serialize + Ord, V: Serialize>(
     value: &HashMap<K, V>,
     serializer: S,
-) -> std::result::Result<S::Ok, S::Error>
+) -> core::result::Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
-    let vec_map = value.iter().collect::<Vec<_>>();
+    let mut vec_map = value.iter().collect::<Vec<_>>();
+    vec_map.sort_by(|a, b| a.0.cmp(b.0));
     let mut map = serializer.serialize_seq(Some(value.len()))?;
     for v in &vec_map {
         map.serialize_element(v)?;
@@ -258,7 +273,7 @@ where
 
 fn deserialize_map<'de, D>(
     deserializer: D,
-) -> std::result::Result<HashMap<(usize, usize), usize>, D::Error>
+) -> core::result::Result<HashMap<(usize, usize), usize>, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -293,6 +308,7 @@ impl EVIdx {
 // prime: false by default
 // res_type: "" by default
 // res_id: 0 by default
+#[cfg(feature = "std")]
 pub fn pil_code_gen(
     ctx: &mut Context,
     pil: &mut PIL,
@@ -355,6 +371,7 @@ pub fn pil_code_gen(
     Ok(())
 }
 
+#[cfg(feature = "std")]
 fn find_muladd(exp: &Expression) -> Expression {
     if exp.values.is_some() {
         let values = exp.values.as_ref().unwrap();
@@ -388,6 +405,7 @@ fn find_muladd(exp: &Expression) -> Expression {
 /// Children are processed first, then the parents using the results from the children.
 ///
 /// Stops on first error.
+#[cfg(feature = "std")]
 fn iterative_postorder_traversal<'a, N: 'a + Clone, R, E, IterN: Iterator<Item = N>>(
     root: N,
     get_chidren: impl Fn(N) -> IterN,
@@ -420,6 +438,7 @@ fn iterative_postorder_traversal<'a, N: 'a + Clone, R, E, IterN: Iterator<Item =
     Ok(result_stack.pop().unwrap())
 }
 
+#[cfg(feature = "std")]
 pub fn eval_exp(
     code_ctx: &mut ContextC,
     pil: &mut PIL,
@@ -439,6 +458,7 @@ pub fn eval_exp(
     )
 }
 
+#[cfg(feature = "std")]
 fn eval_single_op(
     code_ctx: &mut ContextC,
     pil: &mut PIL,
@@ -554,6 +574,7 @@ fn eval_single_op(
     }
 }
 
+#[cfg(feature = "std")]
 pub fn calculate_deps(
     ctx: &mut Context,
     pil: &mut PIL,
@@ -578,11 +599,13 @@ pub fn calculate_deps(
     Ok(())
 }
 
+#[cfg(feature = "std")]
 pub fn expression_error(_pil: &PIL, strerr: String, _e1: usize, _e2: usize) -> Result<()> {
     //TODO
     bail!(strerr);
 }
 
+#[cfg(feature = "std")]
 pub fn build_code(ctx: &mut Context, pil: &PIL) -> Segment {
     let seg = Segment {
         first: build_linear_code(ctx, pil, "first"),
@@ -602,6 +625,7 @@ pub fn build_code(ctx: &mut Context, pil: &PIL) -> Segment {
     seg
 }
 
+#[cfg(feature = "std")]
 pub fn build_linear_code(ctx: &Context, pil: &PIL, loop_pos: &str) -> Vec<Section> {
     let exp_and_expprimes = match loop_pos {
         "i" | "last" => get_exp_and_expprimes(ctx, pil),
@@ -625,6 +649,7 @@ pub fn build_linear_code(ctx: &Context, pil: &PIL, loop_pos: &str) -> Vec<Sectio
 }
 
 //FIXME where is the exp_id from
+#[cfg(feature = "std")]
 fn get_exp_and_expprimes(ctx: &Context, pil: &PIL) -> HashMap<usize, bool> {
     let mut calc_exps = HashMap::<usize, usize>::new();
     for i in 0..ctx.code.len() {
@@ -648,6 +673,7 @@ fn get_exp_and_expprimes(ctx: &Context, pil: &PIL) -> HashMap<usize, bool> {
     res
 }
 
+#[cfg(feature = "std")]
 pub fn iterate_code(
     code: &mut Segment,
     f: fn(&mut Node, &mut ContextF, pil: &mut PIL),
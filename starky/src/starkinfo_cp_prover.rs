@@ -1,11 +1,14 @@
 use crate::expressionops::ExpressionOps as E;
-use crate::starkinfo::{Program, StarkInfo};
+use crate::starkinfo::{ImPolsChoice, ImPolsSelection, Program, StarkInfo};
 use crate::starkinfo_codegen::Section;
 use crate::starkinfo_codegen::{build_code, pil_code_gen, Context, Node};
 use crate::types::Expression;
 use crate::types::{StarkStruct, PIL};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+
+/// Nodes the branch and bound of `optimal_im_pols` may visit for each max degree.
+const IM_POLS_SEARCH_BUDGET: usize = 1 << 16;
 
 impl StarkInfo {
     #[allow(clippy::unnecessary_unwrap)]
@@ -16,6 +19,7 @@ impl StarkInfo {
         pil: &mut PIL,
         stark_struct: &StarkStruct,
         program: &mut Program,
+        im_pols: ImPolsSelection,
     ) -> Result<()> {
         //log::trace!(
         //    "generate_constraint_polynomial ctx begin: {} {:?}",
@@ -33,20 +37,48 @@ impl StarkInfo {
                 c_exp = e;
             }
         }
-        self.q_deg = 0;
-        let max_deg = (1 << (stark_struct.nBitsExt - stark_struct.nBits)) + 1;
+        let blowup_bits = stark_struct.nBitsExt - stark_struct.nBits;
+        let max_deg = (1 << blowup_bits) + 1;
+        self.im_pols_choices = vec![];
         for d in 2..=max_deg {
             let (im_exps, q_deg) = calculate_im_pols(pil, &c_exp, d)?;
-            if im_exps.is_some()
-                && (self.q_deg == 0
-                    || (im_exps.as_ref().unwrap().len() + (q_deg as usize)
-                        < self.im_exps.len() + self.q_deg))
-            {
-                self.q_deg = q_deg as usize;
-                self.im_exps = im_exps.unwrap();
+            let greedy = im_exps.map(|im_exps| {
+                let mut im_exps: Vec<usize> = im_exps.into_keys().collect();
+                im_exps.sort();
+                ImPolsChoice { max_deg: d, im_exps, q_deg: q_deg as usize, exhaustive: false }
+            });
+            let choice = match im_pols {
+                ImPolsSelection::Greedy => greedy,
+                ImPolsSelection::Optimal => {
+                    optimal_im_pols(pil, &c_exp, d, greedy.as_ref().map(|c| &c.im_exps[..]))
+                }
+            };
+            if let Some(c) = choice {
+                log::debug!(
+                    "im pols of max degree {}: {} im, q_deg {}, width {}, {} with the blowup{}",
+                    d,
+                    c.im_exps.len(),
+                    c.q_deg,
+                    c.width(),
+                    c.width() << blowup_bits,
+                    if c.exhaustive { ", minimal" } else { "" }
+                );
+                self.im_pols_choices.push(c);
             }
         }
 
+        let mut best: Option<&ImPolsChoice> = None;
+        for c in self.im_pols_choices.iter() {
+            if best.is_none_or(|b| b.q_deg == 0 || c.width() < b.width()) {
+                best = Some(c);
+            }
+        }
+        let (q_deg, im_exps) = best.map_or((0, HashMap::new()), |b| {
+            (b.q_deg, b.im_exps.iter().map(|e| (*e, true)).collect())
+        });
+        self.q_deg = q_deg;
+        self.im_exps = im_exps;
+
         //log::trace!("im_exps: {:?} q_deg {}", self.im_exps, self.q_deg);
 
         for k in self.im_exps.keys() {
@@ -286,3 +318,201 @@ pub fn calculate_im_pols(
     //);
     Ok((re, std::cmp::max(rd, abs_max_d) - 1))
 }
+
+/// Degree of `exp` once the expressions of `im_exps` are committed. A sum of constants has
+/// degree 0, as in `_calculate_im_pols`.
+/// `memo` keeps the degrees of the expressions walked through.
+fn im_degree(
+    pil: &PIL,
+    exp: &Expression,
+    im_exps: &BTreeSet<usize>,
+    memo: &mut HashMap<usize, i32>,
+) -> i32 {
+    match exp.op.as_str() {
+        "add" | "sub" | "addc" | "mulc" | "neg" => exp
+            .values
+            .as_ref()
+            .unwrap()
+            .iter()
+            .fold(0, |md, v| std::cmp::max(md, im_degree(pil, v, im_exps, memo))),
+        "mul" => {
+            let values = exp.values.as_ref().unwrap();
+            im_degree(pil, &values[0], im_exps, memo) + im_degree(pil, &values[1], im_exps, memo)
+        }
+        "muladd" => {
+            let values = exp.values.as_ref().unwrap();
+            std::cmp::max(
+                im_degree(pil, &values[0], im_exps, memo)
+                    + im_degree(pil, &values[1], im_exps, memo),
+                im_degree(pil, &values[2], im_exps, memo),
+            )
+        }
+        "cm" | "const" | "x" => 1,
+        "exp" => {
+            let id = exp.id.unwrap();
+            if im_exps.contains(&id) {
+                return 1;
+            }
+            if let Some(d) = memo.get(&id) {
+                return *d;
+            }
+            let d = im_degree(pil, &pil.expressions[id], im_exps, memo);
+            memo.insert(id, d);
+            d
+        }
+        "number" | "public" | "challenge" | "eval" => 0,
+        _ => panic!("Exp op not defined: {}", exp.op),
+    }
+}
+
+/// The expressions reachable from `exp` without going through `im_exps`.
+fn im_reachable(pil: &PIL, exp: &Expression, im_exps: &BTreeSet<usize>, out: &mut BTreeSet<usize>) {
+    if exp.op.as_str() == "exp" {
+        let id = exp.id.unwrap();
+        if !im_exps.contains(&id) && out.insert(id) {
+            im_reachable(pil, &pil.expressions[id], im_exps, out);
+        }
+    } else if let Some(values) = exp.values.as_ref() {
+        for v in values.iter() {
+            im_reachable(pil, v, im_exps, out);
+        }
+    }
+}
+
+struct ImPolsSearch<'a> {
+    pil: &'a PIL,
+    c_exp: &'a Expression,
+    max_deg: i32,
+    best: Option<BTreeSet<usize>>,
+    visited: usize,
+}
+
+impl ImPolsSearch<'_> {
+    /// Max degree of the constraint polynomial and of the committed expressions.
+    fn degree(&self, im_exps: &BTreeSet<usize>) -> i32 {
+        let mut memo = HashMap::new();
+        im_exps.iter().fold(im_degree(self.pil, self.c_exp, im_exps, &mut memo), |md, id| {
+            std::cmp::max(md, im_degree(self.pil, &self.pil.expressions[*id], im_exps, &mut memo))
+        })
+    }
+
+    /// Finds the first of the constraint polynomial and the committed expressions over
+    /// `max_deg`, and returns the expressions which may lower it, highest degree first.
+    fn violation(&self, im_exps: &BTreeSet<usize>) -> Option<Vec<usize>> {
+        let mut memo = HashMap::new();
+        let root = if im_degree(self.pil, self.c_exp, im_exps, &mut memo) > self.max_deg {
+            self.c_exp
+        } else {
+            im_exps
+                .iter()
+                .map(|id| &self.pil.expressions[*id])
+                .find(|e| im_degree(self.pil, e, im_exps, &mut memo) > self.max_deg)?
+        };
+        let mut reachable = BTreeSet::new();
+        im_reachable(self.pil, root, im_exps, &mut reachable);
+        // committing an expression of degree 1 lowers nothing
+        let mut candidates: Vec<(i32, usize)> = reachable
+            .into_iter()
+            .map(|id| (memo.get(&id).copied().unwrap_or(0), id))
+            .filter(|(d, _)| *d > 1)
+            .collect();
+        candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        Some(candidates.into_iter().map(|(_, id)| id).collect())
+    }
+
+    fn search(&mut self, im_exps: &mut BTreeSet<usize>, excluded: &BTreeSet<usize>) {
+        if self.visited >= IM_POLS_SEARCH_BUDGET {
+            return;
+        }
+        self.visited += 1;
+        let bound = |s: &Self| s.best.as_ref().map_or(usize::MAX, |b| b.len());
+        if im_exps.len() >= bound(self) {
+            return;
+        }
+        let candidates = match self.violation(im_exps) {
+            None => {
+                self.best = Some(im_exps.clone());
+                return;
+            }
+            Some(candidates) => candidates,
+        };
+        // every solution commits one of the candidates, the ones tried first are excluded after
+        let mut excluded = excluded.clone();
+        for c in candidates {
+            if im_exps.len() + 1 >= bound(self) {
+                return;
+            }
+            if excluded.contains(&c) {
+                continue;
+            }
+            im_exps.insert(c);
+            self.search(im_exps, &excluded);
+            im_exps.remove(&c);
+            excluded.insert(c);
+        }
+    }
+}
+
+/// Fewest intermediate polynomials keeping the constraint polynomial and the intermediate
+/// polynomials within `max_deg`, by branch and bound from the `seed` choice.
+fn optimal_im_pols(
+    pil: &PIL,
+    exp: &Expression,
+    max_deg: usize,
+    seed: Option<&[usize]>,
+) -> Option<ImPolsChoice> {
+    let mut search =
+        ImPolsSearch { pil, c_exp: exp, max_deg: max_deg as i32, best: None, visited: 0 };
+    if let Some(seed) = seed {
+        let seed: BTreeSet<usize> = seed.iter().copied().collect();
+        if search.violation(&seed).is_none() {
+            search.best = Some(seed);
+        }
+    }
+    search.search(&mut BTreeSet::new(), &BTreeSet::new());
+
+    let im_exps = search.best.take()?;
+    let q_deg = std::cmp::max(search.degree(&im_exps), 1) as usize - 1;
+    Some(ImPolsChoice {
+        max_deg,
+        im_exps: im_exps.into_iter().collect(),
+        q_deg,
+        exhaustive: search.visited < IM_POLS_SEARCH_BUDGET,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::starkinfo::{ImPolsSelection, StarkInfo};
+    use crate::types::{load_json, StarkStruct, PIL};
+
+    #[test]
+    fn test_optimal_im_pols() {
+        let stark_struct = load_json::<StarkStruct>("data/starkStruct.json").unwrap();
+        for pil_file in [
+            "data/fib.pil.json",
+            "data/pe.pil.json",
+            "data/plookup.pil.json",
+            "data/connection.pil.json",
+        ] {
+            let pil = load_json::<PIL>(pil_file).unwrap();
+            let (greedy, _) =
+                StarkInfo::new(&mut pil.clone(), &stark_struct, None, ImPolsSelection::Greedy)
+                    .unwrap();
+            let (optimal, _) =
+                StarkInfo::new(&mut pil.clone(), &stark_struct, None, ImPolsSelection::Optimal)
+                    .unwrap();
+            assert!(
+                optimal.im_exps.len() + optimal.q_deg <= greedy.im_exps.len() + greedy.q_deg,
+                "{}",
+                pil_file
+            );
+            // every max degree the greedy walk satisfies is considered, with no more im pols
+            for g in greedy.im_pols_choices.iter() {
+                let o = optimal.im_pols_choices.iter().find(|o| o.max_deg == g.max_deg).unwrap();
+                assert!(o.exhaustive);
+                assert!(o.im_exps.len() <= g.im_exps.len(), "{} {:?} {:?}", pil_file, o, g);
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+#![allow(non_snake_case)]
+use crate::polsarray::{PolKind, PolsArray};
+use crate::types::PIL;
+use anyhow::{anyhow, bail, Result};
+use fields::field_gl::Fr as FGL;
+
+/// Builds the constant or committed polynomials of a PIL, addressed by reference name.
+///
+/// e.g. the JS statement `pols.Fibonacci.l1[i] = v` becomes
+/// `builder.set("Fibonacci.l1", 0, i, v)`, and `pols.PoseidonG.C[j][i] = v` becomes
+/// `builder.set("PoseidonG.C", j, i, v)`.
+pub struct TraceBuilder<'a> {
+    pil: &'a PIL,
+    kind: PolKind,
+    pols: PolsArray,
+}
+
+impl<'a> TraceBuilder<'a> {
+    pub fn new(pil: &'a PIL, kind: PolKind) -> Self {
+        TraceBuilder { pil, kind, pols: PolsArray::new(pil, kind) }
+    }
+
+    /// Number of rows.
+    pub fn n(&self) -> usize {
+        self.pols.n
+    }
+
+    /// Resolves `name[idx]` to its polynomial id, idx must be 0 for a non-array reference.
+    pub fn pol_id(&self, name: &str, idx: usize) -> Result<usize> {
+        let ref_ =
+            self.pil.references.get(name).ok_or_else(|| anyhow!("Unknown reference {}", name))?;
+        let type_ = match self.kind {
+            PolKind::Commit => "cmP",
+            PolKind::Constant => "constP",
+        };
+        if ref_.type_ != type_ {
+            bail!("Invalid reference {}: {}, expected {}", name, ref_.type_, type_);
+        }
+        let len = if ref_.isArray { ref_.len.unwrap_or(0) } else { 1 };
+        if idx >= len {
+            bail!("Invalid index of {}: {}, length {}", name, idx, len);
+        }
+        let (ns, np) =
+            name.split_once('.').ok_or_else(|| anyhow!("Invalid reference name {}", name))?;
+        Ok(self.pols.get_pol_id(self.pil, &ns.to_string(), &np.to_string(), idx))
+    }
+
+    pub fn column(&self, name: &str, idx: usize) -> Result<&[FGL]> {
+        let id = self.pol_id(name, idx)?;
+        Ok(&self.pols.array[id])
+    }
+
+    pub fn column_mut(&mut self, name: &str, idx: usize) -> Result<&mut [FGL]> {
+        let id = self.pol_id(name, idx)?;
+        Ok(&mut self.pols.array[id])
+    }
+
+    pub fn get(&self, name: &str, idx: usize, row: usize) -> Result<FGL> {
+        let n = self.n();
+        self.column(name, idx)?
+            .get(row)
+            .copied()
+            .ok_or_else(|| anyhow!("Invalid row of {}: {}, n {}", name, row, n))
+    }
+
+    pub fn set(&mut self, name: &str, idx: usize, row: usize, value: FGL) -> Result<()> {
+        let n = self.n();
+        let v = self
+            .column_mut(name, idx)?
+            .get_mut(row)
+            .ok_or_else(|| anyhow!("Invalid row of {}: {}, n {}", name, row, n))?;
+        *v = value;
+        Ok(())
+    }
+
+    /// Fills the column `name[idx]` with `f(row)`.
+    pub fn fill<F: Fn(usize) -> FGL>(&mut self, name: &str, idx: usize, f: F) -> Result<()> {
+        for (i, v) in self.column_mut(name, idx)?.iter_mut().enumerate() {
+            *v = f(i);
+        }
+        Ok(())
+    }
+
+    pub fn build(self) -> PolsArray {
+        self.pols
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::polsarray::{PolKind, PolsArray};
+    use crate::trace_builder::TraceBuilder;
+    use crate::types::{load_json, PIL};
+    use fields::field_gl::Fr as FGL;
+
+    #[test]
+    fn test_trace_builder_fibonacci() {
+        let pil = load_json::<PIL>("data/fib.pil.json").unwrap();
+        let mut b = TraceBuilder::new(&pil, PolKind::Constant);
+        let n = b.n();
+        b.fill("Fibonacci.L1", 0, |i| FGL::from((i == 0) as u64)).unwrap();
+        b.fill("Fibonacci.LLAST", 0, |i| FGL::from((i == n - 1) as u64)).unwrap();
+        let const_pols = b.build();
+
+        let mut expect = PolsArray::new(&pil, PolKind::Constant);
+        expect.load("data/fib.const").unwrap();
+        assert_eq!(const_pols.array, expect.array);
+
+        let mut b = TraceBuilder::new(&pil, PolKind::Commit);
+        b.column_mut("Fibonacci.l1", 0).unwrap()[1] = FGL::from(5);
+        assert_eq!(b.get("Fibonacci.l1", 0, 1).unwrap(), FGL::from(5));
+        assert_eq!(b.column("Fibonacci.l2", 0).unwrap().len(), n);
+    }
+
+    #[test]
+    fn test_trace_builder_invalid_reference() {
+        let pil = load_json::<PIL>("data/fib.pil.json").unwrap();
+        let mut b = TraceBuilder::new(&pil, PolKind::Commit);
+        assert!(b.set("Fibonacci.l3", 0, 0, FGL::ZERO).is_err());
+        // constant polynomial in a commit trace
+        assert!(b.set("Fibonacci.L1", 0, 0, FGL::ZERO).is_err());
+        // not an array
+        assert!(b.set("Fibonacci.l1", 1, 0, FGL::ZERO).is_err());
+        assert!(b.set("Fibonacci.l1", 0, b.n(), FGL::ZERO).is_err());
+    }
+}
@@ -1,12 +1,23 @@
-use ::rand::Rand;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use anyhow::Result;
+use core::fmt::{Debug, Display};
+use core::hash::Hash;
 use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
-use ff::PrimeField;
+use fields::ff::PrimeField;
 use fields::field_gl::Fr as FGL;
 use fields::Field;
 use serde::{de::DeserializeOwned, ser::Serialize};
-use std::fmt::{Debug, Display};
-use std::hash::Hash;
+
+/// `rand::Rand` with std, for the random elements of the tests and `dev`.
+#[cfg(feature = "std")]
+pub trait MaybeRand: ::rand::Rand {}
+#[cfg(feature = "std")]
+impl<T: ::rand::Rand> MaybeRand for T {}
+#[cfg(not(feature = "std"))]
+pub trait MaybeRand {}
+#[cfg(not(feature = "std"))]
+impl<T> MaybeRand for T {}
 
 pub trait MTNodeType
 where
@@ -23,7 +34,7 @@ where
 #[allow(clippy::type_complexity)]
 pub trait MerkleTree
 where
-    Self: Sized,
+    Self: Sized + Send,
 {
     type MTNode: Copy
         + Display
@@ -32,13 +43,17 @@ where
         + MTNodeType
         + Debug
         + Serialize
-        + DeserializeOwned;
+        + DeserializeOwned
+        + Send
+        + Sync;
     type ExtendField: FieldExtension;
     type BaseField: Clone + Default + Debug + PartialEq + Serialize + DeserializeOwned;
     fn new() -> Self;
+    #[cfg(feature = "std")]
     fn to_extend(&self, p_be: &mut Vec<Self::ExtendField>);
     fn to_basefield(node: &Self::MTNode) -> Vec<Self::BaseField>;
     fn from_basefield(node: &Self::BaseField) -> Self::MTNode;
+    #[cfg(feature = "std")]
     fn merkelize(&mut self, buff: Vec<FGL>, width: usize, height: usize) -> Result<()>;
     fn get_element(&self, idx: usize, sub_idx: usize) -> FGL;
     fn get_group_proof(&self, idx: usize) -> Result<(Vec<FGL>, Vec<Vec<Self::BaseField>>)>;
@@ -83,7 +98,7 @@ pub trait FieldExtension:
     + Neg<Output = Self>
     + Sub<Output = Self>
     + SubAssign
-    + Rand
+    + MaybeRand
     + Display
     + Send
     + Sync
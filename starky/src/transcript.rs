@@ -1,4 +1,6 @@
 use crate::poseidon_opt::Poseidon;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::traits::FieldExtension;
 use crate::traits::Transcript;
 use anyhow::Result;
@@ -1,12 +1,17 @@
 #![allow(non_snake_case)]
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use core::fmt;
 use serde::ser::SerializeMap;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::fmt;
+#[cfg(feature = "std")]
 use std::fs::File;
+#[cfg(feature = "std")]
 use std::io::Read;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct Public {
@@ -165,7 +170,7 @@ pub struct PIL {
 fn ordered_map<S, K: Ord + Serialize, V: Serialize>(
     value: &HashMap<K, V>,
     serializer: S,
-) -> std::result::Result<S::Ok, S::Error>
+) -> core::result::Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
 {
@@ -198,13 +203,23 @@ pub struct StarkStruct {
     pub nQueries: usize,
     pub verificationHashType: String,
     pub steps: Vec<Step>,
+    /// Transcript initialization, see [`crate::fiat_shamir`]. 0 if absent, so the stark
+    /// structs and the proofs made before it still verify.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub fiatShamirVersion: usize,
 }
 
+fn is_zero(v: &usize) -> bool {
+    *v == 0
+}
+
+#[cfg(feature = "std")]
 pub fn load_json<T>(filename: &str) -> Result<T>
 where
     T: serde::de::DeserializeOwned,
 {
-    let mut file = File::open(filename).map_err(|e| anyhow!("open {} error: {:?}", filename, e))?;
+    let mut file =
+        File::open(filename).map_err(|e| anyhow::anyhow!("open {} error: {:?}", filename, e))?;
     let mut data = String::new();
     file.read_to_string(&mut data)?;
     read_json(data)
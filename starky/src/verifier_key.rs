@@ -0,0 +1,188 @@
+//! The `VerifierKey` of a GL setup, what `stark_verify` needs besides the proof, and `verify`,
+//! which checks a zkin with it under `no_std`, as `starky-verifier` does in wasm.
+use crate::digest::ElementDigest;
+use crate::fiat_shamir::check_version;
+use crate::merklehash::MerkleTreeGL;
+#[cfg(feature = "std")]
+use crate::polsarray::{PolKind, PolsArray};
+#[cfg(feature = "std")]
+use crate::prover_config::ProverConfig;
+use crate::stark_gen::StarkProof;
+#[cfg(feature = "std")]
+use crate::stark_setup::StarkSetup;
+use crate::stark_verify::stark_verify;
+use crate::starkinfo::{Program, StarkInfo};
+use crate::starkinfo_codegen::Segment;
+use crate::transcript::TranscriptGL;
+use crate::types::StarkStruct;
+#[cfg(feature = "std")]
+use crate::types::{load_json, PIL};
+use anyhow::{anyhow, bail, Result};
+use fields::field_gl::Fr as FGL;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifierKey {
+    pub stark_struct: StarkStruct,
+    pub starkinfo: StarkInfo,
+    pub verifier_code: Segment,
+    pub verifier_query_code: Segment,
+    pub const_root: ElementDigest<4, FGL>,
+}
+
+impl VerifierKey {
+    pub fn new(
+        starkinfo: StarkInfo,
+        program: &Program,
+        stark_struct: &StarkStruct,
+        const_root: &ElementDigest<4, FGL>,
+    ) -> Result<Self> {
+        check_version(stark_struct)?;
+        if stark_struct.verificationHashType != "GL" {
+            bail!("the verifier key supports GL, not {}", stark_struct.verificationHashType);
+        }
+        Ok(VerifierKey {
+            stark_struct: stark_struct.clone(),
+            starkinfo,
+            verifier_code: program.verifier_code.clone(),
+            verifier_query_code: program.verifier_query_code.clone(),
+            const_root: *const_root,
+        })
+    }
+
+    /// Rejects a proof whose sizes do not match the key, as `stark_verify` indexes them.
+    fn check_shape(&self, proof: &StarkProof<MerkleTreeGL>) -> Result<()> {
+        let steps = &self.stark_struct.steps;
+        if proof.evals.len() != self.starkinfo.ev_map.len() {
+            bail!("expected {} evals, got {}", self.starkinfo.ev_map.len(), proof.evals.len());
+        }
+        if proof.publics.len() != self.starkinfo.n_publics {
+            bail!("expected {} publics, got {}", self.starkinfo.n_publics, proof.publics.len());
+        }
+        if proof.fri_proof.queries.len() != steps.len() {
+            bail!("expected {} FRI steps, got {}", steps.len(), proof.fri_proof.queries.len());
+        }
+        for (si, query) in proof.fri_proof.queries.iter().enumerate() {
+            if query.pol_queries.len() != self.stark_struct.nQueries {
+                bail!("expected {} queries in step {}", self.stark_struct.nQueries, si);
+            }
+            let n_groups = if si == 0 { 5 } else { 1 };
+            if query.pol_queries.iter().any(|q| q.len() != n_groups) {
+                bail!("expected {} trees in the queries of step {}", n_groups, si);
+            }
+        }
+        let last_bits = steps.last().ok_or_else(|| anyhow!("no FRI steps"))?.nBits;
+        if proof.fri_proof.last.len() != 1 << last_bits {
+            bail!("expected {} elements in finalPol", 1usize << last_bits);
+        }
+        Ok(())
+    }
+
+    pub fn verify(&self, proof: &StarkProof<MerkleTreeGL>) -> Result<bool> {
+        self.check_shape(proof)?;
+        let program = Program {
+            verifier_code: self.verifier_code.clone(),
+            verifier_query_code: self.verifier_query_code.clone(),
+            ..Default::default()
+        };
+        stark_verify::<MerkleTreeGL, TranscriptGL>(
+            proof,
+            &self.const_root,
+            &self.starkinfo,
+            &self.stark_struct,
+            &program,
+        )
+    }
+}
+
+/// Verifies the zkin JSON of a GL proof with the JSON of its `VerifierKey`.
+pub fn verify(key: &str, zkin: &str) -> Result<bool> {
+    let key: VerifierKey =
+        serde_json::from_str(key).map_err(|e| anyhow!("invalid verifier key: {}", e))?;
+    let proof: StarkProof<MerkleTreeGL> =
+        serde_json::from_str(zkin).map_err(|e| anyhow!("invalid zkin: {}", e))?;
+    key.verify(&proof)
+}
+
+/// Sets a GL PIL up and writes the JSON of its `VerifierKey`.
+#[cfg(feature = "std")]
+pub fn write_verifier_key(
+    stark_struct: &str,
+    pil_file: &str,
+    const_pol_file: &str,
+    key_file: &str,
+    config: &ProverConfig,
+) -> Result<()> {
+    let mut pil = load_json::<PIL>(pil_file)?;
+    let stark_struct = load_json::<StarkStruct>(stark_struct)?;
+    let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
+    const_pol.load(const_pol_file)?;
+    let setup = StarkSetup::<MerkleTreeGL>::new(&const_pol, &mut pil, &stark_struct, None, config)?;
+    let key = VerifierKey::new(setup.starkinfo, &setup.program, &stark_struct, &setup.const_root)?;
+    std::fs::write(key_file, serde_json::to_string(&key)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fiat_shamir::{FIAT_SHAMIR_STRONG, FIAT_SHAMIR_WEAK};
+    use crate::stark_gen::StarkProof;
+    use crate::transcript::TranscriptGL;
+    use crate::verifier_key::*;
+
+    #[test]
+    fn test_verifier_key_verify() {
+        for version in [FIAT_SHAMIR_WEAK, FIAT_SHAMIR_STRONG] {
+            let mut pil = load_json::<PIL>("data/plookup.pil.json.gl").unwrap();
+            let mut const_pol = PolsArray::new(&pil, PolKind::Constant);
+            const_pol.load("data/plookup.const.gl").unwrap();
+            let mut cm_pol = PolsArray::new(&pil, PolKind::Commit);
+            cm_pol.load("data/plookup.cm.gl").unwrap();
+            let mut stark_struct = load_json::<StarkStruct>("data/starkStruct.json.gl").unwrap();
+            stark_struct.fiatShamirVersion = version;
+            let setup = StarkSetup::<MerkleTreeGL>::new(
+                &const_pol,
+                &mut pil,
+                &stark_struct,
+                None,
+                &ProverConfig::default(),
+            )
+            .unwrap();
+            let proof = StarkProof::<MerkleTreeGL>::stark_gen::<TranscriptGL>(
+                cm_pol,
+                const_pol,
+                &setup.const_tree,
+                &setup.starkinfo,
+                &setup.program,
+                &pil,
+                &stark_struct,
+                "273030697313060285579891744179749754319274977764",
+            )
+            .unwrap();
+
+            let key =
+                VerifierKey::new(setup.starkinfo, &setup.program, &stark_struct, &setup.const_root)
+                    .unwrap();
+            let key = serde_json::to_string(&key).unwrap();
+            let zkin = serde_json::to_value(&proof).unwrap();
+            assert!(verify(&key, &zkin.to_string()).unwrap());
+
+            // an evaluation, the last FRI polynomial and a constant changed
+            for path in [["evals", "0", "0"], ["finalPol", "0", "1"], ["s0_valsC", "0", "0"]] {
+                let mut bad = zkin.clone();
+                let e = path.iter().fold(&mut bad, |v, k| match k.parse::<usize>() {
+                    Ok(i) => &mut v[i],
+                    Err(_) => &mut v[*k],
+                });
+                let v: u64 = e.as_str().unwrap().parse().unwrap();
+                *e = serde_json::Value::String(((v + 1) % 0xffffffff00000001).to_string());
+                assert!(!verify(&key, &bad.to_string()).unwrap_or(false));
+            }
+
+            // a query dropped is an error, not a panic
+            let mut bad = zkin.clone();
+            bad["s0_vals1"].as_array_mut().unwrap().pop();
+            assert!(verify(&key, &bad.to_string()).is_err());
+        }
+    }
+}
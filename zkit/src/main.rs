@@ -2,7 +2,10 @@ extern crate clap;
 use clap::{command, Parser};
 use dsl_compile::circom_compiler;
 use groth16::api::*;
+use starky::constant::{MAX_OPS_PER_THREAD, MIN_OPS_PER_THREAD};
 use starky::prove::stark_prove;
+use starky::prover_config::ProverConfig;
+use starky::starkinfo::ImPolsSelection;
 use std::time::Instant;
 
 /// Trust setup for Plonk
@@ -148,6 +151,9 @@ struct Compressor12SetupOpt {
     exec_file: String, // File required to execute
     #[arg(long, default_value = "0")]
     force_n_bits: usize,
+    /// Also write the report of the rows used to this file, as JSON
+    #[arg(long)]
+    report: Option<String>,
 }
 
 /// Exec compressor12 for converting R1CS to PIL
@@ -158,6 +164,11 @@ struct Compressor12ExecOpt {
     input_file: String,
     #[arg(long = "w", default_value = "mycircuit.verifier.wasm")]
     wasm_file: String,
+    /// Compute the witness natively from the .r1cs and this .sym of the verifier, not its wasm
+    #[arg(long = "sym", requires = "r1cs_file")]
+    sym_file: Option<String>,
+    #[arg(long = "r1cs")]
+    r1cs_file: Option<String>,
     #[arg(long = "p", default_value = "mycircuit.c12.pil")]
     pil_file: String,
     // output files :  $C12_VERIFIER.exec
@@ -167,6 +178,93 @@ struct Compressor12ExecOpt {
     commit_file: String,
 }
 
+/// Setup compressor12 for the GL recursive verifier of a PIL, compiled without circom
+#[derive(Parser, Debug)]
+struct Compressor12StarkSetupOpt {
+    #[arg(short, long = "stark_stuct", default_value = "stark_struct.json")]
+    stark_struct: String,
+    #[arg(short, long = "piljson", default_value = "pil.json")]
+    piljson: String,
+    #[arg(long = "o", default_value = "pols.const")]
+    const_pols: String,
+    #[arg(short, long = "norm_stage", action= clap::ArgAction::SetTrue)]
+    norm_stage: bool,
+    #[arg(long = "skip_main", action= clap::ArgAction::SetTrue)]
+    skip_main: bool,
+    #[arg(long = "enable_input", action= clap::ArgAction::SetTrue)]
+    enable_input: bool,
+    #[arg(short, long = "agg_stage", action= clap::ArgAction::SetTrue)]
+    agg_stage: bool,
+    /// The verifier key of the PIL, which the exec reads
+    #[arg(long = "k", default_value = "mycircuit.c12.key.json")]
+    key_file: String,
+    #[arg(long = "c", default_value = "mycircuit.c12.const")]
+    const_file: String,
+    #[arg(long = "p", default_value = "mycircuit.c12.pil")]
+    pil_file: String,
+    #[arg(long = "e", default_value = "mycircuit.c12.exec")]
+    exec_file: String,
+    #[arg(long, default_value = "0")]
+    force_n_bits: usize,
+    /// Also write the report of the rows used to this file, as JSON
+    #[arg(long)]
+    report: Option<String>,
+}
+
+/// Exec compressor12 for the GL recursive verifier of a PIL, the witness computed natively
+#[derive(Parser, Debug)]
+struct Compressor12StarkExecOpt {
+    /// The verifier key the setup wrote
+    #[arg(long = "k", default_value = "mycircuit.c12.key.json")]
+    key_file: String,
+    #[arg(short, long = "norm_stage", action= clap::ArgAction::SetTrue)]
+    norm_stage: bool,
+    #[arg(long = "skip_main", action= clap::ArgAction::SetTrue)]
+    skip_main: bool,
+    #[arg(long = "enable_input", action= clap::ArgAction::SetTrue)]
+    enable_input: bool,
+    #[arg(short, long = "agg_stage", action= clap::ArgAction::SetTrue)]
+    agg_stage: bool,
+    #[arg(long = "i", default_value = "mycircuit.proof.zkin.json")]
+    input_file: String,
+    #[arg(long = "p", default_value = "mycircuit.c12.pil")]
+    pil_file: String,
+    #[arg(long = "e", default_value = "mycircuit.c12.exec")]
+    exec_file: String,
+    #[arg(long = "m", default_value = "mycircuit.c12.cm")]
+    commit_file: String,
+}
+
+/// Setup compressor18 for converting R1CS to PIL, on 18 columns
+#[derive(Parser, Debug)]
+struct Compressor18SetupOpt {
+    #[arg(long = "r", default_value = "mycircuit.verifier.r1cs")]
+    r1cs_file: String,
+    #[arg(long = "c", default_value = "mycircuit.c18.const")]
+    const_file: String, // Output file required to build the constants
+    #[arg(long = "p", default_value = "mycircuit.c18.pil")]
+    pil_file: String, // Proposed PIL
+    #[arg(long = "e", default_value = "mycircuit.c18.exec")]
+    exec_file: String, // File required to execute
+    #[arg(long, default_value = "0")]
+    force_n_bits: usize,
+}
+
+/// Exec compressor18 for converting R1CS to PIL, on 18 columns
+#[derive(Parser, Debug)]
+struct Compressor18ExecOpt {
+    #[arg(long = "i", default_value = "mycircuit.proof.zkin.json")]
+    input_file: String,
+    #[arg(long = "w", default_value = "mycircuit.verifier.wasm")]
+    wasm_file: String,
+    #[arg(long = "p", default_value = "mycircuit.c18.pil")]
+    pil_file: String,
+    #[arg(long = "e", default_value = "mycircuit.c18.exec")]
+    exec_file: String,
+    #[arg(long = "m", default_value = "mycircuit.c18.cm")]
+    commit_file: String,
+}
+
 /// generate the input1.zkin.json and input2.zkin.json into out.zkin.json
 #[derive(Parser, Debug)]
 struct JoinZkinExecOpt {
@@ -229,6 +327,141 @@ pub struct Groth16VerifyOpt {
     proof_file: String,
 }
 
+/// Convert a trace between the raw, col, csv, json and powdr formats
+#[derive(Parser, Debug)]
+struct PolsConvertOpt {
+    #[arg(short, long = "piljson", default_value = "pil.json")]
+    piljson: String,
+    /// cm or const
+    #[arg(short, long = "kind", default_value = "cm")]
+    kind: String,
+    #[arg(short, long = "input")]
+    input_file: String,
+    #[arg(long = "from", default_value = "raw")]
+    input_format: String,
+    #[arg(short, long = "output")]
+    output_file: String,
+    #[arg(long = "to", default_value = "raw")]
+    output_format: String,
+}
+
+/// Print a column of a trace, by `namespace.name`, in a row range
+#[derive(Parser, Debug)]
+struct PolsDumpOpt {
+    #[arg(short, long = "piljson", default_value = "pil.json")]
+    piljson: String,
+    /// cm or const
+    #[arg(short, long = "kind", default_value = "cm")]
+    kind: String,
+    #[arg(short, long = "input")]
+    input_file: String,
+    #[arg(short, long = "format", default_value = "raw")]
+    input_format: String,
+    /// namespace.name, or namespace.name[i] for arrays
+    #[arg(short, long = "name")]
+    name: String,
+    #[arg(long = "start", default_value = "0")]
+    start: usize,
+    #[arg(long = "end")]
+    end: Option<usize>,
+}
+
+/// Print the commitments, q_deg, intermediates, evaluations, estimated proof size and memory,
+/// and degree of each identity of a PIL
+#[derive(Parser, Debug)]
+struct PilInfoOpt {
+    #[arg(short, long = "piljson", default_value = "pil.json")]
+    piljson: String,
+    #[arg(short, long = "stark_struct", default_value = "stark_struct.json")]
+    stark_struct: String,
+    /// Print JSON instead of a table
+    #[arg(long = "json", action = clap::ArgAction::SetTrue)]
+    json: bool,
+}
+
+/// Estimate the R1CS constraints and compressor12 rows of the GL recursive verifier of a PIL,
+/// without rendering nor compiling it
+#[derive(Parser, Debug)]
+struct VerifierEstimateOpt {
+    #[arg(short, long = "piljson", default_value = "pil.json")]
+    piljson: String,
+    #[arg(short, long = "stark_struct", default_value = "stark_struct.json")]
+    stark_struct: String,
+    #[arg(short, long = "norm_stage", action= clap::ArgAction::SetTrue)]
+    norm_stage: bool,
+    #[arg(long = "skip_main", action= clap::ArgAction::SetTrue)]
+    skip_main: bool,
+    #[arg(short, long = "agg_stage", action= clap::ArgAction::SetTrue)]
+    agg_stage: bool,
+    /// Print JSON instead of a table
+    #[arg(long = "json", action = clap::ArgAction::SetTrue)]
+    json: bool,
+}
+
+/// Compile a .pil to a .pil.json as pilcom does
+#[derive(Parser, Debug)]
+struct PilCompileOpt {
+    #[arg(short, long = "pil")]
+    pil_file: String,
+    #[arg(short, long = "output", default_value = "pil.json")]
+    output: String,
+    /// Directories searched for the includes, after the one of the including file
+    #[arg(short = 'I', long = "include", value_delimiter = ',')]
+    include_paths: Vec<String>,
+    /// Compile only these namespaces
+    #[arg(short, long = "namespaces", value_delimiter = ',')]
+    namespaces: Option<Vec<String>>,
+}
+
+/// Print a pil.json as PIL source, e.g. to read or diff it
+#[derive(Parser, Debug)]
+struct PilPrintOpt {
+    #[arg(short, long = "piljson", default_value = "pil.json")]
+    piljson: String,
+    /// Write to a file instead of stdout
+    #[arg(short, long = "output")]
+    output: Option<String>,
+}
+
+/// Write the circom templates bundled in stark-circuits next to a generated verifier
+#[derive(Parser, Debug)]
+struct CircomLibOpt {
+    /// GL, BN128 or BLS12381
+    #[arg(short = 't', long = "hash_type", default_value = "GL")]
+    hash_type: String,
+    #[arg(short, long = "circom", default_value = "stark_verfier.circom")]
+    circom_file: String,
+}
+
+/// Generate a standalone Rust verifier of a GL STARK
+#[derive(Parser, Debug)]
+struct GenerateRustVerifierOpt {
+    #[arg(short, long = "stark_stuct", default_value = "stark_struct.json")]
+    stark_struct: String,
+    #[arg(short, long = "piljson", default_value = "pil.json")]
+    piljson: String,
+    #[arg(long = "o", default_value = "pols.const")]
+    const_pols: String,
+    /// Also write the words of this proof to `<output>.proof`
+    #[arg(short, long = "zkin")]
+    zkin: Option<String>,
+    #[arg(short, long = "output", default_value = "verify_circuit.rs")]
+    output: String,
+}
+
+/// Export the verifier key of a GL STARK for starky-verifier
+#[derive(Parser, Debug)]
+struct StarkVerifierKeyOpt {
+    #[arg(short, long = "stark_stuct", default_value = "stark_struct.json")]
+    stark_struct: String,
+    #[arg(short, long = "piljson", default_value = "pil.json")]
+    piljson: String,
+    #[arg(long = "o", default_value = "pols.const")]
+    const_pols: String,
+    #[arg(short, long = "output", default_value = "verifier_key.json")]
+    output: String,
+}
+
 #[derive(Parser, Debug)]
 enum Command {
     /// Compile circom circuits to r1cs, and generate witness
@@ -237,14 +470,41 @@ enum Command {
     #[command(name = "generate_verifier")]
     GenerateVerifier(GenerateVerifierOpt),
 
+    #[command(name = "generate_rust_verifier")]
+    GenerateRustVerifier(GenerateRustVerifierOpt),
+
     #[command(name = "stark_prove")]
     StarkProve(StarkProveOpt),
+    #[command(name = "stark_verifier_key")]
+    StarkVerifierKey(StarkVerifierKeyOpt),
     #[command(name = "compressor12_setup")]
     Compressor12Setup(Compressor12SetupOpt),
     #[command(name = "compressor12_exec")]
     Compressor12Exec(Compressor12ExecOpt),
+    #[command(name = "compressor12_stark_setup")]
+    Compressor12StarkSetup(Compressor12StarkSetupOpt),
+    #[command(name = "compressor12_stark_exec")]
+    Compressor12StarkExec(Compressor12StarkExecOpt),
+    #[command(name = "compressor18_setup")]
+    Compressor18Setup(Compressor18SetupOpt),
+    #[command(name = "compressor18_exec")]
+    Compressor18Exec(Compressor18ExecOpt),
     #[command(name = "join_zkin")]
     JoinZkin(JoinZkinExecOpt),
+    #[command(name = "pols_convert")]
+    PolsConvert(PolsConvertOpt),
+    #[command(name = "pols_dump")]
+    PolsDump(PolsDumpOpt),
+    #[command(name = "circom_lib")]
+    CircomLib(CircomLibOpt),
+    #[command(name = "pil_info")]
+    PilInfo(PilInfoOpt),
+    #[command(name = "pil_compile")]
+    PilCompile(PilCompileOpt),
+    #[command(name = "verifier_estimate")]
+    VerifierEstimate(VerifierEstimateOpt),
+    #[command(name = "pil_print")]
+    PilPrint(PilPrintOpt),
 
     #[command(name = "groth16_setup")]
     Groth16Setup(Groth16SetupOpt),
@@ -259,12 +519,44 @@ enum Command {
 struct Cli {
     #[command(subcommand)]
     command: Command,
+
+    /// Size of the stark prover thread pool, 0 to use the global pool. The groth16 prover only
+    /// calculates its witness in it
+    #[arg(long = "threads", global = true, default_value = "0")]
+    threads: usize,
+    #[arg(long = "min_ops_per_thread", global = true, default_value_t = MIN_OPS_PER_THREAD)]
+    min_ops_per_thread: usize,
+    #[arg(long = "max_ops_per_thread", global = true, default_value_t = MAX_OPS_PER_THREAD)]
+    max_ops_per_thread: usize,
+    /// Selection of the intermediate polynomials in a setup, greedy or optimal
+    #[arg(long = "im_pols", global = true, default_value = "greedy")]
+    im_pols: ImPolsSelection,
+}
+
+impl Cli {
+    fn prover_config(&self) -> anyhow::Result<ProverConfig> {
+        if self.threads == 0
+            && self.min_ops_per_thread == MIN_OPS_PER_THREAD
+            && self.max_ops_per_thread == MAX_OPS_PER_THREAD
+        {
+            Ok(ProverConfig::default())
+        } else {
+            ProverConfig::new(self.threads, self.min_ops_per_thread, self.max_ops_per_thread)
+        }
+    }
 }
 
 fn main() {
     let args = Cli::parse();
     env_logger::init();
     let start = Instant::now();
+    let config = match args.prover_config() {
+        Ok(config) => config,
+        Err(x) => {
+            println!("execute error: {x}");
+            std::process::exit(400)
+        }
+    };
     let exec_result = match args.command {
         Command::Compile(args) => circom_compiler(
             args.input,
@@ -282,6 +574,14 @@ fn main() {
             }
         },
 
+        Command::GenerateRustVerifier(args) => starky::stark_verifier_rust::generate(
+            &args.stark_struct,
+            &args.piljson,
+            &args.const_pols,
+            args.zkin.as_deref(),
+            &args.output,
+            &config,
+        ),
         Command::StarkProve(args) => stark_prove(
             &args.stark_struct,
             &args.piljson,
@@ -293,6 +593,15 @@ fn main() {
             &args.circom_file,
             &args.zkin,
             &args.prover_addr,
+            args.im_pols,
+            &config,
+        ),
+        Command::StarkVerifierKey(args) => starky::verifier_key::write_verifier_key(
+            &args.stark_struct,
+            &args.piljson,
+            &args.const_pols,
+            &args.output,
+            &config,
         ),
         Command::Compressor12Setup(args) => recursion::compressor12_setup::setup(
             &args.r1cs_file,
@@ -300,17 +609,156 @@ fn main() {
             &args.const_file,
             &args.exec_file,
             args.force_n_bits,
+            &config,
+        )
+        .and_then(|report| {
+            println!("{report}");
+            match &args.report {
+                Some(report_file) => report.save(report_file),
+                None => Ok(()),
+            }
+        }),
+        Command::Compressor12Exec(args) => match (&args.r1cs_file, &args.sym_file) {
+            (Some(r1cs_file), Some(sym_file)) => recursion::compressor12_exec::exec_native(
+                &args.input_file,
+                r1cs_file,
+                sym_file,
+                &args.pil_file,
+                &args.exec_file,
+                &args.commit_file,
+                &config,
+            ),
+            _ => recursion::compressor12_exec::exec(
+                &args.input_file,
+                &args.wasm_file,
+                &args.pil_file,
+                &args.exec_file,
+                &args.commit_file,
+                &config,
+            ),
+        },
+        Command::Compressor12StarkSetup(args) => {
+            recursion::verifier_circuit::stark_verifier::setup(
+                &args.stark_struct,
+                &args.piljson,
+                &args.const_pols,
+                &starky::pil2circom::StarkOption {
+                    enable_input: args.enable_input,
+                    verkey_input: args.norm_stage,
+                    skip_main: args.skip_main,
+                    agg_stage: args.agg_stage,
+                },
+                &args.key_file,
+                &args.pil_file,
+                &args.const_file,
+                &args.exec_file,
+                args.force_n_bits,
+                &config,
+            )
+            .and_then(|report| {
+                println!("{report}");
+                match &args.report {
+                    Some(report_file) => report.save(report_file),
+                    None => Ok(()),
+                }
+            })
+        }
+        Command::Compressor12StarkExec(args) => recursion::verifier_circuit::stark_verifier::exec(
+            &args.key_file,
+            &starky::pil2circom::StarkOption {
+                enable_input: args.enable_input,
+                verkey_input: args.norm_stage,
+                skip_main: args.skip_main,
+                agg_stage: args.agg_stage,
+            },
+            &args.input_file,
+            &args.pil_file,
+            &args.exec_file,
+            &args.commit_file,
+            &config,
+        ),
+        Command::Compressor18Setup(args) => recursion::compressor18_setup::setup(
+            &args.r1cs_file,
+            &args.pil_file,
+            &args.const_file,
+            &args.exec_file,
+            args.force_n_bits,
+            &config,
         ),
-        Command::Compressor12Exec(args) => recursion::compressor12_exec::exec(
+        Command::Compressor18Exec(args) => recursion::compressor18_exec::exec(
             &args.input_file,
             &args.wasm_file,
             &args.pil_file,
             &args.exec_file,
             &args.commit_file,
+            &config,
         ),
         Command::JoinZkin(args) => {
             starky::zkin_join::join_zkin(&args.zkin1, &args.zkin2, &args.zkinout)
         }
+        Command::PolsConvert(args) => starky::pols_format::pols_convert(
+            &args.piljson,
+            &args.kind,
+            &args.input_file,
+            &args.input_format,
+            &args.output_file,
+            &args.output_format,
+        ),
+        Command::PolsDump(args) => starky::pols_format::pols_dump(
+            &args.piljson,
+            &args.kind,
+            &args.input_file,
+            &args.input_format,
+            &args.name,
+            args.start,
+            args.end,
+            std::io::stdout().lock(),
+        ),
+        Command::CircomLib(args) => {
+            stark_circuits::write_verifier_library(&args.hash_type, &args.circom_file).map(
+                |unbundled| {
+                    if !unbundled.is_empty() {
+                        println!("link the other includes with -l: {}", unbundled.join(", "));
+                    }
+                },
+            )
+        }
+        Command::PilInfo(args) => starky::pil_info::pil_info(
+            &args.piljson,
+            &args.stark_struct,
+            args.im_pols,
+            args.json,
+            std::io::stdout().lock(),
+        ),
+        Command::VerifierEstimate(args) => recursion::verifier_estimate::verifier_estimate(
+            &args.piljson,
+            &args.stark_struct,
+            &starky::pil2circom::StarkOption {
+                enable_input: false,
+                verkey_input: args.norm_stage,
+                skip_main: args.skip_main,
+                agg_stage: args.agg_stage,
+            },
+            args.im_pols,
+            args.json,
+            std::io::stdout().lock(),
+        ),
+        Command::PilCompile(args) => recursion::pilcom::pil_compile(
+            &args.pil_file,
+            &args.output,
+            &recursion::pilcom::CompileOptions {
+                include_paths: args.include_paths.iter().map(std::path::PathBuf::from).collect(),
+                namespaces: args.namespaces.clone(),
+            },
+        ),
+        Command::PilPrint(args) => match &args.output {
+            Some(output) => {
+                std::fs::File::create(output).map_err(anyhow::Error::from).and_then(|f| {
+                    starky::pil_print::pil_print(&args.piljson, std::io::BufWriter::new(f))
+                })
+            }
+            None => starky::pil_print::pil_print(&args.piljson, std::io::stdout().lock()),
+        },
         Command::Groth16Setup(args) => groth16_setup(
             &args.curve_type,
             &args.circuit_file,
@@ -327,6 +775,7 @@ fn main() {
             &args.public_input_file,
             &args.proof_file,
             args.to_hex,
+            &config,
         ),
         Command::Groth16Verify(args) => groth16_verify(
             &args.curve_type,
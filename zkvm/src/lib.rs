@@ -136,6 +136,7 @@ fn generate_verifier<F: FieldElement>(
             nQueries: 2,
             verificationHashType: "GL".to_owned(),
             steps,
+            fiatShamirVersion: 0,
         };
 
         // generate circom